@@ -0,0 +1,26 @@
+-- ============================================================================
+-- Module: Refresh Token
+-- Description: Create refresh_tokens table for rotating refresh token families.
+-- ============================================================================
+
+CREATE TABLE refresh_tokens (
+    id BIGSERIAL PRIMARY KEY, -- Unique refresh token ID
+    user_id BIGINT NOT NULL, -- Owner user ID
+    family_id UUID NOT NULL, -- Token family, shared by every rotation of one login
+    token_hash VARCHAR(64) UNIQUE NOT NULL, -- SHA-256 hex digest of the token
+    expires_at TIMESTAMP NOT NULL, -- Expiration timestamp
+    used_at TIMESTAMP, -- Rotation timestamp (NULL means not used yet)
+    revoked_at TIMESTAMP, -- Revocation timestamp (NULL means not revoked)
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_refresh_tokens_user_id ON refresh_tokens(user_id);
+CREATE INDEX idx_refresh_tokens_family_id ON refresh_tokens(family_id);
+CREATE INDEX idx_refresh_tokens_expires_at ON refresh_tokens(expires_at);
+
+COMMENT ON TABLE refresh_tokens IS 'Refresh tokens table: stores hashed refresh tokens grouped by rotation family';
+COMMENT ON COLUMN refresh_tokens.family_id IS 'Token family, reusing a rotated token revokes the whole family';
+COMMENT ON COLUMN refresh_tokens.token_hash IS 'SHA-256 hex digest of the refresh token';
+COMMENT ON COLUMN refresh_tokens.used_at IS 'Rotation timestamp, NULL means the token is still current';
+COMMENT ON COLUMN refresh_tokens.revoked_at IS 'Revocation timestamp, NULL means not revoked';
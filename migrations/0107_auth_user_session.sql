@@ -0,0 +1,34 @@
+-- ============================================================================
+-- Module: User Session
+-- Description: Create user_sessions table, the server-side registry of logins.
+-- ============================================================================
+
+CREATE TABLE user_sessions (
+    id UUID PRIMARY KEY, -- Session ID, embedded as `jti` in access tokens and shared with the refresh token family
+    user_id BIGINT NOT NULL, -- Owner user ID
+    ip_address INET, -- Client IP address of the latest login/refresh
+    user_agent TEXT, -- Client user agent of the latest login/refresh
+    expires_at TIMESTAMP NOT NULL, -- Expiration timestamp, extended on every refresh
+    last_seen_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Last authenticated request timestamp
+    revoked_at TIMESTAMP, -- Revocation timestamp (NULL means active)
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_user_sessions_user_id ON user_sessions(user_id) WHERE revoked_at IS NULL;
+CREATE INDEX idx_user_sessions_expires_at ON user_sessions(expires_at);
+
+COMMENT ON TABLE user_sessions IS 'User sessions table: one row per login, checked on every authenticated request';
+COMMENT ON COLUMN user_sessions.id IS 'Session ID, equals the access token jti and the refresh token family_id';
+COMMENT ON COLUMN user_sessions.last_seen_at IS 'Last authenticated request timestamp';
+COMMENT ON COLUMN user_sessions.revoked_at IS 'Revocation timestamp, NULL means active';
+
+-- ============================================================================
+-- Module: Seed session management permission.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'User Session', 'system:user:session', 3, 6, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:user:*'
+ON CONFLICT (code) DO NOTHING;
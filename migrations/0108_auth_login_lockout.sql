@@ -0,0 +1,53 @@
+-- ============================================================================
+-- Module: Login Lockout
+-- Description: Track failed logins on users and lock accounts (status 4).
+-- ============================================================================
+
+ALTER TABLE users
+    ADD COLUMN failed_login_count INTEGER NOT NULL DEFAULT 0, -- Consecutive failed logins since the last success
+    ADD COLUMN locked_until TIMESTAMP; -- Automatic unlock timestamp (NULL with status 4 means locked until an admin unlocks)
+
+COMMENT ON COLUMN users.failed_login_count IS 'Consecutive failed logins, reset on successful login or unlock';
+COMMENT ON COLUMN users.locked_until IS 'Automatic unlock timestamp for locked users, NULL means manual unlock only';
+
+-- ============================================================================
+-- Module: Get login credentials (with lockout state)
+-- ============================================================================
+
+DROP FUNCTION IF EXISTS get_login_credentials(VARCHAR);
+
+CREATE FUNCTION get_login_credentials(p_username VARCHAR(50))
+RETURNS TABLE (
+    id BIGINT,
+    password_hash VARCHAR(255),
+    status SMALLINT,
+    is_system BOOLEAN,
+    failed_login_count INTEGER,
+    locked_until TIMESTAMP
+) AS $$
+BEGIN
+    RETURN QUERY
+    SELECT
+        u.id,
+        u.password_hash,
+        u.status,
+        u.is_system,
+        u.failed_login_count,
+        u.locked_until
+    FROM users u
+    WHERE u.username = p_username
+      AND u.deleted_at IS NULL;
+END;
+$$ LANGUAGE plpgsql STABLE;
+
+COMMENT ON FUNCTION get_login_credentials(VARCHAR) IS 'Efficiently retrieves user credentials and lockout state for login authentication';
+
+-- ============================================================================
+-- Module: Seed unlock permission.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'Unlock User', 'system:user:unlock', 3, 7, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:user:*'
+ON CONFLICT (code) DO NOTHING;
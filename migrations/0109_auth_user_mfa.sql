@@ -0,0 +1,45 @@
+-- ============================================================================
+-- Module: User MFA
+-- Description: Create user_mfa and user_recovery_codes tables for TOTP two-factor authentication.
+-- ============================================================================
+
+CREATE TABLE user_mfa (
+    user_id BIGINT PRIMARY KEY, -- Owner user ID
+    secret VARCHAR(64) NOT NULL, -- Base32 TOTP secret shared with the authenticator app
+    enabled BOOLEAN NOT NULL DEFAULT FALSE, -- Whether enrollment was confirmed with a valid code
+    last_used_step BIGINT, -- Time step of the last accepted code, older or equal steps are rejected as replays
+    enabled_at TIMESTAMP, -- Enrollment confirmation timestamp
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Last update timestamp
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+COMMENT ON TABLE user_mfa IS 'User MFA table: one TOTP enrollment per user, pending until confirmed';
+COMMENT ON COLUMN user_mfa.secret IS 'Base32 TOTP secret, required in plain form to compute codes';
+COMMENT ON COLUMN user_mfa.enabled IS 'Whether enrollment was confirmed, pending enrollments are not enforced at login';
+COMMENT ON COLUMN user_mfa.last_used_step IS 'Time step of the last accepted code, prevents code replay';
+
+CREATE TABLE user_recovery_codes (
+    id BIGSERIAL PRIMARY KEY, -- Unique recovery code ID
+    user_id BIGINT NOT NULL, -- Owner user ID
+    code_hash VARCHAR(255) NOT NULL, -- Argon2 hash of the recovery code
+    used_at TIMESTAMP, -- Usage timestamp (NULL means unused)
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_user_recovery_codes_user_id ON user_recovery_codes(user_id) WHERE used_at IS NULL;
+
+COMMENT ON TABLE user_recovery_codes IS 'User recovery codes table: one-time codes replacing a TOTP code when the device is lost';
+COMMENT ON COLUMN user_recovery_codes.code_hash IS 'Argon2 hash of the recovery code';
+COMMENT ON COLUMN user_recovery_codes.used_at IS 'Usage timestamp, NULL means the code can still be used';
+
+-- ============================================================================
+-- Module: Seed MFA reset permission.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'Reset User MFA', 'system:user:mfa', 3, 8, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:user:*'
+ON CONFLICT (code) DO NOTHING;
@@ -0,0 +1,23 @@
+-- ============================================================================
+-- Module: User Passkey
+-- Description: Create user_passkeys table for WebAuthn (passkey) login.
+-- ============================================================================
+
+CREATE TABLE user_passkeys (
+    id BIGSERIAL PRIMARY KEY, -- Unique passkey ID
+    user_id BIGINT NOT NULL, -- Owner user ID
+    credential_id VARCHAR(1024) UNIQUE NOT NULL, -- Base64url credential ID chosen by the authenticator
+    public_key BYTEA NOT NULL, -- SEC1 uncompressed P-256 public key (ES256)
+    sign_count BIGINT NOT NULL DEFAULT 0, -- Last seen signature counter, used to detect cloned authenticators
+    name VARCHAR(100) NOT NULL, -- Display name chosen by the user
+    last_used_at TIMESTAMP, -- Last successful login timestamp
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_user_passkeys_user_id ON user_passkeys(user_id);
+
+COMMENT ON TABLE user_passkeys IS 'User passkeys table: WebAuthn credential public keys registered by users';
+COMMENT ON COLUMN user_passkeys.credential_id IS 'Base64url credential ID, used to look up the key during login';
+COMMENT ON COLUMN user_passkeys.public_key IS 'SEC1 uncompressed P-256 public key';
+COMMENT ON COLUMN user_passkeys.sign_count IS 'Last seen signature counter, must increase on every login unless always 0';
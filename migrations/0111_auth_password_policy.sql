@@ -0,0 +1,27 @@
+-- ============================================================================
+-- Module: Password Policy
+-- Description: Track password age and forced changes on users, and keep a
+--              history of previous password hashes to prevent reuse.
+-- ============================================================================
+
+ALTER TABLE users
+    ADD COLUMN password_changed_at TIMESTAMP, -- Last password change, used for password expiry
+    ADD COLUMN must_change_password BOOLEAN NOT NULL DEFAULT FALSE; -- Password must be changed before using the API
+
+UPDATE users SET password_changed_at = COALESCE(updated_at, created_at, CURRENT_TIMESTAMP);
+
+COMMENT ON COLUMN users.password_changed_at IS 'Last password change timestamp, the password expires after the configured max age';
+COMMENT ON COLUMN users.must_change_password IS 'When TRUE, only the password change endpoints are allowed until the password is changed';
+
+CREATE TABLE password_history (
+    id BIGSERIAL PRIMARY KEY, -- Unique history entry ID
+    user_id BIGINT NOT NULL, -- Owner user ID
+    password_hash VARCHAR(255) NOT NULL, -- Argon2 hash of a previous password
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- When the password was replaced
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_password_history_user_id ON password_history(user_id, created_at DESC);
+
+COMMENT ON TABLE password_history IS 'Password history table: previous password hashes, checked to prevent reuse';
+COMMENT ON COLUMN password_history.password_hash IS 'Argon2 hash of a replaced password';
@@ -0,0 +1,28 @@
+-- ============================================================================
+-- Module: Email Verification and Password Reset
+-- Description: Track verified emails on users and store single-use tokens
+--              sent by email (password reset, email verification).
+-- ============================================================================
+
+ALTER TABLE users
+    ADD COLUMN email_verified_at TIMESTAMP; -- When the current email was verified (NULL = not verified)
+
+COMMENT ON COLUMN users.email_verified_at IS 'Verification timestamp of the current email, reset to NULL when the email changes';
+
+CREATE TABLE user_action_tokens (
+    id BIGSERIAL PRIMARY KEY, -- Unique token ID
+    user_id BIGINT NOT NULL, -- Owner user ID
+    purpose VARCHAR(32) NOT NULL, -- Token purpose: password_reset, email_verify
+    token_hash VARCHAR(64) UNIQUE NOT NULL, -- SHA-256 hex digest of the token sent by email
+    email VARCHAR(100) NOT NULL, -- Email address the token was sent to
+    expires_at TIMESTAMP NOT NULL, -- Expiration timestamp
+    used_at TIMESTAMP, -- When the token was used or superseded (NULL = unused)
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_user_action_tokens_user_purpose ON user_action_tokens(user_id, purpose);
+
+COMMENT ON TABLE user_action_tokens IS 'User action tokens table: single-use, expiring tokens sent by email';
+COMMENT ON COLUMN user_action_tokens.token_hash IS 'SHA-256 hex digest of the signed token, the token itself is never stored';
+COMMENT ON COLUMN user_action_tokens.email IS 'Recipient email, an email verification only applies while the user still has this email';
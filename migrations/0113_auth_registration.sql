@@ -0,0 +1,10 @@
+-- ============================================================================
+-- Module: Self-Registration
+-- Description: Permission to approve or reject self-registered (pending) users.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'Approve User', 'system:user:approve', 3, 9, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:user:*'
+ON CONFLICT (code) DO NOTHING;
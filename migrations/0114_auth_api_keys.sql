@@ -0,0 +1,38 @@
+-- ============================================================================
+-- Module: API Keys
+-- Description: Create api_keys table for personal access tokens used by
+--              scripts and integrations instead of an interactive login.
+-- ============================================================================
+
+CREATE TABLE api_keys (
+    id BIGSERIAL PRIMARY KEY, -- Unique API key ID
+    user_id BIGINT NOT NULL, -- Owner user ID, requests act as this user
+    name VARCHAR(100) NOT NULL, -- Display name chosen when the key was created
+    prefix VARCHAR(16) NOT NULL, -- First characters of the key, shown to identify it
+    key_hash VARCHAR(64) UNIQUE NOT NULL, -- SHA-256 hex digest of the key
+    scopes TEXT[] NOT NULL DEFAULT '{}', -- Permission codes granted to the key (subset of the owner's)
+    expires_at TIMESTAMP NOT NULL, -- Expiration timestamp
+    last_used_at TIMESTAMP, -- Last request made with the key
+    last_used_ip INET, -- Client IP address of the last request
+    created_by BIGINT, -- User who created the key (the owner, or an admin)
+    revoked_at TIMESTAMP, -- Revocation timestamp (NULL = active)
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_api_keys_user_id ON api_keys(user_id);
+
+COMMENT ON TABLE api_keys IS 'API keys table: hashed personal access tokens with scoped permissions';
+COMMENT ON COLUMN api_keys.key_hash IS 'SHA-256 hex digest of the key, the key itself is only shown once';
+COMMENT ON COLUMN api_keys.scopes IS 'Permission codes the key may use, intersected with the owner''s current permissions';
+
+-- ============================================================================
+-- Module: Seed API key management permission.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'Manage User API Keys', 'system:user:apikey', 3, 10, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:user:*'
+ON CONFLICT (code) DO NOTHING;
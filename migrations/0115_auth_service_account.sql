@@ -0,0 +1,110 @@
+-- ============================================================================
+-- Module: Service Accounts
+-- Description: Non-human users that cannot log in interactively. They
+--              authenticate with a client id and secret exchanged for a
+--              short-lived JWT, and are owned by an accountable human user.
+-- ============================================================================
+
+ALTER TABLE users
+    ADD COLUMN user_type SMALLINT NOT NULL DEFAULT 1 CHECK (user_type IN (1, 2)), -- 1: human, 2: service account
+    ADD COLUMN owner_id BIGINT REFERENCES users(id); -- Accountable human user of a service account
+
+CREATE INDEX idx_users_owner_id ON users(owner_id);
+
+COMMENT ON COLUMN users.user_type IS 'User type: 1=human, 2=service account (no password, no interactive login)';
+COMMENT ON COLUMN users.owner_id IS 'Human user accountable for a service account, NULL for humans';
+
+CREATE TABLE service_account_credentials (
+    id BIGSERIAL PRIMARY KEY, -- Unique credential ID
+    user_id BIGINT NOT NULL, -- Service account user ID
+    client_id VARCHAR(64) UNIQUE NOT NULL, -- Public client identifier
+    secret_hash VARCHAR(64) NOT NULL, -- SHA-256 hex digest of the client secret
+    created_by BIGINT, -- User who created the credential
+    last_used_at TIMESTAMP, -- Last successful token exchange
+    revoked_at TIMESTAMP, -- Revocation timestamp (NULL = active)
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_service_account_credentials_user_id ON service_account_credentials(user_id);
+
+COMMENT ON TABLE service_account_credentials IS 'Service account credentials table: client id and hashed secret pairs';
+COMMENT ON COLUMN service_account_credentials.secret_hash IS 'SHA-256 hex digest of the secret, the secret itself is only shown once';
+
+-- ============================================================================
+-- Module: Aggregated view for user with roles (with user type and owner).
+-- ============================================================================
+
+CREATE OR REPLACE VIEW user_with_roles AS
+SELECT
+    u.id AS id,
+    u.username,
+    u.email,
+    u.real_name,
+    u.password_hash,
+    u.avatar_url,
+    u.status,
+    u.is_system,
+    u.last_login_at ,
+    u.created_at,
+    u.updated_at,
+    COALESCE(
+        JSON_AGG(
+            JSON_BUILD_OBJECT(
+                'label', r.name,
+                'value', r.id
+            ) ORDER BY r.id
+        ) FILTER (WHERE r.id IS NOT NULL),
+        '[]'::json
+    ) AS roles,
+    u.user_type,
+    u.owner_id
+FROM users u
+LEFT JOIN user_roles ur ON u.id = ur.user_id
+LEFT JOIN roles r ON ur.role_id = r.id AND r.deleted_at IS NULL
+WHERE u.deleted_at IS NULL
+GROUP BY u.id, u.username, u.email, u.real_name, u.avatar_url, u.status, u.is_system, u.last_login_at, u.created_at;
+
+-- ============================================================================
+-- Module: Get login credentials (humans only)
+-- ============================================================================
+
+DROP FUNCTION IF EXISTS get_login_credentials(VARCHAR);
+
+CREATE FUNCTION get_login_credentials(p_username VARCHAR(50))
+RETURNS TABLE (
+    id BIGINT,
+    password_hash VARCHAR(255),
+    status SMALLINT,
+    is_system BOOLEAN,
+    failed_login_count INTEGER,
+    locked_until TIMESTAMP
+) AS $$
+BEGIN
+    RETURN QUERY
+    SELECT
+        u.id,
+        u.password_hash,
+        u.status,
+        u.is_system,
+        u.failed_login_count,
+        u.locked_until
+    FROM users u
+    WHERE u.username = p_username
+      AND u.user_type = 1
+      AND u.deleted_at IS NULL;
+END;
+$$ LANGUAGE plpgsql STABLE;
+
+COMMENT ON FUNCTION get_login_credentials(VARCHAR) IS 'Efficiently retrieves human user credentials and lockout state for login authentication';
+
+-- ============================================================================
+-- Module: Seed service account management permission.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'Manage Service Accounts', 'system:user:service', 3, 11, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:user:*'
+ON CONFLICT (code) DO NOTHING;
@@ -0,0 +1,22 @@
+-- ============================================================================
+-- Module: User Identity
+-- Description: Create user_identities table linking users to their accounts
+--              at an external OpenID Connect identity provider.
+-- ============================================================================
+
+CREATE TABLE user_identities (
+    id BIGSERIAL PRIMARY KEY, -- Unique identity ID
+    user_id BIGINT NOT NULL, -- Linked user ID
+    issuer VARCHAR(255) NOT NULL, -- Identity provider issuer URL
+    subject VARCHAR(255) NOT NULL, -- Stable user ID at the provider (sub claim)
+    email VARCHAR(100), -- Email reported by the provider at the last login
+    last_login_at TIMESTAMP, -- Last single sign-on with this identity
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Link timestamp
+    UNIQUE (issuer, subject),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_user_identities_user_id ON user_identities(user_id);
+
+COMMENT ON TABLE user_identities IS 'User identities table: external identity provider accounts linked to users';
+COMMENT ON COLUMN user_identities.subject IS 'sub claim of the ID token, unique per issuer';
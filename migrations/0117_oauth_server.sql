@@ -0,0 +1,84 @@
+-- ============================================================================
+-- Module: OAuth2 Authorization Server
+-- Description: Registered client applications, authorization codes and
+--              issued access tokens of the OAuth2 / OpenID Connect provider
+--              that lets internal apps sign in with rustzen accounts.
+-- ============================================================================
+
+CREATE TABLE oauth_clients (
+    id BIGSERIAL PRIMARY KEY, -- Unique client ID
+    client_id VARCHAR(64) UNIQUE NOT NULL, -- Public client identifier
+    secret_hash VARCHAR(64), -- SHA-256 hex digest of the client secret (NULL = public client)
+    name VARCHAR(100) NOT NULL, -- Application name shown on the consent page
+    redirect_uris TEXT[] NOT NULL DEFAULT '{}', -- Exact redirect URIs allowed for the authorization code flow
+    grant_types TEXT[] NOT NULL DEFAULT '{}', -- Allowed grants: authorization_code, client_credentials
+    service_account_id BIGINT, -- Service account the client acts as for client_credentials
+    status SMALLINT NOT NULL DEFAULT 1 CHECK (status IN (1, 2)), -- 1: enabled, 2: disabled
+    created_by BIGINT, -- User who registered the client
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Last update timestamp
+    deleted_at TIMESTAMP, -- Soft delete timestamp
+    FOREIGN KEY (service_account_id) REFERENCES users(id) ON DELETE SET NULL,
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+COMMENT ON TABLE oauth_clients IS 'OAuth clients table: applications allowed to obtain rustzen tokens';
+COMMENT ON COLUMN oauth_clients.secret_hash IS 'SHA-256 hex digest of the secret, NULL for public clients that rely on PKCE only';
+
+CREATE TABLE oauth_authorization_codes (
+    code_hash VARCHAR(64) PRIMARY KEY, -- SHA-256 hex digest of the authorization code
+    client_id BIGINT NOT NULL, -- Client the code was issued to
+    user_id BIGINT NOT NULL, -- User who approved the request
+    redirect_uri TEXT NOT NULL, -- Redirect URI of the request, required again at the token endpoint
+    scope TEXT NOT NULL, -- Granted scopes, space separated
+    nonce TEXT, -- OpenID Connect nonce, copied into the ID token
+    code_challenge VARCHAR(128) NOT NULL, -- PKCE S256 code challenge
+    expires_at TIMESTAMP NOT NULL, -- Expiration timestamp
+    used_at TIMESTAMP, -- Redemption timestamp (codes are single use)
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    FOREIGN KEY (client_id) REFERENCES oauth_clients(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_oauth_authorization_codes_expires_at ON oauth_authorization_codes(expires_at);
+
+COMMENT ON TABLE oauth_authorization_codes IS 'OAuth authorization codes table: short-lived single use codes of the authorization code flow';
+
+CREATE TABLE oauth_access_tokens (
+    jti UUID PRIMARY KEY, -- JWT ID of the access token
+    client_id BIGINT NOT NULL, -- Client the token was issued to
+    user_id BIGINT NOT NULL, -- Subject of the token
+    scope TEXT NOT NULL, -- Granted scopes, space separated
+    expires_at TIMESTAMP NOT NULL, -- Expiration timestamp
+    revoked_at TIMESTAMP, -- Revocation timestamp (NULL = active)
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    FOREIGN KEY (client_id) REFERENCES oauth_clients(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_oauth_access_tokens_user_id ON oauth_access_tokens(user_id);
+CREATE INDEX idx_oauth_access_tokens_client_id ON oauth_access_tokens(client_id);
+
+COMMENT ON TABLE oauth_access_tokens IS 'OAuth access tokens table: issued tokens, checked by introspection and userinfo';
+
+-- ============================================================================
+-- Module: Seed OAuth client management menu.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'OAuth Clients', 'system:oauth:*', 1, 1, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:*'
+ON CONFLICT (code) DO NOTHING;
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, v.name, v.code, v.menu_type, v.sort_order, 1, TRUE
+FROM menus m,
+    (VALUES
+        ('OAuth Client List', 'system:oauth:list', 2, 1),
+        ('OAuth Client Create', 'system:oauth:create', 3, 2),
+        ('OAuth Client Update', 'system:oauth:update', 3, 3),
+        ('OAuth Client Delete', 'system:oauth:delete', 3, 4)
+    ) AS v(name, code, menu_type, sort_order)
+WHERE m.code = 'system:oauth:*'
+ON CONFLICT (code) DO NOTHING;
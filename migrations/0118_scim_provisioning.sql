@@ -0,0 +1,16 @@
+-- ============================================================================
+-- Module: SCIM Provisioning
+-- Description: Store the externalId the provisioning client (HR system or
+--              identity provider) assigns to the users and groups it manages.
+-- ============================================================================
+
+ALTER TABLE users ADD COLUMN scim_external_id VARCHAR(255); -- SCIM externalId of the user
+ALTER TABLE roles ADD COLUMN scim_external_id VARCHAR(255); -- SCIM externalId of the group mapped onto the role
+
+CREATE UNIQUE INDEX idx_users_scim_external_id ON users(scim_external_id)
+    WHERE scim_external_id IS NOT NULL AND deleted_at IS NULL;
+CREATE UNIQUE INDEX idx_roles_scim_external_id ON roles(scim_external_id)
+    WHERE scim_external_id IS NOT NULL AND deleted_at IS NULL;
+
+COMMENT ON COLUMN users.scim_external_id IS 'externalId set by the SCIM provisioning client, NULL for users it does not manage';
+COMMENT ON COLUMN roles.scim_external_id IS 'externalId set by the SCIM provisioning client for the group mapped onto this role';
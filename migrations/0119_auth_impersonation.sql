@@ -0,0 +1,70 @@
+-- ============================================================================
+-- Module: Impersonation
+-- Description: Admins can sign in as another user to see what they see. The
+--              session belongs to the impersonated user and records the real
+--              actor, and the operation log keeps both identities.
+-- ============================================================================
+
+ALTER TABLE user_sessions
+    ADD COLUMN impersonator_id BIGINT REFERENCES users(id) ON DELETE CASCADE; -- Real user of an impersonation session
+
+CREATE INDEX idx_user_sessions_impersonator_id ON user_sessions(impersonator_id)
+    WHERE impersonator_id IS NOT NULL AND revoked_at IS NULL;
+
+COMMENT ON COLUMN user_sessions.impersonator_id IS 'User acting as the session owner, NULL for the owner''s own logins';
+
+ALTER TABLE operation_logs
+    ADD COLUMN actor_user_id BIGINT, -- Real user behind an impersonated request
+    ADD COLUMN actor_username VARCHAR(50); -- Username of the real user
+
+COMMENT ON COLUMN operation_logs.actor_user_id IS 'Real user ID when user_id was impersonated, NULL otherwise';
+COMMENT ON COLUMN operation_logs.actor_username IS 'Real username when user_id was impersonated, NULL otherwise';
+
+-- ============================================================================
+-- Module: Log operation with the impersonating actor
+-- ============================================================================
+
+DROP FUNCTION IF EXISTS log_operation(BIGINT, VARCHAR, VARCHAR, TEXT, JSONB, INET, TEXT, VARCHAR, INTEGER);
+
+CREATE FUNCTION log_operation(
+    p_user_id BIGINT,
+    p_username VARCHAR(50),
+    p_action VARCHAR(100),
+    p_description TEXT,
+    p_data JSONB DEFAULT NULL,
+    p_ip_address INET DEFAULT NULL,
+    p_user_agent TEXT DEFAULT NULL,
+    p_status VARCHAR(20) DEFAULT 'SUCCESS',
+    p_duration_ms INTEGER DEFAULT NULL,
+    p_actor_user_id BIGINT DEFAULT NULL,
+    p_actor_username VARCHAR(50) DEFAULT NULL
+)
+RETURNS BIGINT AS $$
+DECLARE
+    log_id BIGINT;
+BEGIN
+    -- Ensure current month partition exists
+    PERFORM create_log_partition(CURRENT_DATE::DATE);
+    -- Insert log record
+    INSERT INTO operation_logs (
+        user_id, username, action, description, data, status, duration_ms, ip_address, user_agent,
+        actor_user_id, actor_username, created_at
+    ) VALUES (
+        p_user_id, p_username, p_action, p_description, p_data, p_status, p_duration_ms, p_ip_address, p_user_agent,
+        p_actor_user_id, p_actor_username, CURRENT_TIMESTAMP
+    ) RETURNING id INTO log_id;
+    RETURN log_id;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON FUNCTION log_operation(BIGINT, VARCHAR, VARCHAR, TEXT, JSONB, INET, TEXT, VARCHAR, INTEGER, BIGINT, VARCHAR) IS 'Logs a single operation with automatic partition handling, including data field and impersonating actor.';
+
+-- ============================================================================
+-- Module: Seed impersonation permission.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'Impersonate User', 'system:user:impersonate', 3, 12, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:user:*'
+ON CONFLICT (code) DO NOTHING;
@@ -0,0 +1,110 @@
+-- ============================================================================
+-- Module: Permission version
+-- Description: Every change that can alter a user's effective permissions
+--              (role menus, role status, menu code/status, user roles, user
+--              status) bumps the user's permission version, so that cached
+--              permissions built from an older version can be found and
+--              refreshed.
+-- ============================================================================
+
+ALTER TABLE users
+    ADD COLUMN permission_version BIGINT NOT NULL DEFAULT 1; -- Bumped when the effective permissions may have changed
+
+COMMENT ON COLUMN users.permission_version IS 'Version of the user''s effective permissions, bumped by triggers on roles, menus and their assignments';
+
+CREATE OR REPLACE FUNCTION bump_permission_version(p_user_ids BIGINT[])
+RETURNS VOID AS $$
+    UPDATE users SET permission_version = permission_version + 1
+    WHERE id = ANY(p_user_ids);
+$$ LANGUAGE sql;
+
+COMMENT ON FUNCTION bump_permission_version(BIGINT[]) IS 'Marks the cached permissions of the given users as stale';
+
+-- User status and deletion: the user_permissions view only covers active users
+CREATE OR REPLACE FUNCTION users_permission_version_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.permission_version = OLD.permission_version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER users_permission_version BEFORE UPDATE OF status, deleted_at ON users
+    FOR EACH ROW
+    WHEN (OLD.status IS DISTINCT FROM NEW.status OR OLD.deleted_at IS DISTINCT FROM NEW.deleted_at)
+    EXECUTE FUNCTION users_permission_version_trigger();
+
+-- User roles: assigned or removed
+CREATE OR REPLACE FUNCTION user_roles_permission_version_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM bump_permission_version(ARRAY(SELECT DISTINCT user_id FROM changed_rows));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER user_roles_inserted_permission_version AFTER INSERT ON user_roles
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION user_roles_permission_version_trigger();
+
+CREATE TRIGGER user_roles_deleted_permission_version AFTER DELETE ON user_roles
+    REFERENCING OLD TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION user_roles_permission_version_trigger();
+
+-- Role menus: granted or revoked, for every user holding the role
+CREATE OR REPLACE FUNCTION role_menus_permission_version_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM bump_permission_version(ARRAY(
+        SELECT DISTINCT ur.user_id
+        FROM user_roles ur
+        WHERE ur.role_id IN (SELECT role_id FROM changed_rows)
+    ));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER role_menus_inserted_permission_version AFTER INSERT ON role_menus
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION role_menus_permission_version_trigger();
+
+CREATE TRIGGER role_menus_deleted_permission_version AFTER DELETE ON role_menus
+    REFERENCING OLD TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION role_menus_permission_version_trigger();
+
+-- Roles: enabled, disabled or deleted
+CREATE OR REPLACE FUNCTION roles_permission_version_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM bump_permission_version(ARRAY(
+        SELECT ur.user_id FROM user_roles ur WHERE ur.role_id = NEW.id
+    ));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER roles_permission_version AFTER UPDATE OF status, deleted_at ON roles
+    FOR EACH ROW
+    WHEN (OLD.status IS DISTINCT FROM NEW.status OR OLD.deleted_at IS DISTINCT FROM NEW.deleted_at)
+    EXECUTE FUNCTION roles_permission_version_trigger();
+
+-- Menus: permission code renamed, enabled, disabled or deleted
+CREATE OR REPLACE FUNCTION menus_permission_version_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM bump_permission_version(ARRAY(
+        SELECT DISTINCT ur.user_id
+        FROM role_menus rm
+        JOIN user_roles ur ON ur.role_id = rm.role_id
+        WHERE rm.menu_id = NEW.id
+    ));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER menus_permission_version AFTER UPDATE OF code, status, deleted_at ON menus
+    FOR EACH ROW
+    WHEN (OLD.code IS DISTINCT FROM NEW.code
+          OR OLD.status IS DISTINCT FROM NEW.status
+          OR OLD.deleted_at IS DISTINCT FROM NEW.deleted_at)
+    EXECUTE FUNCTION menus_permission_version_trigger();
@@ -0,0 +1,32 @@
+-- ============================================================================
+-- Module: Permission invalidation notifications
+-- Description: Permission version bumps are announced on the
+--              'permission_changed' channel with a '<user_id>:<version>'
+--              payload, so that every server instance evicts the affected
+--              users from its permission cache. Notifications are delivered
+--              on commit, and not at all when the transaction rolls back.
+-- ============================================================================
+
+CREATE OR REPLACE FUNCTION bump_permission_version(p_user_ids BIGINT[])
+RETURNS VOID AS $$
+DECLARE
+    bumped RECORD;
+BEGIN
+    FOR bumped IN
+        UPDATE users SET permission_version = permission_version + 1
+        WHERE id = ANY(p_user_ids)
+        RETURNING id, permission_version
+    LOOP
+        PERFORM pg_notify('permission_changed', bumped.id || ':' || bumped.permission_version);
+    END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION users_permission_version_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.permission_version = OLD.permission_version + 1;
+    PERFORM pg_notify('permission_changed', NEW.id || ':' || NEW.permission_version);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
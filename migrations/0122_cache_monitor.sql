@@ -0,0 +1,20 @@
+-- ============================================================================
+-- Module: Seed cache monitor menu.
+-- Description: Cache statistics and flushing by key prefix.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'Cache Monitor', 'system:cache:*', 1, 1, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:*'
+ON CONFLICT (code) DO NOTHING;
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, v.name, v.code, v.menu_type, v.sort_order, 1, TRUE
+FROM menus m,
+    (VALUES
+        ('Cache Statistics', 'system:cache:list', 2, 1),
+        ('Cache Flush', 'system:cache:flush', 3, 2)
+    ) AS v(name, code, menu_type, sort_order)
+WHERE m.code = 'system:cache:*'
+ON CONFLICT (code) DO NOTHING;
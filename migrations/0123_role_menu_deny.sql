@@ -0,0 +1,84 @@
+-- ============================================================================
+-- Module: Deny permissions
+-- Description: A role can deny a menu's permission code instead of granting
+--              it. Denials win over grants from any role, so a broad role
+--              such as `system:*` can be narrowed with `system:user:delete`.
+-- ============================================================================
+
+ALTER TABLE role_menus
+    ADD COLUMN is_deny BOOLEAN NOT NULL DEFAULT FALSE; -- TRUE: the role is denied the menu's permission code
+
+COMMENT ON COLUMN role_menus.is_deny IS 'Denies the menu permission code to holders of the role, overriding grants from any role';
+
+-- Appends is_deny, the existing columns are unchanged
+CREATE OR REPLACE VIEW user_permissions AS
+SELECT DISTINCT
+    u.id as user_id,
+    u.username,
+    m.code as menu_code,
+    m.menu_type,
+    r.code as role_code,
+    m.id as menu_id,
+    r.id as role_id,
+    rm.is_deny
+FROM users u
+JOIN user_roles ur ON u.id = ur.user_id
+JOIN roles r ON ur.role_id = r.id AND r.status = 1 AND r.deleted_at IS NULL
+JOIN role_menus rm ON r.id = rm.role_id
+JOIN menus m ON rm.menu_id = m.id AND m.deleted_at IS NULL
+WHERE u.deleted_at IS NULL
+  AND u.status = 1
+  AND m.code IS NOT NULL;
+
+-- Denied codes are returned with a `!` prefix
+CREATE OR REPLACE FUNCTION get_user_permissions(p_user_id BIGINT)
+RETURNS TEXT[] AS $$
+DECLARE
+    perms TEXT[];
+BEGIN
+    SELECT array_agg(DISTINCT CASE WHEN up.is_deny THEN '!' || up.menu_code ELSE up.menu_code END)
+    INTO perms
+    FROM user_permissions up
+    WHERE up.user_id = p_user_id;
+
+    RETURN COALESCE(perms, ARRAY[]::TEXT[]);
+END;
+$$ LANGUAGE plpgsql STABLE;
+
+COMMENT ON FUNCTION get_user_permissions(BIGINT) IS 'Retrieves the permission codes of a user, denied codes prefixed with !';
+
+-- Granted menus stay in `menus`, denied ones are listed in `deny_menus`
+CREATE OR REPLACE VIEW role_with_menus AS
+SELECT
+    r.id AS id,
+    r.name,
+    r.code,
+    r.description,
+    r.status,
+    r.created_at,
+    r.updated_at,
+    r.deleted_at,
+    r.is_system,
+    COALESCE(
+        JSON_AGG(
+            JSON_BUILD_OBJECT(
+                'label', m.name,
+                'value', m.id
+            ) ORDER BY m.id
+        ) FILTER (WHERE m.id IS NOT NULL AND NOT rm.is_deny),
+        '[]'::json
+    ) AS menus,
+    COALESCE(
+        JSON_AGG(
+            JSON_BUILD_OBJECT(
+                'label', m.name,
+                'value', m.id
+            ) ORDER BY m.id
+        ) FILTER (WHERE m.id IS NOT NULL AND rm.is_deny),
+        '[]'::json
+    ) AS deny_menus
+FROM roles r
+LEFT JOIN role_menus rm ON r.id = rm.role_id
+LEFT JOIN menus m ON rm.menu_id = m.id AND m.deleted_at IS NULL
+WHERE r.deleted_at IS NULL
+GROUP BY r.id, r.name, r.code, r.description, r.status, r.created_at, r.updated_at, r.deleted_at, r.is_system;
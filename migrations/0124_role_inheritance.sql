@@ -0,0 +1,193 @@
+-- ============================================================================
+-- Module: Role inheritance
+-- Description: A role can inherit the granted and denied menus of a parent
+--              role, transitively, so that e.g. "Senior Operator" only lists
+--              what it adds to "Operator". Inheritance passes through enabled
+--              roles only; the application rejects cycles.
+-- ============================================================================
+
+ALTER TABLE roles
+    ADD COLUMN parent_role_id BIGINT REFERENCES roles(id) ON DELETE SET NULL; -- Role whose menus are inherited (NULL = none)
+
+CREATE INDEX idx_roles_parent_role_id ON roles(parent_role_id) WHERE parent_role_id IS NOT NULL;
+
+COMMENT ON COLUMN roles.parent_role_id IS 'Parent role whose granted and denied menus this role inherits';
+
+-- ============================================================================
+-- Module: Role closure
+-- ============================================================================
+
+-- Every non-deleted role with itself and its enabled ancestors. UNION keeps
+-- the recursion finite even if a cycle slipped in.
+CREATE OR REPLACE VIEW role_closure AS
+WITH RECURSIVE closure(role_id, ancestor_id) AS (
+    SELECT r.id, r.id
+    FROM roles r
+    WHERE r.deleted_at IS NULL
+    UNION
+    SELECT c.role_id, p.id
+    FROM closure c
+    JOIN roles r ON r.id = c.ancestor_id
+    JOIN roles p ON p.id = r.parent_role_id AND p.status = 1 AND p.deleted_at IS NULL
+)
+SELECT role_id, ancestor_id FROM closure;
+
+COMMENT ON VIEW role_closure IS 'Roles with themselves and their enabled ancestors, whose menus they inherit';
+
+-- Roles with all roles below them, regardless of status
+CREATE OR REPLACE FUNCTION role_descendants(p_role_ids BIGINT[])
+RETURNS TABLE(role_id BIGINT) AS $$
+    WITH RECURSIVE descendants(id) AS (
+        SELECT unnest(p_role_ids)
+        UNION
+        SELECT r.id FROM roles r JOIN descendants d ON r.parent_role_id = d.id
+    )
+    SELECT id FROM descendants;
+$$ LANGUAGE sql STABLE;
+
+COMMENT ON FUNCTION role_descendants(BIGINT[]) IS 'The given roles and every role inheriting from them';
+
+-- ============================================================================
+-- Module: Transitive user permissions
+-- ============================================================================
+
+-- role_code and role_id stay the assigned role; the menus come from it and its ancestors
+CREATE OR REPLACE VIEW user_permissions AS
+SELECT DISTINCT
+    u.id as user_id,
+    u.username,
+    m.code as menu_code,
+    m.menu_type,
+    r.code as role_code,
+    m.id as menu_id,
+    r.id as role_id,
+    rm.is_deny
+FROM users u
+JOIN user_roles ur ON u.id = ur.user_id
+JOIN roles r ON ur.role_id = r.id AND r.status = 1 AND r.deleted_at IS NULL
+JOIN role_closure rc ON rc.role_id = r.id
+JOIN role_menus rm ON rm.role_id = rc.ancestor_id
+JOIN menus m ON rm.menu_id = m.id AND m.deleted_at IS NULL
+WHERE u.deleted_at IS NULL
+  AND u.status = 1
+  AND m.code IS NOT NULL;
+
+-- Direct grants stay in `menus` / `deny_menus`, grants from ancestors are appended
+CREATE OR REPLACE VIEW role_with_menus AS
+SELECT
+    r.id AS id,
+    r.name,
+    r.code,
+    r.description,
+    r.status,
+    r.created_at,
+    r.updated_at,
+    r.deleted_at,
+    r.is_system,
+    COALESCE(
+        JSON_AGG(
+            JSON_BUILD_OBJECT(
+                'label', m.name,
+                'value', m.id
+            ) ORDER BY m.id
+        ) FILTER (WHERE m.id IS NOT NULL AND NOT rm.is_deny),
+        '[]'::json
+    ) AS menus,
+    COALESCE(
+        JSON_AGG(
+            JSON_BUILD_OBJECT(
+                'label', m.name,
+                'value', m.id
+            ) ORDER BY m.id
+        ) FILTER (WHERE m.id IS NOT NULL AND rm.is_deny),
+        '[]'::json
+    ) AS deny_menus,
+    r.parent_role_id,
+    (
+        SELECT COALESCE(
+            JSON_AGG(JSON_BUILD_OBJECT('label', im.name, 'value', im.id) ORDER BY im.id),
+            '[]'::json
+        )
+        FROM (
+            SELECT DISTINCT am.id, am.name
+            FROM role_closure rc
+            JOIN role_menus arm ON arm.role_id = rc.ancestor_id AND NOT arm.is_deny
+            JOIN menus am ON am.id = arm.menu_id AND am.deleted_at IS NULL
+            WHERE rc.role_id = r.id AND rc.ancestor_id <> r.id
+        ) im
+    ) AS inherited_menus,
+    (
+        SELECT COALESCE(
+            JSON_AGG(JSON_BUILD_OBJECT('label', im.name, 'value', im.id) ORDER BY im.id),
+            '[]'::json
+        )
+        FROM (
+            SELECT DISTINCT am.id, am.name
+            FROM role_closure rc
+            JOIN role_menus arm ON arm.role_id = rc.ancestor_id AND arm.is_deny
+            JOIN menus am ON am.id = arm.menu_id AND am.deleted_at IS NULL
+            WHERE rc.role_id = r.id AND rc.ancestor_id <> r.id
+        ) im
+    ) AS inherited_deny_menus
+FROM roles r
+LEFT JOIN role_menus rm ON r.id = rm.role_id
+LEFT JOIN menus m ON rm.menu_id = m.id AND m.deleted_at IS NULL
+WHERE r.deleted_at IS NULL
+GROUP BY r.id, r.name, r.code, r.description, r.status, r.created_at, r.updated_at, r.deleted_at, r.is_system;
+
+-- ============================================================================
+-- Module: Permission versions of inheriting roles
+-- ============================================================================
+
+-- Role menus: granted or revoked, for every user holding the role or a role inheriting it
+CREATE OR REPLACE FUNCTION role_menus_permission_version_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM bump_permission_version(ARRAY(
+        SELECT DISTINCT ur.user_id
+        FROM user_roles ur
+        WHERE ur.role_id IN (
+            SELECT d.role_id FROM role_descendants(ARRAY(SELECT DISTINCT role_id FROM changed_rows)) d
+        )
+    ));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Roles: enabled, disabled, deleted or moved to another parent
+CREATE OR REPLACE FUNCTION roles_permission_version_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM bump_permission_version(ARRAY(
+        SELECT DISTINCT ur.user_id
+        FROM user_roles ur
+        WHERE ur.role_id IN (SELECT d.role_id FROM role_descendants(ARRAY[NEW.id]) d)
+    ));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER roles_permission_version ON roles;
+
+CREATE TRIGGER roles_permission_version AFTER UPDATE OF status, deleted_at, parent_role_id ON roles
+    FOR EACH ROW
+    WHEN (OLD.status IS DISTINCT FROM NEW.status
+          OR OLD.deleted_at IS DISTINCT FROM NEW.deleted_at
+          OR OLD.parent_role_id IS DISTINCT FROM NEW.parent_role_id)
+    EXECUTE FUNCTION roles_permission_version_trigger();
+
+-- Menus: permission code renamed, enabled, disabled or deleted
+CREATE OR REPLACE FUNCTION menus_permission_version_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM bump_permission_version(ARRAY(
+        SELECT DISTINCT ur.user_id
+        FROM user_roles ur
+        WHERE ur.role_id IN (
+            SELECT d.role_id
+            FROM role_descendants(ARRAY(SELECT rm.role_id FROM role_menus rm WHERE rm.menu_id = NEW.id)) d
+        )
+    ));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
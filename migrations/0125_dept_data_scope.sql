@@ -0,0 +1,175 @@
+-- ============================================================================
+-- Module: Departments
+-- Description: Organizational units as a tree (like menus), users belong to
+--              at most one department.
+-- ============================================================================
+
+CREATE TABLE depts (
+    id BIGSERIAL PRIMARY KEY, -- Unique department ID
+    parent_id BIGINT NOT NULL DEFAULT 0, -- Parent department ID (0 for root)
+    name VARCHAR(100) NOT NULL, -- Department name
+    code VARCHAR(50) UNIQUE NOT NULL, -- Unique department code
+    status SMALLINT NOT NULL DEFAULT 1 CHECK (status IN (1, 2)), -- 1: normal, 2: disabled
+    sort_order INTEGER NOT NULL DEFAULT 0, -- Sort order among siblings
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, -- Creation timestamp
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, -- Last update timestamp
+    deleted_at TIMESTAMP -- Soft delete timestamp
+);
+
+CREATE INDEX idx_depts_parent_sort ON depts(parent_id, sort_order) WHERE deleted_at IS NULL;
+
+COMMENT ON TABLE depts IS 'Departments table: organizational units, parent_id 0 is a root';
+
+ALTER TABLE users
+    ADD COLUMN dept_id BIGINT REFERENCES depts(id) ON DELETE SET NULL; -- Department of the user (NULL = none)
+
+CREATE INDEX idx_users_dept_id ON users(dept_id) WHERE dept_id IS NOT NULL;
+
+COMMENT ON COLUMN users.dept_id IS 'Department the user belongs to';
+
+-- Departments with all departments below them
+CREATE OR REPLACE FUNCTION dept_descendants(p_dept_id BIGINT)
+RETURNS TABLE(dept_id BIGINT) AS $$
+    WITH RECURSIVE descendants(id) AS (
+        SELECT p_dept_id
+        UNION
+        SELECT d.id FROM depts d JOIN descendants ds ON d.parent_id = ds.id
+        WHERE d.deleted_at IS NULL
+    )
+    SELECT id FROM descendants;
+$$ LANGUAGE sql STABLE;
+
+COMMENT ON FUNCTION dept_descendants(BIGINT) IS 'The given department and every non-deleted department below it';
+
+-- ============================================================================
+-- Module: Role data scope
+-- ============================================================================
+
+ALTER TABLE roles
+    ADD COLUMN data_scope SMALLINT NOT NULL DEFAULT 1 CHECK (data_scope IN (1, 2, 3, 4)); -- 1: all, 2: own department, 3: department and children, 4: self only
+
+COMMENT ON COLUMN roles.data_scope IS 'Rows visible in scoped lists: 1 all, 2 own department, 3 department and children, 4 self only';
+
+-- Appends data_scope, the existing columns are unchanged
+CREATE OR REPLACE VIEW role_with_menus AS
+SELECT
+    r.id AS id,
+    r.name,
+    r.code,
+    r.description,
+    r.status,
+    r.created_at,
+    r.updated_at,
+    r.deleted_at,
+    r.is_system,
+    COALESCE(
+        JSON_AGG(
+            JSON_BUILD_OBJECT(
+                'label', m.name,
+                'value', m.id
+            ) ORDER BY m.id
+        ) FILTER (WHERE m.id IS NOT NULL AND NOT rm.is_deny),
+        '[]'::json
+    ) AS menus,
+    COALESCE(
+        JSON_AGG(
+            JSON_BUILD_OBJECT(
+                'label', m.name,
+                'value', m.id
+            ) ORDER BY m.id
+        ) FILTER (WHERE m.id IS NOT NULL AND rm.is_deny),
+        '[]'::json
+    ) AS deny_menus,
+    r.parent_role_id,
+    (
+        SELECT COALESCE(
+            JSON_AGG(JSON_BUILD_OBJECT('label', im.name, 'value', im.id) ORDER BY im.id),
+            '[]'::json
+        )
+        FROM (
+            SELECT DISTINCT am.id, am.name
+            FROM role_closure rc
+            JOIN role_menus arm ON arm.role_id = rc.ancestor_id AND NOT arm.is_deny
+            JOIN menus am ON am.id = arm.menu_id AND am.deleted_at IS NULL
+            WHERE rc.role_id = r.id AND rc.ancestor_id <> r.id
+        ) im
+    ) AS inherited_menus,
+    (
+        SELECT COALESCE(
+            JSON_AGG(JSON_BUILD_OBJECT('label', im.name, 'value', im.id) ORDER BY im.id),
+            '[]'::json
+        )
+        FROM (
+            SELECT DISTINCT am.id, am.name
+            FROM role_closure rc
+            JOIN role_menus arm ON arm.role_id = rc.ancestor_id AND arm.is_deny
+            JOIN menus am ON am.id = arm.menu_id AND am.deleted_at IS NULL
+            WHERE rc.role_id = r.id AND rc.ancestor_id <> r.id
+        ) im
+    ) AS inherited_deny_menus,
+    r.data_scope
+FROM roles r
+LEFT JOIN role_menus rm ON r.id = rm.role_id
+LEFT JOIN menus m ON rm.menu_id = m.id AND m.deleted_at IS NULL
+WHERE r.deleted_at IS NULL
+GROUP BY r.id, r.name, r.code, r.description, r.status, r.created_at, r.updated_at, r.deleted_at, r.is_system;
+
+-- ============================================================================
+-- Module: Aggregated view for user with roles (with department).
+-- ============================================================================
+
+CREATE OR REPLACE VIEW user_with_roles AS
+SELECT
+    u.id AS id,
+    u.username,
+    u.email,
+    u.real_name,
+    u.password_hash,
+    u.avatar_url,
+    u.status,
+    u.is_system,
+    u.last_login_at ,
+    u.created_at,
+    u.updated_at,
+    COALESCE(
+        JSON_AGG(
+            JSON_BUILD_OBJECT(
+                'label', r.name,
+                'value', r.id
+            ) ORDER BY r.id
+        ) FILTER (WHERE r.id IS NOT NULL),
+        '[]'::json
+    ) AS roles,
+    u.user_type,
+    u.owner_id,
+    u.dept_id,
+    d.name AS dept_name
+FROM users u
+LEFT JOIN user_roles ur ON u.id = ur.user_id
+LEFT JOIN roles r ON ur.role_id = r.id AND r.deleted_at IS NULL
+LEFT JOIN depts d ON d.id = u.dept_id AND d.deleted_at IS NULL
+WHERE u.deleted_at IS NULL
+GROUP BY u.id, u.username, u.email, u.real_name, u.avatar_url, u.status, u.is_system, u.last_login_at, u.created_at, d.name;
+
+-- ============================================================================
+-- Module: Seed department menu.
+-- ============================================================================
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, 'Department Management', 'system:dept:*', 1, 1, 1, TRUE
+FROM menus m
+WHERE m.code = 'system:*'
+ON CONFLICT (code) DO NOTHING;
+
+INSERT INTO menus (parent_id, name, code, menu_type, sort_order, status, is_system)
+SELECT m.id, v.name, v.code, v.menu_type, v.sort_order, 1, TRUE
+FROM menus m,
+    (VALUES
+        ('Department List', 'system:dept:list', 2, 1),
+        ('Department Create', 'system:dept:create', 3, 2),
+        ('Department Update', 'system:dept:update', 3, 3),
+        ('Department Delete', 'system:dept:delete', 3, 4),
+        ('Department Options', 'system:dept:options', 3, 5)
+    ) AS v(name, code, menu_type, sort_order)
+WHERE m.code = 'system:dept:*'
+ON CONFLICT (code) DO NOTHING;
@@ -0,0 +1,140 @@
+-- ============================================================================
+-- Module: Time-bound role grants
+-- Description: A role can be granted to a user for a validity window, e.g.
+--              for on-call rotations and contractors. Permissions only use
+--              grants inside their window; a background sweeper advances
+--              `state` so that permission caches are invalidated when a grant
+--              starts or expires. Expired grants are kept for the user edit API.
+-- ============================================================================
+
+ALTER TABLE user_roles
+    ADD COLUMN valid_from TIMESTAMP, -- Start of the grant (NULL = immediately)
+    ADD COLUMN valid_until TIMESTAMP, -- End of the grant (NULL = never expires)
+    ADD COLUMN state SMALLINT NOT NULL DEFAULT 2 CHECK (state IN (1, 2, 3)), -- 1: upcoming, 2: active, 3: expired
+    ADD CONSTRAINT chk_user_roles_validity CHECK (valid_from IS NULL OR valid_until IS NULL OR valid_from < valid_until);
+
+CREATE INDEX idx_user_roles_pending_sweep ON user_roles(valid_from, valid_until)
+    WHERE state <> 3 AND (valid_from IS NOT NULL OR valid_until IS NOT NULL);
+
+COMMENT ON COLUMN user_roles.valid_from IS 'Start of a time-bound grant, NULL when the grant starts immediately';
+COMMENT ON COLUMN user_roles.valid_until IS 'End of a time-bound grant, NULL when the grant never expires';
+COMMENT ON COLUMN user_roles.state IS 'Grant state last applied on write or by the sweeper: 1 upcoming, 2 active, 3 expired';
+
+-- State of a grant window at a point in time (UTC, like every TIMESTAMP written by the application)
+CREATE OR REPLACE FUNCTION user_role_state(p_valid_from TIMESTAMP, p_valid_until TIMESTAMP, p_at TIMESTAMP)
+RETURNS SMALLINT AS $$
+    SELECT (CASE
+        WHEN p_valid_until IS NOT NULL AND p_valid_until <= p_at THEN 3
+        WHEN p_valid_from IS NOT NULL AND p_valid_from > p_at THEN 1
+        ELSE 2
+    END)::SMALLINT;
+$$ LANGUAGE sql IMMUTABLE;
+
+COMMENT ON FUNCTION user_role_state(TIMESTAMP, TIMESTAMP, TIMESTAMP) IS 'Grant state at a time: 1 upcoming, 2 active, 3 expired';
+
+-- Grants currently inside their validity window
+CREATE OR REPLACE VIEW active_user_roles AS
+SELECT ur.user_id, ur.role_id, ur.created_at, ur.valid_from, ur.valid_until
+FROM user_roles ur
+WHERE user_role_state(ur.valid_from, ur.valid_until, (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::TIMESTAMP) = 2;
+
+COMMENT ON VIEW active_user_roles IS 'User role grants inside their validity window';
+
+-- ============================================================================
+-- Module: Grant state on write
+-- ============================================================================
+
+CREATE OR REPLACE FUNCTION user_roles_state_trigger()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.state := user_role_state(NEW.valid_from, NEW.valid_until, (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::TIMESTAMP);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER user_roles_state BEFORE INSERT OR UPDATE OF valid_from, valid_until ON user_roles
+    FOR EACH ROW EXECUTE FUNCTION user_roles_state_trigger();
+
+-- User roles: validity changed, started or expired by the sweeper
+CREATE TRIGGER user_roles_updated_permission_version AFTER UPDATE ON user_roles
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION user_roles_permission_version_trigger();
+
+-- ============================================================================
+-- Module: Permissions from active grants only
+-- ============================================================================
+
+CREATE OR REPLACE VIEW user_permissions AS
+SELECT DISTINCT
+    u.id as user_id,
+    u.username,
+    m.code as menu_code,
+    m.menu_type,
+    r.code as role_code,
+    m.id as menu_id,
+    r.id as role_id,
+    rm.is_deny
+FROM users u
+JOIN active_user_roles ur ON u.id = ur.user_id
+JOIN roles r ON ur.role_id = r.id AND r.status = 1 AND r.deleted_at IS NULL
+JOIN role_closure rc ON rc.role_id = r.id
+JOIN role_menus rm ON rm.role_id = rc.ancestor_id
+JOIN menus m ON rm.menu_id = m.id AND m.deleted_at IS NULL
+WHERE u.deleted_at IS NULL
+  AND u.status = 1
+  AND m.code IS NOT NULL;
+
+-- ============================================================================
+-- Module: Aggregated view for user with roles (with time-bound grants).
+-- ============================================================================
+
+-- `roles` lists the permanent grants, `role_grants` the time-bound ones in any state
+CREATE OR REPLACE VIEW user_with_roles AS
+SELECT
+    u.id AS id,
+    u.username,
+    u.email,
+    u.real_name,
+    u.password_hash,
+    u.avatar_url,
+    u.status,
+    u.is_system,
+    u.last_login_at ,
+    u.created_at,
+    u.updated_at,
+    COALESCE(
+        JSON_AGG(
+            JSON_BUILD_OBJECT(
+                'label', r.name,
+                'value', r.id
+            ) ORDER BY r.id
+        ) FILTER (WHERE r.id IS NOT NULL),
+        '[]'::json
+    ) AS roles,
+    u.user_type,
+    u.owner_id,
+    u.dept_id,
+    d.name AS dept_name,
+    (
+        SELECT COALESCE(
+            JSON_AGG(
+                JSON_BUILD_OBJECT(
+                    'roleId', g.role_id,
+                    'roleName', gr.name,
+                    'validFrom', g.valid_from,
+                    'validUntil', g.valid_until,
+                    'state', user_role_state(g.valid_from, g.valid_until, (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::TIMESTAMP)
+                ) ORDER BY g.valid_from NULLS FIRST, g.role_id
+            ),
+            '[]'::json
+        )
+        FROM user_roles g
+        JOIN roles gr ON gr.id = g.role_id AND gr.deleted_at IS NULL
+        WHERE g.user_id = u.id AND (g.valid_from IS NOT NULL OR g.valid_until IS NOT NULL)
+    ) AS role_grants
+FROM users u
+LEFT JOIN user_roles ur ON u.id = ur.user_id AND ur.valid_from IS NULL AND ur.valid_until IS NULL
+LEFT JOIN roles r ON ur.role_id = r.id AND r.deleted_at IS NULL
+LEFT JOIN depts d ON d.id = u.dept_id AND d.deleted_at IS NULL
+WHERE u.deleted_at IS NULL
+GROUP BY u.id, u.username, u.email, u.real_name, u.avatar_url, u.status, u.is_system, u.last_login_at, u.created_at, d.name;
@@ -0,0 +1,26 @@
+-- ============================================================================
+-- Module: Permissions from enabled menus only
+-- Description: Disabling or deleting a menu revokes its permission code.
+--              The menus trigger from 0120 already bumps the permission
+--              versions of the affected users on status changes.
+-- ============================================================================
+
+CREATE OR REPLACE VIEW user_permissions AS
+SELECT DISTINCT
+    u.id as user_id,
+    u.username,
+    m.code as menu_code,
+    m.menu_type,
+    r.code as role_code,
+    m.id as menu_id,
+    r.id as role_id,
+    rm.is_deny
+FROM users u
+JOIN active_user_roles ur ON u.id = ur.user_id
+JOIN roles r ON ur.role_id = r.id AND r.status = 1 AND r.deleted_at IS NULL
+JOIN role_closure rc ON rc.role_id = r.id
+JOIN role_menus rm ON rm.role_id = rc.ancestor_id
+JOIN menus m ON rm.menu_id = m.id AND m.status = 1 AND m.deleted_at IS NULL
+WHERE u.deleted_at IS NULL
+  AND u.status = 1
+  AND m.code IS NOT NULL;
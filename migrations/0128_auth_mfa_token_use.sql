@@ -0,0 +1,18 @@
+-- ============================================================================
+-- Module: Single-use MFA pending tokens
+-- Description: Record the ID of every MFA pending token that completed a
+--              login, so that the token cannot complete another one before
+--              it expires. Rows are dropped once the token has expired.
+-- ============================================================================
+
+CREATE TABLE used_mfa_tokens (
+    jti UUID PRIMARY KEY, -- JWT ID of the MFA pending token
+    user_id BIGINT NOT NULL, -- User the token was issued to
+    expires_at TIMESTAMP NOT NULL, -- Expiration of the token
+    used_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, -- When the token completed a login
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_used_mfa_tokens_user_id ON used_mfa_tokens(user_id);
+
+COMMENT ON TABLE used_mfa_tokens IS 'MFA pending tokens that were already used to complete a login';
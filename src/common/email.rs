@@ -0,0 +1,106 @@
+use serde::{Deserialize, Deserializer, de::Error};
+
+/// Longest email address stored (`users.email` is VARCHAR(100))
+pub const EMAIL_MAX_LEN: usize = 100;
+
+/// Characters allowed in the local part besides letters and digits (RFC 5322 atext)
+const LOCAL_PART_SYMBOLS: &str = "!#$%&'*+/=?^_`{|}~.-";
+
+/// Whether `email` is a plain `local@domain` address.
+///
+/// Quoted local parts, comments and address literals are not accepted, so a
+/// valid address never carries whitespace, line breaks or angle brackets into
+/// a mail header or SMTP command.
+pub fn is_valid_email(email: &str) -> bool {
+    if email.len() > EMAIL_MAX_LEN {
+        return false;
+    }
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    let word = |c: char| c.is_ascii_alphanumeric() || (!c.is_ascii() && c.is_alphanumeric());
+
+    let local_ok = !local.is_empty()
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && local.chars().all(|c| word(c) || LOCAL_PART_SYMBOLS.contains(c));
+    let domain_ok = domain.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| word(c) || c == '-')
+    });
+    local_ok && domain_ok
+}
+
+/// Deserializes a trimmed email address, rejecting invalid ones.
+/// Use with `#[serde(deserialize_with = "deserialize_email")]`.
+pub fn deserialize_email<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let email = String::deserialize(deserializer)?.trim().to_string();
+    if !is_valid_email(&email) {
+        return Err(D::Error::custom("invalid email address"));
+    }
+    Ok(email)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_emails() {
+        for email in [
+            "bob@example.com",
+            "first.last+tag@mail.example.co.uk",
+            "o'neil@example.com",
+            "admin@localhost",
+            "jürgen@bücher.de",
+        ] {
+            assert!(is_valid_email(email), "{}", email);
+        }
+    }
+
+    #[test]
+    fn test_invalid_emails() {
+        let too_long = format!("{}@example.com", "a".repeat(EMAIL_MAX_LEN));
+        for email in [
+            "",
+            "bob",
+            "@example.com",
+            "bob@",
+            "bob@@example.com",
+            "bob@example..com",
+            "bob@-example.com",
+            ".bob@example.com",
+            "bo..b@example.com",
+            "bob smith@example.com",
+            "<bob@example.com>",
+            "\"bob\"@example.com",
+            "bob@[127.0.0.1]",
+            too_long.as_str(),
+        ] {
+            assert!(!is_valid_email(email), "{}", email);
+        }
+    }
+
+    #[test]
+    fn test_rejects_header_injection() {
+        assert!(!is_valid_email("a@b\r\nBcc: eve@example.com"));
+        assert!(!is_valid_email("a@b.com\r\nRCPT TO:<eve@example.com>"));
+        assert!(!is_valid_email("a@b.com\n"));
+    }
+
+    #[test]
+    fn test_deserialize_email() {
+        #[derive(Deserialize)]
+        struct Dto {
+            #[serde(deserialize_with = "deserialize_email")]
+            email: String,
+        }
+        let dto: Dto = serde_json::from_str(r#"{"email": " bob@example.com "}"#).unwrap();
+        assert_eq!(dto.email, "bob@example.com");
+        assert!(serde_json::from_str::<Dto>(r#"{"email": "a@b\r\nBcc: e@x.io"}"#).is_err());
+    }
+}
@@ -53,6 +53,34 @@ pub enum ServiceError {
     #[error("Failed to generate token")]
     TokenCreationFailed,
 
+    /// Too many failed logins from one client.
+    #[error("Too many failed login attempts")]
+    TooManyLoginAttempts,
+
+    /// The TOTP or recovery code was invalid or already used.
+    #[error("Invalid MFA code")]
+    InvalidMfaCode,
+
+    /// The passkey assertion or registration could not be verified.
+    #[error("Passkey verification failed")]
+    InvalidPasskey,
+
+    /// An emailed link (password reset, email verification) is invalid, expired or used.
+    #[error("Invalid or expired link")]
+    InvalidEmailToken,
+
+    /// Public self-registration is turned off.
+    #[error("Registration is disabled")]
+    RegistrationDisabled,
+
+    /// Single sign-on through the identity provider failed.
+    #[error("Single sign-on failed: {0}")]
+    SsoLoginFailed(String),
+
+    /// A rotated refresh token was presented again.
+    #[error("Refresh token reuse detected")]
+    RefreshTokenReused,
+
     /// The user does not have permission to perform this action.
     #[error("Permission denied")]
     PermissionDenied,
@@ -65,6 +93,18 @@ pub enum ServiceError {
     #[error("Email already exists")]
     EmailConflict,
 
+    /// A new password does not satisfy the password policy.
+    #[error("Password {0}")]
+    WeakPassword(String),
+
+    /// A password was used recently and cannot be reused.
+    #[error("Password was used recently")]
+    PasswordReused,
+
+    /// The user must change the password before using the API.
+    #[error("Password change required")]
+    PasswordChangeRequired,
+
     /// An operation was attempted that is invalid given the current state.
     #[error("Invalid operation: {0}")]
     InvalidOperation(String),
@@ -80,6 +120,10 @@ pub enum ServiceError {
     /// Failed to create avatar file.
     #[error("Failed to create avatar file")]
     CreateAvatarFileFailed,
+
+    /// The cache backend could not be reached.
+    #[error("Cache backend unavailable")]
+    CacheUnavailable,
 }
 
 // --- Axum Error Handling ---
@@ -146,6 +190,41 @@ impl From<ServiceError> for AppError {
                 10103, // Business-Auth-03
                 "Failed to generate login token. Please try again.".to_string(),
             ),
+            ServiceError::RefreshTokenReused => (
+                StatusCode::UNAUTHORIZED,
+                10104, // Business-Auth-04
+                "Refresh token has already been used. Please log in again.".to_string(),
+            ),
+            ServiceError::TooManyLoginAttempts => (
+                StatusCode::TOO_MANY_REQUESTS,
+                10105, // Business-Auth-05
+                "Too many failed login attempts. Please try again later.".to_string(),
+            ),
+            ServiceError::InvalidMfaCode => (
+                StatusCode::UNAUTHORIZED,
+                10106, // Business-Auth-06
+                "Invalid verification code.".to_string(),
+            ),
+            ServiceError::InvalidPasskey => (
+                StatusCode::UNAUTHORIZED,
+                10107, // Business-Auth-07
+                "Passkey verification failed.".to_string(),
+            ),
+            ServiceError::InvalidEmailToken => (
+                StatusCode::BAD_REQUEST,
+                10108, // Business-Auth-08
+                "The link is invalid or has expired. Please request a new one.".to_string(),
+            ),
+            ServiceError::RegistrationDisabled => (
+                StatusCode::FORBIDDEN,
+                10109, // Business-Auth-09
+                "Registration is disabled.".to_string(),
+            ),
+            ServiceError::SsoLoginFailed(reason) => (
+                StatusCode::UNAUTHORIZED,
+                10110, // Business-Auth-10
+                format!("Single sign-on failed: {}.", reason),
+            ),
             ServiceError::UsernameConflict => (
                 StatusCode::CONFLICT,
                 10201, // Business-User-01
@@ -156,6 +235,16 @@ impl From<ServiceError> for AppError {
                 10202, // Business-User-02
                 "Email already exists.".to_string(),
             ),
+            ServiceError::WeakPassword(reason) => (
+                StatusCode::BAD_REQUEST,
+                10203, // Business-User-03
+                format!("Password {}.", reason),
+            ),
+            ServiceError::PasswordReused => (
+                StatusCode::BAD_REQUEST,
+                10204, // Business-User-04
+                "Password was used recently. Please choose a different one.".to_string(),
+            ),
             // 2xxxx: System Errors
             ServiceError::DatabaseQueryFailed => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -172,6 +261,11 @@ impl From<ServiceError> for AppError {
                 20003, // System-Common-03
                 "Failed to create avatar file. Please try again later.".to_string(),
             ),
+            ServiceError::CacheUnavailable => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                20004, // System-Common-04
+                "Cache backend is unavailable. Please try again later.".to_string(),
+            ),
             // ServiceError::InternalServerError => (
             //     StatusCode::INTERNAL_SERVER_ERROR,
             //     20002, // System-Common-02
@@ -188,6 +282,11 @@ impl From<ServiceError> for AppError {
                 30001, // System-Auth-02
                 "You do not have permission to perform this action.".to_string(),
             ),
+            ServiceError::PasswordChangeRequired => (
+                StatusCode::FORBIDDEN,
+                30002, // System-Auth-03
+                "Your password must be changed before continuing.".to_string(),
+            ),
         };
         AppError((status, code, message))
     }
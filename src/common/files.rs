@@ -14,7 +14,7 @@ pub async fn save_avatar(multipart: &mut Multipart) -> Result<String, ServiceErr
         .await
         .map_err(|_| ServiceError::CreateAvatarFolderFailed)?;
 
-    while let Some(field) = multipart
+    if let Some(field) = multipart
         .next_field()
         .await
         .map_err(|_| ServiceError::InvalidOperation("Invalid multipart data".into()))?
@@ -22,14 +22,12 @@ pub async fn save_avatar(multipart: &mut Multipart) -> Result<String, ServiceErr
         // 验证文件类型
         let content_type = field.content_type().unwrap_or("");
         if !content_type.starts_with("image/") {
-            return Err(
-                ServiceError::InvalidOperation("Only image files are allowed".into()).into()
-            );
+            return Err(ServiceError::InvalidOperation("Only image files are allowed".into()));
         }
 
         // 获取文件扩展名
         let filename = field.file_name().unwrap_or("unknown").to_string();
-        let extension = filename.split('.').last().unwrap_or("jpg");
+        let extension = filename.split('.').next_back().unwrap_or("jpg");
 
         // 生成唯一文件名
         let file_path = format!("{}/{}.{}", USER_AVATAR_DIR, Uuid::new_v4(), extension);
@@ -42,9 +40,7 @@ pub async fn save_avatar(multipart: &mut Multipart) -> Result<String, ServiceErr
 
         // 验证文件大小
         if data.len() > USER_AVATAR_MAX_SIZE {
-            return Err(
-                ServiceError::InvalidOperation("File size must be less than 1MB".into()).into()
-            );
+            return Err(ServiceError::InvalidOperation("File size must be less than 1MB".into()));
         }
 
         // 保存文件
@@ -58,5 +54,5 @@ pub async fn save_avatar(multipart: &mut Multipart) -> Result<String, ServiceErr
         return Ok(avatar_url);
     }
 
-    Err(ServiceError::InvalidOperation("No file provided".into()).into())
+    Err(ServiceError::InvalidOperation("No file provided".into()))
 }
@@ -1,4 +1,5 @@
 pub mod api;
+pub mod email;
 pub mod error;
 pub mod files;
 pub mod pagination;
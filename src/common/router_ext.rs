@@ -13,10 +13,7 @@ use sqlx::PgPool;
 pub trait RouterExt<S> {
     /// Add route with permission check
     ///
-    /// Examples:
-    /// - Single: PermissionsCheck::Single("system:user:list")
-    /// - Any: PermissionsCheck::Any(vec!["dashboard:view", "admin:all"])
-    /// - All: PermissionsCheck::All(vec!["admin:delete", "admin:confirm"])
+    /// Example: PermissionsCheck::Single("system:user:list")
     fn route_with_permission(
         self,
         path: &str,
@@ -53,7 +50,7 @@ impl RouterExt<PgPool> for Router<PgPool> {
 /// Steps:
 /// 1. Extract current user from request
 /// 2. Get database pool
-/// 3. Check user permissions (cache-first)
+/// 3. Check user permissions (cache-first, loaded on a miss)
 /// 4. Allow or deny access
 async fn permission_middleware(
     request: Request,
@@ -75,9 +72,14 @@ async fn permission_middleware(
         current_user.username
     );
 
+    let pool = request.extensions().get::<PgPool>().cloned().ok_or_else(|| {
+        tracing::error!("PgPool not found - auth middleware missing?");
+        AppError::from(ServiceError::InvalidToken)
+    })?;
+
     // Check permissions with caching
     let has_permission =
-        PermissionService::check_permissions(current_user.user_id, &permissions_check).await?;
+        PermissionService::check_permissions(&pool, &current_user, &permissions_check).await?;
 
     // Deny if no permission
     if !has_permission {
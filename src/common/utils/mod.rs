@@ -1,4 +1 @@
-pub mod format;
-pub mod string;
 pub mod system;
-pub mod time;
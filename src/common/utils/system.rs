@@ -1,6 +1,5 @@
 use serde::Serialize;
 use std::collections::HashSet;
-use std::path::Path;
 use sysinfo::{Disk, Disks, System};
 
 #[derive(Debug, Serialize)]
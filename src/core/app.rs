@@ -3,11 +3,15 @@ use crate::{
     core::{
         config::CONFIG,
         db::{create_default_pool, test_connection},
+        jwt,
+        permission::PermissionService,
     },
     features::{
         auth::router::{protected_auth_routes, public_auth_routes},
         dashboard::router::dashboard_routes,
-        system::system_routes,
+        oauth::router::{jwks_handler, oauth_routes, protected_oauth_routes},
+        scim::router::scim_routes,
+        system::{system_routes, user::service::UserService},
     },
     middleware::{auth::auth_middleware, log::log_middleware},
 };
@@ -29,23 +33,34 @@ use tracing;
 /// Creates and starts the main application server.
 ///
 /// This function orchestrates the entire application startup process:
-/// 1. Initializes the database connection pool.
-/// 2. Sets up Cross-Origin Resource Sharing (CORS) middleware.
-/// 3. Defines and separates public and protected API routes.
-/// 4. Applies authentication middleware to protected routes.
-/// 5. Binds to a TCP listener and serves the application.
+/// 1. Validates the configuration and loads the JWT signing keys.
+/// 2. Initializes the database connection pool.
+/// 3. Sets up Cross-Origin Resource Sharing (CORS) middleware.
+/// 4. Defines and separates public and protected API routes.
+/// 5. Applies authentication middleware to protected routes.
+/// 6. Binds to a TCP listener and serves the application.
 ///
 /// # Errors
 ///
-/// Returns an error if any part of the setup fails, such as database
-/// connection, or binding to the network address.
+/// Returns an error if any part of the setup fails, such as an unsafe
+/// production configuration, database connection, or binding to the network address.
 #[tracing::instrument(name = "create_server")]
 pub async fn create_server() -> Result<(), Box<dyn std::error::Error>> {
+    // Refuse to start with an unsafe configuration or unusable keys
+    CONFIG.validate()?;
+    jwt::init()?;
+
     // Initialize database pool
     tracing::info!("Initializing database connection pool...");
     let pool = create_default_pool().await?;
     test_connection(&pool).await?;
 
+    // Evict permissions invalidated by this or any other instance
+    PermissionService::spawn_invalidation_listener(pool.clone());
+
+    // Start and expire time-bound role grants
+    UserService::spawn_role_grant_sweeper(pool.clone());
+
     // Configure CORS
     tracing::info!("Configuring CORS middleware...");
     // DEV-NOTE: This is a permissive CORS configuration for development.
@@ -60,12 +75,16 @@ pub async fn create_server() -> Result<(), Box<dyn std::error::Error>> {
     let protected_api = Router::new()
         .nest("/auth", protected_auth_routes())
         .nest("/dashboard", dashboard_routes())
+        .nest("/oauth", protected_oauth_routes())
         .nest("/system", system_routes())
         .route_layer(middleware::from_fn_with_state(pool.clone(), log_middleware)) // log middleware
         .route_layer(middleware::from_fn_with_state(pool.clone(), auth_middleware)); // auth middleware
 
     // public routes
-    let public_api = Router::new().nest("/auth", public_auth_routes());
+    let public_api = Router::new()
+        .nest("/auth", public_auth_routes())
+        .nest("/oauth", oauth_routes())
+        .nest("/scim/v2", scim_routes());
 
     // uploads file service
     let uploads_service = ServeDir::new("uploads")
@@ -74,6 +93,7 @@ pub async fn create_server() -> Result<(), Box<dyn std::error::Error>> {
     // combine all routes
     let app = Router::new()
         .route("/api/summary", get(summary))
+        .route("/.well-known/jwks.json", get(jwks_handler))
         .nest("/api", public_api.merge(protected_api))
         .nest_service("/uploads", uploads_service) // uploads file service
         .layer(cors)
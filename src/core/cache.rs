@@ -0,0 +1,464 @@
+use crate::core::config::CONFIG;
+
+use async_trait::async_trait;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use redis::{AsyncCommands, aio::ConnectionManager};
+use serde::{Serialize, de::DeserializeOwned};
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// Keys deleted per `DEL` command when flushing Redis
+const REDIS_DELETE_BATCH: usize = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("invalid cache value: {0}")]
+    Value(String),
+}
+
+/// Key-value store with expiring entries, shared by the permission cache,
+/// dictionary lookups and login throttling.
+///
+/// Keys are namespaced as `<prefix>:<id>`, e.g. `perm:42` or `dict:user_status`.
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// Backend name shown by the cache monitor
+    fn backend(&self) -> &'static str;
+
+    /// Maximum number of entries, `None` when the backend manages its own memory
+    fn capacity(&self) -> Option<u64> {
+        None
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, CacheError>;
+
+    async fn set(&self, key: &str, value: String, ttl: Duration) -> Result<(), CacheError>;
+
+    async fn delete(&self, key: &str) -> Result<(), CacheError>;
+
+    /// Increment a counter, which expires `ttl` after its first increment
+    async fn incr(&self, key: &str, ttl: Duration) -> Result<i64, CacheError>;
+
+    /// Live keys starting with `prefix`
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>, CacheError>;
+
+    /// Delete the keys starting with `prefix`, returning how many were deleted
+    async fn flush_prefix(&self, prefix: &str) -> Result<u64, CacheError> {
+        let keys = self.keys(prefix).await?;
+        for key in &keys {
+            self.delete(key).await?;
+        }
+        Ok(keys.len() as u64)
+    }
+}
+
+/// Bounded in-memory store: least recently used entries are evicted when full,
+/// expired entries are dropped on access.
+pub struct MemoryStore {
+    entries: Mutex<LruCache<String, MemoryEntry>>,
+    capacity: NonZeroUsize,
+}
+
+struct MemoryEntry {
+    value: String,
+    expires_at: Instant,
+}
+
+impl MemoryStore {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self { entries: Mutex::new(LruCache::new(capacity)), capacity }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<String, MemoryEntry>> {
+        // A panic while holding the lock cannot leave the map inconsistent
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryStore {
+    fn backend(&self) -> &'static str {
+        "memory"
+    }
+
+    fn capacity(&self) -> Option<u64> {
+        Some(self.capacity.get() as u64)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, CacheError> {
+        let mut entries = self.lock();
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Ok(Some(entry.value.clone())),
+            Some(_) => {
+                entries.pop(key);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn set(&self, key: &str, value: String, ttl: Duration) -> Result<(), CacheError> {
+        let entry = MemoryEntry { value, expires_at: Instant::now() + ttl };
+        self.lock().put(key.to_string(), entry);
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CacheError> {
+        self.lock().pop(key);
+        Ok(())
+    }
+
+    async fn incr(&self, key: &str, ttl: Duration) -> Result<i64, CacheError> {
+        let now = Instant::now();
+        let mut entries = self.lock();
+        if let Some(entry) = entries.get_mut(key)
+            && entry.expires_at > now
+        {
+            let count =
+                entry.value.parse::<i64>().map_err(|e| CacheError::Value(e.to_string()))? + 1;
+            entry.value = count.to_string();
+            return Ok(count);
+        }
+        entries.put(key.to_string(), MemoryEntry { value: "1".to_string(), expires_at: now + ttl });
+        Ok(1)
+    }
+
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>, CacheError> {
+        let now = Instant::now();
+        Ok(self
+            .lock()
+            .iter()
+            .filter(|(key, entry)| key.starts_with(prefix) && entry.expires_at > now)
+            .map(|(key, _)| key.clone())
+            .collect())
+    }
+}
+
+/// Redis store, shared by all instances. Keys are stored under a namespace so
+/// that several deployments can share one database.
+pub struct RedisStore {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+    namespace: String,
+}
+
+impl RedisStore {
+    pub fn new(url: &str, namespace: &str) -> Result<Self, CacheError> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            connection: OnceCell::new(),
+            namespace: namespace.to_string(),
+        })
+    }
+
+    /// Shared connection, established on first use and re-established on failure
+    async fn connection(&self) -> Result<ConnectionManager, CacheError> {
+        let connection =
+            self.connection.get_or_try_init(|| ConnectionManager::new(self.client.clone())).await?;
+        Ok(connection.clone())
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.namespace, key)
+    }
+}
+
+/// Escapes glob characters of a `SCAN MATCH` pattern
+fn escape_pattern(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[async_trait]
+impl CacheStore for RedisStore {
+    fn backend(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, CacheError> {
+        Ok(self.connection().await?.get(self.key(key)).await?)
+    }
+
+    async fn set(&self, key: &str, value: String, ttl: Duration) -> Result<(), CacheError> {
+        let ttl_ms = ttl.as_millis().max(1) as u64;
+        let _: () = self.connection().await?.pset_ex(self.key(key), value, ttl_ms).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CacheError> {
+        let _: () = self.connection().await?.del(self.key(key)).await?;
+        Ok(())
+    }
+
+    async fn incr(&self, key: &str, ttl: Duration) -> Result<i64, CacheError> {
+        let key = self.key(key);
+        // Create the counter with its expiry, then increment, in one transaction
+        let (count,): (i64,) = redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg(&key)
+            .arg(0)
+            .arg("PX")
+            .arg(ttl.as_millis().max(1) as u64)
+            .arg("NX")
+            .ignore()
+            .cmd("INCR")
+            .arg(&key)
+            .query_async(&mut self.connection().await?)
+            .await?;
+        Ok(count)
+    }
+
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>, CacheError> {
+        let mut connection = self.connection().await?;
+        let pattern = format!("{}*", escape_pattern(&self.key(prefix)));
+        let mut iter = connection.scan_match::<_, String>(pattern).await?;
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+            if let Some(key) = key.strip_prefix(&self.namespace) {
+                keys.push(key.to_string());
+            }
+        }
+        Ok(keys)
+    }
+
+    async fn flush_prefix(&self, prefix: &str) -> Result<u64, CacheError> {
+        let keys: Vec<String> = self.keys(prefix).await?.iter().map(|key| self.key(key)).collect();
+        let mut connection = self.connection().await?;
+        let mut deleted = 0;
+        for batch in keys.chunks(REDIS_DELETE_BATCH) {
+            let count: u64 = connection.del(batch).await?;
+            deleted += count;
+        }
+        Ok(deleted)
+    }
+}
+
+/// Hit and miss counters of one key prefix
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrefixCounters {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Statistics of the cache, per key prefix
+#[derive(Debug, Clone)]
+pub struct CacheStats {
+    pub backend: &'static str,
+    pub capacity: Option<u64>,
+    /// Live entries per prefix
+    pub entries: BTreeMap<String, u64>,
+    /// Lookups per prefix since this instance started
+    pub counters: BTreeMap<String, PrefixCounters>,
+}
+
+/// The cache store with typed access and hit/miss accounting.
+///
+/// Cache failures never fail a request: reads fall back to a miss and writes
+/// are dropped, with an error logged.
+pub struct Cache {
+    store: Box<dyn CacheStore>,
+    counters: RwLock<BTreeMap<String, PrefixCounters>>,
+}
+
+/// Prefix of a key, the part before the first `:`
+fn key_prefix(key: &str) -> &str {
+    key.split(':').next().unwrap_or(key)
+}
+
+impl Cache {
+    pub fn new(store: Box<dyn CacheStore>) -> Self {
+        Self { store, counters: RwLock::new(BTreeMap::new()) }
+    }
+
+    fn record(&self, key: &str, hit: bool) {
+        if let Ok(mut counters) = self.counters.write() {
+            let counter = counters.entry(key_prefix(key).to_string()).or_default();
+            if hit {
+                counter.hits += 1;
+            } else {
+                counter.misses += 1;
+            }
+        }
+    }
+
+    /// Cached value, `None` when missing, expired or unreadable
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = match self.store.get(key).await {
+            Ok(value) => value.and_then(|value| match serde_json::from_str(&value) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    tracing::warn!("Dropping unreadable cache entry '{}': {}", key, e);
+                    None
+                }
+            }),
+            Err(e) => {
+                tracing::error!("Cache read of '{}' failed: {}", key, e);
+                None
+            }
+        };
+        self.record(key, value.is_some());
+        value
+    }
+
+    pub async fn set_json<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) {
+        let value = match serde_json::to_string(value) {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::error!("Cannot serialize cache entry '{}': {}", key, e);
+                return;
+            }
+        };
+        if let Err(e) = self.store.set(key, value, ttl).await {
+            tracing::error!("Cache write of '{}' failed: {}", key, e);
+        }
+    }
+
+    pub async fn delete(&self, key: &str) {
+        if let Err(e) = self.store.delete(key).await {
+            tracing::error!("Cache delete of '{}' failed: {}", key, e);
+        }
+    }
+
+    /// Current value of a counter, 0 when missing
+    pub async fn get_counter(&self, key: &str) -> i64 {
+        let count = self.get_json::<i64>(key).await;
+        count.unwrap_or(0)
+    }
+
+    /// Increment a counter, which expires `ttl` after its first increment.
+    /// Returns `None` when the store is unavailable.
+    pub async fn incr(&self, key: &str, ttl: Duration) -> Option<i64> {
+        self.store
+            .incr(key, ttl)
+            .await
+            .map_err(|e| tracing::error!("Cache increment of '{}' failed: {}", key, e))
+            .ok()
+    }
+
+    /// Delete the keys starting with `prefix`, returning how many were deleted
+    pub async fn flush_prefix(&self, prefix: &str) -> Result<u64, CacheError> {
+        let deleted = self.store.flush_prefix(prefix).await?;
+        tracing::info!("Flushed {} cache entries with prefix '{}'", deleted, prefix);
+        Ok(deleted)
+    }
+
+    pub async fn stats(&self) -> Result<CacheStats, CacheError> {
+        let mut entries = BTreeMap::new();
+        for key in self.store.keys("").await? {
+            *entries.entry(key_prefix(&key).to_string()).or_default() += 1;
+        }
+        let counters = self.counters.read().map(|counters| counters.clone()).unwrap_or_default();
+        Ok(CacheStats {
+            backend: self.store.backend(),
+            capacity: self.store.capacity(),
+            entries,
+            counters,
+        })
+    }
+}
+
+/// Cache store from the configuration
+pub static CACHE: Lazy<Cache> = Lazy::new(|| {
+    let memory = || Box::new(MemoryStore::new(CONFIG.cache_max_entries));
+    let store: Box<dyn CacheStore> = match CONFIG.cache_backend.as_str() {
+        "memory" => memory(),
+        "redis" => match RedisStore::new(&CONFIG.redis_url, &CONFIG.redis_key_prefix) {
+            Ok(store) => Box::new(store),
+            Err(e) => {
+                tracing::error!("Invalid Redis URL, falling back to the memory cache: {}", e);
+                memory()
+            }
+        },
+        other => {
+            tracing::warn!("Unknown cache backend '{}', falling back to memory", other);
+            memory()
+        }
+    };
+    tracing::info!("Using {} cache backend", store.backend());
+    Cache::new(store)
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn exercise(store: &dyn CacheStore) {
+        let ttl = Duration::from_secs(60);
+        store.set("test:a", "1".to_string(), ttl).await.unwrap();
+        store.set("test:b", "2".to_string(), ttl).await.unwrap();
+        store.set("other:c", "3".to_string(), ttl).await.unwrap();
+        assert_eq!(store.get("test:a").await.unwrap().as_deref(), Some("1"));
+
+        assert_eq!(store.incr("test:counter", ttl).await.unwrap(), 1);
+        assert_eq!(store.incr("test:counter", ttl).await.unwrap(), 2);
+
+        let mut keys = store.keys("test:").await.unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["test:a", "test:b", "test:counter"]);
+
+        assert_eq!(store.flush_prefix("test:").await.unwrap(), 3);
+        assert_eq!(store.get("test:a").await.unwrap(), None);
+        assert_eq!(store.get("other:c").await.unwrap().as_deref(), Some("3"));
+        store.delete("other:c").await.unwrap();
+        assert_eq!(store.get("other:c").await.unwrap(), None);
+
+        store.set("test:short", "x".to_string(), Duration::from_millis(20)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(store.get("test:short").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        exercise(&MemoryStore::new(100)).await;
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_evicts_least_recently_used() {
+        let store = MemoryStore::new(2);
+        let ttl = Duration::from_secs(60);
+        store.set("a", "1".to_string(), ttl).await.unwrap();
+        store.set("b", "2".to_string(), ttl).await.unwrap();
+        store.get("a").await.unwrap();
+        store.set("c", "3".to_string(), ttl).await.unwrap();
+        assert!(store.get("a").await.unwrap().is_some());
+        assert!(store.get("b").await.unwrap().is_none());
+        assert!(store.get("c").await.unwrap().is_some());
+    }
+
+    /// Runs against a local Redis when `RUSTZEN_TEST_REDIS_URL` is set
+    #[tokio::test]
+    async fn test_redis_store() {
+        let Ok(url) = std::env::var("RUSTZEN_TEST_REDIS_URL") else {
+            return;
+        };
+        let namespace = format!("rustzen-test-{}:", uuid::Uuid::new_v4());
+        exercise(&RedisStore::new(&url, &namespace).unwrap()).await;
+    }
+
+    #[tokio::test]
+    async fn test_cache_counts_hits_per_prefix() {
+        let cache = Cache::new(Box::new(MemoryStore::new(10)));
+        cache.set_json("perm:1", &vec!["a".to_string()], Duration::from_secs(60)).await;
+        assert!(cache.get_json::<Vec<String>>("perm:1").await.is_some());
+        assert!(cache.get_json::<Vec<String>>("perm:2").await.is_none());
+
+        let stats = cache.stats().await.unwrap();
+        assert_eq!(stats.entries.get("perm"), Some(&1));
+        let counters = stats.counters["perm"];
+        assert_eq!((counters.hits, counters.misses), (1, 1));
+    }
+}
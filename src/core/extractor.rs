@@ -1,7 +1,11 @@
-use crate::common::error::{AppError, ServiceError};
+use crate::{
+    common::error::{AppError, ServiceError},
+    core::{jwt::ActorClaims, permission::PermissionSet},
+};
 
 use axum::{extract::FromRequestParts, http::request::Parts};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 /// Current authenticated user info from auth middleware
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,12 +14,41 @@ pub struct CurrentUser {
     pub user_id: i64,
     /// Username
     pub username: String,
+    /// Session ID (token jti), nil for API key requests
+    pub session_id: Uuid,
+    /// API key the request was authenticated with, if any
+    pub api_key: Option<ApiKeyScope>,
+    /// Real user behind the request while `user_id` is impersonated
+    pub actor: Option<ActorClaims>,
+}
+
+/// API key of a request and the permission codes it may use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyScope {
+    pub id: i64,
+    /// Key scopes implied by the owner's current permissions
+    pub scopes: PermissionSet,
 }
 
 impl CurrentUser {
     /// Create new CurrentUser instance
-    pub fn new(user_id: i64, username: String) -> Self {
-        Self { user_id, username }
+    pub fn new(user_id: i64, username: String, session_id: Uuid) -> Self {
+        Self { user_id, username, session_id, api_key: None, actor: None }
+    }
+
+    /// Create a CurrentUser for a request made with an impersonation token
+    pub fn impersonated(
+        user_id: i64,
+        username: String,
+        session_id: Uuid,
+        actor: ActorClaims,
+    ) -> Self {
+        Self { user_id, username, session_id, api_key: None, actor: Some(actor) }
+    }
+
+    /// Create a CurrentUser for a request authenticated with an API key
+    pub fn from_api_key(user_id: i64, username: String, api_key: ApiKeyScope) -> Self {
+        Self { user_id, username, session_id: Uuid::nil(), api_key: Some(api_key), actor: None }
     }
 }
 
@@ -28,17 +61,12 @@ where
 {
     type Rejection = AppError;
 
-    fn from_request_parts(
-        parts: &mut Parts,
-        _state: &S,
-    ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
-        async move {
-            parts.extensions.get::<CurrentUser>().cloned().ok_or_else(|| {
-                tracing::error!(
-                    "CurrentUser not found - auth middleware missing or user not authenticated"
-                );
-                AppError::from(ServiceError::InvalidToken)
-            })
-        }
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<CurrentUser>().cloned().ok_or_else(|| {
+            tracing::error!(
+                "CurrentUser not found - auth middleware missing or user not authenticated"
+            );
+            AppError::from(ServiceError::InvalidToken)
+        })
     }
 }
@@ -1,36 +1,105 @@
 use chrono::{Duration, Utc};
-use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
-use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
+use jsonwebtoken::{TokenData, Validation, decode, decode_header, encode, errors::ErrorKind};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use tracing;
+use uuid::Uuid;
 
-use crate::core::config::CONFIG;
+use crate::core::{
+    config::CONFIG,
+    jwk::{JwtKeys, KeyError},
+};
 
 /// JWT Configuration, loaded from environment variables.
 ///
 /// This struct holds the essential settings for JWT generation and validation,
-/// including the secret key and token expiration duration.
+/// including the signing keys and token expiration duration.
 #[derive(Debug)]
 pub struct JwtConfig {
-    /// The secret key used for signing and verifying tokens.
-    pub secret: String,
+    /// The keys used for signing and verifying tokens.
+    pub keys: JwtKeys,
     /// The duration in seconds for which a token is valid.
     pub expiration: i64,
+    /// The duration in seconds for which a refresh token is valid.
+    pub refresh_expiration: i64,
+    /// The duration in seconds for which an MFA pending token is valid.
+    pub mfa_expiration: i64,
 }
 
-/// The global JWT configuration instance, initialized lazily.
+impl JwtConfig {
+    /// Loads the signing keys: the PEM key files when `JWT_KEY_FILE` is set,
+    /// the `JWT_SECRET` otherwise.
+    fn load() -> Result<Self, KeyError> {
+        let keys = if CONFIG.jwt_key_file.is_empty() {
+            JwtKeys::hmac(&CONFIG.jwt_secret)
+        } else {
+            let signing_pem = read_pem(&CONFIG.jwt_key_file)?;
+            let previous_pems = CONFIG
+                .jwt_previous_key_files
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(read_pem)
+                .collect::<Result<Vec<_>, _>>()?;
+            JwtKeys::from_pem(&signing_pem, &previous_pems)?
+        };
+
+        let config = JwtConfig {
+            keys,
+            expiration: CONFIG.jwt_expiration,
+            refresh_expiration: CONFIG.jwt_refresh_expiration,
+            mfa_expiration: CONFIG.mfa_token_expiration,
+        };
+        tracing::info!("JWT_CONFIG: {:?}", config);
+        Ok(config)
+    }
+}
+
+fn read_pem(path: &str) -> Result<String, KeyError> {
+    std::fs::read_to_string(path).map_err(|e| KeyError(format!("cannot read {}: {}", path, e)))
+}
+
+static JWT_CONFIG_CELL: OnceCell<JwtConfig> = OnceCell::new();
+
+/// Loads the JWT configuration, so that startup fails on unusable key files.
+///
+/// # Errors
 ///
-/// Reads `JWT_SECRET` and `JWT_EXPIRATION` from environment variables.
-/// Provides default values and logs warnings if they are not set, which is
-/// crucial for security and debugging during development.
-pub static JWT_CONFIG: Lazy<JwtConfig> = Lazy::new(|| {
-    let secret = CONFIG.jwt_secret.clone();
-    let expiration = CONFIG.jwt_expiration;
+/// Returns a `KeyError` if a key file cannot be read or parsed.
+pub fn init() -> Result<(), KeyError> {
+    JWT_CONFIG_CELL.get_or_try_init(JwtConfig::load).map(|_| ())
+}
 
-    tracing::info!("JWT_CONFIG: {:?}", JwtConfig { secret: secret.clone(), expiration });
-    JwtConfig { secret, expiration }
+/// The global JWT configuration instance, initialized by [`init`] at startup.
+pub static JWT_CONFIG: Lazy<&'static JwtConfig> = Lazy::new(|| {
+    JWT_CONFIG_CELL.get_or_init(|| JwtConfig::load().expect("Failed to load JWT keys"))
 });
 
+/// Signs claims with the current signing key, setting `typ` when given.
+fn sign<T: Serialize>(
+    claims: &T,
+    typ: Option<&str>,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let mut header = JWT_CONFIG.keys.header();
+    if let Some(typ) = typ {
+        header.typ = Some(typ.to_string());
+    }
+    encode(&header, claims, JWT_CONFIG.keys.encoding_key())
+}
+
+/// Verifies a token with the key named by its header.
+///
+/// The algorithm is taken from the key, never from the token alone.
+fn verify<T: DeserializeOwned>(
+    token: &str,
+    mut validation: Validation,
+) -> Result<TokenData<T>, jsonwebtoken::errors::Error> {
+    let header = decode_header(token)?;
+    let key = JWT_CONFIG.keys.decoding_key(&header).ok_or(ErrorKind::InvalidSignature)?;
+    validation.algorithms = vec![header.alg];
+    decode::<T>(token, key, &validation)
+}
+
 /// Represents the claims in the JWT payload.
 ///
 /// These claims contain the token's subject information and metadata.
@@ -40,6 +109,43 @@ pub struct Claims {
     pub user_id: i64,
     /// The username associated with the token.
     pub username: String,
+    /// JWT ID: the server-side session this token belongs to.
+    pub jti: Uuid,
+    /// Expiration time (as a Unix timestamp).
+    pub exp: usize,
+    /// Issued at time (as a Unix timestamp).
+    pub iat: usize,
+    /// The real user acting as the subject, only set on impersonation tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<ActorClaims>,
+}
+
+/// The actor of an impersonation token (the `act` claim of RFC 8693).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ActorClaims {
+    /// The ID of the user who started the impersonation.
+    pub user_id: i64,
+    /// The username of that user.
+    pub username: String,
+}
+
+/// Audience of MFA pending tokens, keeping them apart from access tokens.
+const MFA_AUDIENCE: &str = "mfa";
+
+/// Represents the claims of an MFA pending token.
+///
+/// Issued after a successful password check for users with MFA enabled, and
+/// only accepted by the MFA verification step of the login.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MfaClaims {
+    /// The ID of the user who passed the password check.
+    pub user_id: i64,
+    /// The username associated with the token.
+    pub username: String,
+    /// JWT ID, recorded when the token completes a login so it cannot be used twice.
+    pub jti: Uuid,
+    /// Audience, always `mfa`.
+    pub aud: String,
     /// Expiration time (as a Unix timestamp).
     pub exp: usize,
     /// Issued at time (as a Unix timestamp).
@@ -52,20 +158,76 @@ pub struct Claims {
 ///
 /// * `user_id` - The ID of the user for whom the token is generated.
 /// * `username` - The username of the user.
+/// * `session_id` - The session the token is issued for, embedded as `jti`.
+///
+/// # Errors
+///
+/// Returns a `jsonwebtoken::errors::Error` if token generation fails.
+pub fn generate_token(
+    user_id: i64,
+    username: &str,
+    session_id: Uuid,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    generate_token_with_expiration(user_id, username, session_id, JWT_CONFIG.expiration)
+}
+
+/// Generates a new JWT valid for `expiration` seconds instead of the configured default.
 ///
 /// # Errors
 ///
 /// Returns a `jsonwebtoken::errors::Error` if token generation fails.
-pub fn generate_token(user_id: i64, username: &str) -> Result<String, jsonwebtoken::errors::Error> {
+pub fn generate_token_with_expiration(
+    user_id: i64,
+    username: &str,
+    session_id: Uuid,
+    expiration: i64,
+) -> Result<String, jsonwebtoken::errors::Error> {
     let now = Utc::now();
-    let exp = (now + Duration::seconds(JWT_CONFIG.expiration)).timestamp() as usize;
+    let exp = (now + Duration::seconds(expiration)).timestamp() as usize;
     let iat = now.timestamp() as usize;
 
-    let claims = Claims { user_id, username: username.to_string(), exp, iat };
+    let claims =
+        Claims { user_id, username: username.to_string(), jti: session_id, exp, iat, act: None };
 
     tracing::debug!("Generating token for user '{}' (ID: {})", username, user_id);
 
-    encode(&Header::default(), &claims, &EncodingKey::from_secret(JWT_CONFIG.secret.as_bytes()))
+    sign(&claims, None)
+}
+
+/// Generates a JWT that lets `actor` act as the given user for `expiration` seconds.
+///
+/// # Errors
+///
+/// Returns a `jsonwebtoken::errors::Error` if token generation fails.
+pub fn generate_impersonation_token(
+    user_id: i64,
+    username: &str,
+    session_id: Uuid,
+    actor: ActorClaims,
+    expiration: i64,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let exp = (now + Duration::seconds(expiration)).timestamp() as usize;
+    let iat = now.timestamp() as usize;
+
+    tracing::debug!(
+        "Generating impersonation token for user '{}' (ID: {}) acted by '{}' (ID: {})",
+        username,
+        user_id,
+        actor.username,
+        actor.user_id
+    );
+
+    let claims = Claims {
+        user_id,
+        username: username.to_string(),
+        jti: session_id,
+        exp,
+        iat,
+        act: Some(actor),
+    };
+
+    sign(&claims, None)
 }
 
 /// Verifies a JWT and returns the claims if valid.
@@ -79,13 +241,160 @@ pub fn generate_token(user_id: i64, username: &str) -> Result<String, jsonwebtok
 /// Returns a `jsonwebtoken::errors::Error` if the token is invalid, expired,
 /// or if verification otherwise fails.
 pub fn verify_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let validation = Validation::new(Algorithm::HS256);
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(JWT_CONFIG.secret.as_bytes()),
-        &validation,
-    )?;
+    let validation = Validation::default();
+    let token_data = verify::<Claims>(token, validation)?;
 
     tracing::trace!("Successfully verified token for user '{}'", token_data.claims.username);
     Ok(token_data.claims)
 }
+
+/// Generates a short-lived MFA pending token for a given user.
+///
+/// # Errors
+///
+/// Returns a `jsonwebtoken::errors::Error` if token generation fails.
+pub fn generate_mfa_token(
+    user_id: i64,
+    username: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let exp = (now + Duration::seconds(JWT_CONFIG.mfa_expiration)).timestamp() as usize;
+    let iat = now.timestamp() as usize;
+
+    let claims = MfaClaims {
+        user_id,
+        username: username.to_string(),
+        jti: Uuid::new_v4(),
+        aud: MFA_AUDIENCE.to_string(),
+        exp,
+        iat,
+    };
+
+    tracing::debug!("Generating MFA token for user '{}' (ID: {})", username, user_id);
+
+    sign(&claims, None)
+}
+
+/// Verifies an MFA pending token and returns its claims if valid.
+///
+/// # Errors
+///
+/// Returns a `jsonwebtoken::errors::Error` if the token is invalid, expired,
+/// or not an MFA pending token.
+pub fn verify_mfa_token(token: &str) -> Result<MfaClaims, jsonwebtoken::errors::Error> {
+    let mut validation = Validation::default();
+    validation.set_audience(&[MFA_AUDIENCE]);
+    let token_data = verify::<MfaClaims>(token, validation)?;
+
+    tracing::trace!("Successfully verified MFA token for user '{}'", token_data.claims.username);
+    Ok(token_data.claims)
+}
+
+/// Header type of OAuth access tokens (RFC 9068), keeping them apart from ID tokens.
+const OAUTH_ACCESS_TOKEN_TYPE: &str = "at+jwt";
+
+/// Represents the claims of an OAuth access token issued to a client application.
+///
+/// Carries the subject's permission codes so that resource servers can apply
+/// rustzen's RBAC. Not accepted by rustzen's own API, which only takes
+/// session tokens ([`Claims`]).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OAuthAccessClaims {
+    /// Issuer URL of the authorization server.
+    pub iss: String,
+    /// The subject: the user ID, as a string.
+    pub sub: String,
+    /// Audience: the client ID the token was issued to.
+    pub aud: String,
+    /// The client ID the token was issued to.
+    pub client_id: String,
+    /// The username of the subject.
+    pub username: String,
+    /// Granted scopes, space separated.
+    pub scope: String,
+    /// Permission codes of the subject when the token was issued.
+    pub permissions: Vec<String>,
+    /// JWT ID, the key of the issued token record.
+    pub jti: Uuid,
+    /// Expiration time (as a Unix timestamp).
+    pub exp: usize,
+    /// Issued at time (as a Unix timestamp).
+    pub iat: usize,
+}
+
+/// Represents the claims of an OpenID Connect ID token.
+///
+/// Profile and email claims are only set when the matching scope was granted.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IdTokenClaims {
+    /// Issuer URL of the authorization server.
+    pub iss: String,
+    /// The subject: the user ID, as a string.
+    pub sub: String,
+    /// Audience: the client ID.
+    pub aud: String,
+    /// Expiration time (as a Unix timestamp).
+    pub exp: usize,
+    /// Issued at time (as a Unix timestamp).
+    pub iat: usize,
+    /// Nonce of the authorization request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+}
+
+/// Generates an OAuth access token.
+///
+/// # Errors
+///
+/// Returns a `jsonwebtoken::errors::Error` if token generation fails.
+pub fn generate_oauth_access_token(
+    claims: &OAuthAccessClaims,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    tracing::debug!("Generating OAuth access token for client '{}'", claims.client_id);
+
+    sign(claims, Some(OAUTH_ACCESS_TOKEN_TYPE))
+}
+
+/// Verifies an OAuth access token of any client and returns its claims if valid.
+///
+/// # Errors
+///
+/// Returns a `jsonwebtoken::errors::Error` if the token is invalid, expired,
+/// from another issuer, or not an access token.
+pub fn verify_oauth_access_token(
+    token: &str,
+    issuer: &str,
+) -> Result<OAuthAccessClaims, jsonwebtoken::errors::Error> {
+    if decode_header(token)?.typ.as_deref() != Some(OAUTH_ACCESS_TOKEN_TYPE) {
+        return Err(ErrorKind::InvalidToken.into());
+    }
+
+    let mut validation = Validation::default();
+    validation.set_issuer(&[issuer]);
+    validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+    // Resource servers introspect the tokens of every client
+    validation.validate_aud = false;
+    let token_data = verify::<OAuthAccessClaims>(token, validation)?;
+
+    tracing::trace!("Successfully verified OAuth access token '{}'", token_data.claims.jti);
+    Ok(token_data.claims)
+}
+
+/// Generates an OpenID Connect ID token.
+///
+/// # Errors
+///
+/// Returns a `jsonwebtoken::errors::Error` if token generation fails.
+pub fn generate_id_token(claims: &IdTokenClaims) -> Result<String, jsonwebtoken::errors::Error> {
+    tracing::debug!("Generating ID token for subject '{}'", claims.sub);
+
+    sign(claims, None)
+}
@@ -0,0 +1,371 @@
+use crate::core::config::CONFIG;
+
+use ldap3::{Ldap, LdapConnAsync, LdapConnSettings, LdapError, Scope, SearchEntry, ldap_escape};
+use once_cell::sync::Lazy;
+use std::time::Duration;
+
+/// Timeout of the connection and of each directory operation, in seconds
+const LDAP_TIMEOUT_SECS: u64 = 5;
+
+/// LDAP result code of a bind with a wrong password
+const INVALID_CREDENTIALS: u32 = 49;
+
+/// Directory entry of an authenticated user
+#[derive(Debug, Clone)]
+pub struct LdapUser {
+    pub dn: String,
+    pub email: Option<String>,
+    pub real_name: Option<String>,
+    /// Group names: the CN of each group DN
+    pub groups: Vec<String>,
+}
+
+/// LDAP / Active Directory authentication by search and bind.
+///
+/// The user entry is found with the search DN (or anonymously), then the
+/// password is checked by binding as that entry. A fresh connection is used
+/// for every login, so no directory session outlives a request.
+pub struct LdapClient {
+    url: String,
+    starttls: bool,
+    bind_dn: String,
+    bind_password: String,
+    base_dn: String,
+    user_filter: String,
+    email_attribute: String,
+    name_attribute: String,
+    group_attribute: String,
+}
+
+impl LdapClient {
+    pub fn from_config() -> Self {
+        Self {
+            url: CONFIG.ldap_url.clone(),
+            starttls: CONFIG.ldap_starttls,
+            bind_dn: CONFIG.ldap_bind_dn.clone(),
+            bind_password: CONFIG.ldap_bind_password.clone(),
+            base_dn: CONFIG.ldap_base_dn.clone(),
+            user_filter: CONFIG.ldap_user_filter.clone(),
+            email_attribute: CONFIG.ldap_email_attribute.clone(),
+            name_attribute: CONFIG.ldap_name_attribute.clone(),
+            group_attribute: CONFIG.ldap_group_attribute.clone(),
+        }
+    }
+
+    /// Server URL, identifies the directory in linked user identities
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Checks a username and password against the directory.
+    ///
+    /// Returns `None` for unknown or ambiguous usernames and wrong passwords;
+    /// errors are reserved for an unreachable or misbehaving server.
+    pub async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<LdapUser>, LdapError> {
+        // An empty password would be an unauthenticated bind, which servers accept
+        if username.is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+
+        let mut ldap = self.connect().await?;
+        let result = self.search_and_bind(&mut ldap, username, password).await;
+        let _ = ldap.unbind().await;
+        result
+    }
+
+    async fn connect(&self) -> Result<Ldap, LdapError> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(Duration::from_secs(LDAP_TIMEOUT_SECS))
+            .set_starttls(self.starttls);
+        let (conn, ldap) = LdapConnAsync::with_settings(settings, &self.url).await?;
+        ldap3::drive!(conn);
+        Ok(ldap)
+    }
+
+    async fn search_and_bind(
+        &self,
+        ldap: &mut Ldap,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<LdapUser>, LdapError> {
+        let timeout = Duration::from_secs(LDAP_TIMEOUT_SECS);
+
+        // 1. find the user entry
+        ldap.with_timeout(timeout)
+            .simple_bind(&self.bind_dn, &self.bind_password)
+            .await?
+            .success()?;
+        let filter = self.user_filter.replace("{username}", &ldap_escape(username));
+        let attributes = [&self.email_attribute, &self.name_attribute, &self.group_attribute]
+            .map(String::as_str);
+        let (mut entries, _) = ldap
+            .with_timeout(timeout)
+            .search(&self.base_dn, Scope::Subtree, &filter, attributes.to_vec())
+            .await?
+            .success()?;
+        if entries.len() != 1 {
+            tracing::warn!("LDAP search for '{}' returned {} entries", username, entries.len());
+            return Ok(None);
+        }
+        let entry = SearchEntry::construct(entries.remove(0));
+
+        // 2. check the password by binding as the user
+        let bind = ldap.with_timeout(timeout).simple_bind(&entry.dn, password).await?;
+        if bind.rc == INVALID_CREDENTIALS {
+            return Ok(None);
+        }
+        bind.success()?;
+
+        let attribute = |name: &str| {
+            entry
+                .attrs
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, values)| values.clone())
+                .unwrap_or_default()
+        };
+        Ok(Some(LdapUser {
+            email: attribute(&self.email_attribute).into_iter().next(),
+            real_name: attribute(&self.name_attribute).into_iter().next(),
+            groups: attribute(&self.group_attribute).iter().map(|dn| group_name(dn)).collect(),
+            dn: entry.dn,
+        }))
+    }
+}
+
+/// Global LDAP client from the configuration
+pub static LDAP_CLIENT: Lazy<LdapClient> = Lazy::new(LdapClient::from_config);
+
+/// Name of a group: the value of the first RDN of its DN when that is a CN,
+/// the DN itself otherwise.
+pub fn group_name(dn: &str) -> String {
+    dn.split(',')
+        .next()
+        .and_then(|rdn| rdn.split_once('='))
+        .filter(|(attribute, _)| attribute.trim().eq_ignore_ascii_case("cn"))
+        .map(|(_, value)| value.trim().to_string())
+        .unwrap_or_else(|| dn.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ldap3::asn1::{PL, StructureTag, TagClass, parse_tag};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    const SEARCH_DN: &str = "cn=search,dc=example,dc=com";
+    const SEARCH_PASSWORD: &str = "search-secret";
+
+    /// Directory entry of the stand-in server: DN, uid, password, attributes
+    type Entry = (&'static str, &'static str, &'static str, Vec<(&'static str, Vec<&'static str>)>);
+
+    fn directory() -> Vec<Entry> {
+        vec![(
+            "uid=alice,ou=people,dc=example,dc=com",
+            "alice",
+            "alice-secret",
+            vec![
+                ("mail", vec!["alice@example.com"]),
+                ("cn", vec!["Alice Liddell"]),
+                (
+                    "memberOf",
+                    vec!["cn=admins,ou=groups,dc=example,dc=com", "CN=ops,OU=groups,DC=example"],
+                ),
+            ],
+        )]
+    }
+
+    fn tag(class: TagClass, id: u64, payload: PL) -> StructureTag {
+        StructureTag { class, id, payload }
+    }
+
+    fn octets(value: &str) -> StructureTag {
+        tag(TagClass::Universal, 4, PL::P(value.as_bytes().to_vec()))
+    }
+
+    /// LDAPResult of an operation with the given result code
+    fn ldap_result(op: u64, code: u8) -> StructureTag {
+        tag(
+            TagClass::Application,
+            op,
+            PL::C(vec![tag(TagClass::Universal, 10, PL::P(vec![code])), octets(""), octets("")]),
+        )
+    }
+
+    /// BER encoding (tag numbers below 31 only)
+    fn encode(tag: &StructureTag, out: &mut Vec<u8>) {
+        let class = match tag.class {
+            TagClass::Universal => 0x00,
+            TagClass::Application => 0x40,
+            TagClass::Context => 0x80,
+            TagClass::Private => 0xc0,
+        };
+        let (constructed, content) = match &tag.payload {
+            PL::P(bytes) => (0x00, bytes.clone()),
+            PL::C(children) => {
+                let mut content = Vec::new();
+                children.iter().for_each(|child| encode(child, &mut content));
+                (0x20, content)
+            }
+        };
+        out.push(class | constructed | tag.id as u8);
+        if content.len() < 0x80 {
+            out.push(content.len() as u8);
+        } else {
+            let len = (content.len() as u32).to_be_bytes();
+            let skip = len.iter().take_while(|byte| **byte == 0).count();
+            out.push(0x80 | (4 - skip) as u8);
+            out.extend_from_slice(&len[skip..]);
+        }
+        out.extend(content);
+    }
+
+    fn text(tag: &StructureTag) -> String {
+        tag.clone()
+            .expect_primitive()
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Answers the bind, search and unbind requests of one connection
+    async fn serve(mut stream: TcpStream) {
+        let mut buffer = Vec::new();
+        let mut search_bound = false;
+        loop {
+            let (message, consumed) = loop {
+                if let Ok((rest, message)) = parse_tag(&buffer) {
+                    break (message, buffer.len() - rest.len());
+                }
+                let mut chunk = [0u8; 4096];
+                match stream.read(&mut chunk).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                }
+            };
+            buffer.drain(..consumed);
+
+            let parts = message.expect_constructed().unwrap();
+            let (message_id, op) = (parts[0].clone(), parts[1].clone());
+            let mut replies = Vec::new();
+            match op.id {
+                0 => {
+                    let bind = op.expect_constructed().unwrap();
+                    let (name, password) = (text(&bind[1]), text(&bind[2]));
+                    search_bound = name == SEARCH_DN && password == SEARCH_PASSWORD;
+                    let user_bound = directory()
+                        .iter()
+                        .any(|(dn, _, secret, _)| *dn == name && *secret == password);
+                    replies.push(ldap_result(1, if search_bound || user_bound { 0 } else { 49 }));
+                }
+                3 => {
+                    let search = op.expect_constructed().unwrap();
+                    let filter = search[6].clone().expect_constructed().unwrap();
+                    let (attribute, value) = (text(&filter[0]), text(&filter[1]));
+                    if !search_bound {
+                        replies.push(ldap_result(5, 50));
+                    } else {
+                        for (dn, uid, _, attributes) in directory() {
+                            if attribute == "uid" && value == uid {
+                                let attributes = attributes
+                                    .iter()
+                                    .map(|(name, values)| {
+                                        let values = values.iter().map(|v| octets(v)).collect();
+                                        tag(
+                                            TagClass::Universal,
+                                            16,
+                                            PL::C(vec![
+                                                octets(name),
+                                                tag(TagClass::Universal, 17, PL::C(values)),
+                                            ]),
+                                        )
+                                    })
+                                    .collect();
+                                replies.push(tag(
+                                    TagClass::Application,
+                                    4,
+                                    PL::C(vec![
+                                        octets(dn),
+                                        tag(TagClass::Universal, 16, PL::C(attributes)),
+                                    ]),
+                                ));
+                            }
+                        }
+                        replies.push(ldap_result(5, 0));
+                    }
+                }
+                _ => return,
+            }
+
+            let mut out = Vec::new();
+            for reply in replies {
+                let envelope = tag(TagClass::Universal, 16, PL::C(vec![message_id.clone(), reply]));
+                encode(&envelope, &mut out);
+            }
+            if stream.write_all(&out).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Starts the stand-in directory server and returns its URL
+    async fn mock_directory() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ldap://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream));
+            }
+        });
+        url
+    }
+
+    fn client(url: &str) -> LdapClient {
+        LdapClient {
+            url: url.to_string(),
+            starttls: false,
+            bind_dn: SEARCH_DN.to_string(),
+            bind_password: SEARCH_PASSWORD.to_string(),
+            base_dn: "dc=example,dc=com".to_string(),
+            user_filter: "(uid={username})".to_string(),
+            email_attribute: "mail".to_string(),
+            name_attribute: "cn".to_string(),
+            group_attribute: "memberof".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_group_name() {
+        assert_eq!(group_name("cn=admins,ou=groups,dc=example,dc=com"), "admins");
+        assert_eq!(group_name("CN=Domain Admins,CN=Users,DC=corp"), "Domain Admins");
+        assert_eq!(group_name("ou=ops,dc=example"), "ou=ops,dc=example");
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_against_mock_directory() {
+        let client = client(&mock_directory().await);
+
+        let user = client.authenticate("alice", "alice-secret").await.unwrap().unwrap();
+        assert_eq!(user.dn, "uid=alice,ou=people,dc=example,dc=com");
+        assert_eq!(user.email.as_deref(), Some("alice@example.com"));
+        assert_eq!(user.real_name.as_deref(), Some("Alice Liddell"));
+        assert_eq!(user.groups, vec!["admins".to_string(), "ops".to_string()]);
+
+        // Wrong password, unknown user, filter injection and empty password
+        assert!(client.authenticate("alice", "wrong").await.unwrap().is_none());
+        assert!(client.authenticate("bob", "alice-secret").await.unwrap().is_none());
+        assert!(client.authenticate("*", "alice-secret").await.unwrap().is_none());
+        assert!(client.authenticate("alice", "").await.unwrap().is_none());
+
+        // A misconfigured search DN is an error, not a failed login
+        let mut misconfigured = client;
+        misconfigured.bind_password = "wrong".to_string();
+        assert!(misconfigured.authenticate("alice", "alice-secret").await.is_err());
+    }
+}
@@ -0,0 +1,414 @@
+use crate::core::config::CONFIG;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use data_encoding::BASE64;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    TlsConnector,
+    rustls::{ClientConfig, RootCertStore, pki_types::ServerName},
+};
+use uuid::Uuid;
+
+/// Errors raised while delivering a mail
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("TLS error: {0}")]
+    Tls(String),
+    #[error("SMTP server replied {0}: {1}")]
+    Smtp(u16, String),
+    #[error("Invalid mail configuration: {0}")]
+    Config(String),
+    #[error("Line break in the {0} of a mail")]
+    LineBreak(&'static str),
+}
+
+/// A plain-text mail
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    /// Rejects a recipient or subject that would break out of its header or
+    /// SMTP command, e.g. `a@b.com\r\nBcc: eve@example.com`
+    fn check_headers(&self, from: &str) -> Result<(), MailError> {
+        let has_line_break = |value: &str| value.contains(['\r', '\n']);
+        if has_line_break(&self.to) {
+            return Err(MailError::LineBreak("recipient"));
+        }
+        if has_line_break(&self.subject) {
+            return Err(MailError::LineBreak("subject"));
+        }
+        if has_line_break(from) {
+            return Err(MailError::LineBreak("sender"));
+        }
+        Ok(())
+    }
+
+    /// Formats the mail as an RFC 5322 message with CRLF line endings
+    pub fn to_message(&self, from: &str) -> String {
+        let domain = address_of(from).rsplit('@').next().unwrap_or("localhost").to_string();
+        let body = self.body.replace("\r\n", "\n").replace('\n', "\r\n");
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\n\
+             MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Transfer-Encoding: 8bit\r\n\r\n{}\r\n",
+            from,
+            self.to,
+            encode_header(&self.subject),
+            Utc::now().to_rfc2822(),
+            Uuid::new_v4(),
+            domain,
+            body
+        )
+    }
+}
+
+/// Mail transport used to deliver account mails (password reset, email verification)
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError>;
+}
+
+/// Mail transport from the configuration
+pub static MAILER: Lazy<Box<dyn Mailer>> = Lazy::new(|| match CONFIG.mail_transport.as_str() {
+    "smtp" => Box::new(SmtpMailer::from_config()),
+    "file" => Box::new(FileMailer::new(Some(CONFIG.mail_file_path.clone()), &CONFIG.mail_from)),
+    "stdout" => Box::new(FileMailer::new(None, &CONFIG.mail_from)),
+    other => {
+        tracing::warn!("Unknown mail transport '{}', falling back to stdout", other);
+        Box::new(FileMailer::new(None, &CONFIG.mail_from))
+    }
+});
+
+/// Development transport: appends formatted mails to a file, or prints them to stdout
+pub struct FileMailer {
+    path: Option<String>,
+    from: String,
+}
+
+impl FileMailer {
+    pub fn new(path: Option<String>, from: &str) -> Self {
+        Self { path, from: from.to_string() }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError> {
+        mail.check_headers(&self.from)?;
+        let entry = format!("{}\r\n{}\r\n", mail.to_message(&self.from), "=".repeat(72));
+        match &self.path {
+            Some(path) => {
+                let mut file =
+                    tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+                file.write_all(entry.as_bytes()).await?;
+            }
+            None => {
+                let mut stdout = tokio::io::stdout();
+                stdout.write_all(entry.as_bytes()).await?;
+                stdout.flush().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// SMTP encryption mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Plain text, for local relays such as MailHog
+    None,
+    /// Upgrade a plain connection with STARTTLS (usually port 587)
+    StartTls,
+    /// TLS from the first byte (usually port 465)
+    Tls,
+}
+
+/// SMTP transport with optional TLS and AUTH PLAIN
+pub struct SmtpMailer {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub tls: SmtpTls,
+    pub from: String,
+}
+
+/// Byte stream an SMTP conversation runs on (plain or TLS)
+trait SmtpStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> SmtpStream for T {}
+
+impl SmtpMailer {
+    pub fn from_config() -> Self {
+        let tls = match CONFIG.smtp_tls.as_str() {
+            "starttls" => SmtpTls::StartTls,
+            "tls" => SmtpTls::Tls,
+            _ => SmtpTls::None,
+        };
+        Self {
+            host: CONFIG.smtp_host.clone(),
+            port: CONFIG.smtp_port,
+            username: CONFIG.smtp_username.clone(),
+            password: CONFIG.smtp_password.clone(),
+            tls,
+            from: CONFIG.mail_from.clone(),
+        }
+    }
+
+    /// Wraps a connection in TLS, verifying the server against the webpki roots
+    async fn connect_tls(
+        &self,
+        stream: Box<dyn SmtpStream>,
+    ) -> Result<Box<dyn SmtpStream>, MailError> {
+        let roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+        let config = ClientConfig::builder_with_provider(Arc::new(
+            tokio_rustls::rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| MailError::Tls(e.to_string()))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let server_name = ServerName::try_from(self.host.clone())
+            .map_err(|e| MailError::Config(format!("invalid SMTP host: {}", e)))?;
+
+        let stream = TlsConnector::from(Arc::new(config)).connect(server_name, stream).await?;
+        Ok(Box::new(stream))
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError> {
+        mail.check_headers(&self.from)?;
+        let tcp: Box<dyn SmtpStream> =
+            Box::new(TcpStream::connect((self.host.as_str(), self.port)).await?);
+        let stream = match self.tls {
+            SmtpTls::Tls => self.connect_tls(tcp).await?,
+            _ => tcp,
+        };
+
+        let from = address_of(&self.from);
+        let helo = format!("EHLO {}", from.rsplit('@').next().unwrap_or("localhost"));
+        let mut conn = SmtpConnection::new(stream);
+        conn.expect(220).await?;
+        conn.command(&helo, 250).await?;
+
+        if self.tls == SmtpTls::StartTls {
+            conn.command("STARTTLS", 220).await?;
+            conn = SmtpConnection::new(self.connect_tls(conn.into_inner()).await?);
+            conn.command(&helo, 250).await?;
+        }
+
+        if !self.username.is_empty() {
+            let credentials = format!("\0{}\0{}", self.username, self.password);
+            conn.command(&format!("AUTH PLAIN {}", BASE64.encode(credentials.as_bytes())), 235)
+                .await?;
+        }
+
+        conn.command(&format!("MAIL FROM:<{}>", from), 250).await?;
+        conn.command(&format!("RCPT TO:<{}>", address_of(&mail.to)), 250).await?;
+        conn.command("DATA", 354).await?;
+        conn.command(&format!("{}.", dot_stuff(&mail.to_message(&self.from))), 250).await?;
+        conn.command("QUIT", 221).await?;
+        Ok(())
+    }
+}
+
+/// Line-oriented SMTP client connection
+struct SmtpConnection {
+    stream: BufReader<Box<dyn SmtpStream>>,
+}
+
+impl SmtpConnection {
+    fn new(stream: Box<dyn SmtpStream>) -> Self {
+        Self { stream: BufReader::new(stream) }
+    }
+
+    fn into_inner(self) -> Box<dyn SmtpStream> {
+        self.stream.into_inner()
+    }
+
+    /// Sends a command line and checks the reply code
+    async fn command(&mut self, line: &str, expected: u16) -> Result<(), MailError> {
+        let stream = self.stream.get_mut();
+        stream.write_all(line.as_bytes()).await?;
+        stream.write_all(b"\r\n").await?;
+        stream.flush().await?;
+        self.expect(expected).await
+    }
+
+    /// Reads a (possibly multi-line) reply and checks its code
+    async fn expect(&mut self, expected: u16) -> Result<(), MailError> {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err(MailError::Smtp(0, "connection closed".to_string()));
+            }
+            reply.push_str(line.trim_end());
+            // The last line of a reply has a space (or nothing) after the code
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+            reply.push(' ');
+        }
+
+        let code = reply.get(..3).and_then(|code| code.parse().ok()).unwrap_or(0);
+        if code != expected {
+            return Err(MailError::Smtp(code, reply));
+        }
+        Ok(())
+    }
+}
+
+/// Email address of a mailbox such as `Name <user@example.com>`
+fn address_of(mailbox: &str) -> &str {
+    match (mailbox.find('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox.trim(),
+    }
+}
+
+/// RFC 2047 encodes a header value when it is not plain ASCII
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64.encode(value.as_bytes()))
+    }
+}
+
+/// Escapes lines starting with a dot, as required inside SMTP DATA
+fn dot_stuff(message: &str) -> String {
+    message
+        .split("\r\n")
+        .map(|line| if line.starts_with('.') { format!(".{}", line) } else { line.to_string() })
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_message_format() {
+        let mail = Mail {
+            to: "bob@example.com".to_string(),
+            subject: "Grüße".to_string(),
+            body: "line 1\n.line 2".to_string(),
+        };
+        let message = mail.to_message("rustzen <no-reply@example.com>");
+
+        assert!(
+            message.starts_with("From: rustzen <no-reply@example.com>\r\nTo: bob@example.com\r\n")
+        );
+        assert!(message.contains("Subject: =?UTF-8?B?R3LDvMOfZQ==?=\r\n"));
+        assert!(message.contains("@example.com>\r\n"));
+        assert!(message.ends_with("\r\n\r\nline 1\r\n.line 2\r\n"));
+        assert!(dot_stuff(&message).ends_with("\r\nline 1\r\n..line 2\r\n"));
+        assert_eq!(address_of("rustzen <no-reply@example.com>"), "no-reply@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_line_breaks() {
+        let mailer = FileMailer::new(Some("/nonexistent/mails.log".to_string()), "no-reply@x.io");
+        let injected = [
+            ("a@b.com\r\nBcc: eve@example.com", "Hello"),
+            ("a@b.com>\r\nRCPT TO:<eve@example.com", "Hello"),
+            ("a@b.com", "Hello\nBcc: eve@example.com"),
+        ];
+        for (to, subject) in injected {
+            let mail =
+                Mail { to: to.to_string(), subject: subject.to_string(), body: String::new() };
+            assert!(matches!(mailer.send(&mail).await, Err(MailError::LineBreak(_))), "{:?}", to);
+        }
+
+        // Nothing is sent to the SMTP server either
+        let mailer = SmtpMailer {
+            host: "127.0.0.1".to_string(),
+            port: 9,
+            username: String::new(),
+            password: String::new(),
+            tls: SmtpTls::None,
+            from: "no-reply@x.io".to_string(),
+        };
+        let mail = Mail {
+            to: injected[1].0.to_string(),
+            subject: "Hello".to_string(),
+            body: String::new(),
+        };
+        assert!(matches!(mailer.send(&mail).await, Err(MailError::LineBreak("recipient"))));
+    }
+
+    #[tokio::test]
+    async fn test_smtp_conversation() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // Minimal SMTP server recording the client's commands
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = BufReader::new(socket);
+            let mut lines = Vec::new();
+            socket.get_mut().write_all(b"220 test ESMTP\r\n").await.unwrap();
+            loop {
+                let mut line = String::new();
+                if socket.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                let reply: &[u8] = match line.as_str() {
+                    l if l.starts_with("EHLO") => b"250-test\r\n250 AUTH PLAIN\r\n",
+                    l if l.starts_with("AUTH PLAIN") => b"235 ok\r\n",
+                    "DATA" => b"354 go ahead\r\n",
+                    "." => b"250 queued\r\n",
+                    "QUIT" => b"221 bye\r\n",
+                    l if l.starts_with("MAIL") || l.starts_with("RCPT") => b"250 ok\r\n",
+                    _ => b"",
+                };
+                lines.push(line);
+                socket.get_mut().write_all(reply).await.unwrap();
+            }
+            lines
+        });
+
+        let mailer = SmtpMailer {
+            host: "127.0.0.1".to_string(),
+            port,
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            tls: SmtpTls::None,
+            from: "rustzen <no-reply@example.com>".to_string(),
+        };
+        let mail = Mail {
+            to: "bob@example.com".to_string(),
+            subject: "Hello".to_string(),
+            body: ".hidden".to_string(),
+        };
+        mailer.send(&mail).await.unwrap();
+
+        let lines = server.await.unwrap();
+        assert_eq!(lines[0], "EHLO example.com");
+        assert_eq!(lines[1], format!("AUTH PLAIN {}", BASE64.encode(b"\0user\0secret")));
+        assert_eq!(lines[2], "MAIL FROM:<no-reply@example.com>");
+        assert_eq!(lines[3], "RCPT TO:<bob@example.com>");
+        assert_eq!(lines[4], "DATA");
+        assert!(lines.contains(&"..hidden".to_string()));
+        assert_eq!(lines[lines.len() - 2], ".");
+        assert_eq!(lines[lines.len() - 1], "QUIT");
+    }
+}
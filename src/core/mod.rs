@@ -1,9 +1,20 @@
 pub mod app;
+pub mod cache;
 pub mod config;
 pub mod db;
 pub mod extractor;
+pub mod jwk;
 pub mod jwt;
+pub mod ldap;
+pub mod mailer;
+pub mod oidc;
 pub mod password;
 pub mod permission;
+pub mod role_mapping;
+pub mod scim;
+pub mod throttle;
+pub mod token;
+pub mod totp;
+pub mod webauthn;
 
 // pub mod web_embed;
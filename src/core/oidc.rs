@@ -0,0 +1,433 @@
+use crate::core::config::CONFIG;
+
+use chrono::{DateTime, Duration, Utc};
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header, jwk::JwkSet};
+use once_cell::sync::Lazy;
+use rand::{RngCore, rngs::OsRng};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Number of random bytes in a PKCE code verifier (43 base64url characters)
+const VERIFIER_BYTES: usize = 32;
+/// Lifetime of a pending login (authorization request) in seconds
+const PENDING_LOGIN_EXPIRE_SECS: i64 = 600;
+/// How long discovery metadata and signing keys are cached, in seconds
+const PROVIDER_CACHE_SECS: i64 = 3600;
+/// ID token signature algorithms accepted from the provider (never `none` or HMAC)
+const ALLOWED_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+/// Reasons an OpenID Connect login is rejected
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("identity provider request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("identity provider returned {0}")]
+    Provider(String),
+    #[error("discovery issuer {0} does not match the configured issuer")]
+    Issuer(String),
+    #[error("invalid ID token: {0}")]
+    IdToken(String),
+}
+
+/// Provider endpoints from the discovery document
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// Validated ID token claims
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    /// Subject, the user's stable ID at the provider
+    pub sub: String,
+    pub nonce: Option<String>,
+    /// All other claims, looked up by the configured claim names
+    #[serde(flatten)]
+    pub claims: Map<String, Value>,
+}
+
+impl IdTokenClaims {
+    /// A string claim
+    pub fn string(&self, claim: &str) -> Option<&str> {
+        self.claims.get(claim).and_then(Value::as_str).filter(|value| !value.is_empty())
+    }
+
+    /// A string claim as a one-element list, or the strings of an array claim
+    pub fn strings(&self, claim: &str) -> Vec<String> {
+        match self.claims.get(claim) {
+            Some(Value::String(value)) => vec![value.clone()],
+            Some(Value::Array(values)) => {
+                values.iter().filter_map(Value::as_str).map(str::to_string).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether the provider verified the email (some providers send it as a string)
+    pub fn email_verified(&self) -> bool {
+        match self.claims.get("email_verified") {
+            Some(Value::Bool(verified)) => *verified,
+            Some(Value::String(verified)) => verified == "true",
+            _ => false,
+        }
+    }
+}
+
+/// Token endpoint response, only the ID token is used
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+/// Cached discovery metadata and signing keys
+#[derive(Debug, Clone)]
+struct ProviderCache {
+    metadata: ProviderMetadata,
+    jwks: JwkSet,
+    fetched_at: DateTime<Utc>,
+}
+
+/// OpenID Connect relying party using the authorization code flow with PKCE.
+///
+/// The provider is configured by its issuer URL only; endpoints and signing
+/// keys come from the discovery document and are cached, with the keys
+/// refetched when an ID token is signed by an unknown key (key rotation).
+pub struct OidcClient {
+    issuer_url: String,
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+    scopes: String,
+    http: reqwest::Client,
+    cache: RwLock<Option<ProviderCache>>,
+}
+
+impl OidcClient {
+    pub fn new(
+        issuer_url: &str,
+        client_id: &str,
+        client_secret: &str,
+        redirect_uri: &str,
+        scopes: &str,
+    ) -> Self {
+        Self {
+            issuer_url: issuer_url.trim_end_matches('/').to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            scopes: scopes.to_string(),
+            http: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .expect("HTTP client configuration is valid"),
+            cache: RwLock::new(None),
+        }
+    }
+
+    fn from_config() -> Self {
+        Self::new(
+            &CONFIG.oidc_issuer_url,
+            &CONFIG.oidc_client_id,
+            &CONFIG.oidc_client_secret,
+            &CONFIG.oidc_redirect_uri,
+            &CONFIG.oidc_scopes,
+        )
+    }
+
+    /// Issuer URL the provider is identified by
+    pub fn issuer(&self) -> &str {
+        &self.issuer_url
+    }
+
+    /// Builds the URL the browser is sent to for signing in at the provider.
+    pub async fn authorization_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_challenge: &str,
+    ) -> Result<String, OidcError> {
+        let metadata = self.provider(false).await?.metadata;
+        let url = reqwest::Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("scope", self.scopes.as_str()),
+                ("state", state),
+                ("nonce", nonce),
+                ("code_challenge", code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| OidcError::Provider(format!("invalid authorization endpoint: {}", e)))?;
+        Ok(url.into())
+    }
+
+    /// Exchanges an authorization code for the ID token.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<String, OidcError> {
+        let metadata = self.provider(false).await?.metadata;
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(OidcError::Provider(format!("{} from token endpoint: {}", status, body)));
+        }
+        response
+            .json::<TokenResponse>()
+            .await?
+            .id_token
+            .ok_or_else(|| OidcError::Provider("no id_token in token response".to_string()))
+    }
+
+    /// Verifies the signature, issuer, audience, lifetime and nonce of an ID token.
+    pub async fn validate_id_token(
+        &self,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let header = decode_header(id_token).map_err(|e| OidcError::IdToken(e.to_string()))?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(OidcError::IdToken(format!("algorithm {:?} is not allowed", header.alg)));
+        }
+
+        let mut provider = self.provider(false).await?;
+        if Self::find_key(&provider.jwks, header.kid.as_deref()).is_none() {
+            // Unknown key: the provider may have rotated its keys
+            provider = self.provider(true).await?;
+        }
+        let jwk = Self::find_key(&provider.jwks, header.kid.as_deref())
+            .ok_or_else(|| OidcError::IdToken("signing key not found".to_string()))?;
+        let key = DecodingKey::from_jwk(jwk).map_err(|e| OidcError::IdToken(e.to_string()))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&provider.metadata.issuer]);
+        validation.set_audience(&[&self.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| OidcError::IdToken(e.to_string()))?
+            .claims;
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(OidcError::IdToken("nonce mismatch".to_string()));
+        }
+        Ok(claims)
+    }
+
+    /// Key matching the token's `kid`, or the only key when the token has none
+    fn find_key<'a>(jwks: &'a JwkSet, kid: Option<&str>) -> Option<&'a jsonwebtoken::jwk::Jwk> {
+        match kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }
+    }
+
+    /// Discovery metadata and signing keys, from the cache unless stale or `refresh` is set
+    async fn provider(&self, refresh: bool) -> Result<ProviderCache, OidcError> {
+        if !refresh
+            && let Some(cache) = self.cache.read().ok().and_then(|cache| cache.clone())
+            && Utc::now() - cache.fetched_at < Duration::seconds(PROVIDER_CACHE_SECS)
+        {
+            return Ok(cache);
+        }
+
+        let discovery_url = format!("{}/.well-known/openid-configuration", self.issuer_url);
+        let metadata: ProviderMetadata =
+            self.http.get(&discovery_url).send().await?.error_for_status()?.json().await?;
+        if metadata.issuer.trim_end_matches('/') != self.issuer_url {
+            return Err(OidcError::Issuer(metadata.issuer));
+        }
+        let jwks: JwkSet =
+            self.http.get(&metadata.jwks_uri).send().await?.error_for_status()?.json().await?;
+
+        tracing::debug!("Loaded OIDC provider metadata and {} keys", jwks.keys.len());
+        let cache = ProviderCache { metadata, jwks, fetched_at: Utc::now() };
+        if let Ok(mut cached) = self.cache.write() {
+            *cached = Some(cache.clone());
+        }
+        Ok(cache)
+    }
+}
+
+/// Global OIDC client from the configuration
+pub static OIDC_CLIENT: Lazy<OidcClient> = Lazy::new(OidcClient::from_config);
+
+/// Generates a random PKCE code verifier.
+pub fn generate_code_verifier() -> String {
+    let mut bytes = [0u8; VERIFIER_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+/// S256 PKCE code challenge of a code verifier.
+pub fn code_challenge(code_verifier: &str) -> String {
+    BASE64URL_NOPAD.encode(&Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// Authorization request waiting for the provider's callback
+#[derive(Debug, Clone)]
+pub struct PendingLogin {
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+/// Thread-safe store of pending logins by `state`, each usable once
+pub struct PendingLoginStore {
+    logins: RwLock<HashMap<String, (PendingLogin, DateTime<Utc>)>>,
+}
+
+impl PendingLoginStore {
+    fn new() -> Self {
+        Self { logins: RwLock::new(HashMap::new()) }
+    }
+
+    /// Remember a pending login until it is completed or expires
+    pub fn insert(&self, state: &str, login: PendingLogin) {
+        if let Ok(mut logins) = self.logins.write() {
+            let now = Utc::now();
+            logins.retain(|_, (_, expires_at)| *expires_at > now);
+            logins.insert(
+                state.to_string(),
+                (login, now + Duration::seconds(PENDING_LOGIN_EXPIRE_SECS)),
+            );
+        }
+    }
+
+    /// Remove and return a pending login, `None` if unknown or expired
+    pub fn take(&self, state: &str) -> Option<PendingLogin> {
+        let (login, expires_at) = self.logins.write().ok()?.remove(state)?;
+        (expires_at > Utc::now()).then_some(login)
+    }
+}
+
+/// Global pending OIDC login store
+pub static OIDC_PENDING_LOGINS: Lazy<PendingLoginStore> = Lazy::new(PendingLoginStore::new);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, routing::get, routing::post};
+    use p256::ecdsa::{Signature, SigningKey, signature::Signer};
+    use std::sync::{Arc, Mutex};
+
+    const CLIENT_ID: &str = "rustzen";
+
+    /// Signs claims as an ES256 JWT with key ID `k1`
+    fn sign(key: &SigningKey, claims: &Value) -> String {
+        let header = BASE64URL_NOPAD.encode(br#"{"alg":"ES256","typ":"JWT","kid":"k1"}"#);
+        let payload = BASE64URL_NOPAD.encode(claims.to_string().as_bytes());
+        let input = format!("{}.{}", header, payload);
+        let signature: Signature = key.sign(input.as_bytes());
+        format!("{}.{}", input, BASE64URL_NOPAD.encode(&signature.to_bytes()))
+    }
+
+    /// Starts a mock provider; its token endpoint returns the ID token in the slot
+    async fn mock_provider(key: &SigningKey) -> (String, Arc<Mutex<String>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+
+        let point = key.verifying_key().to_encoded_point(false);
+        let jwks = serde_json::json!({ "keys": [{
+            "kty": "EC", "crv": "P-256", "kid": "k1", "use": "sig", "alg": "ES256",
+            "x": BASE64URL_NOPAD.encode(point.x().unwrap()),
+            "y": BASE64URL_NOPAD.encode(point.y().unwrap()),
+        }]});
+        let discovery = serde_json::json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{}/authorize", issuer),
+            "token_endpoint": format!("{}/token", issuer),
+            "jwks_uri": format!("{}/jwks", issuer),
+        });
+        let id_token = Arc::new(Mutex::new(String::new()));
+        let slot = id_token.clone();
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(move || async move { Json(discovery) }))
+            .route("/jwks", get(move || async move { Json(jwks) }))
+            .route(
+                "/token",
+                post(move || async move {
+                    let id_token = slot.lock().unwrap().clone();
+                    Json(serde_json::json!({ "id_token": id_token }))
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (issuer, id_token)
+    }
+
+    #[test]
+    fn test_code_challenge_matches_rfc7636() {
+        // RFC 7636 Appendix B
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+        assert_eq!(generate_code_verifier().len(), 43);
+    }
+
+    #[tokio::test]
+    async fn test_login_against_mock_provider() {
+        let key = SigningKey::random(&mut OsRng);
+        let (issuer, token_slot) = mock_provider(&key).await;
+        let now = Utc::now().timestamp();
+        let claims = |aud: &str| {
+            serde_json::json!({
+                "iss": issuer, "sub": "user-1", "aud": aud, "nonce": "n1",
+                "iat": now, "exp": now + 300,
+                "preferred_username": "alice", "email_verified": true, "groups": ["admins"],
+            })
+        };
+        *token_slot.lock().unwrap() = sign(&key, &claims(CLIENT_ID));
+
+        let client = OidcClient::new(&issuer, CLIENT_ID, "secret", "http://app/callback", "openid");
+        let url = client.authorization_url("s1", "n1", "c1").await.unwrap();
+        assert!(url.starts_with(&format!("{}/authorize?response_type=code", issuer)));
+        assert!(url.contains("code_challenge_method=S256"));
+
+        let id_token = client.exchange_code("code", "verifier").await.unwrap();
+        let validated = client.validate_id_token(&id_token, "n1").await.unwrap();
+        assert_eq!(validated.sub, "user-1");
+        assert_eq!(validated.string("preferred_username"), Some("alice"));
+        assert_eq!(validated.strings("groups"), vec!["admins".to_string()]);
+        assert!(validated.email_verified());
+
+        // Wrong nonce, wrong audience and foreign signing keys are rejected
+        assert!(client.validate_id_token(&id_token, "n2").await.is_err());
+        let other_audience = sign(&key, &claims("other"));
+        assert!(client.validate_id_token(&other_audience, "n1").await.is_err());
+        let forged = sign(&SigningKey::random(&mut OsRng), &claims(CLIENT_ID));
+        assert!(client.validate_id_token(&forged, "n1").await.is_err());
+    }
+}
@@ -1,9 +1,40 @@
-use crate::common::error::ServiceError;
+use crate::{common::error::ServiceError, core::config::CONFIG};
 
 use argon2::{
     Argon2,
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
+use once_cell::sync::Lazy;
+
+/// Password hash of accounts without a password (service accounts, SSO users):
+/// not a PHC string, so no password ever matches it
+pub const NO_PASSWORD_HASH: &str = "!";
+
+/// Common passwords that are rejected even when they satisfy the character rules
+const BUILTIN_BANNED_PASSWORDS: &[&str] = &[
+    "password",
+    "password1",
+    "password123",
+    "passw0rd",
+    "p@ssw0rd",
+    "p@ssword1",
+    "qwerty123",
+    "qwertyuiop",
+    "admin123",
+    "administrator",
+    "welcome1",
+    "welcome123",
+    "letmein1",
+    "iloveyou1",
+    "changeme",
+    "changeme1",
+    "abc12345",
+    "abcd1234",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "rustzen@123",
+];
 
 /// Password utilities for secure hashing and verification.
 pub struct PasswordUtils;
@@ -55,10 +86,113 @@ impl PasswordUtils {
     }
 }
 
+/// Password policy from the configuration, applied whenever a password is set
+pub static PASSWORD_POLICY: Lazy<PasswordPolicy> = Lazy::new(PasswordPolicy::from_config);
+
+/// Password strength rules: length, character classes and banned passwords.
+///
+/// Reuse of previous passwords is checked separately, against stored hashes.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Lowercased banned passwords
+    pub banned: Vec<String>,
+}
+
+impl PasswordPolicy {
+    /// Builds the policy from `CONFIG`, merging the built-in banned list
+    pub fn from_config() -> Self {
+        let banned = BUILTIN_BANNED_PASSWORDS
+            .iter()
+            .map(|p| p.to_string())
+            .chain(
+                CONFIG
+                    .password_banned
+                    .split(',')
+                    .map(|p| p.trim().to_lowercase())
+                    .filter(|p| !p.is_empty()),
+            )
+            .collect();
+        Self {
+            min_length: CONFIG.password_min_length,
+            require_uppercase: CONFIG.password_require_uppercase,
+            require_lowercase: CONFIG.password_require_lowercase,
+            require_digit: CONFIG.password_require_digit,
+            require_symbol: CONFIG.password_require_symbol,
+            banned,
+        }
+    }
+
+    /// Checks a new password for the given user.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the password satisfies every rule
+    /// * `Err(ServiceError::WeakPassword)` - With the first rule that failed
+    pub fn validate(&self, password: &str, username: &str) -> Result<(), ServiceError> {
+        let weak = |reason: &str| Err(ServiceError::WeakPassword(reason.to_string()));
+
+        if password.chars().count() < self.min_length {
+            return weak(&format!("must be at least {} characters long", self.min_length));
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            return weak("must contain an uppercase letter");
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            return weak("must contain a lowercase letter");
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            return weak("must contain a digit");
+        }
+        if self.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+            return weak("must contain a symbol");
+        }
+
+        let lowered = password.to_lowercase();
+        if self.banned.contains(&lowered) {
+            return weak("is too common");
+        }
+        if !username.is_empty() && lowered.contains(&username.to_lowercase()) {
+            return weak("must not contain the username");
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 8,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: true,
+            banned: vec!["p@ssw0rd1".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_password_policy_rules() {
+        let policy = policy();
+
+        assert!(policy.validate("Tr0ub4dor&3", "alice").is_ok());
+        assert!(policy.validate("", "alice").is_err());
+        assert!(policy.validate("Sh0rt!", "alice").is_err());
+        assert!(policy.validate("tr0ub4dor&3", "alice").is_err());
+        assert!(policy.validate("TR0UB4DOR&3", "alice").is_err());
+        assert!(policy.validate("Troubador&x", "alice").is_err());
+        assert!(policy.validate("Tr0ub4dor33", "alice").is_err());
+        assert!(policy.validate("P@ssw0rd1", "alice").is_err());
+        assert!(policy.validate("xAlice#2024", "alice").is_err());
+    }
+
     #[test]
     fn test_password_hashing_and_verification() {
         let password = "test_password_123";
@@ -1,39 +1,235 @@
-use crate::common::error::ServiceError;
+use crate::{
+    common::error::ServiceError,
+    core::{
+        cache::{CACHE, Cache},
+        config::CONFIG,
+        extractor::CurrentUser,
+    },
+};
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, RwLock};
+use sqlx::{PgPool, postgres::PgListener};
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
 
-/// Permission cache expiration time (1 hour)
-const CACHE_EXPIRE_HOURS: i64 = 1;
+/// Cache key prefix of user permissions
+pub const PERMISSION_KEY_PREFIX: &str = "perm:";
+
+/// Channel the database notifies permission version changes on, with a
+/// `<user_id>:<version>` payload (see `bump_permission_version`)
+pub const PERMISSION_CHANNEL: &str = "permission_changed";
+
+/// Delay before the invalidation listener reconnects after an error
+const LISTENER_RETRY_SECS: u64 = 5;
+
+/// Separator of permission code segments, e.g. `system:user:list`
+const SEGMENT_SEPARATOR: char = ':';
+
+/// Wildcard segment: matches any one segment, and when last, any number of
+/// remaining segments
+const WILDCARD: &str = "*";
+
+/// Prefix of a denied permission code, e.g. `!system:user:delete`
+pub const DENY_PREFIX: char = '!';
+
+/// Granted and denied permission codes, matched segment-wise through prefix
+/// tries so that a check costs O(depth) regardless of how many codes are granted.
+///
+/// `*` matches any single segment, and a trailing `*` also matches everything
+/// below it: `system:*` implies `system:user:list`, `system:*:list` implies
+/// `system:user:list` but not `system:user:delete`, and `*` implies everything.
+///
+/// Codes prefixed with `!` are denied. A denial beats any grant, however
+/// specific the grant is: `system:user:delete` with `!system:user:*` is denied.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<String>", into = "Vec<String>")]
+pub struct PermissionSet {
+    granted: PermissionNode,
+    denied: PermissionNode,
+    codes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PermissionNode {
+    children: HashMap<String, PermissionNode>,
+    /// A code ends at this node
+    terminal: bool,
+}
+
+impl PermissionNode {
+    fn insert(&mut self, code: &str) {
+        let mut node = self;
+        for segment in code.split(SEGMENT_SEPARATOR) {
+            node = node.children.entry(segment.to_string()).or_default();
+        }
+        node.terminal = true;
+    }
+
+    fn matches(&self, segments: &[&str]) -> bool {
+        let Some((segment, rest)) = segments.split_first() else {
+            return self.terminal;
+        };
+        if let Some(child) = self.children.get(*segment)
+            && child.matches(rest)
+        {
+            return true;
+        }
+        // A trailing wildcard covers this segment and everything below it
+        self.children
+            .get(WILDCARD)
+            .is_some_and(|wildcard| wildcard.terminal || wildcard.matches(rest))
+    }
+}
+
+impl PermissionSet {
+    fn insert(&mut self, code: &str) {
+        let code = code.trim();
+        match code.strip_prefix(DENY_PREFIX) {
+            Some(denied) if !denied.is_empty() => self.denied.insert(denied),
+            None if !code.is_empty() => self.granted.insert(code),
+            _ => return,
+        }
+        self.codes.push(code.to_string());
+    }
+
+    /// Whether the granted codes imply `code` and no denied code covers it
+    pub fn allows(&self, code: &str) -> bool {
+        let segments: Vec<&str> = code.split(SEGMENT_SEPARATOR).collect();
+        !self.denied.matches(&segments) && self.granted.matches(&segments)
+    }
+
+    /// The granted and `!`-prefixed denied codes, as stored
+    pub fn codes(&self) -> &[String] {
+        &self.codes
+    }
+
+    /// The `!`-prefixed denied codes
+    pub fn denied_codes(&self) -> impl Iterator<Item = &String> {
+        self.codes.iter().filter(|code| code.starts_with(DENY_PREFIX))
+    }
+
+    /// Granted codes, split into segments
+    fn granted_segments(&self) -> Vec<Vec<String>> {
+        let granted = self.codes.iter().filter(|code| !code.starts_with(DENY_PREFIX));
+        granted.map(|code| split_code(code)).collect()
+    }
+
+    /// Denied codes without the `!` prefix, split into segments
+    fn denied_segments(&self) -> Vec<Vec<String>> {
+        self.denied_codes().map(|code| split_code(&code[DENY_PREFIX.len_utf8()..])).collect()
+    }
+
+    /// Whether a denied code covers part of `code`, which may contain wildcards
+    pub fn denies_any(&self, code: &str) -> bool {
+        let code = split_code(code);
+        self.denied_segments().iter().any(|denied| meet_codes(denied, &code).is_some())
+    }
+
+    /// Whether `other` allows every code this set allows, e.g. whether an
+    /// impersonated user can do nothing the impersonator cannot.
+    ///
+    /// Each granted code must be implied by a single code of `other`, so the
+    /// check may refuse a subset `other` only covers with several codes.
+    pub fn is_subset_of(&self, other: &PermissionSet) -> bool {
+        let own_denials: PermissionSet =
+            self.denied_codes().map(|code| &code[DENY_PREFIX.len_utf8()..]).collect();
+        let other_denials = other.denied_segments();
+        self.granted_segments().iter().all(|code| {
+            other.allows(&join_code(code))
+                // What `other` denies must be denied here as well
+                && other_denials.iter().all(|denied| {
+                    meet_codes(code, denied).is_none_or(|both| own_denials.allows(&join_code(&both)))
+                })
+        })
+    }
+
+    /// Codes allowed by both sets, e.g. the scopes of an API key limited to
+    /// its owner's permissions. The denials of either set apply.
+    pub fn intersect(&self, other: &PermissionSet) -> PermissionSet {
+        let theirs = other.granted_segments();
+        let mut codes: Vec<String> = Vec::new();
+        for mine in self.granted_segments() {
+            for code in theirs.iter().filter_map(|their| meet_codes(&mine, their)) {
+                let code = join_code(&code);
+                if !codes.contains(&code) {
+                    codes.push(code);
+                }
+            }
+        }
+        codes.into_iter().chain(self.denied_codes().chain(other.denied_codes()).cloned()).collect()
+    }
+}
+
+fn split_code(code: &str) -> Vec<String> {
+    code.split(SEGMENT_SEPARATOR).map(str::to_string).collect()
+}
+
+fn join_code<S: AsRef<str>>(segments: &[S]) -> String {
+    let segments: Vec<&str> = segments.iter().map(AsRef::as_ref).collect();
+    segments.join(&SEGMENT_SEPARATOR.to_string())
+}
+
+/// The code implied by two granted codes, split into segments: every code
+/// matching the result matches both. `None` when no code matches both.
+fn meet_codes<'a>(a: &'a [String], b: &'a [String]) -> Option<Vec<&'a str>> {
+    match (a, b) {
+        // A trailing wildcard matches whatever the other code matches
+        ([last], rest) | (rest, [last]) if last == WILDCARD && !rest.is_empty() => {
+            Some(rest.iter().map(String::as_str).collect())
+        }
+        ([], []) => Some(Vec::new()),
+        ([x, a_rest @ ..], [y, b_rest @ ..]) => {
+            let segment = match (x.as_str(), y.as_str()) {
+                (x, y) if x == y => x,
+                (WILDCARD, y) => y,
+                (x, WILDCARD) => x,
+                _ => return None,
+            };
+            let mut code = vec![segment];
+            code.extend(meet_codes(a_rest, b_rest)?);
+            Some(code)
+        }
+        _ => None,
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for PermissionSet {
+    fn from_iter<I: IntoIterator<Item = S>>(codes: I) -> Self {
+        let mut set = Self::default();
+        for code in codes {
+            set.insert(code.as_ref());
+        }
+        set
+    }
+}
+
+impl From<Vec<String>> for PermissionSet {
+    fn from(codes: Vec<String>) -> Self {
+        codes.into_iter().collect()
+    }
+}
+
+impl From<PermissionSet> for Vec<String> {
+    fn from(set: PermissionSet) -> Self {
+        set.codes
+    }
+}
 
 /// Permission check types for flexible access control
 #[derive(Debug, Clone)]
 pub enum PermissionsCheck {
-    /// User needs any one of the permissions (OR logic)
-    Any(Vec<&'static str>),
-    /// User needs all permissions (AND logic)
-    All(Vec<&'static str>),
     /// User needs this specific permission
     Single(&'static str),
 }
 
 impl PermissionsCheck {
-    /// Core permission validation logic
-    pub fn check(&self, user_permissions: &HashSet<String>) -> bool {
-        if user_permissions.contains("*") {
-            return true;
-        }
+    /// Core permission validation logic, see [`PermissionSet`] for wildcards
+    pub fn check(&self, user_permissions: &PermissionSet) -> bool {
         match self {
-            PermissionsCheck::Single(code) => user_permissions.contains(*code),
-            PermissionsCheck::Any(codes) => {
-                codes.iter().any(|code| user_permissions.contains(*code))
-            }
-            PermissionsCheck::All(codes) => {
-                codes.iter().all(|code| user_permissions.contains(*code))
-            }
+            PermissionsCheck::Single(code) => user_permissions.allows(code),
         }
     }
 
@@ -41,121 +237,451 @@ impl PermissionsCheck {
     pub fn description(&self) -> String {
         match self {
             PermissionsCheck::Single(permission) => format!("single permission '{}'", permission),
-            PermissionsCheck::Any(permissions) => format!("any of permissions {:?}", permissions),
-            PermissionsCheck::All(permissions) => format!("all permissions {:?}", permissions),
         }
     }
 }
 
-/// Cached user permissions with expiration
+/// Cached user permissions, expired by the cache store
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserPermissionCache {
     /// User's permissions set
-    pub permissions: HashSet<String>,
+    pub permissions: PermissionSet,
     /// Cache creation timestamp
     pub cached_at: DateTime<Utc>,
+    /// User's permission version the cache was built from
+    pub version: i64,
 }
 
 impl UserPermissionCache {
     /// Create new permission cache
-    pub fn new(permissions: Vec<String>) -> Self {
-        Self { permissions: permissions.into_iter().collect(), cached_at: Utc::now() }
-    }
-
-    /// Check if cache has expired
-    pub fn is_expired(&self) -> bool {
-        let now = Utc::now();
-        let expire_time = self.cached_at + Duration::hours(CACHE_EXPIRE_HOURS);
-        now > expire_time
+    pub fn new(permissions: Vec<String>, version: i64) -> Self {
+        Self { permissions: permissions.into_iter().collect(), cached_at: Utc::now(), version }
     }
 }
 
-/// Thread-safe in-memory permission cache manager
+/// User permissions kept in the cache store under `perm:<user_id>`
 pub struct PermissionCacheManager {
-    cache: Arc<RwLock<HashMap<i64, UserPermissionCache>>>,
+    cache: &'static Cache,
+    ttl: Duration,
+    /// Newest permission version invalidated per user, so that permissions
+    /// loaded before an invalidation but stored after it are not kept
+    invalidated: Mutex<HashMap<i64, i64>>,
 }
 
 impl PermissionCacheManager {
-    fn new() -> Self {
-        Self { cache: Arc::new(RwLock::new(HashMap::new())) }
+    fn new(cache: &'static Cache, ttl: Duration) -> Self {
+        Self { cache, ttl, invalidated: Mutex::new(HashMap::new()) }
+    }
+
+    /// Newest permission version invalidated for a user
+    fn invalidated_version(&self, user_id: i64) -> Option<i64> {
+        self.invalidated.lock().unwrap_or_else(PoisonError::into_inner).get(&user_id).copied()
+    }
+
+    fn key(user_id: i64) -> String {
+        format!("{}{}", PERMISSION_KEY_PREFIX, user_id)
     }
 
     /// Get cached permissions for user
-    pub fn get(&self, user_id: i64) -> Option<UserPermissionCache> {
-        self.cache.read().ok()?.get(&user_id).cloned()
+    pub async fn get(&self, user_id: i64) -> Option<UserPermissionCache> {
+        self.cache.get_json(&Self::key(user_id)).await
     }
 
-    /// Store user permissions in cache
-    pub fn set(&self, user_id: i64, permission_cache: UserPermissionCache) {
-        if let Ok(mut cache) = self.cache.write() {
-            cache.insert(user_id, permission_cache);
+    /// Store user permissions in cache, unless a newer version was invalidated
+    /// while they were loaded
+    pub async fn set(&self, user_id: i64, permission_cache: &UserPermissionCache) {
+        self.cache.set_json(&Self::key(user_id), permission_cache, self.ttl).await;
+        // Checked after storing: an invalidation recorded later finds the entry itself
+        if self.invalidated_version(user_id).is_some_and(|v| v > permission_cache.version) {
+            self.cache.delete(&Self::key(user_id)).await;
             tracing::debug!(
-                "Cached {} permissions for user {} (expires in {}h)",
-                cache.get(&user_id).map(|c| c.permissions.len()).unwrap_or(0),
+                "Dropped permissions of user {} loaded from stale version {}",
                 user_id,
-                CACHE_EXPIRE_HOURS
+                permission_cache.version
             );
+            return;
         }
+        tracing::debug!(
+            "Cached {} permissions for user {} (expires in {}s)",
+            permission_cache.permissions.codes().len(),
+            user_id,
+            self.ttl.as_secs()
+        );
     }
 
-    /// Remove user permissions from cache
-    pub fn remove(&self, user_id: i64) {
-        if let Ok(mut cache) = self.cache.write() {
-            cache.remove(&user_id);
-            tracing::debug!("Removed permission cache for user {}", user_id);
+    /// Remove user permissions from cache if they were built from a version
+    /// older than `version`
+    pub async fn remove_stale(&self, user_id: i64, version: i64) {
+        // Recorded before checking the entry, see `set`
+        self.invalidated
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(user_id)
+            .and_modify(|latest| *latest = (*latest).max(version))
+            .or_insert(version);
+        if self.get(user_id).await.is_some_and(|c| c.version < version) {
+            self.cache.delete(&Self::key(user_id)).await;
+            tracing::debug!("Evicted stale permission cache for user {}", user_id);
         }
     }
+
+    /// Remove all cached permissions
+    pub async fn clear(&self) {
+        if let Err(e) = self.cache.flush_prefix(PERMISSION_KEY_PREFIX).await {
+            tracing::error!("Failed to clear permission cache: {}", e);
+        }
+    }
+
+    /// Remove user permissions from cache
+    pub async fn remove(&self, user_id: i64) {
+        self.cache.delete(&Self::key(user_id)).await;
+        tracing::debug!("Removed permission cache for user {}", user_id);
+    }
 }
 
 /// Global permission cache instance
-static PERMISSION_CACHE: Lazy<PermissionCacheManager> = Lazy::new(PermissionCacheManager::new);
+static PERMISSION_CACHE: Lazy<PermissionCacheManager> = Lazy::new(|| {
+    PermissionCacheManager::new(&CACHE, Duration::from_secs(CONFIG.permission_cache_ttl))
+});
 
 /// Permission service with intelligent caching
 pub struct PermissionService;
 
 impl PermissionService {
-    /// Check user permissions with simple caching
+    /// Check user permissions with simple caching, loading them on a cache miss.
+    /// Requests made with an API key are checked against the key's scopes instead.
     pub async fn check_permissions(
-        user_id: i64,
+        pool: &PgPool,
+        current_user: &CurrentUser,
         permissions_check: &PermissionsCheck,
     ) -> Result<bool, ServiceError> {
+        let user_id = current_user.user_id;
         tracing::debug!("Checking {} for user {}", permissions_check.description(), user_id);
 
-        // Only check cache, do not auto-refresh
-        if let Some(cache) = PERMISSION_CACHE.get(user_id) {
-            if cache.is_expired() {
-                tracing::info!("Cache expired for user {}", user_id);
-                PERMISSION_CACHE.remove(user_id);
-                return Err(ServiceError::InvalidToken);
-            }
-            let has_permission = permissions_check.check(&cache.permissions);
+        if let Some(api_key) = &current_user.api_key {
+            let has_permission = permissions_check.check(&api_key.scopes);
             tracing::debug!(
-                "Permission check {} for user {} ({})",
+                "Permission check {} for user {} with API key {} ({})",
                 if has_permission { "GRANTED" } else { "DENIED" },
                 user_id,
+                api_key.id,
                 permissions_check.description()
             );
             return Ok(has_permission);
         }
-        tracing::warn!("No permission cache for user {} - requiring re-auth", user_id);
-        Err(ServiceError::InvalidToken)
+
+        // Logged in on another instance, evicted or expired: load from the database
+        let permissions = match PERMISSION_CACHE.get(user_id).await {
+            Some(cache) => cache.permissions,
+            None => Self::load_user_permissions(pool, user_id).await?.into_iter().collect(),
+        };
+
+        let has_permission = permissions_check.check(&permissions);
+        tracing::debug!(
+            "Permission check {} for user {} ({})",
+            if has_permission { "GRANTED" } else { "DENIED" },
+            user_id,
+            permissions_check.description()
+        );
+        Ok(has_permission)
     }
 
-    /// Cache user permissions (called during login)
-    pub fn cache_user_permissions(user_id: i64, permissions: &[String]) {
-        let permission_cache = UserPermissionCache::new(permissions.to_vec());
-        PERMISSION_CACHE.set(user_id, permission_cache.clone());
+    /// Load user permissions from the `user_permissions` view into the cache.
+    /// System users get `*`.
+    pub async fn load_user_permissions(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Vec<String>, ServiceError> {
+        // Version and permissions come from the same snapshot
+        let (version, permissions): (i64, Vec<String>) = sqlx::query_as(
+            "SELECT permission_version,
+                    CASE WHEN is_system THEN ARRAY['*'] ELSE get_user_permissions(id) END
+             FROM users
+             WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in load_user_permissions, user_id={}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })?
+        .ok_or_else(|| {
+            tracing::warn!("No user {} to load permissions for", user_id);
+            ServiceError::InvalidToken
+        })?;
+
+        Self::cache_user_permissions(user_id, &permissions, version).await;
+        Ok(permissions)
+    }
+
+    /// Cache user permissions built from the user's permission `version`
+    pub async fn cache_user_permissions(user_id: i64, permissions: &[String], version: i64) {
+        let permission_cache = UserPermissionCache::new(permissions.to_vec(), version);
+        PERMISSION_CACHE.set(user_id, &permission_cache).await;
         tracing::info!(
-            "Cached {} permissions for user {} (expires in {}h)",
-            permission_cache.permissions.len(),
+            "Cached {} permissions for user {} (expires in {}s)",
+            permission_cache.permissions.codes().len(),
             user_id,
-            CACHE_EXPIRE_HOURS
+            PERMISSION_CACHE.ttl.as_secs()
         );
     }
 
-    /// Clear user cache (called during logout)
-    pub fn clear_user_cache(user_id: i64) {
-        PERMISSION_CACHE.remove(user_id);
-        tracing::info!("Cleared cache for user {} (logout)", user_id);
+    /// Clear user cache (called during logout, or when the user is gone)
+    pub async fn clear_user_cache(user_id: i64) {
+        PERMISSION_CACHE.remove(user_id).await;
+        tracing::info!("Cleared cache for user {}", user_id);
+    }
+
+    /// Evict user permissions built from a permission version older than `version`
+    pub async fn evict_stale(user_id: i64, version: i64) {
+        PERMISSION_CACHE.remove_stale(user_id, version).await;
+    }
+
+    /// Evict permissions invalidated on any instance, as notified by the
+    /// database on [`PERMISSION_CHANNEL`]. Runs for the lifetime of the server.
+    ///
+    /// Notifications sent while the listener is disconnected are lost, so the
+    /// whole cache is cleared whenever the connection drops.
+    pub fn spawn_invalidation_listener(pool: PgPool) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = Self::listen_for_invalidations(&pool).await {
+                    tracing::error!("Permission invalidation listener failed: {:?}", e);
+                }
+                PERMISSION_CACHE.clear().await;
+                tokio::time::sleep(Duration::from_secs(LISTENER_RETRY_SECS)).await;
+            }
+        });
+    }
+
+    async fn listen_for_invalidations(pool: &PgPool) -> Result<(), sqlx::Error> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(PERMISSION_CHANNEL).await?;
+        tracing::info!("Listening for permission invalidations on '{}'", PERMISSION_CHANNEL);
+
+        loop {
+            // `None` means the connection was lost and is re-established on the next call
+            let Some(notification) = listener.try_recv().await? else {
+                tracing::warn!("Permission invalidation listener reconnecting, clearing cache");
+                PERMISSION_CACHE.clear().await;
+                continue;
+            };
+            match parse_invalidation(notification.payload()) {
+                Some((user_id, version)) => PERMISSION_CACHE.remove_stale(user_id, version).await,
+                None => tracing::warn!(
+                    "Ignoring malformed permission invalidation '{}'",
+                    notification.payload()
+                ),
+            }
+        }
+    }
+}
+
+/// Parses a `<user_id>:<version>` invalidation payload
+fn parse_invalidation(payload: &str) -> Option<(i64, i64)> {
+    let (user_id, version) = payload.split_once(':')?;
+    Some((user_id.parse().ok()?, version.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache::MemoryStore;
+
+    #[test]
+    fn test_permission_set_wildcards() {
+        let set: PermissionSet = ["system:*", "log:*:list", "dashboard:view"].into_iter().collect();
+        assert!(set.allows("system:user:list"));
+        assert!(set.allows("system:user"));
+        assert!(!set.allows("system"));
+        assert!(set.allows("log:login:list"));
+        assert!(!set.allows("log:login:export"));
+        assert!(!set.allows("log:login:list:detail"));
+        assert!(set.allows("dashboard:view"));
+        assert!(!set.allows("dashboard:edit"));
+        assert!(!set.allows("dashboard"));
+
+        let all: PermissionSet = ["*"].into_iter().collect();
+        assert!(all.allows("system:user:delete"));
+        assert!(!PermissionSet::default().allows("system:user:list"));
+    }
+
+    #[test]
+    fn test_permission_set_deny_beats_allow() {
+        let set: PermissionSet =
+            ["system:*", "system:user:delete", "!system:user:delete", "!log:*"]
+                .into_iter()
+                .collect();
+        assert!(set.allows("system:user:list"));
+        assert!(!set.allows("system:user:delete"));
+        assert!(!set.allows("log:login:list"));
+        assert_eq!(set.denied_codes().count(), 2);
+
+        let all: PermissionSet = ["*", "!system:*:delete"].into_iter().collect();
+        assert!(all.allows("system:role:update"));
+        assert!(!all.allows("system:role:delete"));
+        assert!(!PermissionsCheck::Single("system:user:delete").check(&set));
+
+        let only_denied: PermissionSet = ["!system:user:delete", "!"].into_iter().collect();
+        assert!(!only_denied.allows("system:user:list"));
+        assert_eq!(only_denied.codes(), ["!system:user:delete"]);
+    }
+
+    #[test]
+    fn test_intersect_broader_scope_keeps_owner_grants() {
+        // The owner lost a role since the key was created
+        let scopes: PermissionSet = ["system:*", "log:*"].into_iter().collect();
+        let owner: PermissionSet = ["system:user:list", "dashboard:view"].into_iter().collect();
+        let key = scopes.intersect(&owner);
+        assert!(key.allows("system:user:list"));
+        assert!(!key.allows("system:user:delete"));
+        assert!(!key.allows("log:login:list"));
+        assert!(!key.allows("dashboard:view"));
+        assert_eq!(key.codes(), ["system:user:list"]);
+    }
+
+    #[test]
+    fn test_intersect_narrower_scope() {
+        let scopes: PermissionSet = ["system:user:list", "system:role:*"].into_iter().collect();
+        let owner: PermissionSet = ["*"].into_iter().collect();
+        let key = scopes.intersect(&owner);
+        assert!(key.allows("system:user:list"));
+        assert!(key.allows("system:role:update"));
+        assert!(!key.allows("system:user:update"));
+    }
+
+    #[test]
+    fn test_intersect_crossing_wildcards() {
+        let scopes: PermissionSet = ["system:*:list"].into_iter().collect();
+        let owner: PermissionSet = ["system:user:*", "log:*"].into_iter().collect();
+        let key = scopes.intersect(&owner);
+        assert!(key.allows("system:user:list"));
+        assert!(!key.allows("system:role:list"));
+        assert!(!key.allows("system:user:delete"));
+        assert_eq!(key.codes(), ["system:user:list"]);
+
+        let scopes: PermissionSet = ["*:user"].into_iter().collect();
+        let owner: PermissionSet = ["system:*"].into_iter().collect();
+        let key = scopes.intersect(&owner);
+        assert!(key.allows("system:user"));
+        assert!(!key.allows("system"));
+        assert!(!key.allows("system:user:list"));
+    }
+
+    #[test]
+    fn test_intersect_owner_deny_wins() {
+        let scopes: PermissionSet = ["system:user:*", "system:user:delete"].into_iter().collect();
+        let owner: PermissionSet = ["system:*", "!system:user:delete"].into_iter().collect();
+        let key = scopes.intersect(&owner);
+        assert!(key.allows("system:user:list"));
+        assert!(!key.allows("system:user:delete"));
+        assert_eq!(key.denied_codes().collect::<Vec<_>>(), ["!system:user:delete"]);
+
+        // A denial of the key applies as well
+        let scopes: PermissionSet = ["system:*", "!system:role:*"].into_iter().collect();
+        let key = scopes.intersect(&owner);
+        assert!(!key.allows("system:role:list"));
+        assert!(!PermissionsCheck::Single("system:role:list").check(&key));
+        assert!(PermissionsCheck::Single("system:dept:list").check(&key));
+    }
+
+    #[test]
+    fn test_denies_any() {
+        let set: PermissionSet = ["*", "!system:user:delete"].into_iter().collect();
+        assert!(set.denies_any("system:user:delete"));
+        assert!(set.denies_any("system:user:*"));
+        assert!(set.denies_any("*"));
+        assert!(!set.denies_any("system:user:list"));
+        assert!(!set.denies_any("system:role:*"));
+    }
+
+    #[test]
+    fn test_is_subset_of() {
+        let admin: PermissionSet = ["system:*", "log:*:list"].into_iter().collect();
+        let viewer: PermissionSet = ["system:user:list", "log:login:list"].into_iter().collect();
+        assert!(viewer.is_subset_of(&admin));
+        assert!(!admin.is_subset_of(&viewer));
+        assert!(admin.is_subset_of(&admin));
+        assert!(PermissionSet::default().is_subset_of(&viewer));
+        assert!(admin.is_subset_of(&["*"].into_iter().collect()));
+
+        // A role manager is not covered by a user manager
+        let role_admin: PermissionSet = ["system:role:*"].into_iter().collect();
+        let user_admin: PermissionSet = ["system:user:*"].into_iter().collect();
+        assert!(!role_admin.is_subset_of(&user_admin));
+    }
+
+    #[test]
+    fn test_is_subset_of_with_denials() {
+        let actor: PermissionSet = ["system:*", "!system:user:delete"].into_iter().collect();
+        // The target may delete users, the actor may not
+        let target: PermissionSet = ["system:user:*"].into_iter().collect();
+        assert!(!target.is_subset_of(&actor));
+        // The target is denied the same
+        let target: PermissionSet = ["system:user:*", "!system:user:delete"].into_iter().collect();
+        assert!(target.is_subset_of(&actor));
+        let target: PermissionSet = ["system:*", "!system:user:*"].into_iter().collect();
+        assert!(target.is_subset_of(&actor));
+        assert!(!actor.is_subset_of(&["system:*", "!system:*:delete"].into_iter().collect()));
+    }
+
+    #[test]
+    fn test_permission_set_serde_roundtrip() {
+        let set: PermissionSet = ["system:user:*"].into_iter().collect();
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(json, r#"["system:user:*"]"#);
+        let set: PermissionSet = serde_json::from_str(&json).unwrap();
+        assert!(set.allows("system:user:list"));
+        assert!(PermissionsCheck::Single("system:user:list").check(&set));
+        assert!(!PermissionsCheck::Single("system:role:list").check(&set));
+    }
+
+    #[test]
+    fn test_parse_invalidation() {
+        assert_eq!(parse_invalidation("42:7"), Some((42, 7)));
+        assert_eq!(parse_invalidation("42"), None);
+        assert_eq!(parse_invalidation("x:7"), None);
+    }
+
+    #[tokio::test]
+    async fn test_remove_stale() {
+        let store = Box::leak(Box::new(Cache::new(Box::new(MemoryStore::new(10)))));
+        let cache = PermissionCacheManager::new(store, Duration::from_secs(60));
+        cache.set(1, &UserPermissionCache::new(vec!["system:user:list".to_string()], 3)).await;
+        cache.remove_stale(1, 3).await;
+        assert!(cache.get(1).await.is_some());
+        cache.remove_stale(1, 4).await;
+        assert!(cache.get(1).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_between_load_and_set() {
+        let store = Box::leak(Box::new(Cache::new(Box::new(MemoryStore::new(10)))));
+        let cache = PermissionCacheManager::new(store, Duration::from_secs(60));
+
+        // Loaded at version 3, then version 4 is notified before the set
+        let loaded = UserPermissionCache::new(vec!["system:user:list".to_string()], 3);
+        cache.remove_stale(1, 4).await;
+        cache.set(1, &loaded).await;
+        assert!(cache.get(1).await.is_none());
+
+        // Permissions loaded from the notified version are kept
+        cache.set(1, &UserPermissionCache::new(Vec::new(), 4)).await;
+        assert_eq!(cache.get(1).await.map(|c| c.version), Some(4));
+
+        // An older notification arriving late does not lower the bar
+        cache.remove_stale(1, 2).await;
+        cache.set(1, &loaded).await;
+        assert!(cache.get(1).await.is_none());
+        assert_eq!(cache.invalidated_version(1), Some(4));
+
+        // Other users are unaffected
+        cache.set(2, &loaded).await;
+        assert!(cache.get(2).await.is_some());
     }
 }
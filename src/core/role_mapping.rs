@@ -0,0 +1,36 @@
+/// Parses `group=ROLE_CODE` pairs separated by commas.
+pub fn parse_role_mapping(mapping: &str) -> Vec<(String, String)> {
+    mapping
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(group, role)| (group.trim().to_string(), role.trim().to_string()))
+        .filter(|(group, role)| !group.is_empty() && !role.is_empty())
+        .collect()
+}
+
+/// Role codes mapped from the groups of a user, without duplicates.
+pub fn mapped_role_codes(groups: &[String], mapping: &[(String, String)]) -> Vec<String> {
+    let mut codes: Vec<String> = mapping
+        .iter()
+        .filter(|(group, _)| groups.contains(group))
+        .map(|(_, role)| role.clone())
+        .collect();
+    codes.sort();
+    codes.dedup();
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_mapping() {
+        let mapping = parse_role_mapping(" admins = SYSTEM_ADMIN, ops=OPERATOR,devs=OPERATOR,bad");
+        assert_eq!(mapping.len(), 3);
+
+        let groups = vec!["ops".to_string(), "devs".to_string(), "other".to_string()];
+        assert_eq!(mapped_role_codes(&groups, &mapping), vec!["OPERATOR".to_string()]);
+        assert!(mapped_role_codes(&[], &mapping).is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use serde_json::Value;
+
+/// Comparison operators of SCIM filters (RFC 7644 section 3.4.2.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+    Pr,
+}
+
+/// One `attribute op value` expression of a filter.
+///
+/// Attribute names are lowercased, as SCIM attribute names are case-insensitive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub attribute: String,
+    pub op: CompareOp,
+    /// JSON value of the comparison, `Null` for `pr`
+    pub value: Value,
+}
+
+/// Attribute path of a PATCH operation, e.g. `members[value eq "2"]` or `name.formatted`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributePath {
+    /// Lowercased attribute name, including a sub-attribute written with a dot
+    pub attribute: String,
+    /// Value filter in brackets, empty when there is none
+    pub filter: Vec<Comparison>,
+    /// Lowercased sub-attribute after a value filter
+    pub sub_attribute: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct InvalidFilter(pub String);
+
+/// Splits a filter into tokens, keeping quoted strings whole.
+fn tokenize(filter: &str) -> Result<Vec<String>, InvalidFilter> {
+    let mut tokens = Vec::new();
+    let mut chars = filter.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            let mut token = String::from(chars.next().unwrap_or_default());
+            let mut closed = false;
+            while let Some(c) = chars.next() {
+                token.push(c);
+                if c == '\\' {
+                    token.extend(chars.next());
+                } else if c == '"' {
+                    closed = true;
+                    break;
+                }
+            }
+            if !closed {
+                return Err(InvalidFilter("unterminated string".to_string()));
+            }
+            tokens.push(token);
+        } else if matches!(c, '(' | ')' | '[' | ']') {
+            return Err(InvalidFilter("grouping is not supported".to_string()));
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses a filter of comparisons joined by `and`.
+///
+/// `or`, `not`, grouping and the ordering operators are not supported; the
+/// identity providers we provision from only send simple equality filters.
+pub fn parse_filter(filter: &str) -> Result<Vec<Comparison>, InvalidFilter> {
+    let tokens = tokenize(filter)?;
+    let mut comparisons = Vec::new();
+    let mut tokens = tokens.into_iter();
+
+    loop {
+        let attribute = tokens.next().ok_or_else(|| InvalidFilter("empty filter".to_string()))?;
+        if !attribute.starts_with(|c: char| c.is_ascii_alphabetic())
+            || !attribute.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        {
+            return Err(InvalidFilter(format!("invalid attribute '{}'", attribute)));
+        }
+        let op = tokens.next().ok_or_else(|| InvalidFilter("missing operator".to_string()))?;
+        let op = match op.to_ascii_lowercase().as_str() {
+            "eq" => CompareOp::Eq,
+            "ne" => CompareOp::Ne,
+            "co" => CompareOp::Co,
+            "sw" => CompareOp::Sw,
+            "ew" => CompareOp::Ew,
+            "pr" => CompareOp::Pr,
+            other => return Err(InvalidFilter(format!("unsupported operator '{}'", other))),
+        };
+        let value = if op == CompareOp::Pr {
+            Value::Null
+        } else {
+            let token = tokens.next().ok_or_else(|| InvalidFilter("missing value".to_string()))?;
+            serde_json::from_str(&token)
+                .map_err(|_| InvalidFilter(format!("invalid value {}", token)))?
+        };
+        comparisons.push(Comparison { attribute: attribute.to_ascii_lowercase(), op, value });
+
+        match tokens.next() {
+            None => return Ok(comparisons),
+            Some(token) if token.eq_ignore_ascii_case("and") => {}
+            Some(token) => return Err(InvalidFilter(format!("unsupported operator '{}'", token))),
+        }
+    }
+}
+
+/// Parses the attribute path of a PATCH operation.
+pub fn parse_path(path: &str) -> Result<AttributePath, InvalidFilter> {
+    let path = path.trim();
+    let Some((attribute, rest)) = path.split_once('[') else {
+        return Ok(AttributePath {
+            attribute: path.to_ascii_lowercase(),
+            filter: Vec::new(),
+            sub_attribute: None,
+        });
+    };
+    let (filter, rest) =
+        rest.rsplit_once(']').ok_or_else(|| InvalidFilter("unclosed value filter".to_string()))?;
+    let sub_attribute = match rest {
+        "" => None,
+        rest => Some(
+            rest.strip_prefix('.')
+                .filter(|sub| !sub.is_empty())
+                .ok_or_else(|| InvalidFilter(format!("invalid path '{}'", path)))?
+                .to_ascii_lowercase(),
+        ),
+    };
+    Ok(AttributePath {
+        attribute: attribute.to_ascii_lowercase(),
+        filter: parse_filter(filter)?,
+        sub_attribute,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_filter() {
+        let filter = parse_filter(r#"userName eq "Alice \"A\"" and active eq true"#).unwrap();
+        assert_eq!(filter.len(), 2);
+        assert_eq!(filter[0].attribute, "username");
+        assert_eq!(filter[0].op, CompareOp::Eq);
+        assert_eq!(filter[0].value, json!("Alice \"A\""));
+        assert_eq!(filter[1].value, json!(true));
+
+        let filter = parse_filter("emails.value PR").unwrap();
+        assert_eq!(filter[0].op, CompareOp::Pr);
+        assert_eq!(filter[0].value, Value::Null);
+
+        assert!(parse_filter(r#"userName eq "a" or userName eq "b""#).is_err());
+        assert!(parse_filter(r#"(userName eq "a")"#).is_err());
+        assert!(parse_filter(r#"meta.created gt "2020-01-01""#).is_err());
+        assert!(parse_filter(r#"userName eq "open"#).is_err());
+        assert!(parse_filter("userName eq").is_err());
+        assert!(parse_filter("").is_err());
+    }
+
+    #[test]
+    fn test_parse_path() {
+        let path = parse_path(r#"members[value eq "42"]"#).unwrap();
+        assert_eq!(path.attribute, "members");
+        assert_eq!(path.filter[0].value, json!("42"));
+        assert_eq!(path.sub_attribute, None);
+
+        let path = parse_path(r#"emails[type eq "work"].value"#).unwrap();
+        assert_eq!(path.attribute, "emails");
+        assert_eq!(path.sub_attribute.as_deref(), Some("value"));
+
+        let path = parse_path("name.givenName").unwrap();
+        assert_eq!(path.attribute, "name.givenname");
+        assert!(path.filter.is_empty());
+
+        assert!(parse_path(r#"members[value eq "42""#).is_err());
+    }
+}
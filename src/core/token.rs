@@ -0,0 +1,94 @@
+use hmac::{Hmac, Mac};
+use rand::{RngCore, rngs::OsRng};
+use sha2::{Digest, Sha256};
+
+/// Number of random bytes in an opaque token (256 bits).
+const TOKEN_BYTES: usize = 32;
+
+/// Utilities for opaque, database-stored tokens (refresh tokens etc.).
+///
+/// Only the SHA-256 digest of a token is ever persisted, so a leaked
+/// database row cannot be replayed as a credential.
+pub struct TokenUtils;
+
+impl TokenUtils {
+    /// Generates a new random token encoded as lowercase hex.
+    pub fn generate_token() -> String {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        hex::encode(bytes)
+    }
+
+    /// Hashes a token for storage and lookup.
+    pub fn hash_token(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
+    /// Generates a random token signed for `purpose`, formatted as `<token>.<signature>`.
+    ///
+    /// Signed tokens are sent to users (e.g. by email). The signature lets
+    /// forged or mistyped tokens, and tokens issued for another purpose, be
+    /// rejected before any database lookup.
+    pub fn generate_signed_token(secret: &[u8], purpose: &str) -> String {
+        let token = Self::generate_token();
+        let signature =
+            hex::encode(Self::signature_mac(secret, purpose, &token).finalize().into_bytes());
+        format!("{}.{}", token, signature)
+    }
+
+    /// Verifies the signature of a token from [`TokenUtils::generate_signed_token`].
+    pub fn verify_signed_token(secret: &[u8], purpose: &str, signed_token: &str) -> bool {
+        let Some((token, signature)) = signed_token.split_once('.') else {
+            return false;
+        };
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        Self::signature_mac(secret, purpose, token).verify_slice(&signature).is_ok()
+    }
+
+    /// HMAC-SHA256 over the purpose and the token
+    fn signature_mac(secret: &[u8], purpose: &str, token: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+        mac.update(purpose.as_bytes());
+        mac.update(b".");
+        mac.update(token.as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_token_is_random_hex() {
+        let token1 = TokenUtils::generate_token();
+        let token2 = TokenUtils::generate_token();
+
+        assert_eq!(token1.len(), TOKEN_BYTES * 2);
+        assert!(token1.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token1, token2);
+    }
+
+    #[test]
+    fn test_hash_token_is_deterministic() {
+        let token = TokenUtils::generate_token();
+
+        assert_eq!(TokenUtils::hash_token(&token), TokenUtils::hash_token(&token));
+        assert_ne!(TokenUtils::hash_token(&token), token);
+        assert_ne!(TokenUtils::hash_token("a"), TokenUtils::hash_token("b"));
+    }
+
+    #[test]
+    fn test_signed_token_round_trip() {
+        let token = TokenUtils::generate_signed_token(b"secret", "password_reset");
+
+        assert!(TokenUtils::verify_signed_token(b"secret", "password_reset", &token));
+        assert!(!TokenUtils::verify_signed_token(b"secret", "email_verify", &token));
+        assert!(!TokenUtils::verify_signed_token(b"other", "password_reset", &token));
+        assert!(!TokenUtils::verify_signed_token(b"secret", "password_reset", &token[1..]));
+        assert!(!TokenUtils::verify_signed_token(b"secret", "password_reset", "abc"));
+    }
+}
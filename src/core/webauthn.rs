@@ -0,0 +1,503 @@
+use chrono::{DateTime, Duration, Utc};
+use ciborium::value::Value;
+use data_encoding::BASE64URL_NOPAD;
+use once_cell::sync::Lazy;
+use p256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
+use rand::{RngCore, rngs::OsRng};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Number of random bytes in a ceremony challenge
+const CHALLENGE_BYTES: usize = 32;
+/// Lifetime of a pending ceremony challenge in seconds
+const CHALLENGE_EXPIRE_SECS: i64 = 300;
+/// COSE algorithm identifier of ES256 (ECDSA P-256 with SHA-256)
+pub const COSE_ALG_ES256: i64 = -7;
+
+/// Authenticator data flag: user present
+const FLAG_USER_PRESENT: u8 = 0x01;
+/// Authenticator data flag: user verified
+const FLAG_USER_VERIFIED: u8 = 0x04;
+/// Authenticator data flag: attested credential data included
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+/// Reasons a WebAuthn ceremony response is rejected
+#[derive(Debug, thiserror::Error)]
+pub enum WebAuthnError {
+    #[error("malformed {0}")]
+    Malformed(&'static str),
+    #[error("unexpected client data type {0}")]
+    ClientDataType(String),
+    #[error("challenge mismatch")]
+    Challenge,
+    #[error("origin {0} is not allowed")]
+    Origin(String),
+    #[error("authenticator data is for another relying party")]
+    RpIdHash,
+    #[error("user presence or verification flag missing")]
+    UserVerification,
+    #[error("unsupported credential key, only ES256 is accepted")]
+    UnsupportedKey,
+    #[error("invalid signature")]
+    Signature,
+    #[error("signature counter did not increase, the authenticator may be cloned")]
+    SignCount,
+}
+
+/// Relying party settings a ceremony is verified against
+#[derive(Debug, Clone)]
+pub struct RelyingParty {
+    /// RP ID, the effective domain credentials are scoped to
+    pub id: String,
+    /// Exact origin the browser reports, e.g. `https://admin.example.com`
+    pub origin: String,
+}
+
+/// Credential created by a successful registration ceremony
+#[derive(Debug, Clone)]
+pub struct RegisteredCredential {
+    /// Credential ID chosen by the authenticator
+    pub credential_id: Vec<u8>,
+    /// SEC1 uncompressed P-256 public key
+    pub public_key: Vec<u8>,
+    /// Initial signature counter
+    pub sign_count: u32,
+}
+
+/// `clientDataJSON` collected by the browser
+#[derive(Debug, Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+    origin: String,
+}
+
+/// Parsed authenticator data
+struct AuthenticatorData<'a> {
+    rp_id_hash: &'a [u8],
+    flags: u8,
+    sign_count: u32,
+    /// Credential ID and COSE public key, present on registration
+    attested_credential: Option<(Vec<u8>, Value)>,
+}
+
+/// Minimal WebAuthn relying party (ES256 credentials, attestation not verified).
+///
+/// Attestation statements are ignored on purpose: passkeys are accepted from
+/// any authenticator, what matters is that later assertions are signed by the
+/// registered key.
+pub struct WebAuthnUtils;
+
+impl WebAuthnUtils {
+    /// Generates a new random challenge encoded as base64url.
+    pub fn generate_challenge() -> String {
+        let mut bytes = [0u8; CHALLENGE_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        BASE64URL_NOPAD.encode(&bytes)
+    }
+
+    /// Decodes base64url as sent by browsers (padding tolerated).
+    pub fn decode_base64url(value: &str) -> Result<Vec<u8>, WebAuthnError> {
+        BASE64URL_NOPAD
+            .decode(value.trim_end_matches('=').as_bytes())
+            .map_err(|_| WebAuthnError::Malformed("base64url"))
+    }
+
+    /// Encodes bytes as unpadded base64url.
+    pub fn encode_base64url(bytes: &[u8]) -> String {
+        BASE64URL_NOPAD.encode(bytes)
+    }
+
+    /// Extracts the challenge from `clientDataJSON`, to look up the pending ceremony.
+    pub fn challenge_of(client_data_json: &[u8]) -> Result<String, WebAuthnError> {
+        Ok(Self::parse_client_data(client_data_json)?.challenge)
+    }
+
+    /// Verifies the response of a registration ceremony (`navigator.credentials.create`).
+    pub fn verify_registration(
+        rp: &RelyingParty,
+        challenge: &str,
+        client_data_json: &[u8],
+        attestation_object: &[u8],
+    ) -> Result<RegisteredCredential, WebAuthnError> {
+        Self::verify_client_data(rp, "webauthn.create", challenge, client_data_json)?;
+
+        let attestation: Value = ciborium::de::from_reader(attestation_object)
+            .map_err(|_| WebAuthnError::Malformed("attestation object"))?;
+        let auth_data = Self::map_get(&attestation, &Value::Text("authData".into()))
+            .and_then(Value::as_bytes)
+            .ok_or(WebAuthnError::Malformed("attestation object"))?;
+
+        let auth_data = Self::parse_authenticator_data(auth_data)?;
+        Self::verify_authenticator_data(rp, &auth_data)?;
+
+        let (credential_id, cose_key) =
+            auth_data.attested_credential.ok_or(WebAuthnError::Malformed("authenticator data"))?;
+        let public_key = Self::cose_es256_to_sec1(&cose_key)?;
+
+        Ok(RegisteredCredential { credential_id, public_key, sign_count: auth_data.sign_count })
+    }
+
+    /// Verifies the response of an authentication ceremony (`navigator.credentials.get`).
+    /// Returns the new signature counter to store.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_assertion(
+        rp: &RelyingParty,
+        challenge: &str,
+        public_key: &[u8],
+        stored_sign_count: u32,
+        client_data_json: &[u8],
+        authenticator_data: &[u8],
+        signature: &[u8],
+    ) -> Result<u32, WebAuthnError> {
+        Self::verify_client_data(rp, "webauthn.get", challenge, client_data_json)?;
+
+        let auth_data = Self::parse_authenticator_data(authenticator_data)?;
+        Self::verify_authenticator_data(rp, &auth_data)?;
+
+        // The signature covers authenticatorData || SHA-256(clientDataJSON)
+        let key =
+            VerifyingKey::from_sec1_bytes(public_key).map_err(|_| WebAuthnError::UnsupportedKey)?;
+        let signature = Signature::from_der(signature).map_err(|_| WebAuthnError::Signature)?;
+        let mut message = authenticator_data.to_vec();
+        message.extend_from_slice(&Sha256::digest(client_data_json));
+        key.verify(&message, &signature).map_err(|_| WebAuthnError::Signature)?;
+
+        // Authenticators without a counter always report 0
+        if (auth_data.sign_count != 0 || stored_sign_count != 0)
+            && auth_data.sign_count <= stored_sign_count
+        {
+            return Err(WebAuthnError::SignCount);
+        }
+        Ok(auth_data.sign_count)
+    }
+
+    fn parse_client_data(client_data_json: &[u8]) -> Result<ClientData, WebAuthnError> {
+        serde_json::from_slice(client_data_json)
+            .map_err(|_| WebAuthnError::Malformed("client data"))
+    }
+
+    fn verify_client_data(
+        rp: &RelyingParty,
+        kind: &str,
+        challenge: &str,
+        client_data_json: &[u8],
+    ) -> Result<(), WebAuthnError> {
+        let client_data = Self::parse_client_data(client_data_json)?;
+        if client_data.kind != kind {
+            return Err(WebAuthnError::ClientDataType(client_data.kind));
+        }
+        if client_data.challenge.trim_end_matches('=') != challenge {
+            return Err(WebAuthnError::Challenge);
+        }
+        if client_data.origin != rp.origin {
+            return Err(WebAuthnError::Origin(client_data.origin));
+        }
+        Ok(())
+    }
+
+    fn verify_authenticator_data(
+        rp: &RelyingParty,
+        auth_data: &AuthenticatorData<'_>,
+    ) -> Result<(), WebAuthnError> {
+        if auth_data.rp_id_hash != Sha256::digest(rp.id.as_bytes()).as_slice() {
+            return Err(WebAuthnError::RpIdHash);
+        }
+        // Passkeys replace the password, so the user must be verified, not just present
+        let required = FLAG_USER_PRESENT | FLAG_USER_VERIFIED;
+        if auth_data.flags & required != required {
+            return Err(WebAuthnError::UserVerification);
+        }
+        Ok(())
+    }
+
+    /// Parses `rpIdHash (32) | flags (1) | signCount (4) | [attestedCredentialData]`
+    fn parse_authenticator_data(data: &[u8]) -> Result<AuthenticatorData<'_>, WebAuthnError> {
+        const MALFORMED: WebAuthnError = WebAuthnError::Malformed("authenticator data");
+        if data.len() < 37 {
+            return Err(MALFORMED);
+        }
+        let flags = data[32];
+        let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL != 0 {
+            // aaguid (16) | credentialIdLength (2) | credentialId | credentialPublicKey (COSE)
+            let rest = data.get(37 + 16..).ok_or(MALFORMED)?;
+            let id_len = u16::from_be_bytes([
+                *rest.first().ok_or(MALFORMED)?,
+                *rest.get(1).ok_or(MALFORMED)?,
+            ]) as usize;
+            let credential_id = rest.get(2..2 + id_len).ok_or(MALFORMED)?.to_vec();
+            let cose_key: Value =
+                ciborium::de::from_reader(&rest[2 + id_len..]).map_err(|_| MALFORMED)?;
+            Some((credential_id, cose_key))
+        } else {
+            None
+        };
+
+        Ok(AuthenticatorData { rp_id_hash: &data[..32], flags, sign_count, attested_credential })
+    }
+
+    /// Converts a COSE EC2 P-256 key into SEC1 uncompressed form
+    fn cose_es256_to_sec1(key: &Value) -> Result<Vec<u8>, WebAuthnError> {
+        let int = |label: i64| Self::map_get(key, &Value::Integer(label.into()));
+        let kty = int(1).and_then(Value::as_integer).map(i128::from);
+        let alg = int(3).and_then(Value::as_integer).map(i128::from);
+        let crv = int(-1).and_then(Value::as_integer).map(i128::from);
+        if kty != Some(2) || alg != Some(COSE_ALG_ES256 as i128) || crv != Some(1) {
+            return Err(WebAuthnError::UnsupportedKey);
+        }
+
+        let x = int(-2).and_then(Value::as_bytes).ok_or(WebAuthnError::UnsupportedKey)?;
+        let y = int(-3).and_then(Value::as_bytes).ok_or(WebAuthnError::UnsupportedKey)?;
+        if x.len() != 32 || y.len() != 32 {
+            return Err(WebAuthnError::UnsupportedKey);
+        }
+
+        let mut sec1 = Vec::with_capacity(65);
+        sec1.push(0x04);
+        sec1.extend_from_slice(x);
+        sec1.extend_from_slice(y);
+        VerifyingKey::from_sec1_bytes(&sec1).map_err(|_| WebAuthnError::UnsupportedKey)?;
+        Ok(sec1)
+    }
+
+    fn map_get<'a>(map: &'a Value, key: &Value) -> Option<&'a Value> {
+        map.as_map()?.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+/// Ceremony a challenge was issued for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ceremony {
+    /// Registering a passkey for a signed-in user
+    Registration { user_id: i64 },
+    /// Signing in with a passkey
+    Authentication,
+}
+
+/// Thread-safe store of pending ceremony challenges, each usable once
+pub struct ChallengeStore {
+    challenges: RwLock<HashMap<String, (Ceremony, DateTime<Utc>)>>,
+}
+
+impl ChallengeStore {
+    fn new() -> Self {
+        Self { challenges: RwLock::new(HashMap::new()) }
+    }
+
+    /// Remember a challenge until it is used or expires
+    pub fn insert(&self, challenge: &str, ceremony: Ceremony) {
+        if let Ok(mut challenges) = self.challenges.write() {
+            let now = Utc::now();
+            challenges.retain(|_, (_, expires_at)| *expires_at > now);
+            challenges.insert(
+                challenge.to_string(),
+                (ceremony, now + Duration::seconds(CHALLENGE_EXPIRE_SECS)),
+            );
+        }
+    }
+
+    /// Remove and return a pending challenge, `None` if unknown or expired
+    pub fn take(&self, challenge: &str) -> Option<Ceremony> {
+        let (ceremony, expires_at) = self.challenges.write().ok()?.remove(challenge)?;
+        (expires_at > Utc::now()).then_some(ceremony)
+    }
+}
+
+/// Global pending WebAuthn challenge store
+pub static WEBAUTHN_CHALLENGES: Lazy<ChallengeStore> = Lazy::new(ChallengeStore::new);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::{SigningKey, signature::Signer};
+
+    const RP_ID: &str = "admin.example.com";
+    const ORIGIN: &str = "https://admin.example.com";
+
+    /// Software authenticator holding one ES256 credential
+    struct SoftAuthenticator {
+        key: SigningKey,
+        credential_id: Vec<u8>,
+        sign_count: u32,
+    }
+
+    impl SoftAuthenticator {
+        fn new() -> Self {
+            Self { key: SigningKey::random(&mut OsRng), credential_id: vec![7; 16], sign_count: 0 }
+        }
+
+        fn client_data(kind: &str, challenge: &str, origin: &str) -> Vec<u8> {
+            serde_json::json!({ "type": kind, "challenge": challenge, "origin": origin })
+                .to_string()
+                .into_bytes()
+        }
+
+        fn auth_data(&self, rp_id: &str, flags: u8, attested: bool) -> Vec<u8> {
+            let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+            data.push(flags | if attested { FLAG_ATTESTED_CREDENTIAL } else { 0 });
+            data.extend_from_slice(&self.sign_count.to_be_bytes());
+            if attested {
+                let point = self.key.verifying_key().to_encoded_point(false);
+                let cose_key = Value::Map(vec![
+                    (Value::Integer(1.into()), Value::Integer(2.into())),
+                    (Value::Integer(3.into()), Value::Integer(COSE_ALG_ES256.into())),
+                    (Value::Integer((-1).into()), Value::Integer(1.into())),
+                    (Value::Integer((-2).into()), Value::Bytes(point.x().unwrap().to_vec())),
+                    (Value::Integer((-3).into()), Value::Bytes(point.y().unwrap().to_vec())),
+                ]);
+                data.extend_from_slice(&[0; 16]);
+                data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+                data.extend_from_slice(&self.credential_id);
+                ciborium::ser::into_writer(&cose_key, &mut data).unwrap();
+            }
+            data
+        }
+
+        /// Returns (clientDataJSON, attestationObject)
+        fn create(&self, challenge: &str, origin: &str) -> (Vec<u8>, Vec<u8>) {
+            let auth_data = self.auth_data(RP_ID, FLAG_USER_PRESENT | FLAG_USER_VERIFIED, true);
+            let attestation = Value::Map(vec![
+                (Value::Text("fmt".into()), Value::Text("none".into())),
+                (Value::Text("attStmt".into()), Value::Map(vec![])),
+                (Value::Text("authData".into()), Value::Bytes(auth_data)),
+            ]);
+            let mut attestation_object = Vec::new();
+            ciborium::ser::into_writer(&attestation, &mut attestation_object).unwrap();
+            (Self::client_data("webauthn.create", challenge, origin), attestation_object)
+        }
+
+        /// Returns (clientDataJSON, authenticatorData, signature)
+        fn get(&mut self, challenge: &str, flags: u8) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+            self.sign_count += 1;
+            let client_data = Self::client_data("webauthn.get", challenge, ORIGIN);
+            let auth_data = self.auth_data(RP_ID, flags, false);
+            let mut message = auth_data.clone();
+            message.extend_from_slice(&Sha256::digest(&client_data));
+            let signature: Signature = self.key.sign(&message);
+            (client_data, auth_data, signature.to_der().as_bytes().to_vec())
+        }
+    }
+
+    fn rp() -> RelyingParty {
+        RelyingParty { id: RP_ID.into(), origin: ORIGIN.into() }
+    }
+
+    fn register(authenticator: &SoftAuthenticator) -> RegisteredCredential {
+        let challenge = WebAuthnUtils::generate_challenge();
+        let (client_data, attestation) = authenticator.create(&challenge, ORIGIN);
+        WebAuthnUtils::verify_registration(&rp(), &challenge, &client_data, &attestation).unwrap()
+    }
+
+    #[test]
+    fn test_registration_extracts_credential() {
+        let authenticator = SoftAuthenticator::new();
+        let credential = register(&authenticator);
+
+        assert_eq!(credential.credential_id, authenticator.credential_id);
+        assert_eq!(
+            credential.public_key,
+            authenticator.key.verifying_key().to_encoded_point(false).as_bytes()
+        );
+        assert_eq!(credential.sign_count, 0);
+    }
+
+    #[test]
+    fn test_registration_rejects_wrong_origin_and_challenge() {
+        let authenticator = SoftAuthenticator::new();
+        let challenge = WebAuthnUtils::generate_challenge();
+
+        let (client_data, attestation) = authenticator.create(&challenge, "https://evil.example");
+        assert!(matches!(
+            WebAuthnUtils::verify_registration(&rp(), &challenge, &client_data, &attestation),
+            Err(WebAuthnError::Origin(_))
+        ));
+
+        let (client_data, attestation) = authenticator.create(&challenge, ORIGIN);
+        let other = WebAuthnUtils::generate_challenge();
+        assert!(matches!(
+            WebAuthnUtils::verify_registration(&rp(), &other, &client_data, &attestation),
+            Err(WebAuthnError::Challenge)
+        ));
+    }
+
+    #[test]
+    fn test_assertion_verifies_signature_and_counter() {
+        let mut authenticator = SoftAuthenticator::new();
+        let credential = register(&authenticator);
+        let challenge = WebAuthnUtils::generate_challenge();
+
+        let flags = FLAG_USER_PRESENT | FLAG_USER_VERIFIED;
+        let (client_data, auth_data, signature) = authenticator.get(&challenge, flags);
+        let sign_count = WebAuthnUtils::verify_assertion(
+            &rp(),
+            &challenge,
+            &credential.public_key,
+            credential.sign_count,
+            &client_data,
+            &auth_data,
+            &signature,
+        )
+        .unwrap();
+        assert_eq!(sign_count, 1);
+
+        // Replaying the same counter is rejected
+        assert!(matches!(
+            WebAuthnUtils::verify_assertion(
+                &rp(),
+                &challenge,
+                &credential.public_key,
+                sign_count,
+                &client_data,
+                &auth_data,
+                &signature,
+            ),
+            Err(WebAuthnError::SignCount)
+        ));
+    }
+
+    #[test]
+    fn test_assertion_rejects_tampering_and_missing_verification() {
+        let mut authenticator = SoftAuthenticator::new();
+        let credential = register(&authenticator);
+        let challenge = WebAuthnUtils::generate_challenge();
+        let verify = |client_data: &[u8], auth_data: &[u8], signature: &[u8]| {
+            WebAuthnUtils::verify_assertion(
+                &rp(),
+                &challenge,
+                &credential.public_key,
+                0,
+                client_data,
+                auth_data,
+                signature,
+            )
+        };
+
+        let (client_data, mut auth_data, signature) =
+            authenticator.get(&challenge, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+        auth_data[36] ^= 0xff;
+        assert!(matches!(
+            verify(&client_data, &auth_data, &signature),
+            Err(WebAuthnError::Signature)
+        ));
+
+        let (client_data, auth_data, signature) = authenticator.get(&challenge, FLAG_USER_PRESENT);
+        assert!(matches!(
+            verify(&client_data, &auth_data, &signature),
+            Err(WebAuthnError::UserVerification)
+        ));
+    }
+
+    #[test]
+    fn test_challenge_store_is_single_use() {
+        let store = ChallengeStore::new();
+        store.insert("abc", Ceremony::Authentication);
+
+        assert_eq!(store.take("abc"), Some(Ceremony::Authentication));
+        assert_eq!(store.take("abc"), None);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::common::email::deserialize_email;
+
 use serde::Deserialize;
 
 /// Request payload for user authentication.
@@ -8,3 +10,162 @@ pub struct LoginRequest {
     /// User's password in plain text
     pub password: String,
 }
+
+/// Request payload for public self-registration.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterRequest {
+    pub username: String,
+    #[serde(deserialize_with = "deserialize_email")]
+    pub email: String,
+    /// Password in plain text, checked against the password policy
+    pub password: String,
+    pub real_name: Option<String>,
+}
+
+/// Request payload for exchanging service account credentials for an access token.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceTokenRequest {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Callback parameters returned by the identity provider after an SSO login.
+#[derive(Deserialize)]
+pub struct OidcCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+/// Request payload for exchanging a refresh token.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenRequest {
+    /// Refresh token issued by login or a previous refresh
+    pub refresh_token: String,
+}
+
+/// Request payload for the second step of an MFA login.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaVerifyRequest {
+    /// MFA pending token returned by the password step
+    pub mfa_token: String,
+    /// TOTP code or one-time recovery code
+    pub code: String,
+}
+
+/// Request payload carrying a TOTP (or recovery) code.
+#[derive(Deserialize)]
+pub struct MfaCodeRequest {
+    /// TOTP code or one-time recovery code
+    pub code: String,
+}
+
+/// Request payload for updating the current user's profile.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProfileRequest {
+    #[serde(deserialize_with = "deserialize_email")]
+    pub email: String,
+    pub real_name: Option<String>,
+}
+
+/// Request payload for changing the current user's password.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    /// Current password, re-checked before the change
+    pub current_password: String,
+    /// New password, checked against the password policy
+    pub new_password: String,
+}
+
+/// Request payload for requesting a password reset email.
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+/// Request payload for resetting a password with an emailed token.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordRequest {
+    /// Token from the password reset email
+    pub token: String,
+    /// New password, checked against the password policy
+    pub new_password: String,
+}
+
+/// Request payload for verifying an email with an emailed token.
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    /// Token from the verification email
+    pub token: String,
+}
+
+/// Request payload for registering a passkey.
+#[derive(Deserialize)]
+pub struct PasskeyRegisterRequest {
+    /// Display name of the passkey, e.g. "MacBook Touch ID"
+    pub name: Option<String>,
+    /// `PublicKeyCredential` returned by `navigator.credentials.create`
+    pub credential: PasskeyCredential<AttestationResponse>,
+}
+
+/// Request payload for starting a passkey login.
+#[derive(Deserialize, Default)]
+pub struct PasskeyLoginOptionsRequest {
+    /// Restrict the login to the passkeys of this user (omit for discoverable passkeys)
+    pub username: Option<String>,
+}
+
+/// Request payload for completing a passkey login.
+#[derive(Deserialize)]
+pub struct PasskeyLoginRequest {
+    /// `PublicKeyCredential` returned by `navigator.credentials.get`
+    pub credential: PasskeyCredential<AssertionResponse>,
+}
+
+/// Request payload for creating an API key.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    /// Display name of the key, e.g. "CI deploy"
+    pub name: String,
+    /// Permission codes granted to the key, each held by the key's owner
+    pub scopes: Vec<String>,
+    /// Lifetime of the key in days, capped by the configured maximum
+    pub expires_in_days: i64,
+}
+
+/// `PublicKeyCredential` serialized by the browser, binary fields as base64url.
+#[derive(Deserialize)]
+pub struct PasskeyCredential<T> {
+    /// Base64url credential ID
+    pub id: String,
+    /// Authenticator response
+    pub response: T,
+}
+
+/// `AuthenticatorAttestationResponse` fields, as base64url.
+#[derive(Deserialize)]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    pub attestation_object: String,
+}
+
+/// `AuthenticatorAssertionResponse` fields, as base64url.
+#[derive(Deserialize)]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    pub authenticator_data: String,
+    pub signature: String,
+    #[serde(rename = "userHandle")]
+    pub user_handle: Option<String>,
+}
@@ -1,6 +1,9 @@
 use crate::common::error::ServiceError;
 
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use uuid::Uuid;
 
 /// Minimal user info for authentication (login)
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -9,6 +12,8 @@ pub struct LoginCredentialsEntity {
     pub password_hash: String,
     pub status: i16,
     pub is_system: bool,
+    pub failed_login_count: i32,
+    pub locked_until: Option<NaiveDateTime>,
 }
 
 /// Basic user info for session/profile
@@ -22,6 +27,146 @@ pub struct AuthUserEntity {
     pub is_system: bool,
 }
 
+/// Profile fields the user can see and edit
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ProfileEntity {
+    pub id: i64,
+    pub username: String,
+    pub email: String,
+    pub real_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub last_login_at: Option<NaiveDateTime>,
+    pub password_changed_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// User looked up by email for account recovery
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EmailUserEntity {
+    pub id: i64,
+    pub username: String,
+    pub email: String,
+    pub status: i16,
+}
+
+/// Valid (unused, unexpired) emailed token
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ActionTokenEntity {
+    pub user_id: i64,
+    pub email: String,
+}
+
+/// Current password and its policy state
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PasswordStateEntity {
+    pub username: String,
+    pub password_hash: String,
+    pub must_change_password: bool,
+    pub password_changed_at: Option<NaiveDateTime>,
+}
+
+/// Stored refresh token (hash only) and its rotation state
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RefreshTokenEntity {
+    pub user_id: i64,
+    pub family_id: Uuid,
+    pub used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+}
+
+/// Server-side login session
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserSessionEntity {
+    pub id: Uuid,
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub expires_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub impersonator_username: Option<String>,
+}
+
+/// Active session with the password state of its user, checked on every request
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ActiveSessionEntity {
+    pub last_seen_at: NaiveDateTime,
+    pub must_change_password: bool,
+    pub password_changed_at: Option<NaiveDateTime>,
+}
+
+/// TOTP enrollment of a user
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserMfaEntity {
+    pub secret: String,
+    pub enabled: bool,
+}
+
+/// Unused recovery code (hash only)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RecoveryCodeEntity {
+    pub id: i64,
+    pub code_hash: String,
+}
+
+/// Registered WebAuthn credential
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PasskeyEntity {
+    pub id: i64,
+    pub user_id: i64,
+    pub credential_id: String,
+    pub public_key: Vec<u8>,
+    pub sign_count: i64,
+    pub name: String,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// API key (hash never selected)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiKeyEntity {
+    pub id: i64,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub last_used_ip: Option<IpAddr>,
+    pub created_by: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Valid API key with its owner, checked on every request made with the key
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiKeyAuthEntity {
+    pub id: i64,
+    pub user_id: i64,
+    pub username: String,
+    pub is_system: bool,
+    pub scopes: Vec<String>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub last_used_ip: Option<IpAddr>,
+}
+
+/// Active client credential of an active service account
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ServiceCredentialEntity {
+    pub id: i64,
+    pub user_id: i64,
+    pub username: String,
+    pub secret_hash: String,
+}
+
+/// User linked to an external identity provider account
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct IdentityUserEntity {
+    pub user_id: i64,
+    pub username: String,
+    pub status: i16,
+    pub is_system: bool,
+}
+
 /// User status enum for authentication and account control.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UserStatus {
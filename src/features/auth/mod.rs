@@ -1,5 +1,6 @@
 pub mod dto;
 pub mod entity;
+pub mod provider;
 pub mod repo;
 pub mod router;
 pub mod service;
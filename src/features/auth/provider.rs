@@ -0,0 +1,163 @@
+use super::{entity::LoginCredentialsEntity, repo::AuthRepository, service::AuthService};
+use crate::{
+    common::error::ServiceError,
+    core::{
+        config::CONFIG,
+        ldap::LDAP_CLIENT,
+        password::{NO_PASSWORD_HASH, PasswordUtils},
+    },
+    features::system::user::repo::UserRepository,
+};
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use sqlx::PgPool;
+
+/// Password login backend, tried in the configured order by [`AuthService::verify_login`].
+///
+/// Throttling, lockout and status checks stay in `verify_login`, so every
+/// provider is subject to them.
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    /// Name in the `auth_providers` setting
+    fn name(&self) -> &'static str;
+
+    /// Check a password. `user` holds the local credentials of the username,
+    /// if the user exists. Returns the ID of the authenticated user, `None`
+    /// when this provider does not accept the login.
+    async fn authenticate(
+        &self,
+        pool: &PgPool,
+        username: &str,
+        password: &str,
+        user: Option<&LoginCredentialsEntity>,
+    ) -> Result<Option<i64>, ServiceError>;
+}
+
+/// Local users with Argon2 password hashes
+pub struct LocalProvider;
+
+#[async_trait]
+impl AuthProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn authenticate(
+        &self,
+        _pool: &PgPool,
+        _username: &str,
+        password: &str,
+        user: Option<&LoginCredentialsEntity>,
+    ) -> Result<Option<i64>, ServiceError> {
+        Ok(user
+            .filter(|user| PasswordUtils::verify_password(password, &user.password_hash))
+            .map(|user| user.id))
+    }
+}
+
+/// LDAP / Active Directory bind.
+///
+/// Directory entries are linked to users in `user_identities`, keyed by the
+/// server URL and the entry DN. Unknown usernames are provisioned when
+/// enabled; an existing local user is never taken over by a directory entry
+/// of the same name.
+pub struct LdapProvider;
+
+#[async_trait]
+impl AuthProvider for LdapProvider {
+    fn name(&self) -> &'static str {
+        "ldap"
+    }
+
+    async fn authenticate(
+        &self,
+        pool: &PgPool,
+        username: &str,
+        password: &str,
+        user: Option<&LoginCredentialsEntity>,
+    ) -> Result<Option<i64>, ServiceError> {
+        let entry = match LDAP_CLIENT.authenticate(username, password).await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                // An unreachable directory must not block the other providers
+                tracing::error!("LDAP authentication failed for username={}: {}", username, e);
+                return Ok(None);
+            }
+        };
+
+        // 1. find or provision the linked user
+        let directory = LDAP_CLIENT.url();
+        let email = entry.email.as_deref();
+        let user_id = match AuthRepository::find_identity_user(pool, directory, &entry.dn).await? {
+            Some(linked) if linked.username == username => {
+                AuthRepository::upsert_identity(pool, linked.user_id, directory, &entry.dn, email)
+                    .await?;
+                linked.user_id
+            }
+            Some(linked) => {
+                tracing::warn!("LDAP entry {} is linked to user {}", entry.dn, linked.username);
+                return Ok(None);
+            }
+            None if user.is_some() => {
+                tracing::warn!("Local user {} is not linked to LDAP entry {}", username, entry.dn);
+                return Ok(None);
+            }
+            None if !CONFIG.ldap_auto_provision => {
+                tracing::warn!("No user linked to LDAP entry {}", entry.dn);
+                return Ok(None);
+            }
+            None => {
+                let Some(email) = email else {
+                    tracing::warn!("LDAP entry {} has no email, not provisioned", entry.dn);
+                    return Ok(None);
+                };
+                // Service accounts have no login credentials but still hold their username
+                if UserRepository::username_exists(pool, username).await?
+                    || UserRepository::email_exists(pool, email).await?
+                {
+                    tracing::warn!("User of LDAP entry {} exists, not provisioned", entry.dn);
+                    return Ok(None);
+                }
+                let user_id = AuthRepository::create_external_user(
+                    pool,
+                    username,
+                    email,
+                    false,
+                    entry.real_name.as_deref(),
+                    NO_PASSWORD_HASH,
+                    directory,
+                    &entry.dn,
+                )
+                .await?;
+                tracing::info!("Provisioned user_id={} for LDAP entry {}", user_id, entry.dn);
+                user_id
+            }
+        };
+
+        // 2. apply the group to role mapping
+        if !user.is_some_and(|user| user.is_system) {
+            AuthService::sync_mapped_roles(pool, user_id, &entry.groups, &CONFIG.ldap_role_mapping)
+                .await?;
+        }
+        Ok(Some(user_id))
+    }
+}
+
+/// Password login providers in the configured order, local only by default
+pub static AUTH_PROVIDERS: Lazy<Vec<Box<dyn AuthProvider>>> = Lazy::new(|| {
+    let mut providers: Vec<Box<dyn AuthProvider>> = Vec::new();
+    for name in CONFIG.auth_providers.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        match name {
+            "local" => providers.push(Box::new(LocalProvider)),
+            "ldap" => providers.push(Box::new(LdapProvider)),
+            other => tracing::warn!("Unknown auth provider '{}', ignored", other),
+        }
+    }
+    if providers.is_empty() {
+        tracing::warn!("No auth provider configured, falling back to local");
+        providers.push(Box::new(LocalProvider));
+    }
+    providers
+});
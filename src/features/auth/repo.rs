@@ -1,8 +1,14 @@
-use super::entity::{AuthUserEntity, LoginCredentialsEntity};
-use crate::common::error::ServiceError;
+use super::entity::{
+    ActionTokenEntity, ActiveSessionEntity, ApiKeyAuthEntity, ApiKeyEntity, AuthUserEntity,
+    EmailUserEntity, IdentityUserEntity, LoginCredentialsEntity, PasskeyEntity,
+    PasswordStateEntity, ProfileEntity, RecoveryCodeEntity, RefreshTokenEntity,
+    ServiceCredentialEntity, UserMfaEntity, UserSessionEntity,
+};
+use crate::{common::error::ServiceError, features::system::user::repo::UserRepository};
 
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use sqlx::PgPool;
+use uuid::Uuid;
 
 /// Auth repository for authentication-specific database operations
 pub struct AuthRepository;
@@ -57,6 +63,114 @@ impl AuthRepository {
         Ok(())
     }
 
+    /// Record a failed login and lock the user once `max_attempts` is reached.
+    /// Returns the user status after the update.
+    pub async fn record_failed_login(
+        pool: &PgPool,
+        id: i64,
+        max_attempts: i32,
+        locked_until: Option<NaiveDateTime>,
+    ) -> Result<i16, ServiceError> {
+        sqlx::query_scalar(
+            "UPDATE users
+             SET failed_login_count = failed_login_count + 1,
+                 status = CASE WHEN failed_login_count + 1 >= $2 THEN 4 ELSE status END,
+                 locked_until = CASE WHEN failed_login_count + 1 >= $2 THEN $3 ELSE locked_until END
+             WHERE id = $1
+             RETURNING status",
+        )
+        .bind(id)
+        .bind(max_attempts)
+        .bind(locked_until)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in record_failed_login, user_id={}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Reset the failed login counter after a successful login
+    pub async fn reset_failed_logins(pool: &PgPool, id: i64) -> Result<(), ServiceError> {
+        sqlx::query("UPDATE users SET failed_login_count = 0 WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error in reset_failed_logins, user_id={}: {:?}", id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+        Ok(())
+    }
+
+    /// Clear the failed logins and lock of a user after a password reset,
+    /// setting the status `next_status` picks from the current one.
+    /// Returns the username, or `None` if the user is gone.
+    pub async fn finish_password_reset(
+        pool: &PgPool,
+        id: i64,
+        next_status: impl FnOnce(i16) -> i16,
+    ) -> Result<Option<String>, ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for password reset: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        let user: Option<(String, i16)> = sqlx::query_as(
+            "SELECT username, status FROM users WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in finish_password_reset, user_id={}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        let Some((username, status)) = user else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            "UPDATE users
+             SET status = $2, failed_login_count = 0, locked_until = NULL, updated_at = $3
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(next_status(status))
+        .bind(Utc::now().naive_utc())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in finish_password_reset, user_id={}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing password reset: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(Some(username))
+    }
+
+    /// Unlock a user whose timed lock has expired.
+    /// Returns false if the user is not locked or the lock is still running.
+    pub async fn unlock_expired_lock(pool: &PgPool, id: i64) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE users
+             SET status = 1, failed_login_count = 0, locked_until = NULL, updated_at = $2
+             WHERE id = $1 AND status = 4 AND locked_until <= $2",
+        )
+        .bind(id)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in unlock_expired_lock, user_id={}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Get all permission keys for a
     /// user by user ID.
     /// Returns a list of permission strings (e.g., "system:user:list").
@@ -78,6 +192,24 @@ impl AuthRepository {
             })
     }
 
+    /// Permission versions of existing users
+    pub async fn get_permission_versions(
+        pool: &PgPool,
+        user_ids: &[i64],
+    ) -> Result<Vec<(i64, i64)>, ServiceError> {
+        sqlx::query_as(
+            "SELECT id, permission_version FROM users
+             WHERE id = ANY($1) AND deleted_at IS NULL",
+        )
+        .bind(user_ids)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in get_permission_versions: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
     pub async fn update_avatar(
         pool: &PgPool,
         user_id: i64,
@@ -94,4 +226,1367 @@ impl AuthRepository {
             })?;
         Ok(())
     }
+
+    /// Get the profile of a user
+    pub async fn get_profile(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Option<ProfileEntity>, ServiceError> {
+        sqlx::query_as::<_, ProfileEntity>(
+            "SELECT id, username, email, real_name, avatar_url, email_verified_at, last_login_at,
+                    password_changed_at, created_at
+             FROM users WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in get_profile, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Update the email and real name of a user
+    pub async fn update_profile(
+        pool: &PgPool,
+        user_id: i64,
+        email: &str,
+        real_name: Option<&str>,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE users
+             SET email = $1, real_name = $2, updated_at = $3,
+                 email_verified_at = CASE WHEN email = $1 THEN email_verified_at END
+             WHERE id = $4 AND deleted_at IS NULL",
+        )
+        .bind(email)
+        .bind(real_name)
+        .bind(Utc::now().naive_utc())
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in update_profile, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Check if an email is used by another user
+    pub async fn email_used_by_other(
+        pool: &PgPool,
+        email: &str,
+        user_id: i64,
+    ) -> Result<bool, ServiceError> {
+        sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1 AND id <> $2 AND deleted_at IS NULL)",
+        )
+        .bind(email)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in email_used_by_other, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Store a new refresh token hash in the given family
+    pub async fn create_refresh_token(
+        pool: &PgPool,
+        user_id: i64,
+        family_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), ServiceError> {
+        sqlx::query(
+            "INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at, created_at)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(user_id)
+        .bind(family_id)
+        .bind(token_hash)
+        .bind(expires_at)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in create_refresh_token, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(())
+    }
+
+    /// Atomically mark a current (unused, unrevoked, unexpired) refresh token as used.
+    /// Returns None if the token cannot be rotated.
+    pub async fn use_refresh_token(
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<Option<RefreshTokenEntity>, ServiceError> {
+        let now = Utc::now().naive_utc();
+        sqlx::query_as::<_, RefreshTokenEntity>(
+            "UPDATE refresh_tokens SET used_at = $1
+             WHERE token_hash = $2 AND used_at IS NULL AND revoked_at IS NULL AND expires_at > $1
+             RETURNING user_id, family_id, used_at, revoked_at, expires_at",
+        )
+        .bind(now)
+        .bind(token_hash)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in use_refresh_token: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Find a refresh token by hash regardless of its state
+    pub async fn find_refresh_token(
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<Option<RefreshTokenEntity>, ServiceError> {
+        sqlx::query_as::<_, RefreshTokenEntity>(
+            "SELECT user_id, family_id, used_at, revoked_at, expires_at
+             FROM refresh_tokens WHERE token_hash = $1",
+        )
+        .bind(token_hash)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_refresh_token: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Revoke every token of a refresh token family
+    pub async fn revoke_refresh_token_family(
+        pool: &PgPool,
+        family_id: Uuid,
+    ) -> Result<u64, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = $1 WHERE family_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(Utc::now().naive_utc())
+        .bind(family_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in revoke_refresh_token_family, family_id={}: {:?}",
+                family_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected())
+    }
+
+    /// Revoke all refresh tokens of a user
+    pub async fn revoke_user_refresh_tokens(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<u64, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = $1 WHERE user_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(Utc::now().naive_utc())
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in revoke_user_refresh_tokens, user_id={}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected())
+    }
+
+    /// Delete expired refresh tokens of a user
+    pub async fn delete_expired_refresh_tokens(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<(), ServiceError> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE user_id = $1 AND expires_at < $2")
+            .bind(user_id)
+            .bind(Utc::now().naive_utc())
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    "Database error in delete_expired_refresh_tokens, user_id={}: {:?}",
+                    user_id,
+                    e
+                );
+                ServiceError::DatabaseQueryFailed
+            })?;
+        Ok(())
+    }
+
+    /// Whether an MFA pending token already completed a login
+    pub async fn is_mfa_token_used(pool: &PgPool, jti: Uuid) -> Result<bool, ServiceError> {
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM used_mfa_tokens WHERE jti = $1)")
+            .bind(jti)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error in is_mfa_token_used: {:?}", e);
+                ServiceError::DatabaseQueryFailed
+            })
+    }
+
+    /// Record that an MFA pending token completed a login, dropping the expired
+    /// records of the user. Returns false if the token was already used.
+    pub async fn use_mfa_token(
+        pool: &PgPool,
+        jti: Uuid,
+        user_id: i64,
+        expires_at: NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let now = Utc::now().naive_utc();
+        sqlx::query("DELETE FROM used_mfa_tokens WHERE user_id = $1 AND expires_at < $2")
+            .bind(user_id)
+            .bind(now)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error in use_mfa_token, user_id={}: {:?}", user_id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+
+        let result = sqlx::query(
+            "INSERT INTO used_mfa_tokens (jti, user_id, expires_at, used_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (jti) DO NOTHING",
+        )
+        .bind(jti)
+        .bind(user_id)
+        .bind(expires_at)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in use_mfa_token, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Create a new login session
+    pub async fn create_session(
+        pool: &PgPool,
+        session_id: Uuid,
+        user_id: i64,
+        ip_address: &str,
+        user_agent: &str,
+        expires_at: NaiveDateTime,
+        impersonator_id: Option<i64>,
+    ) -> Result<(), ServiceError> {
+        let now = Utc::now().naive_utc();
+        sqlx::query(
+            "INSERT INTO user_sessions (id, user_id, ip_address, user_agent, expires_at, last_seen_at, created_at, impersonator_id)
+             VALUES ($1, $2, $3::inet, $4, $5, $6, $6, $7)",
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(ip_address)
+        .bind(user_agent)
+        .bind(expires_at)
+        .bind(now)
+        .bind(impersonator_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in create_session, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(())
+    }
+
+    /// Extend an active session after a refresh.
+    /// Returns false if the session is no longer active.
+    pub async fn extend_session(
+        pool: &PgPool,
+        session_id: Uuid,
+        ip_address: &str,
+        user_agent: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE user_sessions
+             SET ip_address = $2::inet, user_agent = $3, expires_at = $4, last_seen_at = $5
+             WHERE id = $1 AND revoked_at IS NULL",
+        )
+        .bind(session_id)
+        .bind(ip_address)
+        .bind(user_agent)
+        .bind(expires_at)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in extend_session, session_id={}: {:?}", session_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get an active (not revoked, not expired) session of an enabled user with
+    /// the user's password state
+    pub async fn find_active_session(
+        pool: &PgPool,
+        session_id: Uuid,
+        user_id: i64,
+        impersonator_id: Option<i64>,
+    ) -> Result<Option<ActiveSessionEntity>, ServiceError> {
+        sqlx::query_as::<_, ActiveSessionEntity>(
+            "SELECT s.last_seen_at, u.must_change_password, u.password_changed_at
+             FROM user_sessions s
+             JOIN users u ON u.id = s.user_id AND u.status = 1 AND u.deleted_at IS NULL
+             WHERE s.id = $1 AND s.user_id = $2 AND s.revoked_at IS NULL AND s.expires_at > $3
+               AND s.impersonator_id IS NOT DISTINCT FROM $4
+               AND (s.impersonator_id IS NULL OR EXISTS (
+                   SELECT 1 FROM users a
+                   WHERE a.id = s.impersonator_id AND a.status = 1 AND a.deleted_at IS NULL
+               ))",
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(Utc::now().naive_utc())
+        .bind(impersonator_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in find_active_session, session_id={}: {:?}",
+                session_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Record activity on a session
+    pub async fn touch_session(pool: &PgPool, session_id: Uuid) -> Result<(), ServiceError> {
+        sqlx::query("UPDATE user_sessions SET last_seen_at = $1 WHERE id = $2")
+            .bind(Utc::now().naive_utc())
+            .bind(session_id)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    "Database error in touch_session, session_id={}: {:?}",
+                    session_id,
+                    e
+                );
+                ServiceError::DatabaseQueryFailed
+            })?;
+        Ok(())
+    }
+
+    /// List active sessions of a user, most recently used first
+    pub async fn find_active_sessions(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Vec<UserSessionEntity>, ServiceError> {
+        sqlx::query_as::<_, UserSessionEntity>(
+            "SELECT s.id, s.ip_address, s.user_agent, s.expires_at, s.last_seen_at, s.created_at,
+                    a.username AS impersonator_username
+             FROM user_sessions s
+             LEFT JOIN users a ON a.id = s.impersonator_id
+             WHERE s.user_id = $1 AND s.revoked_at IS NULL AND s.expires_at > $2
+             ORDER BY s.last_seen_at DESC",
+        )
+        .bind(user_id)
+        .bind(Utc::now().naive_utc())
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_active_sessions, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Revoke one session of a user. Returns false if it was not active.
+    pub async fn revoke_session(
+        pool: &PgPool,
+        user_id: i64,
+        session_id: Uuid,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE user_sessions SET revoked_at = $1
+             WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL",
+        )
+        .bind(Utc::now().naive_utc())
+        .bind(session_id)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in revoke_session, session_id={}: {:?}", session_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revoke all sessions of a user, including the user's impersonations of others
+    pub async fn revoke_user_sessions(pool: &PgPool, user_id: i64) -> Result<u64, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE user_sessions SET revoked_at = $1
+             WHERE (user_id = $2 OR impersonator_id = $2) AND revoked_at IS NULL",
+        )
+        .bind(Utc::now().naive_utc())
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in revoke_user_sessions, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected())
+    }
+
+    /// Revoke all sessions of a user except one
+    pub async fn revoke_other_sessions(
+        pool: &PgPool,
+        user_id: i64,
+        keep_session_id: Uuid,
+    ) -> Result<u64, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE user_sessions SET revoked_at = $1
+             WHERE user_id = $2 AND id <> $3 AND revoked_at IS NULL",
+        )
+        .bind(Utc::now().naive_utc())
+        .bind(user_id)
+        .bind(keep_session_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in revoke_other_sessions, user_id={}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected())
+    }
+
+    /// Revoke all refresh tokens of a user except those of one family
+    pub async fn revoke_other_refresh_tokens(
+        pool: &PgPool,
+        user_id: i64,
+        keep_family_id: Uuid,
+    ) -> Result<u64, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = $1
+             WHERE user_id = $2 AND family_id <> $3 AND revoked_at IS NULL",
+        )
+        .bind(Utc::now().naive_utc())
+        .bind(user_id)
+        .bind(keep_family_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in revoke_other_refresh_tokens, user_id={}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected())
+    }
+
+    /// Count active sessions of a user
+    pub async fn count_active_sessions(pool: &PgPool, user_id: i64) -> Result<i64, ServiceError> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM user_sessions
+             WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2",
+        )
+        .bind(user_id)
+        .bind(Utc::now().naive_utc())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in count_active_sessions, user_id={}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Find the TOTP enrollment of a user
+    pub async fn find_user_mfa(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Option<UserMfaEntity>, ServiceError> {
+        sqlx::query_as::<_, UserMfaEntity>(
+            "SELECT secret, enabled FROM user_mfa WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_user_mfa, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Store a pending (unconfirmed) TOTP secret, replacing a previous pending one.
+    /// Returns false if the user already has MFA enabled.
+    pub async fn save_pending_mfa(
+        pool: &PgPool,
+        user_id: i64,
+        secret: &str,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "INSERT INTO user_mfa (user_id, secret, enabled, created_at, updated_at)
+             VALUES ($1, $2, FALSE, $3, $3)
+             ON CONFLICT (user_id) DO UPDATE
+             SET secret = EXCLUDED.secret, last_used_step = NULL, updated_at = EXCLUDED.updated_at
+             WHERE user_mfa.enabled = FALSE",
+        )
+        .bind(user_id)
+        .bind(secret)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in save_pending_mfa, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Confirm a pending enrollment, recording the step of the confirming code
+    pub async fn enable_mfa(pool: &PgPool, user_id: i64, step: i64) -> Result<bool, ServiceError> {
+        let now = Utc::now().naive_utc();
+        let result = sqlx::query(
+            "UPDATE user_mfa
+             SET enabled = TRUE, enabled_at = $3, last_used_step = $2, updated_at = $3
+             WHERE user_id = $1 AND enabled = FALSE",
+        )
+        .bind(user_id)
+        .bind(step)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in enable_mfa, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Accept a TOTP time step unless it (or a later one) was already used
+    pub async fn use_totp_step(
+        pool: &PgPool,
+        user_id: i64,
+        step: i64,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE user_mfa SET last_used_step = $2
+             WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)",
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in use_totp_step, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove the TOTP enrollment and recovery codes of a user
+    pub async fn delete_user_mfa(pool: &PgPool, user_id: i64) -> Result<bool, ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for MFA removal: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        let result = sqlx::query("DELETE FROM user_mfa WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error deleting user_mfa, user_id={}: {:?}", user_id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    "Database error deleting user_recovery_codes, user_id={}: {:?}",
+                    user_id,
+                    e
+                );
+                ServiceError::DatabaseQueryFailed
+            })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing MFA removal transaction: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Replace all recovery codes of a user with new hashes
+    pub async fn replace_recovery_codes(
+        pool: &PgPool,
+        user_id: i64,
+        code_hashes: &[String],
+    ) -> Result<(), ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for recovery codes: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    "Database error deleting user_recovery_codes, user_id={}: {:?}",
+                    user_id,
+                    e
+                );
+                ServiceError::DatabaseQueryFailed
+            })?;
+        sqlx::query(
+            "INSERT INTO user_recovery_codes (user_id, code_hash, created_at)
+             SELECT $1, code_hash, $3 FROM UNNEST($2::VARCHAR[]) AS code_hash",
+        )
+        .bind(user_id)
+        .bind(code_hashes)
+        .bind(Utc::now().naive_utc())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error inserting user_recovery_codes, user_id={}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing recovery codes transaction: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(())
+    }
+
+    /// Find unused recovery codes of a user
+    pub async fn find_unused_recovery_codes(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Vec<RecoveryCodeEntity>, ServiceError> {
+        sqlx::query_as::<_, RecoveryCodeEntity>(
+            "SELECT id, code_hash FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in find_unused_recovery_codes, user_id={}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Mark a recovery code as used. Returns false if it was used concurrently.
+    pub async fn use_recovery_code(pool: &PgPool, id: i64) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE user_recovery_codes SET used_at = $2 WHERE id = $1 AND used_at IS NULL",
+        )
+        .bind(id)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in use_recovery_code, id={}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Count unused recovery codes of a user
+    pub async fn count_unused_recovery_codes(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<i64, ServiceError> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in count_unused_recovery_codes, user_id={}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Store a newly registered passkey
+    pub async fn create_passkey(
+        pool: &PgPool,
+        user_id: i64,
+        credential_id: &str,
+        public_key: &[u8],
+        sign_count: i64,
+        name: &str,
+    ) -> Result<PasskeyEntity, ServiceError> {
+        sqlx::query_as::<_, PasskeyEntity>(
+            "INSERT INTO user_passkeys (user_id, credential_id, public_key, sign_count, name, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id, user_id, credential_id, public_key, sign_count, name, last_used_at, created_at",
+        )
+        .bind(user_id)
+        .bind(credential_id)
+        .bind(public_key)
+        .bind(sign_count)
+        .bind(name)
+        .bind(Utc::now().naive_utc())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in create_passkey, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Find passkeys of a user, newest first
+    pub async fn find_passkeys(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Vec<PasskeyEntity>, ServiceError> {
+        sqlx::query_as::<_, PasskeyEntity>(
+            "SELECT id, user_id, credential_id, public_key, sign_count, name, last_used_at, created_at
+             FROM user_passkeys WHERE user_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_passkeys, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Find a passkey by its credential ID
+    pub async fn find_passkey_by_credential_id(
+        pool: &PgPool,
+        credential_id: &str,
+    ) -> Result<Option<PasskeyEntity>, ServiceError> {
+        sqlx::query_as::<_, PasskeyEntity>(
+            "SELECT id, user_id, credential_id, public_key, sign_count, name, last_used_at, created_at
+             FROM user_passkeys WHERE credential_id = $1",
+        )
+        .bind(credential_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_passkey_by_credential_id: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Record a successful passkey login
+    pub async fn update_passkey_usage(
+        pool: &PgPool,
+        id: i64,
+        sign_count: i64,
+    ) -> Result<(), ServiceError> {
+        sqlx::query("UPDATE user_passkeys SET sign_count = $2, last_used_at = $3 WHERE id = $1")
+            .bind(id)
+            .bind(sign_count)
+            .bind(Utc::now().naive_utc())
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error in update_passkey_usage, id={}: {:?}", id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+        Ok(())
+    }
+
+    /// Delete a passkey of a user
+    pub async fn delete_passkey(
+        pool: &PgPool,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query("DELETE FROM user_passkeys WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error in delete_passkey, id={}: {:?}", id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the current password hash and policy state of a user
+    pub async fn get_password_state(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Option<PasswordStateEntity>, ServiceError> {
+        sqlx::query_as::<_, PasswordStateEntity>(
+            "SELECT username, password_hash, must_change_password, password_changed_at
+             FROM users WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in get_password_state, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Get the most recent previous password hashes of a user
+    pub async fn find_password_history(
+        pool: &PgPool,
+        user_id: i64,
+        limit: i64,
+    ) -> Result<Vec<String>, ServiceError> {
+        sqlx::query_scalar(
+            "SELECT password_hash FROM password_history
+             WHERE user_id = $1 ORDER BY created_at DESC, id DESC LIMIT $2",
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error in find_password_history, user_id={}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Replace the password of a user, moving the old hash into the history
+    /// and keeping at most `history_limit` history entries
+    pub async fn update_password(
+        pool: &PgPool,
+        user_id: i64,
+        password_hash: &str,
+        must_change_password: bool,
+        history_limit: i64,
+    ) -> Result<bool, ServiceError> {
+        let now = Utc::now().naive_utc();
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction in update_password: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        sqlx::query(
+            "INSERT INTO password_history (user_id, password_hash, created_at)
+             SELECT id, password_hash, $2 FROM users WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in update_password, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        let result = sqlx::query(
+            "UPDATE users
+             SET password_hash = $1, password_changed_at = $2, must_change_password = $3, updated_at = $2
+             WHERE id = $4 AND deleted_at IS NULL",
+        )
+        .bind(password_hash)
+        .bind(now)
+        .bind(must_change_password)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in update_password, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        sqlx::query(
+            "DELETE FROM password_history WHERE id IN (
+                 SELECT id FROM password_history
+                 WHERE user_id = $1 ORDER BY created_at DESC, id DESC OFFSET $2
+             )",
+        )
+        .bind(user_id)
+        .bind(history_limit)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in update_password, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing update_password: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Find a user by email
+    pub async fn find_user_by_email(
+        pool: &PgPool,
+        email: &str,
+    ) -> Result<Option<EmailUserEntity>, ServiceError> {
+        sqlx::query_as::<_, EmailUserEntity>(
+            "SELECT id, username, email, status FROM users
+             WHERE email = $1 AND user_type = 1 AND deleted_at IS NULL",
+        )
+        .bind(email)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_user_by_email: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Get when the last token for a purpose was issued to a user
+    pub async fn last_action_token_at(
+        pool: &PgPool,
+        user_id: i64,
+        purpose: &str,
+    ) -> Result<Option<NaiveDateTime>, ServiceError> {
+        sqlx::query_scalar(
+            "SELECT MAX(created_at) FROM user_action_tokens WHERE user_id = $1 AND purpose = $2",
+        )
+        .bind(user_id)
+        .bind(purpose)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in last_action_token_at, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Store a new emailed token, superseding the unused tokens of the same purpose
+    pub async fn create_action_token(
+        pool: &PgPool,
+        user_id: i64,
+        purpose: &str,
+        token_hash: &str,
+        email: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), ServiceError> {
+        let now = Utc::now().naive_utc();
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction in create_action_token: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        sqlx::query(
+            "UPDATE user_action_tokens SET used_at = $3
+             WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(purpose)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in create_action_token, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        sqlx::query(
+            "INSERT INTO user_action_tokens (user_id, purpose, token_hash, email, expires_at, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(user_id)
+        .bind(purpose)
+        .bind(token_hash)
+        .bind(email)
+        .bind(expires_at)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in create_action_token, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing create_action_token: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(())
+    }
+
+    /// Find an unused, unexpired emailed token
+    pub async fn find_action_token(
+        pool: &PgPool,
+        purpose: &str,
+        token_hash: &str,
+    ) -> Result<Option<ActionTokenEntity>, ServiceError> {
+        sqlx::query_as::<_, ActionTokenEntity>(
+            "SELECT user_id, email FROM user_action_tokens
+             WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > $3",
+        )
+        .bind(token_hash)
+        .bind(purpose)
+        .bind(Utc::now().naive_utc())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_action_token: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Atomically mark an unused, unexpired emailed token as used.
+    /// Returns `None` if the token is unknown, expired or already used.
+    pub async fn use_action_token(
+        pool: &PgPool,
+        purpose: &str,
+        token_hash: &str,
+    ) -> Result<Option<ActionTokenEntity>, ServiceError> {
+        sqlx::query_as::<_, ActionTokenEntity>(
+            "UPDATE user_action_tokens SET used_at = $3
+             WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > $3
+             RETURNING user_id, email",
+        )
+        .bind(token_hash)
+        .bind(purpose)
+        .bind(Utc::now().naive_utc())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in use_action_token: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Mark the email of a user as verified, if it is still the given email
+    pub async fn mark_email_verified(
+        pool: &PgPool,
+        user_id: i64,
+        email: &str,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE users SET email_verified_at = $3
+             WHERE id = $1 AND email = $2 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(email)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in mark_email_verified, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Store a new API key
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_api_key(
+        pool: &PgPool,
+        user_id: i64,
+        name: &str,
+        prefix: &str,
+        key_hash: &str,
+        scopes: &[String],
+        expires_at: NaiveDateTime,
+        created_by: i64,
+    ) -> Result<ApiKeyEntity, ServiceError> {
+        sqlx::query_as::<_, ApiKeyEntity>(
+            "INSERT INTO api_keys (user_id, name, prefix, key_hash, scopes, expires_at, created_by, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             RETURNING id, name, prefix, scopes, expires_at, last_used_at, last_used_ip, created_by, created_at",
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(prefix)
+        .bind(key_hash)
+        .bind(scopes)
+        .bind(expires_at)
+        .bind(created_by)
+        .bind(Utc::now().naive_utc())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in create_api_key, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Find unrevoked API keys of a user (including expired ones), newest first
+    pub async fn find_api_keys(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Vec<ApiKeyEntity>, ServiceError> {
+        sqlx::query_as::<_, ApiKeyEntity>(
+            "SELECT id, name, prefix, scopes, expires_at, last_used_at, last_used_ip, created_by, created_at
+             FROM api_keys WHERE user_id = $1 AND revoked_at IS NULL ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_api_keys, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Revoke an API key of a user. Returns false if it was not active.
+    pub async fn revoke_api_key(
+        pool: &PgPool,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE api_keys SET revoked_at = $1
+             WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL",
+        )
+        .bind(Utc::now().naive_utc())
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in revoke_api_key, id={}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Find a valid (unrevoked, unexpired) API key of an active user by its hash
+    pub async fn find_api_key_for_auth(
+        pool: &PgPool,
+        key_hash: &str,
+    ) -> Result<Option<ApiKeyAuthEntity>, ServiceError> {
+        sqlx::query_as::<_, ApiKeyAuthEntity>(
+            "SELECT k.id, k.user_id, u.username, u.is_system, k.scopes, k.last_used_at, k.last_used_ip
+             FROM api_keys k
+             JOIN users u ON u.id = k.user_id
+             WHERE k.key_hash = $1 AND k.revoked_at IS NULL AND k.expires_at > $2
+               AND u.status = 1 AND u.deleted_at IS NULL",
+        )
+        .bind(key_hash)
+        .bind(Utc::now().naive_utc())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_api_key_for_auth: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Record a request made with an API key
+    pub async fn touch_api_key(
+        pool: &PgPool,
+        id: i64,
+        ip_address: Option<&str>,
+    ) -> Result<(), ServiceError> {
+        sqlx::query("UPDATE api_keys SET last_used_at = $2, last_used_ip = $3::inet WHERE id = $1")
+            .bind(id)
+            .bind(Utc::now().naive_utc())
+            .bind(ip_address)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error in touch_api_key, id={}: {:?}", id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+        Ok(())
+    }
+
+    /// Find an active credential of an active service account by its client id
+    pub async fn find_service_credential(
+        pool: &PgPool,
+        client_id: &str,
+    ) -> Result<Option<ServiceCredentialEntity>, ServiceError> {
+        sqlx::query_as::<_, ServiceCredentialEntity>(
+            "SELECT c.id, c.user_id, u.username, c.secret_hash
+             FROM service_account_credentials c
+             JOIN users u ON u.id = c.user_id
+             WHERE c.client_id = $1 AND c.revoked_at IS NULL
+               AND u.user_type = 2 AND u.status = 1 AND u.deleted_at IS NULL",
+        )
+        .bind(client_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_service_credential: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Record a successful token exchange with a service account credential
+    pub async fn touch_service_credential(pool: &PgPool, id: i64) -> Result<(), ServiceError> {
+        sqlx::query("UPDATE service_account_credentials SET last_used_at = $2 WHERE id = $1")
+            .bind(id)
+            .bind(Utc::now().naive_utc())
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error in touch_service_credential, id={}: {:?}", id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+        Ok(())
+    }
+
+    /// Find the user linked to an identity provider account
+    pub async fn find_identity_user(
+        pool: &PgPool,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<IdentityUserEntity>, ServiceError> {
+        sqlx::query_as::<_, IdentityUserEntity>(
+            "SELECT u.id AS user_id, u.username, u.status, u.is_system
+             FROM user_identities i
+             JOIN users u ON u.id = i.user_id
+             WHERE i.issuer = $1 AND i.subject = $2 AND u.deleted_at IS NULL",
+        )
+        .bind(issuer)
+        .bind(subject)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_identity_user: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Link an identity provider account to a user, or record a new sign-on
+    /// with an already linked one
+    pub async fn upsert_identity(
+        pool: &PgPool,
+        user_id: i64,
+        issuer: &str,
+        subject: &str,
+        email: Option<&str>,
+    ) -> Result<(), ServiceError> {
+        sqlx::query(
+            "INSERT INTO user_identities (user_id, issuer, subject, email, last_login_at, created_at)
+             VALUES ($1, $2, $3, $4, $5, $5)
+             ON CONFLICT (issuer, subject)
+             DO UPDATE SET email = EXCLUDED.email, last_login_at = EXCLUDED.last_login_at",
+        )
+        .bind(user_id)
+        .bind(issuer)
+        .bind(subject)
+        .bind(email)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in upsert_identity, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(())
+    }
+
+    /// Create a user without password at its first single sign-on or directory
+    /// login, linked to the external account
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_external_user(
+        pool: &PgPool,
+        username: &str,
+        email: &str,
+        email_verified: bool,
+        real_name: Option<&str>,
+        password_hash: &str,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<i64, ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for external user: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        let now = Utc::now().naive_utc();
+        let user_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO users (username, email, password_hash, real_name, status,
+                                email_verified_at, created_at)
+             VALUES ($1, $2, $3, $4, 1, CASE WHEN $5 THEN $6 END, $6)
+             RETURNING id",
+        )
+        .bind(username)
+        .bind(email)
+        .bind(password_hash)
+        .bind(real_name)
+        .bind(email_verified)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error creating external user '{}': {:?}", username, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        sqlx::query(
+            "INSERT INTO user_identities (user_id, issuer, subject, email, last_login_at, created_at)
+             VALUES ($1, $2, $3, $4, $5, $5)",
+        )
+        .bind(user_id)
+        .bind(issuer)
+        .bind(subject)
+        .bind(email)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error linking identity of external user '{}': {:?}", username, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing external user transaction: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Ok(user_id)
+    }
+
+    /// Replace the roles of a user with the roles mapped from its external groups
+    pub async fn set_user_roles(
+        pool: &PgPool,
+        user_id: i64,
+        role_ids: &[i64],
+    ) -> Result<(), ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for user roles: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        UserRepository::insert_user_roles(&mut tx, user_id, role_ids).await?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing user roles transaction: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
 }
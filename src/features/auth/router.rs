@@ -1,29 +1,52 @@
 use super::{
-    dto::LoginRequest,
+    dto::{
+        ChangePasswordRequest, CreateApiKeyRequest, ForgotPasswordRequest, LoginRequest,
+        MfaCodeRequest, MfaVerifyRequest, OidcCallbackRequest, PasskeyLoginOptionsRequest,
+        PasskeyLoginRequest, PasskeyRegisterRequest, RefreshTokenRequest, RegisterRequest,
+        ResetPasswordRequest, ServiceTokenRequest, UpdateProfileRequest, VerifyEmailRequest,
+    },
     service::AuthService,
-    vo::{LoginVo, UserInfoVo},
+    vo::{
+        ApiKeyCreatedVo, ApiKeyVo, LoginResultVo, LoginVo, MfaSetupVo, MfaStatusVo,
+        OidcAuthorizeVo, PasskeyCreationOptionsVo, PasskeyRequestOptionsVo, PasskeyVo, ProfileVo,
+        RecoveryCodesVo, ServiceTokenVo, SessionVo, UserInfoVo,
+    },
 };
 use crate::{
     common::{
         api::{ApiResponse, AppResult},
+        error::ServiceError,
         files::save_avatar,
     },
-    core::{extractor::CurrentUser, permission::PermissionService},
+    core::extractor::CurrentUser,
     features::system::log::service::LogService,
 };
 
 use axum::{
     Json, Router,
-    extract::{ConnectInfo, Multipart, State},
+    extract::{ConnectInfo, Multipart, Path, State},
     http::HeaderMap,
-    routing::{get, post},
+    routing::{delete, get, post, put},
 };
 use sqlx::PgPool;
 use std::{net::SocketAddr, time::Instant};
+use uuid::Uuid;
 
 /// Public auth routes (no token required)
 pub fn public_auth_routes() -> Router<PgPool> {
-    Router::new().route("/login", post(login_handler))
+    Router::new()
+        .route("/login", post(login_handler))
+        .route("/register", post(register_handler))
+        .route("/refresh", post(refresh_handler))
+        .route("/service-token", post(service_token_handler))
+        .route("/mfa/verify", post(mfa_verify_handler))
+        .route("/passkeys/login/options", post(passkey_login_options_handler))
+        .route("/passkeys/login", post(passkey_login_handler))
+        .route("/oidc/authorize", get(oidc_authorize_handler))
+        .route("/oidc/callback", post(oidc_callback_handler))
+        .route("/password/forgot", post(forgot_password_handler))
+        .route("/password/reset", post(reset_password_handler))
+        .route("/email/verify", post(verify_email_handler))
 }
 
 /// Protected auth routes (JWT required)
@@ -32,6 +55,22 @@ pub fn protected_auth_routes() -> Router<PgPool> {
         .route("/me", get(get_login_info_handler))
         .route("/logout", get(logout_handler))
         .route("/avatar", post(update_avatar))
+        .route("/profile", get(get_profile_handler).put(update_profile_handler))
+        .route("/password", put(change_password_handler))
+        .route("/email/verification", post(send_email_verification_handler))
+        .route("/sessions", get(list_sessions_handler).delete(revoke_all_sessions_handler))
+        .route("/sessions/{id}", delete(revoke_session_handler))
+        .route("/mfa", get(mfa_status_handler))
+        .route("/mfa/setup", post(mfa_setup_handler))
+        .route("/mfa/enable", post(mfa_enable_handler))
+        .route("/mfa/disable", post(mfa_disable_handler))
+        .route("/mfa/recovery-codes", post(mfa_recovery_codes_handler))
+        .route("/passkeys", get(list_passkeys_handler))
+        .route("/passkeys/register/options", post(passkey_register_options_handler))
+        .route("/passkeys/register", post(passkey_register_handler))
+        .route("/passkeys/{id}", delete(delete_passkey_handler))
+        .route("/api-keys", get(list_api_keys_handler).post(create_api_key_handler))
+        .route("/api-keys/{id}", delete(revoke_api_key_handler))
 }
 
 /// Login with username/password
@@ -41,7 +80,7 @@ async fn login_handler(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(request): Json<LoginRequest>,
-) -> AppResult<LoginVo> {
+) -> AppResult<LoginResultVo> {
     let start_time = Instant::now();
     tracing::info!("Login attempt from {}", addr.ip());
 
@@ -49,40 +88,56 @@ async fn login_handler(
     let ip_address = addr.ip().to_string();
     let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
 
-    match AuthService::login(&pool, request).await {
+    match AuthService::login(&pool, request, &ip_address, user_agent).await {
         Ok(response) => {
+            let (user_id, action, description) = match &response {
+                LoginResultVo::Success(login) => {
+                    (login.user_info.id, "AUTH_LOGIN", "User login successful")
+                }
+                LoginResultVo::MfaRequired(_) => {
+                    (0, "AUTH_LOGIN_MFA", "Password verified, MFA required")
+                }
+            };
             if let Err(e) = LogService::log_business_operation(
                 &pool,
-                response.user_info.id,
+                user_id,
                 &username,
-                "AUTH_LOGIN",
-                "User login successful",
+                action,
+                description,
                 serde_json::json!({}),
                 "SUCCESS",
                 start_time.elapsed().as_millis() as i32,
                 &ip_address,
-                &user_agent,
+                user_agent,
+                None,
             )
             .await
             {
                 tracing::error!("Failed to log login operation: {:?}", e);
             }
-            tracing::info!("Login successful");
+            tracing::info!("Login step completed: {}", action);
             Ok(ApiResponse::success(response))
         }
         Err(err) => {
             let user_id = 0_i64;
+            let action = match err {
+                ServiceError::UserIsLocked | ServiceError::TooManyLoginAttempts => {
+                    "AUTH_LOGIN_LOCKED"
+                }
+                _ => "AUTH_LOGIN",
+            };
             if let Err(e) = LogService::log_business_operation(
                 &pool,
                 user_id,
                 &username,
-                "AUTH_LOGIN",
+                action,
                 &err.to_string(),
                 serde_json::json!({}),
                 "FAIL",
                 start_time.elapsed().as_millis() as i32,
                 &ip_address,
-                &user_agent,
+                user_agent,
+                None,
             )
             .await
             {
@@ -94,6 +149,352 @@ async fn login_handler(
     }
 }
 
+/// Complete an MFA login with a TOTP or recovery code
+#[tracing::instrument(name = "mfa_verify", skip(pool, addr, headers, request))]
+async fn mfa_verify_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<MfaVerifyRequest>,
+) -> AppResult<LoginVo> {
+    let start_time = Instant::now();
+    tracing::info!("MFA verification from {}", addr.ip());
+
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    let result = AuthService::verify_mfa(&pool, request, &ip_address, user_agent).await;
+    let (user_id, username, action, description, status) = match &result {
+        Ok(response) => (
+            response.user_info.id,
+            response.user_info.username.as_str(),
+            "AUTH_LOGIN",
+            "User login successful (MFA)".to_string(),
+            "SUCCESS",
+        ),
+        Err(err) => (0, "anonymous", "AUTH_MFA_VERIFY", err.to_string(), "FAIL"),
+    };
+    if let Err(e) = LogService::log_business_operation(
+        &pool,
+        user_id,
+        username,
+        action,
+        &description,
+        serde_json::json!({}),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &ip_address,
+        user_agent,
+        None,
+    )
+    .await
+    {
+        tracing::error!("Failed to log MFA verification: {:?}", e);
+    }
+
+    Ok(ApiResponse::success(result?))
+}
+
+/// Start a passkey login
+#[tracing::instrument(name = "passkey_login_options", skip(pool, request))]
+async fn passkey_login_options_handler(
+    State(pool): State<PgPool>,
+    Json(request): Json<PasskeyLoginOptionsRequest>,
+) -> AppResult<PasskeyRequestOptionsVo> {
+    let options = AuthService::passkey_login_options(&pool, request).await?;
+    Ok(ApiResponse::success(options))
+}
+
+/// Complete a passkey login
+#[tracing::instrument(name = "passkey_login", skip(pool, addr, headers, request))]
+async fn passkey_login_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<PasskeyLoginRequest>,
+) -> AppResult<LoginVo> {
+    let start_time = Instant::now();
+    tracing::info!("Passkey login from {}", addr.ip());
+
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    let result = AuthService::passkey_login(&pool, request, &ip_address, user_agent).await;
+    let (user_id, username, action, description, status) = match &result {
+        Ok(response) => (
+            response.user_info.id,
+            response.user_info.username.as_str(),
+            "AUTH_LOGIN",
+            "User login successful (passkey)".to_string(),
+            "SUCCESS",
+        ),
+        Err(err) => (0, "anonymous", "AUTH_PASSKEY_LOGIN", err.to_string(), "FAIL"),
+    };
+    if let Err(e) = LogService::log_business_operation(
+        &pool,
+        user_id,
+        username,
+        action,
+        &description,
+        serde_json::json!({}),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &ip_address,
+        user_agent,
+        None,
+    )
+    .await
+    {
+        tracing::error!("Failed to log passkey login: {:?}", e);
+    }
+
+    Ok(ApiResponse::success(result?))
+}
+
+/// Start an SSO login, returns the identity provider URL to redirect to
+#[tracing::instrument(name = "oidc_authorize")]
+async fn oidc_authorize_handler() -> AppResult<OidcAuthorizeVo> {
+    tracing::info!("SSO login started");
+
+    let response = AuthService::oidc_authorize().await?;
+    Ok(ApiResponse::success(response))
+}
+
+/// Complete an SSO login with the code returned by the identity provider
+#[tracing::instrument(name = "oidc_callback", skip(pool, addr, headers, request))]
+async fn oidc_callback_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<OidcCallbackRequest>,
+) -> AppResult<LoginVo> {
+    let start_time = Instant::now();
+    tracing::info!("SSO callback from {}", addr.ip());
+
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    let result = AuthService::oidc_login(&pool, request, &ip_address, user_agent).await;
+    let (user_id, username, action, description, status) = match &result {
+        Ok(response) => (
+            response.user_info.id,
+            response.user_info.username.as_str(),
+            "AUTH_LOGIN",
+            "User login successful (SSO)".to_string(),
+            "SUCCESS",
+        ),
+        Err(err) => (0, "anonymous", "AUTH_SSO_LOGIN", err.to_string(), "FAIL"),
+    };
+    if let Err(e) = LogService::log_business_operation(
+        &pool,
+        user_id,
+        username,
+        action,
+        &description,
+        serde_json::json!({}),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &ip_address,
+        user_agent,
+        None,
+    )
+    .await
+    {
+        tracing::error!("Failed to log SSO login: {:?}", e);
+    }
+
+    Ok(ApiResponse::success(result?))
+}
+
+/// Public self-registration, creates a user pending admin approval
+#[tracing::instrument(name = "register", skip(pool, addr, headers, request))]
+async fn register_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<RegisterRequest>,
+) -> AppResult<i64> {
+    let start_time = Instant::now();
+    tracing::info!("Registration from {}", addr.ip());
+
+    let username = request.username.trim().to_string();
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    let result = AuthService::register(&pool, request).await;
+    let (user_id, description, status) = match &result {
+        Ok(user_id) => (*user_id, "User registered, pending approval".to_string(), "SUCCESS"),
+        Err(err) => (0, err.to_string(), "FAIL"),
+    };
+    if let Err(e) = LogService::log_business_operation(
+        &pool,
+        user_id,
+        &username,
+        "AUTH_REGISTER",
+        &description,
+        serde_json::json!({}),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &ip_address,
+        user_agent,
+        None,
+    )
+    .await
+    {
+        tracing::error!("Failed to log registration: {:?}", e);
+    }
+
+    Ok(ApiResponse::success(result?))
+}
+
+/// Request a password reset email
+#[tracing::instrument(name = "forgot_password", skip(pool, request))]
+async fn forgot_password_handler(
+    State(pool): State<PgPool>,
+    Json(request): Json<ForgotPasswordRequest>,
+) -> AppResult<()> {
+    tracing::info!("Password reset requested");
+    AuthService::forgot_password(&pool, request).await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// Reset a password with an emailed token
+#[tracing::instrument(name = "reset_password", skip(pool, addr, headers, request))]
+async fn reset_password_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<ResetPasswordRequest>,
+) -> AppResult<()> {
+    let start_time = Instant::now();
+    tracing::info!("Password reset from {}", addr.ip());
+
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    let result = AuthService::reset_password(&pool, request).await;
+    let (user_id, username, description, status) = match &result {
+        Ok((user_id, username)) => {
+            (*user_id, username.as_str(), "Password reset by email".to_string(), "SUCCESS")
+        }
+        Err(err) => (0, "anonymous", err.to_string(), "FAIL"),
+    };
+    if let Err(e) = LogService::log_business_operation(
+        &pool,
+        user_id,
+        username,
+        "AUTH_PASSWORD_RESET",
+        &description,
+        serde_json::json!({}),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &ip_address,
+        user_agent,
+        None,
+    )
+    .await
+    {
+        tracing::error!("Failed to log password reset: {:?}", e);
+    }
+
+    result?;
+    Ok(ApiResponse::success(()))
+}
+
+/// Verify an email with an emailed token
+#[tracing::instrument(name = "verify_email", skip(pool, request))]
+async fn verify_email_handler(
+    State(pool): State<PgPool>,
+    Json(request): Json<VerifyEmailRequest>,
+) -> AppResult<()> {
+    AuthService::verify_email(&pool, request).await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// Exchange a refresh token for a new token pair
+#[tracing::instrument(name = "refresh", skip(pool, addr, headers, request))]
+async fn refresh_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<RefreshTokenRequest>,
+) -> AppResult<LoginVo> {
+    let start_time = Instant::now();
+    tracing::info!("Token refresh from {}", addr.ip());
+
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    match AuthService::refresh(&pool, request, &ip_address, user_agent).await {
+        Ok(response) => Ok(ApiResponse::success(response)),
+        Err(err) => {
+            if let Err(e) = LogService::log_business_operation(
+                &pool,
+                0,
+                "anonymous",
+                "AUTH_REFRESH",
+                &err.to_string(),
+                serde_json::json!({}),
+                "FAIL",
+                start_time.elapsed().as_millis() as i32,
+                &ip_address,
+                user_agent,
+                None,
+            )
+            .await
+            {
+                tracing::error!("Failed to log failed refresh operation: {:?}", e);
+            }
+            tracing::warn!("Token refresh failed: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// Exchange service account credentials for an access token
+#[tracing::instrument(name = "service_token", skip(pool, addr, headers, request))]
+async fn service_token_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<ServiceTokenRequest>,
+) -> AppResult<ServiceTokenVo> {
+    let start_time = Instant::now();
+    tracing::info!("Service token request from {}", addr.ip());
+
+    let client_id = request.client_id.clone();
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    let result = AuthService::issue_service_token(&pool, request, &ip_address, user_agent).await;
+    let (user_id, username, description, status) = match &result {
+        Ok((user_id, username, _)) => {
+            (*user_id, username.as_str(), "Service token issued".to_string(), "SUCCESS")
+        }
+        Err(err) => (0, "anonymous", err.to_string(), "FAIL"),
+    };
+    if let Err(e) = LogService::log_business_operation(
+        &pool,
+        user_id,
+        username,
+        "AUTH_SERVICE_TOKEN",
+        &description,
+        serde_json::json!({ "clientId": client_id }),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &ip_address,
+        user_agent,
+        None,
+    )
+    .await
+    {
+        tracing::error!("Failed to log service token request: {:?}", e);
+    }
+
+    let (_, _, token) = result?;
+    Ok(ApiResponse::success(token))
+}
+
 /// Get current user info with roles and menus
 #[tracing::instrument(name = "get_login_info", skip(current_user, pool))]
 async fn get_login_info_handler(
@@ -102,24 +503,248 @@ async fn get_login_info_handler(
 ) -> AppResult<UserInfoVo> {
     tracing::debug!("Get me info");
 
-    let user_info = AuthService::get_login_info(&pool, current_user.user_id).await?;
+    let user_info = AuthService::get_login_info(&pool, current_user.user_id)
+        .await?
+        .impersonated_by(current_user.actor.as_ref());
 
     tracing::debug!("Me info retrieved: {:?}", user_info);
     Ok(ApiResponse::success(user_info))
 }
 
-/// Logout and clear cache
-#[tracing::instrument(name = "logout", skip(current_user))]
-async fn logout_handler(current_user: CurrentUser) -> AppResult<()> {
+/// Logout, revoke refresh tokens and clear cache
+#[tracing::instrument(name = "logout", skip(current_user, pool))]
+async fn logout_handler(current_user: CurrentUser, State(pool): State<PgPool>) -> AppResult<()> {
     tracing::info!("Logout");
 
-    // Clear user permission cache
-    PermissionService::clear_user_cache(current_user.user_id);
+    AuthService::logout(&pool, current_user.user_id, current_user.session_id).await?;
 
     tracing::info!("Logout completed");
     Ok(ApiResponse::success(()))
 }
 
+/// Send a verification email for the current user's email
+#[tracing::instrument(name = "send_email_verification", skip(current_user, pool))]
+async fn send_email_verification_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+) -> AppResult<()> {
+    AuthService::send_email_verification(&pool, current_user.user_id).await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// Get the profile of the current user
+#[tracing::instrument(name = "get_profile", skip(current_user, pool))]
+async fn get_profile_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+) -> AppResult<ProfileVo> {
+    let profile = AuthService::get_profile(&pool, current_user.user_id).await?;
+    Ok(ApiResponse::success(profile))
+}
+
+/// Update the email and real name of the current user
+#[tracing::instrument(name = "update_profile", skip(current_user, pool, request))]
+async fn update_profile_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Json(request): Json<UpdateProfileRequest>,
+) -> AppResult<ProfileVo> {
+    tracing::info!("Updating profile");
+    let profile = AuthService::update_profile(&pool, current_user.user_id, request).await?;
+    Ok(ApiResponse::success(profile))
+}
+
+/// Change the password of the current user
+#[tracing::instrument(name = "change_password", skip(current_user, pool, request))]
+async fn change_password_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Json(request): Json<ChangePasswordRequest>,
+) -> AppResult<()> {
+    tracing::info!("Changing password");
+    AuthService::change_password(&pool, current_user.user_id, current_user.session_id, request)
+        .await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// List active sessions of the current user
+#[tracing::instrument(name = "list_sessions", skip(current_user, pool))]
+async fn list_sessions_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+) -> AppResult<Vec<SessionVo>> {
+    let sessions =
+        AuthService::list_sessions(&pool, current_user.user_id, Some(current_user.session_id))
+            .await?;
+    Ok(ApiResponse::success(sessions))
+}
+
+/// Revoke one session of the current user
+#[tracing::instrument(name = "revoke_session", skip(current_user, pool))]
+async fn revoke_session_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> AppResult<()> {
+    tracing::info!("Revoking session {}", id);
+    AuthService::revoke_session(&pool, current_user.user_id, id).await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// Log out everywhere: revoke every session of the current user
+#[tracing::instrument(name = "revoke_all_sessions", skip(current_user, pool))]
+async fn revoke_all_sessions_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+) -> AppResult<u64> {
+    tracing::info!("Revoking all sessions");
+    let revoked = AuthService::revoke_all_sessions(&pool, current_user.user_id).await?;
+    Ok(ApiResponse::success(revoked))
+}
+
+/// Get the MFA status of the current user
+#[tracing::instrument(name = "mfa_status", skip(current_user, pool))]
+async fn mfa_status_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+) -> AppResult<MfaStatusVo> {
+    let status = AuthService::mfa_status(&pool, current_user.user_id).await?;
+    Ok(ApiResponse::success(status))
+}
+
+/// Start TOTP enrollment
+#[tracing::instrument(name = "mfa_setup", skip(current_user, pool))]
+async fn mfa_setup_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+) -> AppResult<MfaSetupVo> {
+    tracing::info!("Starting MFA enrollment");
+    let setup = AuthService::setup_mfa(&pool, current_user.user_id, &current_user.username).await?;
+    Ok(ApiResponse::success(setup))
+}
+
+/// Confirm TOTP enrollment, returns the recovery codes
+#[tracing::instrument(name = "mfa_enable", skip(current_user, pool, request))]
+async fn mfa_enable_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Json(request): Json<MfaCodeRequest>,
+) -> AppResult<RecoveryCodesVo> {
+    tracing::info!("Enabling MFA");
+    let codes = AuthService::enable_mfa(&pool, current_user.user_id, &request.code).await?;
+    Ok(ApiResponse::success(codes))
+}
+
+/// Disable MFA with a current code
+#[tracing::instrument(name = "mfa_disable", skip(current_user, pool, request))]
+async fn mfa_disable_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Json(request): Json<MfaCodeRequest>,
+) -> AppResult<()> {
+    tracing::info!("Disabling MFA");
+    AuthService::disable_mfa(&pool, current_user.user_id, &request.code).await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// Replace the recovery codes with a current code
+#[tracing::instrument(name = "mfa_recovery_codes", skip(current_user, pool, request))]
+async fn mfa_recovery_codes_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Json(request): Json<MfaCodeRequest>,
+) -> AppResult<RecoveryCodesVo> {
+    tracing::info!("Regenerating recovery codes");
+    let codes =
+        AuthService::regenerate_recovery_codes(&pool, current_user.user_id, &request.code).await?;
+    Ok(ApiResponse::success(codes))
+}
+
+/// List passkeys of the current user
+#[tracing::instrument(name = "list_passkeys", skip(current_user, pool))]
+async fn list_passkeys_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+) -> AppResult<Vec<PasskeyVo>> {
+    let passkeys = AuthService::list_passkeys(&pool, current_user.user_id).await?;
+    Ok(ApiResponse::success(passkeys))
+}
+
+/// Start registering a passkey
+#[tracing::instrument(name = "passkey_register_options", skip(current_user, pool))]
+async fn passkey_register_options_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+) -> AppResult<PasskeyCreationOptionsVo> {
+    let options = AuthService::passkey_registration_options(
+        &pool,
+        current_user.user_id,
+        &current_user.username,
+    )
+    .await?;
+    Ok(ApiResponse::success(options))
+}
+
+/// Complete registering a passkey
+#[tracing::instrument(name = "passkey_register", skip(current_user, pool, request))]
+async fn passkey_register_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Json(request): Json<PasskeyRegisterRequest>,
+) -> AppResult<PasskeyVo> {
+    tracing::info!("Registering passkey");
+    let passkey = AuthService::register_passkey(&pool, current_user.user_id, request).await?;
+    Ok(ApiResponse::success(passkey))
+}
+
+/// Delete a passkey of the current user
+#[tracing::instrument(name = "delete_passkey", skip(current_user, pool))]
+async fn delete_passkey_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> AppResult<()> {
+    tracing::info!("Deleting passkey {}", id);
+    AuthService::delete_passkey(&pool, current_user.user_id, id).await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// List API keys of the current user
+#[tracing::instrument(name = "list_api_keys", skip(current_user, pool))]
+async fn list_api_keys_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+) -> AppResult<Vec<ApiKeyVo>> {
+    let api_keys = AuthService::list_api_keys(&pool, current_user.user_id).await?;
+    Ok(ApiResponse::success(api_keys))
+}
+
+/// Create an API key for the current user
+#[tracing::instrument(name = "create_api_key", skip(current_user, pool, request))]
+async fn create_api_key_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> AppResult<ApiKeyCreatedVo> {
+    tracing::info!("Creating API key");
+    let api_key =
+        AuthService::create_api_key(&pool, current_user.user_id, current_user.user_id, request)
+            .await?;
+    Ok(ApiResponse::success(api_key))
+}
+
+/// Revoke an API key of the current user
+#[tracing::instrument(name = "revoke_api_key", skip(current_user, pool))]
+async fn revoke_api_key_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> AppResult<()> {
+    tracing::info!("Revoking API key {}", id);
+    AuthService::revoke_api_key(&pool, current_user.user_id, id).await?;
+    Ok(ApiResponse::success(()))
+}
+
 /// Update user profile
 #[tracing::instrument(name = "update_avatar", skip(current_user, pool))]
 async fn update_avatar(
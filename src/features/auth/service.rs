@@ -1,85 +1,1840 @@
 use super::{
-    dto::LoginRequest,
-    entity::{LoginCredentialsEntity, UserStatus},
+    dto::{
+        ChangePasswordRequest, CreateApiKeyRequest, ForgotPasswordRequest, LoginRequest,
+        MfaVerifyRequest, OidcCallbackRequest, PasskeyLoginOptionsRequest, PasskeyLoginRequest,
+        PasskeyRegisterRequest, RefreshTokenRequest, RegisterRequest, ResetPasswordRequest,
+        ServiceTokenRequest, UpdateProfileRequest, VerifyEmailRequest,
+    },
+    entity::{
+        AuthUserEntity, IdentityUserEntity, LoginCredentialsEntity, PasskeyEntity,
+        RefreshTokenEntity, UserStatus,
+    },
+    provider::AUTH_PROVIDERS,
     repo::AuthRepository,
-    vo::{LoginVo, UserInfoVo},
+    vo::{
+        ApiKeyCreatedVo, ApiKeyVo, ImpersonationVo, LoginResultVo, LoginVo, MfaRequiredVo,
+        MfaSetupVo, MfaStatusVo, OidcAuthorizeVo, PasskeyAuthenticatorSelectionVo,
+        PasskeyCreationOptionsVo, PasskeyCredParamVo, PasskeyCredentialDescriptorVo,
+        PasskeyRequestOptionsVo, PasskeyRpVo, PasskeyUserVo, PasskeyVo, ProfileVo, RecoveryCodesVo,
+        ServiceTokenVo, SessionVo, UserInfoVo,
+    },
 };
 use crate::{
     common::error::ServiceError,
     core::{
-        jwt::{self},
-        password::PasswordUtils,
-        permission::PermissionService,
+        config::CONFIG,
+        extractor::{ApiKeyScope, CurrentUser},
+        jwt::{self, ActorClaims, JWT_CONFIG},
+        mailer::{MAILER, Mail},
+        oidc::{self, IdTokenClaims, OIDC_CLIENT, OIDC_PENDING_LOGINS, OidcError, PendingLogin},
+        password::{NO_PASSWORD_HASH, PASSWORD_POLICY, PasswordUtils},
+        permission::{PermissionService, PermissionSet},
+        role_mapping,
+        throttle::IP_LOGIN_THROTTLE,
+        token::TokenUtils,
+        totp::TotpUtils,
+        webauthn::{COSE_ALG_ES256, Ceremony, RelyingParty, WEBAUTHN_CHALLENGES, WebAuthnUtils},
+    },
+    features::system::{
+        role::repo::RoleRepository,
+        user::{dto::CreateUserDto, repo::UserRepository, service::UserService},
     },
 };
 
-use sqlx::PgPool;
-use tracing;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing;
+use uuid::Uuid;
+
+/// Minimum interval between two `last_seen_at` updates of a session
+const SESSION_TOUCH_INTERVAL_SECS: i64 = 60;
+
+/// Number of recovery codes issued when MFA is enabled
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Time the browser gives the user to complete a passkey ceremony
+const PASSKEY_TIMEOUT_MS: u32 = 300_000;
+
+/// Token purpose of password reset links
+const PASSWORD_RESET_PURPOSE: &str = "password_reset";
+
+/// Token purpose of email verification links
+const EMAIL_VERIFY_PURPOSE: &str = "email_verify";
+
+/// Minimum interval between two mails of the same purpose to a user
+const MAIL_COOLDOWN_SECS: i64 = 60;
+
+/// Prefix of API keys, telling them apart from JWTs in the Authorization header
+pub const API_KEY_PREFIX: &str = "rzk_";
+
+/// Number of leading API key characters stored and shown to identify a key
+const API_KEY_DISPLAY_LEN: usize = 12;
+
+/// Outcome of presenting a refresh token
+#[derive(Debug, PartialEq, Eq)]
+enum RefreshDecision {
+    /// Unused and valid: rotate it within its family
+    Rotate,
+    /// Already rotated: reuse of a leaked token, revoke the family
+    RevokeFamily,
+    /// Expired or revoked
+    Reject,
+}
+
+/// Authentication service for login/register operations
+pub struct AuthService;
+
+impl AuthService {
+    /// Login with username/password.
+    ///
+    /// Users with MFA enabled get an MFA pending token instead of a session;
+    /// the login is completed by [`AuthService::verify_mfa`].
+    pub async fn login(
+        pool: &PgPool,
+        request: LoginRequest,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<LoginResultVo, ServiceError> {
+        let start = std::time::Instant::now();
+        tracing::info!("Login attempt received for username: {}", request.username);
+
+        // 1. verify login credentials
+        let user = Self::verify_login(pool, &request.username, &request.password, ip_address)
+            .await
+            .map_err(|e| {
+                tracing::warn!(
+                    "Login verification failed for username={}: {:?}",
+                    request.username,
+                    e
+                );
+                e
+            })?;
+        let verification_time = start.elapsed();
+        tracing::debug!(
+            "User verification completed in {:?} for user_id={}",
+            verification_time,
+            user.id
+        );
+
+        // 2. require the second factor when enrolled
+        if Self::is_mfa_enabled(pool, user.id).await? {
+            let mfa_token = jwt::generate_mfa_token(user.id, &request.username).map_err(|e| {
+                tracing::error!("Failed to generate MFA token for user_id={}: {:?}", user.id, e);
+                ServiceError::TokenCreationFailed
+            })?;
+            tracing::info!("MFA required for username={}, user_id={}", request.username, user.id);
+            return Ok(LoginResultVo::MfaRequired(MfaRequiredVo { mfa_required: true, mfa_token }));
+        }
+
+        // 3. start the session
+        let login =
+            Self::complete_login(pool, user.id, &request.username, ip_address, user_agent).await?;
+
+        let total_time = start.elapsed();
+        tracing::info!(
+            "Login successful for username={}, user_id={}, total_time={:?}",
+            &request.username,
+            user.id,
+            total_time
+        );
+        Ok(LoginResultVo::Success(login))
+    }
+
+    /// Public self-registration: create a pending user with the default role.
+    ///
+    /// The user can log in once an admin approves the registration. A
+    /// verification email is sent right away.
+    pub async fn register(pool: &PgPool, request: RegisterRequest) -> Result<i64, ServiceError> {
+        if !CONFIG.registration_enabled {
+            return Err(ServiceError::RegistrationDisabled);
+        }
+
+        let username = request.username.trim();
+        if !Self::is_valid_username(username) {
+            return Err(ServiceError::InvalidOperation(
+                "Username must be 3-50 letters, digits, '.', '_' or '-'".to_string(),
+            ));
+        }
+        let email = request.email.as_str();
+
+        let role_ids = if CONFIG.registration_default_role.is_empty() {
+            Vec::new()
+        } else {
+            match RoleRepository::find_active_id_by_code(pool, &CONFIG.registration_default_role)
+                .await?
+            {
+                Some(role_id) => vec![role_id],
+                None => {
+                    tracing::warn!(
+                        "Registration default role '{}' not found, registering without a role",
+                        CONFIG.registration_default_role
+                    );
+                    Vec::new()
+                }
+            }
+        };
+
+        let user_id = UserService::create_user(
+            pool,
+            CreateUserDto {
+                username: username.to_string(),
+                email: email.to_string(),
+                password: request.password,
+                real_name: request.real_name.filter(|name| !name.trim().is_empty()),
+                status: Some(UserStatus::Pending as i16),
+                role_ids,
+                must_change_password: false,
+                dept_id: None,
+                role_grants: Vec::new(),
+            },
+        )
+        .await?;
+        tracing::info!("User registered and pending approval: user_id={}", user_id);
+
+        if let Err(e) = Self::send_email_verification(pool, user_id).await {
+            tracing::warn!("Failed to send verification mail to user_id={}: {:?}", user_id, e);
+        }
+        Ok(user_id)
+    }
+
+    /// Second step of an MFA login: exchange the MFA pending token and a
+    /// TOTP or recovery code for a session. The MFA pending token completes a
+    /// single login.
+    ///
+    /// Wrong codes count as failed logins for both the client IP and the user.
+    pub async fn verify_mfa(
+        pool: &PgPool,
+        request: MfaVerifyRequest,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<LoginVo, ServiceError> {
+        // 1. reject throttled clients
+        if IP_LOGIN_THROTTLE.is_blocked(ip_address).await {
+            tracing::warn!("MFA verification throttled for ip={}", ip_address);
+            return Err(ServiceError::TooManyLoginAttempts);
+        }
+
+        // 2. verify the MFA pending token and the user
+        let claims = jwt::verify_mfa_token(&request.mfa_token).map_err(|e| {
+            tracing::debug!("Invalid MFA token: {:?}", e);
+            ServiceError::InvalidToken
+        })?;
+        if AuthRepository::is_mfa_token_used(pool, claims.jti).await? {
+            tracing::warn!("Rejected a reused MFA token for user_id={}", claims.user_id);
+            return Err(ServiceError::InvalidToken);
+        }
+        let user = AuthRepository::get_user_by_id(pool, claims.user_id)
+            .await?
+            .ok_or(ServiceError::InvalidToken)?;
+
+        // 3. verify the second factor
+        if let Err(e) = Self::verify_second_factor(pool, user.id, &request.code).await {
+            if matches!(e, ServiceError::InvalidMfaCode) {
+                IP_LOGIN_THROTTLE.record_failure(ip_address).await;
+                let status = AuthRepository::record_failed_login(
+                    pool,
+                    user.id,
+                    CONFIG.login_max_attempts,
+                    Self::lock_expires_at(),
+                )
+                .await?;
+                if UserStatus::try_from(status)? == UserStatus::Locked {
+                    tracing::warn!("Account locked after failed MFA: user_id={}", user.id);
+                    return Err(ServiceError::UserIsLocked);
+                }
+            }
+            return Err(e);
+        }
+        AuthRepository::reset_failed_logins(pool, user.id).await?;
+
+        // 4. consume the MFA pending token, so that it completes a single login
+        //    (also when a concurrent verification passed the check above)
+        let expires_at = DateTime::from_timestamp(claims.exp as i64, 0)
+            .ok_or(ServiceError::InvalidToken)?
+            .naive_utc();
+        if !AuthRepository::use_mfa_token(pool, claims.jti, user.id, expires_at).await? {
+            tracing::warn!("Rejected a reused MFA token for user_id={}", user.id);
+            return Err(ServiceError::InvalidToken);
+        }
+
+        // 5. start the session
+        let login =
+            Self::complete_login(pool, user.id, &user.username, ip_address, user_agent).await?;
+
+        tracing::info!("MFA login successful for username={}, user_id={}", user.username, user.id);
+        Ok(login)
+    }
+
+    /// Start a session for a fully authenticated user and build the login response
+    async fn complete_login(
+        pool: &PgPool,
+        user_id: i64,
+        username: &str,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<LoginVo, ServiceError> {
+        // 1. start a session with its refresh token family
+        let (session_id, refresh_token) =
+            Self::start_session(pool, user_id, ip_address, user_agent).await?;
+
+        // 2. generate token
+        let token = jwt::generate_token(user_id, username, session_id).map_err(|e| {
+            tracing::error!("Failed to generate token for user_id={}: {:?}", user_id, e);
+            ServiceError::TokenCreationFailed
+        })?;
+
+        tracing::debug!("JWT token generated successfully for user_id={}", user_id);
+
+        // 3. cache user permissions
+        Self::cache_user_permissions(pool, user_id).await.map_err(|e| {
+            tracing::error!(
+                "Failed to cache permissions during login for user_id={}: {:?}",
+                user_id,
+                e
+            );
+            e
+        })?;
+
+        // 4. update last login time
+        let pool_clone = pool.clone();
+        tokio::spawn(async move {
+            let _ = AuthRepository::update_last_login(&pool_clone, user_id).await;
+        });
+
+        // 5. get user info
+        let user_info = Self::get_login_info(pool, user_id).await?;
+
+        // 6. return login vo
+        Ok(LoginVo { token, refresh_token, user_info })
+    }
+
+    /// Exchange a refresh token for a new token pair.
+    ///
+    /// The presented token is rotated: it can never be used again, and the
+    /// response carries its successor in the same family. Presenting an
+    /// already rotated token is treated as theft and revokes the whole family.
+    pub async fn refresh(
+        pool: &PgPool,
+        request: RefreshTokenRequest,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<LoginVo, ServiceError> {
+        let token_hash = TokenUtils::hash_token(&request.refresh_token);
+
+        // 1. rotate the presented token
+        let Some(existing) = AuthRepository::find_refresh_token(pool, &token_hash).await? else {
+            tracing::debug!("Refresh token is unknown");
+            return Err(ServiceError::InvalidToken);
+        };
+        let rotated = match Self::refresh_decision(&existing, Utc::now().naive_utc()) {
+            RefreshDecision::Rotate => AuthRepository::use_refresh_token(pool, &token_hash).await?,
+            RefreshDecision::RevokeFamily => None,
+            RefreshDecision::Reject => {
+                tracing::debug!("Refresh token is expired or revoked");
+                return Err(ServiceError::InvalidToken);
+            }
+        };
+        // Also reached when a concurrent request rotated the token first
+        let Some(current) = rotated else {
+            tracing::warn!(
+                "Refresh token reuse detected for user_id={}, family_id={}",
+                existing.user_id,
+                existing.family_id
+            );
+            Self::end_session(pool, existing.user_id, existing.family_id).await?;
+            return Err(ServiceError::RefreshTokenReused);
+        };
+
+        // 2. make sure the user can still sign in
+        let user = match AuthRepository::get_user_by_id(pool, current.user_id).await? {
+            Some(user) => user,
+            None => {
+                tracing::warn!(
+                    "Refresh denied for inactive user_id={}, revoking family_id={}",
+                    current.user_id,
+                    current.family_id
+                );
+                Self::end_session(pool, current.user_id, current.family_id).await?;
+                return Err(ServiceError::InvalidToken);
+            }
+        };
+
+        // 3. extend the session (the refresh token family is the session)
+        let session_id = current.family_id;
+        let expires_at = Self::refresh_expires_at();
+        if !AuthRepository::extend_session(pool, session_id, ip_address, user_agent, expires_at)
+            .await?
+        {
+            tracing::warn!("Refresh denied for revoked session_id={}", session_id);
+            AuthRepository::revoke_refresh_token_family(pool, session_id).await?;
+            return Err(ServiceError::InvalidToken);
+        }
+
+        // 4. issue the successor token and a new access token
+        let refresh_token =
+            Self::issue_refresh_token(pool, user.id, session_id, expires_at).await?;
+        let token = jwt::generate_token(user.id, &user.username, session_id).map_err(|e| {
+            tracing::error!("Failed to generate token for user_id={}: {:?}", user.id, e);
+            ServiceError::TokenCreationFailed
+        })?;
+
+        // 5. get user info (also refreshes the permission cache)
+        let user_info = Self::get_login_info(pool, user.id).await?;
+
+        tracing::info!("Token refreshed for user_id={}, family_id={}", user.id, current.family_id);
+        Ok(LoginVo { token, refresh_token, user_info })
+    }
+
+    /// Decide what to do with a presented refresh token. A token that was
+    /// already rotated has leaked, so its whole family is revoked, even once
+    /// it is expired or revoked itself.
+    fn refresh_decision(token: &RefreshTokenEntity, now: NaiveDateTime) -> RefreshDecision {
+        if token.used_at.is_some() {
+            RefreshDecision::RevokeFamily
+        } else if token.revoked_at.is_some() || token.expires_at <= now {
+            RefreshDecision::Reject
+        } else {
+            RefreshDecision::Rotate
+        }
+    }
+
+    /// Exchange the client id and secret of a service account for a short-lived access token.
+    ///
+    /// The token belongs to a session without a refresh token, so it can be
+    /// revoked like any other session. Returns the service account's ID and
+    /// username with the token, for the audit log.
+    pub async fn issue_service_token(
+        pool: &PgPool,
+        request: ServiceTokenRequest,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<(i64, String, ServiceTokenVo), ServiceError> {
+        let credential = AuthRepository::find_service_credential(pool, request.client_id.trim())
+            .await?
+            .filter(|credential| {
+                credential.secret_hash == TokenUtils::hash_token(&request.client_secret)
+            })
+            .ok_or_else(|| {
+                tracing::warn!(
+                    "Invalid service account credentials for client_id={}",
+                    request.client_id
+                );
+                ServiceError::InvalidCredentials
+            })?;
+
+        let expires_in = CONFIG.service_account_token_expiration;
+        let session_id = Uuid::new_v4();
+        let expires_at = (Utc::now() + Duration::seconds(expires_in)).naive_utc();
+        AuthRepository::create_session(
+            pool,
+            session_id,
+            credential.user_id,
+            ip_address,
+            user_agent,
+            expires_at,
+            None,
+        )
+        .await?;
+
+        let token = jwt::generate_token_with_expiration(
+            credential.user_id,
+            &credential.username,
+            session_id,
+            expires_in,
+        )
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to generate token for service account user_id={}: {:?}",
+                credential.user_id,
+                e
+            );
+            ServiceError::TokenCreationFailed
+        })?;
+
+        // Service accounts are never system users
+        Self::cache_user_permissions(pool, credential.user_id).await?;
+
+        let pool_clone = pool.clone();
+        tokio::spawn(async move {
+            let _ = AuthRepository::touch_service_credential(&pool_clone, credential.id).await;
+            let _ = AuthRepository::update_last_login(&pool_clone, credential.user_id).await;
+        });
+
+        tracing::info!(
+            "Service token issued for user_id={} with credential {}",
+            credential.user_id,
+            credential.id
+        );
+        Ok((
+            credential.user_id,
+            credential.username,
+            ServiceTokenVo { token, token_type: "Bearer", expires_in },
+        ))
+    }
+
+    /// Start impersonating a user: issue a short-lived token acting as the user.
+    ///
+    /// The token carries the actor, belongs to a session of the user without a
+    /// refresh token, and ends with the actor's sessions. System users and users
+    /// with permissions the actor lacks cannot be impersonated, and impersonation
+    /// cannot be started with an API key or from another impersonation.
+    pub async fn impersonate(
+        pool: &PgPool,
+        actor: &CurrentUser,
+        user_id: i64,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<ImpersonationVo, ServiceError> {
+        if actor.api_key.is_some() || actor.actor.is_some() {
+            tracing::warn!("Impersonation of user_id={} denied to {:?}", user_id, actor);
+            return Err(ServiceError::PermissionDenied);
+        }
+        if actor.user_id == user_id {
+            return Err(ServiceError::InvalidOperation("Cannot impersonate yourself".into()));
+        }
+
+        let user = AuthRepository::get_user_by_id(pool, user_id).await?.ok_or_else(|| {
+            ServiceError::InvalidOperation("Only active users can be impersonated".into())
+        })?;
+        if user.is_system {
+            tracing::warn!(
+                "User_id={} tried to impersonate system user_id={}",
+                actor.user_id,
+                user_id
+            );
+            return Err(ServiceError::InvalidOperation(
+                "System users cannot be impersonated".into(),
+            ));
+        }
+        // Impersonating must not widen what the actor can do
+        let actor_user = AuthRepository::get_user_by_id(pool, actor.user_id)
+            .await?
+            .ok_or(ServiceError::PermissionDenied)?;
+        let actor_permissions = Self::granted_permissions(pool, &actor_user).await?;
+        if !Self::granted_permissions(pool, &user).await?.is_subset_of(&actor_permissions) {
+            tracing::warn!(
+                "User_id={} tried to impersonate user_id={} with broader permissions",
+                actor.user_id,
+                user_id
+            );
+            return Err(ServiceError::PermissionDenied);
+        }
+
+        let expires_in = CONFIG.impersonation_token_expiration;
+        let session_id = Uuid::new_v4();
+        let expires_at = (Utc::now() + Duration::seconds(expires_in)).naive_utc();
+        AuthRepository::create_session(
+            pool,
+            session_id,
+            user.id,
+            ip_address,
+            user_agent,
+            expires_at,
+            Some(actor.user_id),
+        )
+        .await?;
+
+        let actor = ActorClaims { user_id: actor.user_id, username: actor.username.clone() };
+        let token = jwt::generate_impersonation_token(
+            user.id,
+            &user.username,
+            session_id,
+            actor.clone(),
+            expires_in,
+        )
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to generate impersonation token for user_id={}: {:?}",
+                user.id,
+                e
+            );
+            ServiceError::TokenCreationFailed
+        })?;
+
+        // Also caches the user's permissions for the impersonation session
+        let user_info = Self::get_login_info(pool, user.id).await?.impersonated_by(Some(&actor));
+
+        tracing::info!(
+            "User_id={} ({}) started impersonating user_id={} ({}), session {}",
+            actor.user_id,
+            actor.username,
+            user.id,
+            user.username,
+            session_id
+        );
+        Ok(ImpersonationVo { token, expires_in, user_info })
+    }
+
+    /// Start a single sign-on login at the OpenID Connect provider.
+    ///
+    /// The state, nonce and PKCE verifier are kept server-side until the
+    /// browser comes back with the authorization code.
+    pub async fn oidc_authorize() -> Result<OidcAuthorizeVo, ServiceError> {
+        if !CONFIG.oidc_enabled {
+            return Err(ServiceError::InvalidOperation("SSO login is not enabled".to_string()));
+        }
+
+        let state = TokenUtils::generate_token();
+        let nonce = TokenUtils::generate_token();
+        let code_verifier = oidc::generate_code_verifier();
+        let authorization_url = OIDC_CLIENT
+            .authorization_url(&state, &nonce, &oidc::code_challenge(&code_verifier))
+            .await
+            .map_err(Self::oidc_error)?;
+
+        OIDC_PENDING_LOGINS.insert(&state, PendingLogin { nonce, code_verifier });
+        Ok(OidcAuthorizeVo { authorization_url })
+    }
+
+    /// Complete a single sign-on login with the provider's callback and start a session.
+    ///
+    /// The provider account is matched by its linked identity, then by
+    /// verified email, and provisioned on first login when enabled. MFA is
+    /// left to the provider.
+    pub async fn oidc_login(
+        pool: &PgPool,
+        request: OidcCallbackRequest,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<LoginVo, ServiceError> {
+        if !CONFIG.oidc_enabled {
+            return Err(ServiceError::InvalidOperation("SSO login is not enabled".to_string()));
+        }
+
+        // 1. redeem the pending login
+        let pending = OIDC_PENDING_LOGINS.take(&request.state).ok_or_else(|| {
+            tracing::warn!("SSO callback with unknown or expired state");
+            ServiceError::SsoLoginFailed("invalid or expired state".to_string())
+        })?;
+
+        // 2. exchange the code and validate the ID token
+        let id_token = OIDC_CLIENT
+            .exchange_code(&request.code, &pending.code_verifier)
+            .await
+            .map_err(Self::oidc_error)?;
+        let claims = OIDC_CLIENT
+            .validate_id_token(&id_token, &pending.nonce)
+            .await
+            .map_err(Self::oidc_error)?;
+
+        // 3. find or provision the user
+        let user = Self::resolve_oidc_user(pool, &claims).await?;
+        UserStatus::try_from(user.status)?.check_status()?;
+
+        // 4. apply the provider's group to role mapping
+        if !user.is_system {
+            let groups = claims.strings(&CONFIG.oidc_groups_claim);
+            Self::sync_mapped_roles(pool, user.user_id, &groups, &CONFIG.oidc_role_mapping).await?;
+        }
+
+        // 5. start the session
+        let login =
+            Self::complete_login(pool, user.user_id, &user.username, ip_address, user_agent)
+                .await?;
+
+        tracing::info!(
+            "SSO login successful for username={}, user_id={}, subject={}",
+            user.username,
+            user.user_id,
+            claims.sub
+        );
+        Ok(login)
+    }
+
+    /// Find the user of an ID token: linked identity, verified email, or a new user
+    async fn resolve_oidc_user(
+        pool: &PgPool,
+        claims: &IdTokenClaims,
+    ) -> Result<IdentityUserEntity, ServiceError> {
+        let issuer = OIDC_CLIENT.issuer();
+        let email = claims.string("email");
+
+        // 1. already linked identity
+        if let Some(user) = AuthRepository::find_identity_user(pool, issuer, &claims.sub).await? {
+            AuthRepository::upsert_identity(pool, user.user_id, issuer, &claims.sub, email).await?;
+            return Ok(user);
+        }
+
+        // 2. existing user with the same, provider-verified email
+        if claims.email_verified()
+            && let Some(email) = email
+            && let Some(user) = AuthRepository::find_user_by_email(pool, email).await?
+        {
+            let is_system = AuthRepository::get_login_credentials(pool, &user.username)
+                .await?
+                .is_some_and(|credentials| credentials.is_system);
+            if is_system {
+                tracing::warn!(
+                    "Refused to link SSO subject {} to system user_id={} by email",
+                    claims.sub,
+                    user.id
+                );
+                return Err(ServiceError::SsoLoginFailed(
+                    "this account cannot be linked by email".to_string(),
+                ));
+            }
+            AuthRepository::upsert_identity(pool, user.id, issuer, &claims.sub, Some(email))
+                .await?;
+            tracing::info!("Linked SSO subject {} to user_id={} by email", claims.sub, user.id);
+            return Ok(IdentityUserEntity {
+                user_id: user.id,
+                username: user.username,
+                status: user.status,
+                is_system,
+            });
+        }
+
+        // 3. provision a new user
+        if !CONFIG.oidc_auto_provision {
+            tracing::warn!("No user linked to SSO subject {}", claims.sub);
+            return Err(ServiceError::SsoLoginFailed(
+                "no account is linked to this identity".to_string(),
+            ));
+        }
+        let email = email.ok_or_else(|| {
+            ServiceError::SsoLoginFailed("the email claim is missing".to_string())
+        })?;
+        let username = Self::provisioned_username(
+            claims
+                .string(&CONFIG.oidc_username_claim)
+                .or_else(|| email.split_once('@').map(|(local, _)| local)),
+        );
+        let username = username.as_str();
+        if UserRepository::username_exists(pool, username).await? {
+            return Err(ServiceError::SsoLoginFailed(format!(
+                "username {} is already taken",
+                username
+            )));
+        }
+        if UserRepository::email_exists(pool, email).await? {
+            return Err(ServiceError::SsoLoginFailed(format!("email {} is already taken", email)));
+        }
+
+        let user_id = AuthRepository::create_external_user(
+            pool,
+            username,
+            email,
+            claims.email_verified(),
+            claims.string("name"),
+            NO_PASSWORD_HASH,
+            issuer,
+            &claims.sub,
+        )
+        .await?;
+        tracing::info!("Provisioned user_id={} for SSO subject {}", user_id, claims.sub);
+        Ok(IdentityUserEntity {
+            user_id,
+            username: username.to_string(),
+            status: 1,
+            is_system: false,
+        })
+    }
+
+    /// Usernames are 3-50 letters, digits, '.', '_' or '-'
+    fn is_valid_username(username: &str) -> bool {
+        (3..=50).contains(&username.len())
+            && username.chars().all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))
+    }
+
+    /// Username of a provisioned SSO user: the claimed name when it is a
+    /// valid username, a generated one otherwise.
+    fn provisioned_username(claimed: Option<&str>) -> String {
+        match claimed.map(str::trim) {
+            Some(username) if Self::is_valid_username(username) => username.to_string(),
+            _ => format!("sso-{}", &Uuid::new_v4().simple().to_string()[..12]),
+        }
+    }
+
+    /// Replace the roles of a user with the roles mapped from its groups at an
+    /// identity provider or directory. Roles are left alone when no mapping is configured.
+    pub async fn sync_mapped_roles(
+        pool: &PgPool,
+        user_id: i64,
+        groups: &[String],
+        mapping: &str,
+    ) -> Result<(), ServiceError> {
+        let mapping = role_mapping::parse_role_mapping(mapping);
+        if mapping.is_empty() {
+            return Ok(());
+        }
+
+        let mut role_ids = Vec::new();
+        for code in role_mapping::mapped_role_codes(groups, &mapping) {
+            match RoleRepository::find_active_id_by_code(pool, &code).await? {
+                Some(role_id) => role_ids.push(role_id),
+                None => tracing::warn!("Role mapping references unknown role '{}'", code),
+            }
+        }
+        AuthRepository::set_user_roles(pool, user_id, &role_ids).await
+    }
+
+    /// Log provider failures in full, report them to the client in short
+    fn oidc_error(e: OidcError) -> ServiceError {
+        tracing::warn!("SSO login rejected: {}", e);
+        let reason = match e {
+            OidcError::IdToken(_) => "invalid ID token",
+            _ => "identity provider unavailable",
+        };
+        ServiceError::SsoLoginFailed(reason.to_string())
+    }
+
+    /// Logout: end the current session
+    pub async fn logout(pool: &PgPool, user_id: i64, session_id: Uuid) -> Result<(), ServiceError> {
+        Self::end_session(pool, user_id, session_id).await?;
+        tracing::info!("Session {} ended for user_id={}", session_id, user_id);
+        Ok(())
+    }
+
+    /// Verify that the session of an access token is still active and that its
+    /// user is neither disabled nor deleted.
+    /// Called by the auth middleware on every authenticated request.
+    ///
+    /// Impersonation sessions must match the token's actor, and end as soon as
+    /// the actor can no longer sign in.
+    ///
+    /// Returns whether the user must change the password before using the API.
+    pub async fn verify_session(
+        pool: &PgPool,
+        user_id: i64,
+        session_id: Uuid,
+        impersonator_id: Option<i64>,
+    ) -> Result<bool, ServiceError> {
+        let session =
+            AuthRepository::find_active_session(pool, session_id, user_id, impersonator_id)
+                .await?
+                .ok_or_else(|| {
+                    tracing::debug!(
+                        "Session {} is revoked, expired or of a disabled user",
+                        session_id
+                    );
+                    ServiceError::InvalidToken
+                })?;
+
+        // Record activity, but not more often than once per interval
+        if Utc::now().naive_utc() - session.last_seen_at
+            > Duration::seconds(SESSION_TOUCH_INTERVAL_SECS)
+        {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let _ = AuthRepository::touch_session(&pool, session_id).await;
+            });
+        }
+        Ok(Self::password_change_required(
+            session.must_change_password,
+            session.password_changed_at,
+        ))
+    }
+
+    /// List active sessions of a user, flagging the current one
+    pub async fn list_sessions(
+        pool: &PgPool,
+        user_id: i64,
+        current_session_id: Option<Uuid>,
+    ) -> Result<Vec<SessionVo>, ServiceError> {
+        let sessions = AuthRepository::find_active_sessions(pool, user_id).await?;
+        Ok(sessions
+            .into_iter()
+            .map(|session| SessionVo::from_entity(session, current_session_id))
+            .collect())
+    }
+
+    /// Revoke one session of a user
+    pub async fn revoke_session(
+        pool: &PgPool,
+        user_id: i64,
+        session_id: Uuid,
+    ) -> Result<(), ServiceError> {
+        if !Self::end_session(pool, user_id, session_id).await? {
+            return Err(ServiceError::NotFound("Session".to_string()));
+        }
+        tracing::info!("Session {} revoked for user_id={}", session_id, user_id);
+        Ok(())
+    }
+
+    /// Revoke every session of a user ("log out everywhere")
+    pub async fn revoke_all_sessions(pool: &PgPool, user_id: i64) -> Result<u64, ServiceError> {
+        let revoked = AuthRepository::revoke_user_sessions(pool, user_id).await?;
+        AuthRepository::revoke_user_refresh_tokens(pool, user_id).await?;
+        PermissionService::clear_user_cache(user_id).await;
+        tracing::info!("Revoked {} session(s) for user_id={}", revoked, user_id);
+        Ok(revoked)
+    }
+
+    /// Create a session and the first refresh token of its family
+    async fn start_session(
+        pool: &PgPool,
+        user_id: i64,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<(Uuid, String), ServiceError> {
+        let session_id = Uuid::new_v4();
+        let expires_at = Self::refresh_expires_at();
+
+        AuthRepository::create_session(
+            pool, session_id, user_id, ip_address, user_agent, expires_at, None,
+        )
+        .await?;
+        let refresh_token =
+            Self::issue_refresh_token(pool, user_id, session_id, expires_at).await?;
+
+        tracing::debug!("Session {} started for user_id={}", session_id, user_id);
+        Ok((session_id, refresh_token))
+    }
+
+    /// Revoke a session and its refresh token family.
+    /// The permission cache is cleared once the user has no active session left.
+    async fn end_session(
+        pool: &PgPool,
+        user_id: i64,
+        session_id: Uuid,
+    ) -> Result<bool, ServiceError> {
+        let revoked = AuthRepository::revoke_session(pool, user_id, session_id).await?;
+        AuthRepository::revoke_refresh_token_family(pool, session_id).await?;
+
+        if AuthRepository::count_active_sessions(pool, user_id).await? == 0 {
+            PermissionService::clear_user_cache(user_id).await;
+        }
+        Ok(revoked)
+    }
+
+    /// Expiration time for sessions and refresh tokens issued now
+    fn refresh_expires_at() -> NaiveDateTime {
+        (Utc::now() + Duration::seconds(JWT_CONFIG.refresh_expiration)).naive_utc()
+    }
+
+    /// Generate a refresh token in the given family and store its hash
+    async fn issue_refresh_token(
+        pool: &PgPool,
+        user_id: i64,
+        family_id: Uuid,
+        expires_at: NaiveDateTime,
+    ) -> Result<String, ServiceError> {
+        AuthRepository::delete_expired_refresh_tokens(pool, user_id).await?;
+
+        let refresh_token = TokenUtils::generate_token();
+        AuthRepository::create_refresh_token(
+            pool,
+            user_id,
+            family_id,
+            &TokenUtils::hash_token(&refresh_token),
+            expires_at,
+        )
+        .await?;
+
+        tracing::debug!("Refresh token issued for user_id={}, family_id={}", user_id, family_id);
+        Ok(refresh_token)
+    }
+
+    /// Whether a password must be changed: forced by an admin, or older than the max age
+    fn password_change_required(
+        must_change_password: bool,
+        password_changed_at: Option<NaiveDateTime>,
+    ) -> bool {
+        must_change_password
+            || (CONFIG.password_max_age_days > 0
+                && password_changed_at.is_some_and(|changed_at| {
+                    Utc::now().naive_utc() - changed_at
+                        > Duration::days(CONFIG.password_max_age_days)
+                }))
+    }
+
+    /// Get the profile of the current user
+    pub async fn get_profile(pool: &PgPool, user_id: i64) -> Result<ProfileVo, ServiceError> {
+        let profile = AuthRepository::get_profile(pool, user_id)
+            .await?
+            .ok_or(ServiceError::NotFound("User".to_string()))?;
+        Ok(ProfileVo::from(profile))
+    }
+
+    /// Update the email and real name of the current user
+    pub async fn update_profile(
+        pool: &PgPool,
+        user_id: i64,
+        request: UpdateProfileRequest,
+    ) -> Result<ProfileVo, ServiceError> {
+        let email = request.email.as_str();
+        if AuthRepository::email_used_by_other(pool, email, user_id).await? {
+            return Err(ServiceError::EmailConflict);
+        }
+
+        let real_name = request.real_name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+        if !AuthRepository::update_profile(pool, user_id, email, real_name).await? {
+            return Err(ServiceError::NotFound("User".to_string()));
+        }
+        tracing::info!("Profile updated for user_id={}", user_id);
+        Self::get_profile(pool, user_id).await
+    }
+
+    /// Change the password of the current user after re-checking the current one.
+    ///
+    /// Every other session of the user is revoked and the permission cache is
+    /// rebuilt, so only the session that made the change stays logged in.
+    pub async fn change_password(
+        pool: &PgPool,
+        user_id: i64,
+        session_id: Uuid,
+        request: ChangePasswordRequest,
+    ) -> Result<(), ServiceError> {
+        let state = AuthRepository::get_password_state(pool, user_id)
+            .await?
+            .ok_or(ServiceError::NotFound("User".to_string()))?;
+        if !PasswordUtils::verify_password(&request.current_password, &state.password_hash) {
+            return Err(ServiceError::InvalidOperation(
+                "Current password is incorrect".to_string(),
+            ));
+        }
+
+        Self::set_password(pool, user_id, &request.new_password, false).await?;
+
+        // Sessions and refresh tokens share their ID, see `start_session`
+        let revoked = AuthRepository::revoke_other_sessions(pool, user_id, session_id).await?;
+        AuthRepository::revoke_other_refresh_tokens(pool, user_id, session_id).await?;
+
+        PermissionService::clear_user_cache(user_id).await;
+        Self::cache_user_permissions(pool, user_id).await?;
+
+        tracing::info!(
+            "Password changed by user_id={}, revoked {} other session(s)",
+            user_id,
+            revoked
+        );
+        Ok(())
+    }
+
+    /// Set a new password after checking the password policy and history.
+    ///
+    /// The new password may not match the current password or any of the
+    /// previous ones kept in the history (`password_history_count` in total).
+    pub async fn set_password(
+        pool: &PgPool,
+        user_id: i64,
+        password: &str,
+        must_change_password: bool,
+    ) -> Result<(), ServiceError> {
+        Self::check_new_password(pool, user_id, password).await?;
+        Self::write_password(pool, user_id, password, must_change_password).await
+    }
+
+    /// Check a new password against the password policy and history
+    async fn check_new_password(
+        pool: &PgPool,
+        user_id: i64,
+        password: &str,
+    ) -> Result<(), ServiceError> {
+        let state = AuthRepository::get_password_state(pool, user_id)
+            .await?
+            .ok_or(ServiceError::NotFound("User".to_string()))?;
+        PASSWORD_POLICY.validate(password, &state.username)?;
+
+        if CONFIG.password_history_count > 0 {
+            let history = AuthRepository::find_password_history(
+                pool,
+                user_id,
+                Self::password_history_limit(),
+            )
+            .await?;
+            if std::iter::once(&state.password_hash)
+                .chain(history.iter())
+                .any(|hash| PasswordUtils::verify_password(password, hash))
+            {
+                return Err(ServiceError::PasswordReused);
+            }
+        }
+        Ok(())
+    }
+
+    /// Hash and store an already checked password
+    async fn write_password(
+        pool: &PgPool,
+        user_id: i64,
+        password: &str,
+        must_change_password: bool,
+    ) -> Result<(), ServiceError> {
+        let password_hash = PasswordUtils::hash_password(password)?;
+        AuthRepository::update_password(
+            pool,
+            user_id,
+            &password_hash,
+            must_change_password,
+            Self::password_history_limit(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Number of previous password hashes kept, the current password counts as one
+    fn password_history_limit() -> i64 {
+        (CONFIG.password_history_count - 1).max(0)
+    }
+
+    /// Email a password reset link.
+    ///
+    /// Always succeeds for unknown emails and inactive users, so the response
+    /// does not reveal which emails are registered.
+    pub async fn forgot_password(
+        pool: &PgPool,
+        request: ForgotPasswordRequest,
+    ) -> Result<(), ServiceError> {
+        let Some(user) = AuthRepository::find_user_by_email(pool, request.email.trim()).await?
+        else {
+            tracing::info!("Password reset requested for unknown email");
+            return Ok(());
+        };
+        // Locked users may reset, disabled and pending users may not
+        if !matches!(UserStatus::try_from(user.status)?, UserStatus::Normal | UserStatus::Locked) {
+            tracing::info!("Password reset requested for inactive user_id={}", user.id);
+            return Ok(());
+        }
+        if Self::mail_cooldown_active(pool, user.id, PASSWORD_RESET_PURPOSE).await? {
+            tracing::info!("Password reset mail throttled for user_id={}", user.id);
+            return Ok(());
+        }
+
+        let token = Self::issue_email_token(
+            pool,
+            user.id,
+            PASSWORD_RESET_PURPOSE,
+            &user.email,
+            CONFIG.password_reset_token_expiration,
+        )
+        .await?;
+        Self::deliver_mail(Mail {
+            to: user.email,
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Hello {},\n\nUse the link below to choose a new password. It expires in {} minutes.\n\n{}\n\nIf you did not ask for a password reset, you can ignore this email.",
+                user.username,
+                CONFIG.password_reset_token_expiration / 60,
+                Self::mail_link("reset-password", &token)
+            ),
+        });
+        tracing::info!("Password reset mail sent for user_id={}", user.id);
+        Ok(())
+    }
+
+    /// Set a new password with an emailed reset token and end every session of the user.
+    /// The reset also lifts a lockout from failed logins.
+    ///
+    /// Returns the user ID and username for the audit log.
+    pub async fn reset_password(
+        pool: &PgPool,
+        request: ResetPasswordRequest,
+    ) -> Result<(i64, String), ServiceError> {
+        let token_hash = Self::check_email_token(PASSWORD_RESET_PURPOSE, &request.token)?;
+        let token = AuthRepository::find_action_token(pool, PASSWORD_RESET_PURPOSE, &token_hash)
+            .await?
+            .ok_or(ServiceError::InvalidEmailToken)?;
+
+        // Check the password before using up the token, so a rejected password can be retried
+        Self::check_new_password(pool, token.user_id, &request.new_password).await?;
+        AuthRepository::use_action_token(pool, PASSWORD_RESET_PURPOSE, &token_hash)
+            .await?
+            .ok_or(ServiceError::InvalidEmailToken)?;
+
+        Self::write_password(pool, token.user_id, &request.new_password, false).await?;
+        let username = AuthRepository::finish_password_reset(
+            pool,
+            token.user_id,
+            Self::status_after_password_reset,
+        )
+        .await?
+        .ok_or(ServiceError::NotFound("User".to_string()))?;
+        Self::revoke_all_sessions(pool, token.user_id).await?;
+
+        tracing::info!("Password reset for user_id={}", token.user_id);
+        Ok((token.user_id, username))
+    }
+
+    /// Status after a password reset. The emailed token proves the user's
+    /// identity, so a lockout is lifted; disabled and pending users stay so.
+    fn status_after_password_reset(status: i16) -> i16 {
+        match UserStatus::try_from(status) {
+            Ok(UserStatus::Locked) => UserStatus::Normal as i16,
+            _ => status,
+        }
+    }
+
+    /// Email a verification link for the current email of a user
+    pub async fn send_email_verification(pool: &PgPool, user_id: i64) -> Result<(), ServiceError> {
+        let profile = AuthRepository::get_profile(pool, user_id)
+            .await?
+            .ok_or(ServiceError::NotFound("User".to_string()))?;
+        if profile.email_verified_at.is_some() {
+            return Err(ServiceError::InvalidOperation("Email is already verified".to_string()));
+        }
+        if Self::mail_cooldown_active(pool, user_id, EMAIL_VERIFY_PURPOSE).await? {
+            return Err(ServiceError::InvalidOperation(
+                "A verification email was sent recently, please try again later".to_string(),
+            ));
+        }
+
+        let token = Self::issue_email_token(
+            pool,
+            user_id,
+            EMAIL_VERIFY_PURPOSE,
+            &profile.email,
+            CONFIG.email_verify_token_expiration,
+        )
+        .await?;
+        Self::deliver_mail(Mail {
+            to: profile.email,
+            subject: "Verify your email address".to_string(),
+            body: format!(
+                "Hello {},\n\nUse the link below to verify your email address.\n\n{}\n",
+                profile.username,
+                Self::mail_link("verify-email", &token)
+            ),
+        });
+        tracing::info!("Verification mail sent for user_id={}", user_id);
+        Ok(())
+    }
+
+    /// Verify an email with an emailed token.
+    /// The token only applies while the user still has the email it was sent to.
+    pub async fn verify_email(
+        pool: &PgPool,
+        request: VerifyEmailRequest,
+    ) -> Result<(), ServiceError> {
+        let token_hash = Self::check_email_token(EMAIL_VERIFY_PURPOSE, &request.token)?;
+        let token = AuthRepository::use_action_token(pool, EMAIL_VERIFY_PURPOSE, &token_hash)
+            .await?
+            .ok_or(ServiceError::InvalidEmailToken)?;
+        if !AuthRepository::mark_email_verified(pool, token.user_id, &token.email).await? {
+            return Err(ServiceError::InvalidEmailToken);
+        }
+        tracing::info!("Email verified for user_id={}", token.user_id);
+        Ok(())
+    }
+
+    /// Create and store a signed, single-use token to send by email
+    async fn issue_email_token(
+        pool: &PgPool,
+        user_id: i64,
+        purpose: &str,
+        email: &str,
+        expiration_secs: i64,
+    ) -> Result<String, ServiceError> {
+        let token =
+            TokenUtils::generate_signed_token(CONFIG.email_token_secret.as_bytes(), purpose);
+        let expires_at = (Utc::now() + Duration::seconds(expiration_secs)).naive_utc();
+        AuthRepository::create_action_token(
+            pool,
+            user_id,
+            purpose,
+            &TokenUtils::hash_token(&token),
+            email,
+            expires_at,
+        )
+        .await?;
+        Ok(token)
+    }
+
+    /// Check the signature of an emailed token and return its storage hash
+    fn check_email_token(purpose: &str, token: &str) -> Result<String, ServiceError> {
+        let token = token.trim();
+        if !TokenUtils::verify_signed_token(CONFIG.email_token_secret.as_bytes(), purpose, token) {
+            tracing::debug!("Rejected {} token with an invalid signature", purpose);
+            return Err(ServiceError::InvalidEmailToken);
+        }
+        Ok(TokenUtils::hash_token(token))
+    }
+
+    /// Whether a token for the purpose was sent to the user too recently
+    async fn mail_cooldown_active(
+        pool: &PgPool,
+        user_id: i64,
+        purpose: &str,
+    ) -> Result<bool, ServiceError> {
+        Ok(AuthRepository::last_action_token_at(pool, user_id, purpose).await?.is_some_and(
+            |sent_at| Utc::now().naive_utc() - sent_at < Duration::seconds(MAIL_COOLDOWN_SECS),
+        ))
+    }
+
+    /// Link to a page of the web UI carrying an emailed token
+    fn mail_link(page: &str, token: &str) -> String {
+        format!("{}/{}?token={}", CONFIG.mail_link_base_url.trim_end_matches('/'), page, token)
+    }
+
+    /// Send a mail in the background, so response times do not depend on the transport
+    fn deliver_mail(mail: Mail) {
+        tokio::spawn(async move {
+            if let Err(e) = MAILER.send(&mail).await {
+                tracing::error!("Failed to send mail '{}': {:?}", mail.subject, e);
+            }
+        });
+    }
+
+    /// Lock expiration for a lock applied now, `None` for locks without a duration
+    fn lock_expires_at() -> Option<NaiveDateTime> {
+        (CONFIG.login_lock_minutes > 0)
+            .then(|| (Utc::now() + Duration::minutes(CONFIG.login_lock_minutes)).naive_utc())
+    }
+
+    /// Whether login requires a second factor for the user
+    async fn is_mfa_enabled(pool: &PgPool, user_id: i64) -> Result<bool, ServiceError> {
+        Ok(AuthRepository::find_user_mfa(pool, user_id).await?.is_some_and(|mfa| mfa.enabled))
+    }
+
+    /// Get the MFA status of a user
+    pub async fn mfa_status(pool: &PgPool, user_id: i64) -> Result<MfaStatusVo, ServiceError> {
+        let enabled = Self::is_mfa_enabled(pool, user_id).await?;
+        let recovery_codes_remaining =
+            AuthRepository::count_unused_recovery_codes(pool, user_id).await?;
+        Ok(MfaStatusVo { enabled, recovery_codes_remaining })
+    }
+
+    /// Start (or restart) TOTP enrollment with a new secret
+    pub async fn setup_mfa(
+        pool: &PgPool,
+        user_id: i64,
+        username: &str,
+    ) -> Result<MfaSetupVo, ServiceError> {
+        let secret = TotpUtils::generate_secret();
+        if !AuthRepository::save_pending_mfa(pool, user_id, &secret).await? {
+            return Err(ServiceError::InvalidOperation("MFA is already enabled".to_string()));
+        }
+
+        tracing::info!("MFA enrollment started for user_id={}", user_id);
+        let otpauth_uri = TotpUtils::otpauth_uri(&CONFIG.mfa_issuer, username, &secret);
+        Ok(MfaSetupVo { secret, otpauth_uri })
+    }
 
-/// Authentication service for login/register operations
-pub struct AuthService;
+    /// Confirm TOTP enrollment with a valid code and issue recovery codes
+    pub async fn enable_mfa(
+        pool: &PgPool,
+        user_id: i64,
+        code: &str,
+    ) -> Result<RecoveryCodesVo, ServiceError> {
+        let mfa = AuthRepository::find_user_mfa(pool, user_id)
+            .await?
+            .filter(|mfa| !mfa.enabled)
+            .ok_or_else(|| {
+                ServiceError::InvalidOperation("No pending MFA enrollment".to_string())
+            })?;
 
-impl AuthService {
-    /// Login with username/password
-    pub async fn login(pool: &PgPool, request: LoginRequest) -> Result<LoginVo, ServiceError> {
-        let start = std::time::Instant::now();
-        tracing::info!("Login attempt received for username: {}", request.username);
+        let step = TotpUtils::verify_code(&mfa.secret, code, Utc::now().timestamp())
+            .ok_or(ServiceError::InvalidMfaCode)?;
+        if !AuthRepository::enable_mfa(pool, user_id, step).await? {
+            return Err(ServiceError::InvalidOperation("MFA is already enabled".to_string()));
+        }
+        let recovery_codes = Self::issue_recovery_codes(pool, user_id).await?;
 
-        // 1. verify login credentials
+        tracing::info!("MFA enabled for user_id={}", user_id);
+        Ok(RecoveryCodesVo { recovery_codes })
+    }
+
+    /// Disable MFA after verifying a current code
+    pub async fn disable_mfa(pool: &PgPool, user_id: i64, code: &str) -> Result<(), ServiceError> {
+        Self::verify_second_factor(pool, user_id, code).await?;
+        AuthRepository::delete_user_mfa(pool, user_id).await?;
+        tracing::info!("MFA disabled for user_id={}", user_id);
+        Ok(())
+    }
+
+    /// Replace the recovery codes after verifying a current code
+    pub async fn regenerate_recovery_codes(
+        pool: &PgPool,
+        user_id: i64,
+        code: &str,
+    ) -> Result<RecoveryCodesVo, ServiceError> {
+        Self::verify_second_factor(pool, user_id, code).await?;
+        let recovery_codes = Self::issue_recovery_codes(pool, user_id).await?;
+        tracing::info!("Recovery codes regenerated for user_id={}", user_id);
+        Ok(RecoveryCodesVo { recovery_codes })
+    }
+
+    /// Remove the MFA enrollment of a user (admin reset for lost devices)
+    pub async fn reset_mfa(pool: &PgPool, user_id: i64) -> Result<(), ServiceError> {
+        if !AuthRepository::delete_user_mfa(pool, user_id).await? {
+            return Err(ServiceError::InvalidOperation("User has no MFA enrollment".to_string()));
+        }
+        tracing::info!("MFA reset for user_id={}", user_id);
+        Ok(())
+    }
+
+    /// Verify a TOTP code or consume a recovery code of a user with MFA enabled
+    async fn verify_second_factor(
+        pool: &PgPool,
+        user_id: i64,
+        code: &str,
+    ) -> Result<(), ServiceError> {
+        let mfa = AuthRepository::find_user_mfa(pool, user_id)
+            .await?
+            .filter(|mfa| mfa.enabled)
+            .ok_or_else(|| ServiceError::InvalidOperation("MFA is not enabled".to_string()))?;
+        let code = code.trim();
+
+        // TOTP codes are all digits, anything else can only be a recovery code
+        if code.chars().all(|c| c.is_ascii_digit()) {
+            let step = TotpUtils::verify_code(&mfa.secret, code, Utc::now().timestamp())
+                .ok_or(ServiceError::InvalidMfaCode)?;
+            if !AuthRepository::use_totp_step(pool, user_id, step).await? {
+                tracing::warn!("Replayed TOTP code rejected for user_id={}", user_id);
+                return Err(ServiceError::InvalidMfaCode);
+            }
+            return Ok(());
+        }
+
+        let code = code.to_ascii_lowercase();
+        for recovery_code in AuthRepository::find_unused_recovery_codes(pool, user_id).await? {
+            if PasswordUtils::verify_password(&code, &recovery_code.code_hash)
+                && AuthRepository::use_recovery_code(pool, recovery_code.id).await?
+            {
+                tracing::warn!("Recovery code used for user_id={}", user_id);
+                return Ok(());
+            }
+        }
+        Err(ServiceError::InvalidMfaCode)
+    }
+
+    /// Generate new recovery codes, replacing the old ones, and store their hashes
+    async fn issue_recovery_codes(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Vec<String>, ServiceError> {
+        let recovery_codes = TotpUtils::generate_recovery_codes(RECOVERY_CODE_COUNT);
+        let code_hashes = recovery_codes
+            .iter()
+            .map(|code| PasswordUtils::hash_password(code))
+            .collect::<Result<Vec<_>, _>>()?;
+        AuthRepository::replace_recovery_codes(pool, user_id, &code_hashes).await?;
+        Ok(recovery_codes)
+    }
+
+    /// Relying party passkeys are verified against
+    fn relying_party() -> RelyingParty {
+        RelyingParty { id: CONFIG.webauthn_rp_id.clone(), origin: CONFIG.webauthn_origin.clone() }
+    }
+
+    /// Start a passkey registration ceremony for the current user
+    pub async fn passkey_registration_options(
+        pool: &PgPool,
+        user_id: i64,
+        username: &str,
+    ) -> Result<PasskeyCreationOptionsVo, ServiceError> {
+        let challenge = WebAuthnUtils::generate_challenge();
+        WEBAUTHN_CHALLENGES.insert(&challenge, Ceremony::Registration { user_id });
+
+        let exclude_credentials = AuthRepository::find_passkeys(pool, user_id)
+            .await?
+            .into_iter()
+            .map(|passkey| PasskeyCredentialDescriptorVo {
+                kind: "public-key",
+                id: passkey.credential_id,
+            })
+            .collect();
+
+        Ok(PasskeyCreationOptionsVo {
+            challenge,
+            rp: PasskeyRpVo {
+                id: CONFIG.webauthn_rp_id.clone(),
+                name: CONFIG.webauthn_rp_name.clone(),
+            },
+            user: PasskeyUserVo {
+                id: WebAuthnUtils::encode_base64url(&user_id.to_be_bytes()),
+                name: username.to_string(),
+                display_name: username.to_string(),
+            },
+            pub_key_cred_params: vec![PasskeyCredParamVo {
+                kind: "public-key",
+                alg: COSE_ALG_ES256,
+            }],
+            timeout: PASSKEY_TIMEOUT_MS,
+            attestation: "none",
+            authenticator_selection: PasskeyAuthenticatorSelectionVo {
+                resident_key: "preferred",
+                user_verification: "required",
+            },
+            exclude_credentials,
+        })
+    }
+
+    /// Complete a passkey registration ceremony
+    pub async fn register_passkey(
+        pool: &PgPool,
+        user_id: i64,
+        request: PasskeyRegisterRequest,
+    ) -> Result<PasskeyVo, ServiceError> {
+        let response = request.credential.response;
+        let client_data = Self::decode_passkey_field(&response.client_data_json)?;
+        let attestation_object = Self::decode_passkey_field(&response.attestation_object)?;
+
+        let challenge =
+            Self::take_passkey_challenge(&client_data, Ceremony::Registration { user_id })?;
+        let credential = WebAuthnUtils::verify_registration(
+            &Self::relying_party(),
+            &challenge,
+            &client_data,
+            &attestation_object,
+        )
+        .map_err(|e| {
+            tracing::warn!("Passkey registration rejected for user_id={}: {}", user_id, e);
+            ServiceError::InvalidPasskey
+        })?;
+
+        let credential_id = WebAuthnUtils::encode_base64url(&credential.credential_id);
+        if AuthRepository::find_passkey_by_credential_id(pool, &credential_id).await?.is_some() {
+            return Err(ServiceError::InvalidOperation(
+                "Passkey is already registered".to_string(),
+            ));
+        }
+
+        let name = request
+            .name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "Passkey".to_string());
+        let passkey = AuthRepository::create_passkey(
+            pool,
+            user_id,
+            &credential_id,
+            &credential.public_key,
+            credential.sign_count as i64,
+            &name,
+        )
+        .await?;
+
+        tracing::info!("Passkey {} registered for user_id={}", passkey.id, user_id);
+        Ok(passkey.into())
+    }
+
+    /// List passkeys of a user
+    pub async fn list_passkeys(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Vec<PasskeyVo>, ServiceError> {
+        let passkeys = AuthRepository::find_passkeys(pool, user_id).await?;
+        Ok(passkeys.into_iter().map(PasskeyVo::from).collect())
+    }
+
+    /// Delete a passkey of a user
+    pub async fn delete_passkey(pool: &PgPool, user_id: i64, id: i64) -> Result<(), ServiceError> {
+        if !AuthRepository::delete_passkey(pool, user_id, id).await? {
+            return Err(ServiceError::NotFound("Passkey".to_string()));
+        }
+        tracing::info!("Passkey {} deleted for user_id={}", id, user_id);
+        Ok(())
+    }
+
+    /// Start a passkey login ceremony.
+    ///
+    /// Unknown usernames get an empty credential list, like users without
+    /// passkeys, so the endpoint does not reveal which accounts exist.
+    pub async fn passkey_login_options(
+        pool: &PgPool,
+        request: PasskeyLoginOptionsRequest,
+    ) -> Result<PasskeyRequestOptionsVo, ServiceError> {
+        let challenge = WebAuthnUtils::generate_challenge();
+        WEBAUTHN_CHALLENGES.insert(&challenge, Ceremony::Authentication);
+
+        let mut allow_credentials = Vec::new();
+        if let Some(username) = request.username.as_deref()
+            && let Some(user) = AuthRepository::get_login_credentials(pool, username).await?
+        {
+            allow_credentials = AuthRepository::find_passkeys(pool, user.id)
+                .await?
+                .into_iter()
+                .map(|passkey| PasskeyCredentialDescriptorVo {
+                    kind: "public-key",
+                    id: passkey.credential_id,
+                })
+                .collect();
+        }
+
+        Ok(PasskeyRequestOptionsVo {
+            challenge,
+            rp_id: CONFIG.webauthn_rp_id.clone(),
+            timeout: PASSKEY_TIMEOUT_MS,
+            user_verification: "required",
+            allow_credentials,
+        })
+    }
+
+    /// Complete a passkey login ceremony and start a session.
+    ///
+    /// A verified passkey replaces both the password and the TOTP step.
+    pub async fn passkey_login(
+        pool: &PgPool,
+        request: PasskeyLoginRequest,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<LoginVo, ServiceError> {
+        // 1. reject throttled clients
+        if IP_LOGIN_THROTTLE.is_blocked(ip_address).await {
+            tracing::warn!("Passkey login throttled for ip={}", ip_address);
+            return Err(ServiceError::TooManyLoginAttempts);
+        }
+
+        // 2. verify the assertion
+        let passkey = match Self::verify_passkey_assertion(pool, request).await {
+            Ok(passkey) => passkey,
+            Err(e) => {
+                if matches!(e, ServiceError::InvalidPasskey) {
+                    IP_LOGIN_THROTTLE.record_failure(ip_address).await;
+                }
+                return Err(e);
+            }
+        };
+
+        // 3. make sure the user can still sign in
         let user =
-            Self::verify_login(pool, &request.username, &request.password).await.map_err(|e| {
-                tracing::warn!(
-                    "Login verification failed for username={}: {:?}",
-                    request.username,
-                    e
-                );
-                e
+            AuthRepository::get_user_by_id(pool, passkey.user_id).await?.ok_or_else(|| {
+                tracing::warn!("Passkey login denied for inactive user_id={}", passkey.user_id);
+                ServiceError::InvalidPasskey
             })?;
-        let verification_time = start.elapsed();
-        tracing::debug!(
-            "User verification completed in {:?} for user_id={}",
-            verification_time,
-            user.id
+
+        // 4. start the session
+        let login =
+            Self::complete_login(pool, user.id, &user.username, ip_address, user_agent).await?;
+
+        tracing::info!(
+            "Passkey login successful for username={}, user_id={}, passkey_id={}",
+            user.username,
+            user.id,
+            passkey.id
         );
+        Ok(login)
+    }
 
-        // 2. generate token
-        let token = jwt::generate_token(user.id, &request.username).map_err(|e| {
-            tracing::error!("Failed to generate token for user_id={}: {:?}", user.id, e);
-            ServiceError::TokenCreationFailed
+    /// Verify a passkey assertion and record the new signature counter
+    async fn verify_passkey_assertion(
+        pool: &PgPool,
+        request: PasskeyLoginRequest,
+    ) -> Result<PasskeyEntity, ServiceError> {
+        let credential = request.credential;
+        let client_data = Self::decode_passkey_field(&credential.response.client_data_json)?;
+        let authenticator_data =
+            Self::decode_passkey_field(&credential.response.authenticator_data)?;
+        let signature = Self::decode_passkey_field(&credential.response.signature)?;
+
+        let challenge = Self::take_passkey_challenge(&client_data, Ceremony::Authentication)?;
+        let credential_id = credential.id.trim_end_matches('=');
+        let passkey = AuthRepository::find_passkey_by_credential_id(pool, credential_id)
+            .await?
+            .ok_or_else(|| {
+                tracing::warn!("Passkey login with unknown credential");
+                ServiceError::InvalidPasskey
+            })?;
+
+        if let Some(user_handle) = credential.response.user_handle.filter(|h| !h.is_empty())
+            && Self::decode_passkey_field(&user_handle)? != passkey.user_id.to_be_bytes()
+        {
+            tracing::warn!("Passkey {} presented with a foreign user handle", passkey.id);
+            return Err(ServiceError::InvalidPasskey);
+        }
+
+        let sign_count = WebAuthnUtils::verify_assertion(
+            &Self::relying_party(),
+            &challenge,
+            &passkey.public_key,
+            passkey.sign_count as u32,
+            &client_data,
+            &authenticator_data,
+            &signature,
+        )
+        .map_err(|e| {
+            tracing::warn!("Passkey {} assertion rejected: {}", passkey.id, e);
+            ServiceError::InvalidPasskey
         })?;
+        AuthRepository::update_passkey_usage(pool, passkey.id, sign_count as i64).await?;
 
-        tracing::debug!("JWT token generated successfully for user_id={}", user.id);
+        Ok(passkey)
+    }
 
-        // 3. cache user permissions
-        Self::cache_user_permissions(pool, user.id, user.is_system).await.map_err(|e| {
-            tracing::error!(
-                "Failed to cache permissions during login for user_id={}: {:?}",
-                user.id,
-                e
-            );
-            e
+    /// Consume the pending challenge a ceremony response answers
+    fn take_passkey_challenge(
+        client_data: &[u8],
+        expected: Ceremony,
+    ) -> Result<String, ServiceError> {
+        let challenge = WebAuthnUtils::challenge_of(client_data).map_err(|e| {
+            tracing::warn!("Passkey client data rejected: {}", e);
+            ServiceError::InvalidPasskey
         })?;
+        if WEBAUTHN_CHALLENGES.take(&challenge) != Some(expected) {
+            tracing::warn!("Passkey response for an unknown or expired challenge");
+            return Err(ServiceError::InvalidPasskey);
+        }
+        Ok(challenge)
+    }
 
-        // 4. update last login time
-        let pool_clone = pool.clone();
-        let user_id_clone = user.id;
-        tokio::spawn(async move {
-            let _ = AuthRepository::update_last_login(&pool_clone, user_id_clone).await;
-        });
+    fn decode_passkey_field(value: &str) -> Result<Vec<u8>, ServiceError> {
+        WebAuthnUtils::decode_base64url(value).map_err(|_| ServiceError::InvalidPasskey)
+    }
 
-        // 5. get user info
-        let user_info = Self::get_login_info(pool, user.id).await?;
+    /// List API keys of a user
+    pub async fn list_api_keys(pool: &PgPool, user_id: i64) -> Result<Vec<ApiKeyVo>, ServiceError> {
+        let api_keys = AuthRepository::find_api_keys(pool, user_id).await?;
+        Ok(api_keys.into_iter().map(ApiKeyVo::from).collect())
+    }
+
+    /// Create an API key for `owner_id`, scoped to a subset of the owner's permissions.
+    ///
+    /// `created_by` is the owner itself, or the admin creating the key on its behalf.
+    pub async fn create_api_key(
+        pool: &PgPool,
+        owner_id: i64,
+        created_by: i64,
+        request: CreateApiKeyRequest,
+    ) -> Result<ApiKeyCreatedVo, ServiceError> {
+        let name = request.name.trim();
+        if name.is_empty() || name.chars().count() > 100 {
+            return Err(ServiceError::InvalidOperation(
+                "API key name must be 1 to 100 characters".to_string(),
+            ));
+        }
+        if !(1..=CONFIG.api_key_max_days).contains(&request.expires_in_days) {
+            return Err(ServiceError::InvalidOperation(format!(
+                "API key lifetime must be 1 to {} days",
+                CONFIG.api_key_max_days
+            )));
+        }
+
+        let mut scopes: Vec<String> =
+            request.scopes.iter().map(|scope| scope.trim().to_string()).collect();
+        scopes.sort();
+        scopes.dedup();
+        if scopes.is_empty() || scopes.iter().any(|scope| scope.is_empty()) {
+            return Err(ServiceError::InvalidOperation(
+                "API key needs at least one scope".to_string(),
+            ));
+        }
+
+        // A key can never do more than its owner, nor than the admin creating it
+        let owner = AuthRepository::get_user_by_id(pool, owner_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("User".to_string()))?;
+        let permissions = Self::granted_permissions(pool, &owner).await?;
+        let creator_permissions = if created_by == owner_id {
+            None
+        } else {
+            if owner.is_system {
+                tracing::warn!(
+                    "User_id={} tried to create an API key for system user_id={}",
+                    created_by,
+                    owner_id
+                );
+                return Err(ServiceError::PermissionDenied);
+            }
+            let creator = AuthRepository::get_user_by_id(pool, created_by)
+                .await?
+                .ok_or(ServiceError::PermissionDenied)?;
+            Some(Self::granted_permissions(pool, &creator).await?)
+        };
+        if let Some(scope) =
+            Self::ungranted_scope(&scopes, &permissions, creator_permissions.as_ref())
+        {
+            return Err(ServiceError::InvalidOperation(format!(
+                "Scope '{}' is not granted to the key owner and creator",
+                scope
+            )));
+        }
+
+        let key = format!("{}{}", API_KEY_PREFIX, TokenUtils::generate_token());
+        let expires_at = (Utc::now() + Duration::days(request.expires_in_days)).naive_utc();
+        let api_key = AuthRepository::create_api_key(
+            pool,
+            owner_id,
+            name,
+            &key[..API_KEY_DISPLAY_LEN],
+            &TokenUtils::hash_token(&key),
+            &scopes,
+            expires_at,
+            created_by,
+        )
+        .await?;
 
-        let total_time = start.elapsed();
         tracing::info!(
-            "Login successful for username={}, user_id={}, total_time={:?}",
-            &request.username,
-            user.id,
-            total_time
+            "API key {} created for user_id={} by user_id={}",
+            api_key.id,
+            owner_id,
+            created_by
         );
+        Ok(ApiKeyCreatedVo { key, api_key: api_key.into() })
+    }
 
-        // 6. return login vo
-        Ok(LoginVo { token, user_info })
+    /// Permissions held by a user, `*` for system users
+    async fn granted_permissions(
+        pool: &PgPool,
+        user: &AuthUserEntity,
+    ) -> Result<PermissionSet, ServiceError> {
+        if user.is_system {
+            return Ok(["*"].into_iter().collect());
+        }
+        Ok(AuthRepository::get_user_permissions(pool, user.id).await?.into())
+    }
+
+    /// The first requested API key scope not held by the owner and, for a key
+    /// created on the owner's behalf, the creator.
+    ///
+    /// The owner's denials are applied when the key is used. The creator's
+    /// are not, so scopes must stay clear of them.
+    fn ungranted_scope<'a>(
+        scopes: &'a [String],
+        owner: &PermissionSet,
+        creator: Option<&PermissionSet>,
+    ) -> Option<&'a String> {
+        let granted = match creator {
+            Some(creator) => owner.intersect(creator),
+            None => owner.clone(),
+        };
+        scopes.iter().find(|scope| {
+            !granted.allows(scope) || creator.is_some_and(|creator| creator.denies_any(scope))
+        })
+    }
+
+    /// Revoke an API key of a user
+    pub async fn revoke_api_key(pool: &PgPool, user_id: i64, id: i64) -> Result<(), ServiceError> {
+        if !AuthRepository::revoke_api_key(pool, user_id, id).await? {
+            return Err(ServiceError::NotFound("API key".to_string()));
+        }
+        tracing::info!("API key {} revoked for user_id={}", id, user_id);
+        Ok(())
+    }
+
+    /// Authenticate a request made with an API key.
+    /// Called by the auth middleware for bearer tokens starting with [`API_KEY_PREFIX`].
+    ///
+    /// The key's scopes are intersected with the owner's current permissions,
+    /// so revoking a role from the owner also takes it away from the key.
+    pub async fn authenticate_api_key(
+        pool: &PgPool,
+        key: &str,
+        ip_address: Option<&str>,
+    ) -> Result<CurrentUser, ServiceError> {
+        let api_key = AuthRepository::find_api_key_for_auth(pool, &TokenUtils::hash_token(key))
+            .await?
+            .ok_or_else(|| {
+                tracing::debug!("API key is unknown, revoked or expired");
+                ServiceError::InvalidToken
+            })?;
+
+        let mut scopes: PermissionSet = api_key.scopes.into();
+        if !api_key.is_system {
+            let permissions: PermissionSet =
+                AuthRepository::get_user_permissions(pool, api_key.user_id).await?.into();
+            scopes = scopes.intersect(&permissions);
+        }
+
+        // Record usage, but not more often than once per interval from the same IP
+        let last_ip = api_key.last_used_ip.map(|ip| ip.to_string());
+        let stale = api_key.last_used_at.is_none_or(|last_used_at| {
+            Utc::now().naive_utc() - last_used_at > Duration::seconds(SESSION_TOUCH_INTERVAL_SECS)
+        });
+        if stale || last_ip.as_deref() != ip_address {
+            let pool = pool.clone();
+            let ip_address = ip_address.map(str::to_string);
+            tokio::spawn(async move {
+                let _ =
+                    AuthRepository::touch_api_key(&pool, api_key.id, ip_address.as_deref()).await;
+            });
+        }
+
+        Ok(CurrentUser::from_api_key(
+            api_key.user_id,
+            api_key.username,
+            ApiKeyScope { id: api_key.id, scopes },
+        ))
     }
 
     /// Get detailed user info with roles, menus, and permissions
@@ -97,15 +1852,8 @@ impl AuthService {
             user.username
         );
 
-        // Get menus and permissions in parallel
-        let permissions = if user.is_system {
-            vec!["*".to_string()]
-        } else {
-            AuthRepository::get_user_permissions(pool, user_id).await?
-        };
-
-        // Refresh user permissions cache
-        Self::refresh_user_permissions_cache(user_id, user.is_system, &permissions).await?;
+        // Get permissions and refresh the user's permissions cache
+        let permissions = Self::cache_user_permissions(pool, user_id).await?;
 
         tracing::info!(
             "User info retrieved successfully for user_id={}, username={}",
@@ -113,6 +1861,10 @@ impl AuthService {
             user.username
         );
 
+        let password = AuthRepository::get_password_state(pool, user_id)
+            .await?
+            .ok_or(ServiceError::NotFound("User".to_string()))?;
+
         Ok(UserInfoVo {
             id: user.id,
             username: user.username.clone(),
@@ -121,67 +1873,134 @@ impl AuthService {
             avatar_url: user.avatar_url,
             is_system: user.is_system,
             permissions,
+            must_change_password: Self::password_change_required(
+                password.must_change_password,
+                password.password_changed_at,
+            ),
+            impersonating: false,
+            impersonator: None,
         })
     }
 
-    /// Verify login credentials
+    /// Verify login credentials against the configured auth providers, in order.
+    ///
+    /// Failed attempts are counted per IP address (in memory) and per user
+    /// (persisted). Reaching the per-user threshold locks the account,
+    /// either until `locked_until` or, without a duration, until an admin
+    /// unlocks it.
     pub async fn verify_login(
         pool: &PgPool,
         username: &str,
         password: &str,
+        ip_address: &str,
     ) -> Result<LoginCredentialsEntity, ServiceError> {
         tracing::info!("Starting login verification for username: {}", username);
 
-        // 1. get login credentials
-        let user = AuthRepository::get_login_credentials(pool, username)
-            .await?
-            .ok_or(ServiceError::InvalidCredentials)?;
+        // 1. reject throttled clients before touching the database
+        if IP_LOGIN_THROTTLE.is_blocked(ip_address).await {
+            tracing::warn!("Login throttled for ip={}, username={}", ip_address, username);
+            return Err(ServiceError::TooManyLoginAttempts);
+        }
 
-        tracing::debug!(
-            "User found for username={}, user_id={}, status={}",
-            username,
-            user.id,
-            user.status
-        );
+        // 2. get local login credentials, absent for users a provider may provision
+        let mut user = AuthRepository::get_login_credentials(pool, username).await?;
+
+        if let Some(user) = user.as_mut() {
+            tracing::debug!(
+                "User found for username={}, user_id={}, status={}",
+                username,
+                user.id,
+                user.status
+            );
+
+            // 3. lift an expired timed lock
+            let mut status = UserStatus::try_from(user.status)?;
+            if status == UserStatus::Locked
+                && user.locked_until.is_some_and(|until| until <= Utc::now().naive_utc())
+                && AuthRepository::unlock_expired_lock(pool, user.id).await?
+            {
+                tracing::info!("Lock expired for username={}, user_id={}", username, user.id);
+                status = UserStatus::Normal;
+                user.failed_login_count = 0;
+            }
+
+            // 4. check if user is enabled
+            status.check_status()?;
+        }
 
-        // 2. check if user is enabled
-        let status = UserStatus::try_from(user.status)?;
-        status.check_status()?;
+        // 5. verify password with each provider until one accepts it
+        let mut authenticated = None;
+        for provider in AUTH_PROVIDERS.iter() {
+            if let Some(user_id) =
+                provider.authenticate(pool, username, password, user.as_ref()).await?
+            {
+                tracing::debug!("Provider {} accepted username={}", provider.name(), username);
+                authenticated = Some(user_id);
+                break;
+            }
+        }
 
-        // 3. verify password
-        if !PasswordUtils::verify_password(&password.to_string(), &user.password_hash) {
+        let Some(user_id) = authenticated else {
+            IP_LOGIN_THROTTLE.record_failure(ip_address).await;
+            let Some(user) = user else {
+                return Err(ServiceError::InvalidCredentials);
+            };
             tracing::warn!(
                 "Invalid login attempt: password verification failed for username={}, user_id={}",
                 username,
                 user.id
             );
+
+            let locked_until = Self::lock_expires_at();
+            let status = AuthRepository::record_failed_login(
+                pool,
+                user.id,
+                CONFIG.login_max_attempts,
+                locked_until,
+            )
+            .await?;
+            if UserStatus::try_from(status)? == UserStatus::Locked {
+                tracing::warn!(
+                    "Account locked after {} failed logins: username={}, user_id={}, until={:?}",
+                    CONFIG.login_max_attempts,
+                    username,
+                    user.id,
+                    locked_until
+                );
+                return Err(ServiceError::UserIsLocked);
+            }
             return Err(ServiceError::InvalidCredentials);
-        }
+        };
+
+        // 6. reset the failure counter, or load the just provisioned user
+        let user = match user {
+            Some(user) => {
+                if user.failed_login_count > 0 {
+                    AuthRepository::reset_failed_logins(pool, user.id).await?;
+                }
+                user
+            }
+            None => AuthRepository::get_login_credentials(pool, username)
+                .await?
+                .ok_or(ServiceError::InvalidCredentials)?,
+        };
 
         tracing::info!(
             "Login verification successful for username={}, user_id={}",
             username,
-            user.id
+            user_id
         );
         Ok(user)
     }
 
-    /// Cache user permissions
+    /// Cache user permissions, returning them
     pub async fn cache_user_permissions(
         pool: &PgPool,
         user_id: i64,
-        is_system: bool,
-    ) -> Result<(), ServiceError> {
+    ) -> Result<Vec<String>, ServiceError> {
         tracing::debug!("Starting to cache user permissions for user_id: {}", user_id);
 
-        if is_system {
-            PermissionService::cache_user_permissions(user_id, &["*".to_string()]);
-            tracing::info!("Successfully cached * permissions for user_id={}", user_id);
-            return Ok(());
-        }
-        let permissions: Vec<String> = AuthRepository::get_user_permissions(pool, user_id).await?;
-
-        PermissionService::cache_user_permissions(user_id, &permissions);
+        let permissions = PermissionService::load_user_permissions(pool, user_id).await?;
         tracing::info!(
             "Successfully cached {} permissions for user_id={}: {:?}",
             permissions.len(),
@@ -189,31 +2008,40 @@ impl AuthService {
             permissions
         );
 
-        Ok(())
+        Ok(permissions)
     }
 
-    /// Refresh user permissions cache
-    pub async fn refresh_user_permissions_cache(
-        user_id: i64,
-        is_system: bool,
-        permissions: &[String],
-    ) -> Result<(), ServiceError> {
-        tracing::debug!("Refreshing permissions cache for user_id: {}", user_id);
-
-        if is_system {
-            PermissionService::cache_user_permissions(user_id, &["*".to_string()]);
-            tracing::info!("Successfully refreshed * permissions cache for user_id={}", user_id);
-            return Ok(());
+    /// Evict the cached permissions of `user_ids` that were built from an older
+    /// permission version, and of users that no longer exist.
+    ///
+    /// Called after changing the roles or status of known users, so that this
+    /// instance serves the new permissions right away. Every change also bumps
+    /// the permission version of each affected user, and the database notifies
+    /// the bumps to every instance (see
+    /// [`PermissionService::spawn_invalidation_listener`]); role and menu changes
+    /// rely on those notifications alone. The change is already committed, so
+    /// failures are logged rather than returned, and evicted users are reloaded lazily.
+    pub async fn sync_permission_caches(pool: &PgPool, user_ids: &[i64]) {
+        if user_ids.is_empty() {
+            return;
         }
 
-        PermissionService::cache_user_permissions(user_id, permissions);
-        tracing::info!(
-            "Successfully refreshed {} permissions cache for user_id={}: {:?}",
-            permissions.len(),
-            user_id,
-            permissions
-        );
-        Ok(())
+        let current: HashMap<i64, i64> =
+            match AuthRepository::get_permission_versions(pool, user_ids).await {
+                Ok(rows) => rows.into_iter().collect(),
+                Err(e) => {
+                    tracing::error!("Failed to sync permission caches: {:?}", e);
+                    return;
+                }
+            };
+
+        for user_id in user_ids {
+            match current.get(user_id) {
+                Some(version) => PermissionService::evict_stale(*user_id, *version).await,
+                None => PermissionService::clear_user_cache(*user_id).await,
+            }
+        }
+        tracing::debug!("Synced permission caches of {} user(s)", user_ids.len());
     }
 
     pub async fn update_avatar(
@@ -227,3 +2055,137 @@ impl AuthService {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn refresh_token() -> RefreshTokenEntity {
+        RefreshTokenEntity {
+            user_id: 1,
+            family_id: Uuid::new_v4(),
+            used_at: None,
+            revoked_at: None,
+            expires_at: now() + Duration::days(7),
+        }
+    }
+
+    fn permissions(codes: &[&str]) -> PermissionSet {
+        codes.iter().collect()
+    }
+
+    fn scopes(codes: &[&str]) -> Vec<String> {
+        codes.iter().map(|code| code.to_string()).collect()
+    }
+
+    #[test]
+    fn test_ungranted_scope_own_key() {
+        let owner = permissions(&["system:user:*", "!system:user:delete"]);
+        assert_eq!(
+            AuthService::ungranted_scope(&scopes(&["system:user:list"]), &owner, None),
+            None
+        );
+        // The owner's denials are applied when the key is used
+        assert_eq!(AuthService::ungranted_scope(&scopes(&["system:user:*"]), &owner, None), None);
+        let requested = scopes(&["system:user:list", "system:role:list"]);
+        assert_eq!(
+            AuthService::ungranted_scope(&requested, &owner, None).map(String::as_str),
+            Some("system:role:list")
+        );
+    }
+
+    #[test]
+    fn test_ungranted_scope_needs_creator_permissions() {
+        // An admin who only manages API keys cannot hand out the system user's `*`
+        let system = permissions(&["*"]);
+        let admin = permissions(&["system:user:apikey", "system:user:list"]);
+        let requested = scopes(&["*"]);
+        assert_eq!(
+            AuthService::ungranted_scope(&requested, &system, Some(&admin)).map(String::as_str),
+            Some("*")
+        );
+
+        let service_account = permissions(&["system:*"]);
+        let requested = scopes(&["system:user:list"]);
+        assert_eq!(AuthService::ungranted_scope(&requested, &service_account, Some(&admin)), None);
+        let requested = scopes(&["system:role:list"]);
+        assert!(AuthService::ungranted_scope(&requested, &service_account, Some(&admin)).is_some());
+    }
+
+    #[test]
+    fn test_ungranted_scope_respects_creator_denials() {
+        let service_account = permissions(&["system:*"]);
+        let admin = permissions(&["system:*", "!system:user:delete"]);
+        for scope in ["system:user:*", "system:user:delete"] {
+            let requested = scopes(&[scope]);
+            assert!(
+                AuthService::ungranted_scope(&requested, &service_account, Some(&admin)).is_some(),
+                "{}",
+                scope
+            );
+        }
+        let requested = scopes(&["system:role:*"]);
+        assert_eq!(AuthService::ungranted_scope(&requested, &service_account, Some(&admin)), None);
+    }
+
+    #[test]
+    fn test_provisioned_username_keeps_valid_claim() {
+        assert_eq!(AuthService::provisioned_username(Some(" jane.doe ")), "jane.doe");
+        assert_eq!(AuthService::provisioned_username(Some("j_d-1")), "j_d-1");
+    }
+
+    #[test]
+    fn test_provisioned_username_replaces_invalid_claim() {
+        let long = "a".repeat(51);
+        for claimed in [None, Some("jd"), Some(long.as_str()), Some("jane doe"), Some("jäne")] {
+            let username = AuthService::provisioned_username(claimed);
+            assert!(username.starts_with("sso-"));
+            assert!(AuthService::is_valid_username(&username));
+        }
+    }
+
+    #[test]
+    fn test_password_reset_unlocks_locked_account() {
+        let locked = UserStatus::Locked as i16;
+        assert_eq!(AuthService::status_after_password_reset(locked), UserStatus::Normal as i16);
+    }
+
+    #[test]
+    fn test_password_reset_keeps_other_statuses() {
+        for status in [UserStatus::Normal, UserStatus::Disabled, UserStatus::Pending] {
+            let status = status as i16;
+            assert_eq!(AuthService::status_after_password_reset(status), status);
+        }
+    }
+
+    #[test]
+    fn test_refresh_decision_rotates_unused_token() {
+        assert_eq!(AuthService::refresh_decision(&refresh_token(), now()), RefreshDecision::Rotate);
+    }
+
+    #[test]
+    fn test_refresh_decision_revokes_family_on_reuse() {
+        let used =
+            RefreshTokenEntity { used_at: Some(now() - Duration::minutes(5)), ..refresh_token() };
+        assert_eq!(AuthService::refresh_decision(&used, now()), RefreshDecision::RevokeFamily);
+
+        // Reuse is still detected once the family was revoked or the token expired
+        let revoked = RefreshTokenEntity { revoked_at: Some(now()), ..used.clone() };
+        assert_eq!(AuthService::refresh_decision(&revoked, now()), RefreshDecision::RevokeFamily);
+        let expired = RefreshTokenEntity { expires_at: now() - Duration::days(1), ..used };
+        assert_eq!(AuthService::refresh_decision(&expired, now()), RefreshDecision::RevokeFamily);
+    }
+
+    #[test]
+    fn test_refresh_decision_rejects_revoked_or_expired_token() {
+        let revoked = RefreshTokenEntity { revoked_at: Some(now()), ..refresh_token() };
+        assert_eq!(AuthService::refresh_decision(&revoked, now()), RefreshDecision::Reject);
+        let expired = RefreshTokenEntity { expires_at: now(), ..refresh_token() };
+        assert_eq!(AuthService::refresh_decision(&expired, now()), RefreshDecision::Reject);
+    }
+}
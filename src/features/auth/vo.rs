@@ -1,4 +1,9 @@
+use super::entity::{ApiKeyEntity, PasskeyEntity, ProfileEntity, UserSessionEntity};
+use crate::core::jwt::ActorClaims;
+
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 /// Response payload for successful user login.
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -6,10 +11,52 @@ use serde::{Deserialize, Serialize};
 pub struct LoginVo {
     /// JWT token for authenticating subsequent requests
     pub token: String,
+    /// Opaque refresh token for obtaining a new token pair
+    pub refresh_token: String,
     /// User information
     pub user_info: UserInfoVo,
 }
 
+/// Access token issued to a service account. No refresh token is issued:
+/// the client exchanges its credentials again once the token expires.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceTokenVo {
+    pub token: String,
+    /// Always `Bearer`
+    pub token_type: &'static str,
+    /// Token lifetime in seconds
+    pub expires_in: i64,
+}
+
+/// Identity provider URL the browser is redirected to for an SSO login.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OidcAuthorizeVo {
+    pub authorization_url: String,
+}
+
+/// Response payload of the password step of a login.
+///
+/// Users without MFA are logged in directly; users with MFA get a short-lived
+/// token to present to `/auth/mfa/verify` together with a code.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResultVo {
+    Success(LoginVo),
+    MfaRequired(MfaRequiredVo),
+}
+
+/// Response payload when a second factor is required.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaRequiredVo {
+    /// Always true, lets clients tell this response apart from `LoginVo`
+    pub mfa_required: bool,
+    /// Short-lived token proving the password step
+    pub mfa_token: String,
+}
+
 /// Comprehensive user information for authenticated sessions.
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -28,4 +75,263 @@ pub struct UserInfoVo {
     pub is_system: bool,
     /// List of permission codes the user has access to
     pub permissions: Vec<String>,
+    /// Whether the password must be changed (forced by an admin or expired)
+    pub must_change_password: bool,
+    /// Whether the user is being impersonated by the requester
+    pub impersonating: bool,
+    /// The real user while impersonating
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<ImpersonatorVo>,
+}
+
+impl UserInfoVo {
+    /// Flag the info as seen through an impersonation session of `actor`
+    pub fn impersonated_by(mut self, actor: Option<&ActorClaims>) -> Self {
+        self.impersonating = actor.is_some();
+        self.impersonator = actor
+            .map(|actor| ImpersonatorVo { id: actor.user_id, username: actor.username.clone() });
+        self
+    }
+}
+
+/// The admin behind an impersonation session.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpersonatorVo {
+    pub id: i64,
+    pub username: String,
+}
+
+/// Token of an impersonation session.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpersonationVo {
+    /// Access token acting as the user; no refresh token is issued
+    pub token: String,
+    /// Token lifetime in seconds
+    pub expires_in: i64,
+    /// Info of the impersonated user, as returned by `/auth/me`
+    pub user_info: UserInfoVo,
+}
+
+/// Profile of the current user.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileVo {
+    pub id: i64,
+    pub username: String,
+    pub email: String,
+    pub real_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub last_login_at: Option<NaiveDateTime>,
+    pub password_changed_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+impl From<ProfileEntity> for ProfileVo {
+    fn from(entity: ProfileEntity) -> Self {
+        Self {
+            id: entity.id,
+            username: entity.username,
+            email: entity.email,
+            real_name: entity.real_name,
+            avatar_url: entity.avatar_url,
+            email_verified_at: entity.email_verified_at,
+            last_login_at: entity.last_login_at,
+            password_changed_at: entity.password_changed_at,
+            created_at: entity.created_at,
+        }
+    }
+}
+
+/// Active login session of a user.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionVo {
+    /// Session ID (the access token jti)
+    pub id: Uuid,
+    /// Client IP address of the latest login/refresh
+    pub ip_address: Option<String>,
+    /// Client user agent of the latest login/refresh
+    pub user_agent: Option<String>,
+    /// Last authenticated request time
+    pub last_seen_at: NaiveDateTime,
+    /// Session expiration time
+    pub expires_at: NaiveDateTime,
+    /// Login time
+    pub created_at: NaiveDateTime,
+    /// Whether this is the session making the request
+    pub current: bool,
+    /// Username of the admin impersonating the user in this session
+    pub impersonated_by: Option<String>,
+}
+
+impl SessionVo {
+    pub fn from_entity(session: UserSessionEntity, current_session_id: Option<Uuid>) -> Self {
+        Self {
+            current: current_session_id == Some(session.id),
+            id: session.id,
+            ip_address: session.ip_address.map(|ip| ip.to_string()),
+            user_agent: session.user_agent,
+            last_seen_at: session.last_seen_at,
+            expires_at: session.expires_at,
+            created_at: session.created_at,
+            impersonated_by: session.impersonator_username,
+        }
+    }
+}
+
+/// MFA enrollment status of the current user.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaStatusVo {
+    /// Whether MFA is enforced at login
+    pub enabled: bool,
+    /// Number of unused recovery codes
+    pub recovery_codes_remaining: i64,
+}
+
+/// Pending TOTP enrollment, to be confirmed with a valid code.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaSetupVo {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// `otpauth://` URI for QR code enrollment
+    pub otpauth_uri: String,
+}
+
+/// Freshly generated recovery codes, shown only once.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodesVo {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Registered passkey of the current user.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyVo {
+    pub id: i64,
+    pub name: String,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<PasskeyEntity> for PasskeyVo {
+    fn from(passkey: PasskeyEntity) -> Self {
+        Self {
+            id: passkey.id,
+            name: passkey.name,
+            last_used_at: passkey.last_used_at,
+            created_at: passkey.created_at,
+        }
+    }
+}
+
+/// API key, without its secret.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyVo {
+    pub id: i64,
+    pub name: String,
+    /// First characters of the key, to tell keys apart
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub last_used_ip: Option<String>,
+    /// User who created the key, differs from the owner when created by an admin
+    pub created_by: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<ApiKeyEntity> for ApiKeyVo {
+    fn from(api_key: ApiKeyEntity) -> Self {
+        Self {
+            id: api_key.id,
+            name: api_key.name,
+            prefix: api_key.prefix,
+            scopes: api_key.scopes,
+            expires_at: api_key.expires_at,
+            last_used_at: api_key.last_used_at,
+            last_used_ip: api_key.last_used_ip.map(|ip| ip.to_string()),
+            created_by: api_key.created_by,
+            created_at: api_key.created_at,
+        }
+    }
+}
+
+/// Newly created API key. The key itself is only shown once.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyCreatedVo {
+    pub key: String,
+    pub api_key: ApiKeyVo,
+}
+
+/// `PublicKeyCredentialCreationOptions` for `navigator.credentials.create`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyCreationOptionsVo {
+    pub challenge: String,
+    pub rp: PasskeyRpVo,
+    pub user: PasskeyUserVo,
+    pub pub_key_cred_params: Vec<PasskeyCredParamVo>,
+    pub timeout: u32,
+    pub attestation: &'static str,
+    pub authenticator_selection: PasskeyAuthenticatorSelectionVo,
+    pub exclude_credentials: Vec<PasskeyCredentialDescriptorVo>,
+}
+
+/// `PublicKeyCredentialRequestOptions` for `navigator.credentials.get`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyRequestOptionsVo {
+    pub challenge: String,
+    pub rp_id: String,
+    pub timeout: u32,
+    pub user_verification: &'static str,
+    pub allow_credentials: Vec<PasskeyCredentialDescriptorVo>,
+}
+
+/// Relying party entity.
+#[derive(Debug, Serialize)]
+pub struct PasskeyRpVo {
+    pub id: String,
+    pub name: String,
+}
+
+/// User entity, `id` is the base64url user handle.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyUserVo {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+/// Accepted credential algorithm.
+#[derive(Debug, Serialize)]
+pub struct PasskeyCredParamVo {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub alg: i64,
+}
+
+/// Authenticator requirements.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyAuthenticatorSelectionVo {
+    pub resident_key: &'static str,
+    pub user_verification: &'static str,
+}
+
+/// Reference to an existing credential.
+#[derive(Debug, Serialize)]
+pub struct PasskeyCredentialDescriptorVo {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
 }
@@ -18,15 +18,15 @@ impl DashboardRepository {
             sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL")
                 .fetch_one(pool),
 
-            // 获取活跃用户数（7天内登录）
+            // 获取活跃用户数（7天内登录，不含服务账号）
             sqlx::query_scalar::<_, i64>(
-                "SELECT COUNT(*) FROM users WHERE last_login_at > NOW() - INTERVAL '7 days' AND deleted_at IS NULL"
+                "SELECT COUNT(*) FROM users WHERE last_login_at > NOW() - INTERVAL '7 days' AND deleted_at IS NULL AND user_type = 1"
             )
             .fetch_one(pool),
 
-            // 获取今日登录数
+            // 获取今日登录数（不含服务账号）
             sqlx::query_scalar::<_, i64>(
-                "SELECT COUNT(*) FROM users WHERE last_login_at > NOW() - INTERVAL '1 day' AND deleted_at IS NULL"
+                "SELECT COUNT(*) FROM users WHERE last_login_at > NOW() - INTERVAL '1 day' AND deleted_at IS NULL AND user_type = 1"
             )
             .fetch_one(pool),
 
@@ -133,7 +133,7 @@ impl DashboardRepository {
 
         let metrics = SystemMetricsDataVo {
             avg_response_time: avg_response_time_ms,
-            error_rate: error_rate,
+            error_rate,
             total_requests,
         };
 
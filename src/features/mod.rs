@@ -1,3 +1,5 @@
 pub mod auth;
 pub mod dashboard;
+pub mod oauth;
+pub mod scim;
 pub mod system;
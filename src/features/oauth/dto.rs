@@ -0,0 +1,73 @@
+use serde::Deserialize;
+
+/// Register OAuth client request parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateOAuthClientDto {
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub grant_types: Vec<String>,
+    /// Confidential clients get a secret; public clients (SPAs, mobile apps) rely on PKCE only
+    pub confidential: bool,
+    /// Service account the client acts as for the client_credentials grant
+    pub service_account_id: Option<i64>,
+}
+
+/// Update OAuth client request parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOAuthClientDto {
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub grant_types: Vec<String>,
+    pub service_account_id: Option<i64>,
+    pub status: i16,
+}
+
+/// OAuth client query parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthClientQueryDto {
+    /// The page number to retrieve. Defaults to 1.
+    pub current: Option<i64>,
+    /// The number of items per page. Defaults to 10.
+    pub page_size: Option<i64>,
+    /// Filter by client name (case-insensitive search).
+    pub name: Option<String>,
+    /// Filter by client status.
+    pub status: Option<String>,
+}
+
+/// Authorization request, forwarded by the consent page from the client's
+/// redirect. Field names follow RFC 6749.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthorizeRequest {
+    pub response_type: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+}
+
+/// Token endpoint form (RFC 6749 section 4.1.3 and 4.4.2). The client
+/// authenticates with HTTP Basic or the `client_id`/`client_secret` fields.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// Token introspection form (RFC 7662)
+#[derive(Debug, Clone, Deserialize)]
+pub struct IntrospectRequest {
+    pub token: String,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
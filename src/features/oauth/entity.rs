@@ -0,0 +1,33 @@
+use chrono::NaiveDateTime;
+
+/// Registered OAuth client application
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OAuthClientEntity {
+    pub id: i64,
+    pub client_id: String,
+    pub secret_hash: Option<String>,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub grant_types: Vec<String>,
+    pub service_account_id: Option<i64>,
+    pub status: i16,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl OAuthClientEntity {
+    /// Whether the client may use a grant type
+    pub fn allows_grant(&self, grant_type: &str) -> bool {
+        self.grant_types.iter().any(|grant| grant == grant_type)
+    }
+}
+
+/// Redeemed authorization code
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AuthorizationCodeEntity {
+    pub user_id: i64,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub nonce: Option<String>,
+    pub code_challenge: String,
+}
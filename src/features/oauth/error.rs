@@ -0,0 +1,83 @@
+use crate::common::error::ServiceError;
+
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+/// OAuth protocol error (RFC 6749 section 5.2, RFC 6750 section 3.1).
+///
+/// The protocol endpoints answer in the format OAuth client libraries
+/// expect instead of the `ApiResponse` envelope.
+#[derive(Debug)]
+pub struct OAuthError {
+    status: StatusCode,
+    error: &'static str,
+    description: String,
+}
+
+impl OAuthError {
+    fn new(status: StatusCode, error: &'static str, description: impl Into<String>) -> Self {
+        Self { status, error, description: description.into() }
+    }
+
+    pub fn invalid_request(description: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_request", description)
+    }
+
+    pub fn invalid_client() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "invalid_client", "Client authentication failed")
+    }
+
+    pub fn invalid_grant(description: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_grant", description)
+    }
+
+    pub fn unauthorized_client(description: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "unauthorized_client", description)
+    }
+
+    pub fn unsupported_grant_type() -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "unsupported_grant_type", "Unsupported grant type")
+    }
+
+    pub fn invalid_token() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "invalid_token", "The access token is invalid")
+    }
+
+    pub fn insufficient_scope(description: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "insufficient_scope", description)
+    }
+}
+
+impl std::fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.error, self.description)
+    }
+}
+
+impl From<ServiceError> for OAuthError {
+    fn from(err: ServiceError) -> Self {
+        tracing::error!("OAuth endpoint failed: {:?}", err);
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "Internal server error")
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        let body = Json(json!({ "error": self.error, "error_description": self.description }));
+        match self.status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN if self.error != "invalid_client" => {
+                let challenge = format!("Bearer error=\"{}\"", self.error);
+                (self.status, [(header::WWW_AUTHENTICATE, challenge)], body).into_response()
+            }
+            StatusCode::UNAUTHORIZED => {
+                (self.status, [(header::WWW_AUTHENTICATE, "Basic".to_string())], body)
+                    .into_response()
+            }
+            _ => (self.status, body).into_response(),
+        }
+    }
+}
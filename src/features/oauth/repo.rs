@@ -0,0 +1,359 @@
+use super::{
+    dto::OAuthClientQueryDto,
+    entity::{AuthorizationCodeEntity, OAuthClientEntity},
+};
+use crate::common::error::ServiceError;
+
+use chrono::{NaiveDateTime, Utc};
+use sqlx::{PgPool, QueryBuilder};
+use uuid::Uuid;
+
+/// Columns of `OAuthClientEntity`
+const CLIENT_COLUMNS: &str = "id, client_id, secret_hash, name, redirect_uris, grant_types,
+     service_account_id, status, created_at, updated_at";
+
+/// OAuth repository for clients, authorization codes and issued tokens
+pub struct OAuthRepository;
+
+impl OAuthRepository {
+    fn format_query(
+        query: &OAuthClientQueryDto,
+        query_builder: &mut QueryBuilder<'_, sqlx::Postgres>,
+    ) {
+        if let Some(name) = &query.name
+            && !name.trim().is_empty()
+        {
+            query_builder.push(" AND name ILIKE ").push_bind(format!("%{}%", name));
+        }
+        if let Some(status) = &query.status
+            && let Ok(status_num) = status.parse::<i16>()
+        {
+            query_builder.push(" AND status = ").push_bind(status_num);
+        }
+    }
+
+    /// Count clients matching filters
+    async fn count_clients(
+        pool: &PgPool,
+        query: &OAuthClientQueryDto,
+    ) -> Result<i64, ServiceError> {
+        let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
+            QueryBuilder::new("SELECT COUNT(*) FROM oauth_clients WHERE deleted_at IS NULL");
+
+        Self::format_query(query, &mut query_builder);
+
+        let count: (i64,) = query_builder.build_query_as().fetch_one(pool).await.map_err(|e| {
+            tracing::error!("Database error counting OAuth clients: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(count.0)
+    }
+
+    /// Queries clients with pagination
+    pub async fn find_with_pagination(
+        pool: &PgPool,
+        offset: i64,
+        limit: i64,
+        query: OAuthClientQueryDto,
+    ) -> Result<(Vec<OAuthClientEntity>, i64), ServiceError> {
+        let total = Self::count_clients(pool, &query).await?;
+        if total == 0 {
+            return Ok((Vec::new(), total));
+        }
+
+        let mut query_builder: QueryBuilder<'_, sqlx::Postgres> = QueryBuilder::new(format!(
+            "SELECT {} FROM oauth_clients WHERE deleted_at IS NULL",
+            CLIENT_COLUMNS
+        ));
+
+        Self::format_query(&query, &mut query_builder);
+
+        query_builder.push(" ORDER BY created_at DESC");
+        query_builder.push(" LIMIT ").push_bind(limit);
+        query_builder.push(" OFFSET ").push_bind(offset);
+
+        let clients = query_builder.build_query_as().fetch_all(pool).await.map_err(|e| {
+            tracing::error!("Database error in OAuth client pagination: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Ok((clients, total))
+    }
+
+    /// Find a client by ID
+    pub async fn find_by_id(
+        pool: &PgPool,
+        id: i64,
+    ) -> Result<Option<OAuthClientEntity>, ServiceError> {
+        sqlx::query_as::<_, OAuthClientEntity>(&format!(
+            "SELECT {} FROM oauth_clients WHERE id = $1 AND deleted_at IS NULL",
+            CLIENT_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error finding OAuth client ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Find an enabled client by its public client ID
+    pub async fn find_active_client(
+        pool: &PgPool,
+        client_id: &str,
+    ) -> Result<Option<OAuthClientEntity>, ServiceError> {
+        sqlx::query_as::<_, OAuthClientEntity>(&format!(
+            "SELECT {} FROM oauth_clients
+             WHERE client_id = $1 AND status = 1 AND deleted_at IS NULL",
+            CLIENT_COLUMNS
+        ))
+        .bind(client_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_active_client: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Register a client
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_client(
+        pool: &PgPool,
+        client_id: &str,
+        secret_hash: Option<&str>,
+        name: &str,
+        redirect_uris: &[String],
+        grant_types: &[String],
+        service_account_id: Option<i64>,
+        created_by: i64,
+    ) -> Result<i64, ServiceError> {
+        let now = Utc::now().naive_utc();
+        sqlx::query_scalar(
+            "INSERT INTO oauth_clients (client_id, secret_hash, name, redirect_uris, grant_types,
+                                        service_account_id, created_by, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+             RETURNING id",
+        )
+        .bind(client_id)
+        .bind(secret_hash)
+        .bind(name)
+        .bind(redirect_uris)
+        .bind(grant_types)
+        .bind(service_account_id)
+        .bind(created_by)
+        .bind(now)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error creating OAuth client '{}': {:?}", name, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Update a client. Returns false if it does not exist.
+    pub async fn update_client(
+        pool: &PgPool,
+        id: i64,
+        name: &str,
+        redirect_uris: &[String],
+        grant_types: &[String],
+        service_account_id: Option<i64>,
+        status: i16,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE oauth_clients
+             SET name = $2, redirect_uris = $3, grant_types = $4, service_account_id = $5,
+                 status = $6, updated_at = $7
+             WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(name)
+        .bind(redirect_uris)
+        .bind(grant_types)
+        .bind(service_account_id)
+        .bind(status)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error updating OAuth client ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Replace the secret of a confidential client. Returns false if it does not exist.
+    pub async fn update_client_secret(
+        pool: &PgPool,
+        id: i64,
+        secret_hash: &str,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE oauth_clients SET secret_hash = $2, updated_at = $3
+             WHERE id = $1 AND secret_hash IS NOT NULL AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(secret_hash)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error rotating OAuth client secret ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Soft delete a client and revoke its tokens
+    pub async fn soft_delete(pool: &PgPool, id: i64) -> Result<bool, ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for OAuth client: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        let now = Utc::now().naive_utc();
+        let result = sqlx::query(
+            "UPDATE oauth_clients SET deleted_at = $2, updated_at = $2
+             WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error deleting OAuth client ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "UPDATE oauth_access_tokens SET revoked_at = $2
+             WHERE client_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error revoking tokens of OAuth client ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing OAuth client deletion: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(true)
+    }
+
+    /// Store an authorization code
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_authorization_code(
+        pool: &PgPool,
+        code_hash: &str,
+        client_id: i64,
+        user_id: i64,
+        redirect_uri: &str,
+        scope: &str,
+        nonce: Option<&str>,
+        code_challenge: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), ServiceError> {
+        sqlx::query(
+            "INSERT INTO oauth_authorization_codes (code_hash, client_id, user_id, redirect_uri,
+                                                    scope, nonce, code_challenge, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(code_hash)
+        .bind(client_id)
+        .bind(user_id)
+        .bind(redirect_uri)
+        .bind(scope)
+        .bind(nonce)
+        .bind(code_challenge)
+        .bind(expires_at)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in create_authorization_code: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(())
+    }
+
+    /// Mark an unused, unexpired authorization code of a client as used and return it
+    pub async fn redeem_authorization_code(
+        pool: &PgPool,
+        code_hash: &str,
+        client_id: i64,
+    ) -> Result<Option<AuthorizationCodeEntity>, ServiceError> {
+        let now = Utc::now().naive_utc();
+        sqlx::query_as::<_, AuthorizationCodeEntity>(
+            "UPDATE oauth_authorization_codes SET used_at = $3
+             WHERE code_hash = $1 AND client_id = $2 AND used_at IS NULL AND expires_at > $3
+             RETURNING user_id, redirect_uri, scope, nonce, code_challenge",
+        )
+        .bind(code_hash)
+        .bind(client_id)
+        .bind(now)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in redeem_authorization_code: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Record an issued access token
+    pub async fn create_access_token(
+        pool: &PgPool,
+        jti: Uuid,
+        client_id: i64,
+        user_id: i64,
+        scope: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), ServiceError> {
+        sqlx::query(
+            "INSERT INTO oauth_access_tokens (jti, client_id, user_id, scope, expires_at)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(jti)
+        .bind(client_id)
+        .bind(user_id)
+        .bind(scope)
+        .bind(expires_at)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in create_access_token, user_id={}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(())
+    }
+
+    /// Whether an access token is unrevoked and unexpired, and its client and
+    /// user are still enabled
+    pub async fn is_access_token_active(pool: &PgPool, jti: Uuid) -> Result<bool, ServiceError> {
+        sqlx::query_scalar(
+            "SELECT EXISTS(
+                 SELECT 1 FROM oauth_access_tokens t
+                 JOIN oauth_clients c ON c.id = t.client_id
+                 JOIN users u ON u.id = t.user_id
+                 WHERE t.jti = $1 AND t.revoked_at IS NULL AND t.expires_at > $2
+                   AND c.status = 1 AND c.deleted_at IS NULL
+                   AND u.status = 1 AND u.deleted_at IS NULL)",
+        )
+        .bind(jti)
+        .bind(Utc::now().naive_utc())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in is_access_token_active: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+}
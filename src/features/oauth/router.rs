@@ -0,0 +1,260 @@
+use super::{
+    dto::{
+        AuthorizeRequest, CreateOAuthClientDto, IntrospectRequest, OAuthClientQueryDto,
+        TokenRequest, UpdateOAuthClientDto,
+    },
+    error::OAuthError,
+    service::OAuthService,
+    vo::{
+        AuthorizeInfoVo, AuthorizeRedirectVo, IntrospectionVo, OAuthClientSecretVo, OAuthClientVo,
+        OAuthUserInfoVo, ProviderMetadataVo, TokenVo,
+    },
+};
+use crate::{
+    common::{
+        api::{ApiResponse, AppResult},
+        router_ext::RouterExt,
+    },
+    core::{extractor::CurrentUser, jwk::JwkSet, jwt::JWT_CONFIG, permission::PermissionsCheck},
+    features::system::log::service::LogService,
+};
+
+use axum::{
+    Form, Json, Router,
+    extract::{ConnectInfo, Path, Query, State, rejection::FormRejection},
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+};
+use data_encoding::BASE64;
+use sqlx::PgPool;
+use std::{net::SocketAddr, time::Instant};
+
+/// Public OAuth2 / OpenID Connect protocol endpoints (client or bearer authenticated)
+pub fn oauth_routes() -> Router<PgPool> {
+    Router::new()
+        .route("/.well-known/openid-configuration", get(discovery_handler))
+        .route("/.well-known/jwks.json", get(jwks_handler))
+        .route("/token", post(token_handler))
+        .route("/introspect", post(introspect_handler))
+        .route("/userinfo", get(userinfo_handler).post(userinfo_handler))
+}
+
+/// Consent endpoints used by the frontend authorization page (JWT required)
+pub fn protected_oauth_routes() -> Router<PgPool> {
+    Router::new().route("/authorize", get(authorize_info_handler).post(authorize_handler))
+}
+
+/// OAuth client management routes
+pub fn oauth_client_routes() -> Router<PgPool> {
+    Router::new()
+        .route_with_permission(
+            "/",
+            get(get_client_list),
+            PermissionsCheck::Single("system:oauth:list"),
+        )
+        .route_with_permission(
+            "/",
+            post(create_client),
+            PermissionsCheck::Single("system:oauth:create"),
+        )
+        .route_with_permission(
+            "/{id}",
+            put(update_client),
+            PermissionsCheck::Single("system:oauth:update"),
+        )
+        .route_with_permission(
+            "/{id}",
+            delete(delete_client),
+            PermissionsCheck::Single("system:oauth:delete"),
+        )
+        .route_with_permission(
+            "/{id}/secret",
+            post(regenerate_secret),
+            PermissionsCheck::Single("system:oauth:update"),
+        )
+}
+
+/// Client ID and secret of an `Authorization: Basic` header
+fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let encoded = headers.get(header::AUTHORIZATION)?.to_str().ok()?.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(BASE64.decode(encoded.trim().as_bytes()).ok()?).ok()?;
+    let (client_id, client_secret) = decoded.split_once(':')?;
+    Some((client_id.to_string(), client_secret.to_string()))
+}
+
+/// Token responses must not be cached (RFC 6749 section 5.1)
+fn no_store<T: serde::Serialize>(body: T) -> Response {
+    ([(header::CACHE_CONTROL, "no-store"), (header::PRAGMA, "no-cache")], Json(body))
+        .into_response()
+}
+
+/// OpenID Connect discovery document
+async fn discovery_handler() -> Json<ProviderMetadataVo> {
+    Json(OAuthService::discovery())
+}
+
+/// Public keys verifying the tokens issued by rustzen, empty with an HMAC secret
+pub async fn jwks_handler() -> Json<&'static JwkSet> {
+    Json(JWT_CONFIG.keys.jwks())
+}
+
+/// Token endpoint: authorization code and client credentials grants
+#[tracing::instrument(name = "oauth_token", skip(pool, addr, headers, form))]
+async fn token_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    form: Result<Form<TokenRequest>, FormRejection>,
+) -> Result<Response, OAuthError> {
+    let start_time = Instant::now();
+    let Form(request) = form.map_err(|e| OAuthError::invalid_request(e.body_text()))?;
+    tracing::info!("OAuth token request from {}: grant_type={}", addr.ip(), request.grant_type);
+
+    let basic = basic_credentials(&headers);
+    let client_id = basic.as_ref().map(|(id, _)| id.clone()).or_else(|| request.client_id.clone());
+    let grant_type = request.grant_type.clone();
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    let result = OAuthService::token(&pool, request, basic).await;
+    let (user_id, username, description, status) = match &result {
+        Ok((user_id, username, _)) => {
+            (*user_id, username.as_str(), "OAuth token issued".to_string(), "SUCCESS")
+        }
+        Err(err) => (0, "anonymous", err.to_string(), "FAIL"),
+    };
+    if let Err(e) = LogService::log_business_operation(
+        &pool,
+        user_id,
+        username,
+        "OAUTH_TOKEN",
+        &description,
+        serde_json::json!({ "clientId": client_id, "grantType": grant_type }),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &ip_address,
+        user_agent,
+        None,
+    )
+    .await
+    {
+        tracing::error!("Failed to log OAuth token request: {:?}", e);
+    }
+
+    let (_, _, token): (_, _, TokenVo) = result?;
+    Ok(no_store(token))
+}
+
+/// Token introspection for resource servers
+#[tracing::instrument(name = "oauth_introspect", skip(pool, headers, form))]
+async fn introspect_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    form: Result<Form<IntrospectRequest>, FormRejection>,
+) -> Result<Response, OAuthError> {
+    let Form(request) = form.map_err(|e| OAuthError::invalid_request(e.body_text()))?;
+
+    let introspection: IntrospectionVo =
+        OAuthService::introspect(&pool, request, basic_credentials(&headers)).await?;
+
+    tracing::debug!("Token introspected: active={}", introspection.active);
+    Ok(no_store(introspection))
+}
+
+/// OpenID Connect userinfo of the access token's subject
+#[tracing::instrument(name = "oauth_userinfo", skip(pool, headers))]
+async fn userinfo_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Response, OAuthError> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "));
+
+    let userinfo: OAuthUserInfoVo = OAuthService::userinfo(&pool, bearer).await?;
+    Ok(no_store(userinfo))
+}
+
+/// Client and scopes of an authorization request, for the consent page
+#[tracing::instrument(name = "oauth_authorize_info", skip(pool))]
+async fn authorize_info_handler(
+    State(pool): State<PgPool>,
+    Query(request): Query<AuthorizeRequest>,
+) -> AppResult<AuthorizeInfoVo> {
+    let info = OAuthService::authorize_info(&pool, request).await?;
+    Ok(ApiResponse::success(info))
+}
+
+/// Approve an authorization request and get the client redirect
+#[tracing::instrument(name = "oauth_authorize", skip(current_user, pool, request))]
+async fn authorize_handler(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Json(request): Json<AuthorizeRequest>,
+) -> AppResult<AuthorizeRedirectVo> {
+    tracing::info!("Authorization approved for client {}", request.client_id);
+
+    let redirect = OAuthService::authorize(&pool, current_user.user_id, request).await?;
+    Ok(ApiResponse::success(redirect))
+}
+
+/// Get paginated OAuth client list with filtering
+async fn get_client_list(
+    State(pool): State<PgPool>,
+    Query(query): Query<OAuthClientQueryDto>,
+) -> AppResult<Vec<OAuthClientVo>> {
+    tracing::info!("OAuth client list request: query={:?}", query);
+
+    let (clients, total) = OAuthService::get_client_list(&pool, query).await?;
+
+    Ok(ApiResponse::page(clients, total))
+}
+
+/// Register an OAuth client
+async fn create_client(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateOAuthClientDto>,
+) -> AppResult<OAuthClientSecretVo> {
+    tracing::info!("Create OAuth client: name={}", request.name);
+
+    let client = OAuthService::create_client(&pool, request, current_user.user_id).await?;
+
+    Ok(ApiResponse::success(client))
+}
+
+/// Update an OAuth client
+async fn update_client(
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    Json(request): Json<UpdateOAuthClientDto>,
+) -> AppResult<()> {
+    tracing::info!("Update OAuth client {}: name={}", id, request.name);
+
+    OAuthService::update_client(&pool, id, request).await?;
+
+    Ok(ApiResponse::success(()))
+}
+
+/// Delete an OAuth client
+async fn delete_client(State(pool): State<PgPool>, Path(id): Path<i64>) -> AppResult<()> {
+    tracing::info!("Delete OAuth client {}", id);
+
+    OAuthService::delete_client(&pool, id).await?;
+
+    Ok(ApiResponse::success(()))
+}
+
+/// Regenerate the secret of a confidential OAuth client
+async fn regenerate_secret(
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> AppResult<OAuthClientSecretVo> {
+    tracing::info!("Regenerate secret of OAuth client {}", id);
+
+    let client = OAuthService::regenerate_secret(&pool, id).await?;
+
+    Ok(ApiResponse::success(client))
+}
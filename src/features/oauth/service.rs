@@ -0,0 +1,832 @@
+use super::{
+    dto::{
+        AuthorizeRequest, CreateOAuthClientDto, IntrospectRequest, OAuthClientQueryDto,
+        TokenRequest, UpdateOAuthClientDto,
+    },
+    entity::{AuthorizationCodeEntity, OAuthClientEntity},
+    error::OAuthError,
+    repo::OAuthRepository,
+    vo::{
+        AuthorizeInfoVo, AuthorizeRedirectVo, IntrospectionVo, OAuthClientSecretVo, OAuthClientVo,
+        OAuthUserInfoVo, ProviderMetadataVo, TokenVo,
+    },
+};
+use crate::{
+    common::{error::ServiceError, pagination::Pagination},
+    core::{
+        config::CONFIG,
+        jwt::{self, IdTokenClaims, JWT_CONFIG, OAuthAccessClaims},
+        oidc,
+        token::TokenUtils,
+    },
+    features::{
+        auth::{entity::AuthUserEntity, repo::AuthRepository},
+        system::user::{entity::UserType, repo::UserRepository},
+    },
+};
+
+use chrono::{Duration, Utc};
+use reqwest::Url;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Authorization codes are exchanged right after the redirect
+const AUTHORIZATION_CODE_EXPIRE_SECS: i64 = 300;
+
+const GRANT_AUTHORIZATION_CODE: &str = "authorization_code";
+const GRANT_CLIENT_CREDENTIALS: &str = "client_credentials";
+const SUPPORTED_GRANTS: [&str; 2] = [GRANT_AUTHORIZATION_CODE, GRANT_CLIENT_CREDENTIALS];
+
+/// Scopes the provider understands; others are dropped from requests
+const SUPPORTED_SCOPES: [&str; 3] = ["openid", "profile", "email"];
+
+/// Prefix of generated client IDs
+const CLIENT_ID_PREFIX: &str = "rzoc_";
+
+/// Length of the random part of a client ID, in hex characters
+const CLIENT_ID_RANDOM_LEN: usize = 24;
+
+/// Whether a space separated scope string contains `name`
+fn has_scope(scope: &str, name: &str) -> bool {
+    scope.split_whitespace().any(|granted| granted == name)
+}
+
+/// Check an authorization request against its client, returning the granted
+/// scopes: unsupported scopes are dropped and duplicates removed
+fn authorize_scopes(
+    client: &OAuthClientEntity,
+    request: &AuthorizeRequest,
+) -> Result<Vec<String>, ServiceError> {
+    let invalid = |message: &str| ServiceError::InvalidOperation(message.to_string());
+
+    if !client.redirect_uris.contains(&request.redirect_uri) {
+        return Err(invalid("Redirect URI is not registered for this client"));
+    }
+    if request.response_type != "code" || !client.allows_grant(GRANT_AUTHORIZATION_CODE) {
+        return Err(invalid("Unsupported response type"));
+    }
+    if request.code_challenge_method.as_deref() != Some("S256")
+        || request.code_challenge.as_deref().is_none_or(str::is_empty)
+    {
+        return Err(invalid("A PKCE S256 code challenge is required"));
+    }
+
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in request.scope.as_deref().unwrap_or_default().split_whitespace() {
+        if SUPPORTED_SCOPES.contains(&scope) && !scopes.iter().any(|s| s == scope) {
+            scopes.push(scope.to_string());
+        }
+    }
+    Ok(scopes)
+}
+
+/// Whether a client secret authenticates a client. Confidential clients must
+/// send their secret and public clients must not send one.
+fn client_secret_matches(secret_hash: Option<&str>, client_secret: Option<&str>) -> bool {
+    match (secret_hash, client_secret) {
+        (Some(hash), Some(secret)) => hash == TokenUtils::hash_token(secret),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Check the redirect URI and PKCE verifier of a token request against the
+/// redeemed authorization code
+fn verify_authorization_code(
+    grant: &AuthorizationCodeEntity,
+    redirect_uri: &str,
+    code_verifier: &str,
+) -> Result<(), OAuthError> {
+    if grant.redirect_uri != redirect_uri {
+        return Err(OAuthError::invalid_grant("Redirect URI mismatch"));
+    }
+    if oidc::code_challenge(code_verifier) != grant.code_challenge {
+        return Err(OAuthError::invalid_grant("PKCE verification failed"));
+    }
+    Ok(())
+}
+
+/// Client credentials of a token or introspection request
+struct ClientCredentials<'a> {
+    client_id: Option<&'a str>,
+    client_secret: Option<&'a str>,
+}
+
+impl<'a> ClientCredentials<'a> {
+    /// HTTP Basic credentials win over the form fields
+    fn new(
+        basic: Option<&'a (String, String)>,
+        client_id: Option<&'a str>,
+        client_secret: Option<&'a str>,
+    ) -> Self {
+        match basic {
+            Some((id, secret)) => Self { client_id: Some(id), client_secret: Some(secret) },
+            None => Self { client_id, client_secret },
+        }
+    }
+}
+
+pub struct OAuthService;
+
+impl OAuthService {
+    /// Get paginated client list with filtering
+    pub async fn get_client_list(
+        pool: &PgPool,
+        query: OAuthClientQueryDto,
+    ) -> Result<(Vec<OAuthClientVo>, i64), ServiceError> {
+        tracing::info!("Fetching OAuth client list with query: {:?}", query);
+
+        let (limit, offset, _) = Pagination::normalize(query.current, query.page_size);
+
+        let (clients, total) =
+            OAuthRepository::find_with_pagination(pool, offset, limit, query).await?;
+
+        Ok((clients.into_iter().map(OAuthClientVo::from).collect(), total))
+    }
+
+    /// Register a client. The secret of a confidential client is only returned here.
+    pub async fn create_client(
+        pool: &PgPool,
+        request: CreateOAuthClientDto,
+        created_by: i64,
+    ) -> Result<OAuthClientSecretVo, ServiceError> {
+        let grant_types = Self::validate_client(
+            pool,
+            &request.name,
+            &request.redirect_uris,
+            &request.grant_types,
+            request.confidential,
+            request.service_account_id,
+        )
+        .await?;
+
+        let client_id = format!(
+            "{}{}",
+            CLIENT_ID_PREFIX,
+            &TokenUtils::generate_token()[..CLIENT_ID_RANDOM_LEN]
+        );
+        let client_secret = request.confidential.then(TokenUtils::generate_token);
+        let secret_hash = client_secret.as_deref().map(TokenUtils::hash_token);
+
+        let id = OAuthRepository::create_client(
+            pool,
+            &client_id,
+            secret_hash.as_deref(),
+            request.name.trim(),
+            &request.redirect_uris,
+            &grant_types,
+            request.service_account_id,
+            created_by,
+        )
+        .await?;
+        let client = OAuthRepository::find_by_id(pool, id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("OAuth client".to_string()))?;
+
+        tracing::info!("Registered OAuth client {} ({}) by user_id={}", id, client_id, created_by);
+        Ok(OAuthClientSecretVo { client_secret, client: client.into() })
+    }
+
+    /// Update a client
+    pub async fn update_client(
+        pool: &PgPool,
+        id: i64,
+        request: UpdateOAuthClientDto,
+    ) -> Result<(), ServiceError> {
+        let client = OAuthRepository::find_by_id(pool, id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("OAuth client".to_string()))?;
+        if !matches!(request.status, 1 | 2) {
+            return Err(ServiceError::InvalidOperation("Invalid client status".to_string()));
+        }
+        let grant_types = Self::validate_client(
+            pool,
+            &request.name,
+            &request.redirect_uris,
+            &request.grant_types,
+            client.secret_hash.is_some(),
+            request.service_account_id,
+        )
+        .await?;
+
+        if !OAuthRepository::update_client(
+            pool,
+            id,
+            request.name.trim(),
+            &request.redirect_uris,
+            &grant_types,
+            request.service_account_id,
+            request.status,
+        )
+        .await?
+        {
+            return Err(ServiceError::NotFound("OAuth client".to_string()));
+        }
+
+        tracing::info!("Updated OAuth client {}", id);
+        Ok(())
+    }
+
+    /// Delete a client and revoke the access tokens issued to it
+    pub async fn delete_client(pool: &PgPool, id: i64) -> Result<(), ServiceError> {
+        if !OAuthRepository::soft_delete(pool, id).await? {
+            return Err(ServiceError::NotFound("OAuth client".to_string()));
+        }
+        tracing::info!("Deleted OAuth client {}", id);
+        Ok(())
+    }
+
+    /// Replace the secret of a confidential client. The new secret is only returned here.
+    pub async fn regenerate_secret(
+        pool: &PgPool,
+        id: i64,
+    ) -> Result<OAuthClientSecretVo, ServiceError> {
+        let client = OAuthRepository::find_by_id(pool, id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("OAuth client".to_string()))?;
+        if client.secret_hash.is_none() {
+            return Err(ServiceError::InvalidOperation(
+                "Public clients have no secret".to_string(),
+            ));
+        }
+
+        let client_secret = TokenUtils::generate_token();
+        if !OAuthRepository::update_client_secret(pool, id, &TokenUtils::hash_token(&client_secret))
+            .await?
+        {
+            return Err(ServiceError::NotFound("OAuth client".to_string()));
+        }
+
+        tracing::info!("Regenerated secret of OAuth client {}", id);
+        Ok(OAuthClientSecretVo { client_secret: Some(client_secret), client: client.into() })
+    }
+
+    /// Validate client settings, returning the deduplicated grant types
+    async fn validate_client(
+        pool: &PgPool,
+        name: &str,
+        redirect_uris: &[String],
+        grant_types: &[String],
+        confidential: bool,
+        service_account_id: Option<i64>,
+    ) -> Result<Vec<String>, ServiceError> {
+        let invalid = |message: &str| Err(ServiceError::InvalidOperation(message.to_string()));
+
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > 100 {
+            return invalid("Client name must be 1 to 100 characters");
+        }
+
+        let mut grants: Vec<String> = Vec::new();
+        for grant in grant_types {
+            if !SUPPORTED_GRANTS.contains(&grant.as_str()) {
+                return invalid("Unsupported grant type");
+            }
+            if !grants.contains(grant) {
+                grants.push(grant.clone());
+            }
+        }
+        if grants.is_empty() {
+            return invalid("At least one grant type is required");
+        }
+
+        for uri in redirect_uris {
+            match Url::parse(uri) {
+                Ok(url) if url.fragment().is_none() => {}
+                _ => return invalid("Redirect URIs must be absolute URLs without a fragment"),
+            }
+        }
+        if grants.iter().any(|grant| grant == GRANT_AUTHORIZATION_CODE) && redirect_uris.is_empty()
+        {
+            return invalid("The authorization code grant requires a redirect URI");
+        }
+
+        if grants.iter().any(|grant| grant == GRANT_CLIENT_CREDENTIALS)
+            && (!confidential || service_account_id.is_none())
+        {
+            return invalid(
+                "The client credentials grant requires a confidential client with a service account",
+            );
+        }
+        if let Some(service_account_id) = service_account_id {
+            let user = UserRepository::find_by_id(pool, service_account_id).await?;
+            if user.is_none_or(|user| user.user_type != UserType::ServiceAccount as i16) {
+                return invalid("Service account not found");
+            }
+        }
+
+        Ok(grants)
+    }
+
+    /// Check an authorization request, returning the client and the granted scopes.
+    ///
+    /// Errors are shown on the consent page and never redirected, so an
+    /// unregistered redirect URI cannot receive them.
+    async fn validate_authorize(
+        pool: &PgPool,
+        request: &AuthorizeRequest,
+    ) -> Result<(OAuthClientEntity, Vec<String>), ServiceError> {
+        let client = OAuthRepository::find_active_client(pool, &request.client_id)
+            .await?
+            .ok_or_else(|| ServiceError::InvalidOperation("Unknown client".to_string()))?;
+        let scopes = authorize_scopes(&client, request)?;
+        Ok((client, scopes))
+    }
+
+    /// Client and scopes of an authorization request, for the consent page
+    pub async fn authorize_info(
+        pool: &PgPool,
+        request: AuthorizeRequest,
+    ) -> Result<AuthorizeInfoVo, ServiceError> {
+        let (client, scopes) = Self::validate_authorize(pool, &request).await?;
+        Ok(AuthorizeInfoVo { client_id: client.client_id, client_name: client.name, scopes })
+    }
+
+    /// Approve an authorization request for the current user, returning the
+    /// client redirect with the authorization code
+    pub async fn authorize(
+        pool: &PgPool,
+        user_id: i64,
+        request: AuthorizeRequest,
+    ) -> Result<AuthorizeRedirectVo, ServiceError> {
+        let (client, scopes) = Self::validate_authorize(pool, &request).await?;
+
+        let code = TokenUtils::generate_token();
+        let expires_at =
+            (Utc::now() + Duration::seconds(AUTHORIZATION_CODE_EXPIRE_SECS)).naive_utc();
+        OAuthRepository::create_authorization_code(
+            pool,
+            &TokenUtils::hash_token(&code),
+            client.id,
+            user_id,
+            &request.redirect_uri,
+            &scopes.join(" "),
+            request.nonce.as_deref(),
+            request.code_challenge.as_deref().unwrap_or_default(),
+            expires_at,
+        )
+        .await?;
+
+        let mut redirect_url = Url::parse(&request.redirect_uri)
+            .map_err(|_| ServiceError::InvalidOperation("Invalid redirect URI".to_string()))?;
+        {
+            let mut query = redirect_url.query_pairs_mut();
+            query.append_pair("code", &code);
+            if let Some(state) = &request.state {
+                query.append_pair("state", state);
+            }
+        }
+
+        tracing::info!("Authorization code issued to client {} for user_id={}", client.id, user_id);
+        Ok(AuthorizeRedirectVo { redirect_url: redirect_url.into() })
+    }
+
+    /// Authenticate the client of a token or introspection request
+    async fn authenticate_client(
+        pool: &PgPool,
+        credentials: ClientCredentials<'_>,
+    ) -> Result<OAuthClientEntity, OAuthError> {
+        let Some(client_id) = credentials.client_id else {
+            return Err(OAuthError::invalid_client());
+        };
+        let client = OAuthRepository::find_active_client(pool, client_id)
+            .await?
+            .ok_or_else(OAuthError::invalid_client)?;
+
+        if !client_secret_matches(client.secret_hash.as_deref(), credentials.client_secret) {
+            tracing::warn!("OAuth client authentication failed for client_id={}", client_id);
+            return Err(OAuthError::invalid_client());
+        }
+        Ok(client)
+    }
+
+    /// Token endpoint. Returns the subject's ID and username with the tokens,
+    /// for the audit log.
+    pub async fn token(
+        pool: &PgPool,
+        request: TokenRequest,
+        basic: Option<(String, String)>,
+    ) -> Result<(i64, String, TokenVo), OAuthError> {
+        let credentials = ClientCredentials::new(
+            basic.as_ref(),
+            request.client_id.as_deref(),
+            request.client_secret.as_deref(),
+        );
+        let client = Self::authenticate_client(pool, credentials).await?;
+
+        match request.grant_type.as_str() {
+            GRANT_AUTHORIZATION_CODE => {
+                Self::authorization_code_grant(pool, &client, request).await
+            }
+            GRANT_CLIENT_CREDENTIALS => Self::client_credentials_grant(pool, &client).await,
+            _ => Err(OAuthError::unsupported_grant_type()),
+        }
+    }
+
+    async fn authorization_code_grant(
+        pool: &PgPool,
+        client: &OAuthClientEntity,
+        request: TokenRequest,
+    ) -> Result<(i64, String, TokenVo), OAuthError> {
+        if !client.allows_grant(GRANT_AUTHORIZATION_CODE) {
+            return Err(OAuthError::unauthorized_client("Grant type not allowed for this client"));
+        }
+        let (Some(code), Some(redirect_uri), Some(code_verifier)) =
+            (request.code, request.redirect_uri, request.code_verifier)
+        else {
+            return Err(OAuthError::invalid_request(
+                "code, redirect_uri and code_verifier are required",
+            ));
+        };
+
+        let grant = OAuthRepository::redeem_authorization_code(
+            pool,
+            &TokenUtils::hash_token(&code),
+            client.id,
+        )
+        .await?
+        .ok_or_else(|| OAuthError::invalid_grant("Invalid or expired authorization code"))?;
+        verify_authorization_code(&grant, &redirect_uri, &code_verifier)?;
+
+        let user = AuthRepository::get_user_by_id(pool, grant.user_id)
+            .await?
+            .ok_or_else(|| OAuthError::invalid_grant("User is not active"))?;
+        let token = Self::issue_tokens(pool, client, &user, grant.scope, grant.nonce).await?;
+        Ok((user.id, user.username, token))
+    }
+
+    async fn client_credentials_grant(
+        pool: &PgPool,
+        client: &OAuthClientEntity,
+    ) -> Result<(i64, String, TokenVo), OAuthError> {
+        let service_account_id = client
+            .service_account_id
+            .filter(|_| {
+                client.secret_hash.is_some() && client.allows_grant(GRANT_CLIENT_CREDENTIALS)
+            })
+            .ok_or_else(|| {
+                OAuthError::unauthorized_client("Grant type not allowed for this client")
+            })?;
+
+        let user = AuthRepository::get_user_by_id(pool, service_account_id)
+            .await?
+            .ok_or_else(|| OAuthError::invalid_grant("Service account is not active"))?;
+        let token = Self::issue_tokens(pool, client, &user, String::new(), None).await?;
+        Ok((user.id, user.username, token))
+    }
+
+    /// Permission codes carried by the tokens of a user
+    async fn user_permissions(
+        pool: &PgPool,
+        user: &AuthUserEntity,
+    ) -> Result<Vec<String>, ServiceError> {
+        if user.is_system {
+            return Ok(vec!["*".to_string()]);
+        }
+        AuthRepository::get_user_permissions(pool, user.id).await
+    }
+
+    /// Issue an access token, and an ID token when the openid scope was granted
+    async fn issue_tokens(
+        pool: &PgPool,
+        client: &OAuthClientEntity,
+        user: &AuthUserEntity,
+        scope: String,
+        nonce: Option<String>,
+    ) -> Result<TokenVo, OAuthError> {
+        let expires_in = CONFIG.oauth_access_token_expiration;
+        let now = Utc::now();
+        let expires_at = now + Duration::seconds(expires_in);
+        let jti = Uuid::new_v4();
+
+        OAuthRepository::create_access_token(
+            pool,
+            jti,
+            client.id,
+            user.id,
+            &scope,
+            expires_at.naive_utc(),
+        )
+        .await?;
+
+        let claims = OAuthAccessClaims {
+            iss: CONFIG.oauth_issuer.clone(),
+            sub: user.id.to_string(),
+            aud: client.client_id.clone(),
+            client_id: client.client_id.clone(),
+            username: user.username.clone(),
+            scope: scope.clone(),
+            permissions: Self::user_permissions(pool, user).await?,
+            jti,
+            exp: expires_at.timestamp() as usize,
+            iat: now.timestamp() as usize,
+        };
+        let access_token = jwt::generate_oauth_access_token(&claims).map_err(|e| {
+            tracing::error!("Failed to generate OAuth access token: {:?}", e);
+            ServiceError::TokenCreationFailed
+        })?;
+
+        let id_token = if has_scope(&scope, "openid") {
+            let profile = AuthRepository::get_profile(pool, user.id)
+                .await?
+                .ok_or_else(|| OAuthError::invalid_grant("User is not active"))?;
+            let with_profile = has_scope(&scope, "profile");
+            let with_email = has_scope(&scope, "email");
+            let claims = IdTokenClaims {
+                iss: claims.iss,
+                sub: claims.sub,
+                aud: claims.aud,
+                exp: claims.exp,
+                iat: claims.iat,
+                nonce,
+                preferred_username: with_profile.then(|| profile.username.clone()),
+                name: profile.real_name.filter(|_| with_profile),
+                email: with_email.then_some(profile.email),
+                email_verified: with_email.then_some(profile.email_verified_at.is_some()),
+            };
+            Some(jwt::generate_id_token(&claims).map_err(|e| {
+                tracing::error!("Failed to generate ID token: {:?}", e);
+                ServiceError::TokenCreationFailed
+            })?)
+        } else {
+            None
+        };
+
+        tracing::info!(
+            "OAuth tokens issued to client {} for user_id={}, scope='{}'",
+            client.id,
+            user.id,
+            scope
+        );
+        Ok(TokenVo { access_token, token_type: "Bearer", expires_in, scope, id_token })
+    }
+
+    /// Verify an access token and check that it is still active
+    async fn active_access_token(
+        pool: &PgPool,
+        token: &str,
+    ) -> Result<Option<OAuthAccessClaims>, ServiceError> {
+        let Ok(claims) = jwt::verify_oauth_access_token(token, &CONFIG.oauth_issuer) else {
+            return Ok(None);
+        };
+        if !OAuthRepository::is_access_token_active(pool, claims.jti).await? {
+            return Ok(None);
+        }
+        Ok(Some(claims))
+    }
+
+    /// Token introspection for resource servers, which authenticate as
+    /// confidential clients
+    pub async fn introspect(
+        pool: &PgPool,
+        request: IntrospectRequest,
+        basic: Option<(String, String)>,
+    ) -> Result<IntrospectionVo, OAuthError> {
+        let credentials = ClientCredentials::new(
+            basic.as_ref(),
+            request.client_id.as_deref(),
+            request.client_secret.as_deref(),
+        );
+        let client = Self::authenticate_client(pool, credentials).await?;
+        if client.secret_hash.is_none() {
+            return Err(OAuthError::invalid_client());
+        }
+
+        let Some(claims) = Self::active_access_token(pool, &request.token).await? else {
+            return Ok(IntrospectionVo::default());
+        };
+        Ok(IntrospectionVo {
+            active: true,
+            scope: Some(claims.scope),
+            client_id: Some(claims.client_id),
+            username: Some(claims.username),
+            token_type: Some("Bearer"),
+            exp: Some(claims.exp),
+            iat: Some(claims.iat),
+            sub: Some(claims.sub),
+            aud: Some(claims.aud),
+            iss: Some(claims.iss),
+            jti: Some(claims.jti.to_string()),
+            permissions: Some(claims.permissions),
+        })
+    }
+
+    /// OpenID Connect userinfo of the bearer of an access token with the openid scope
+    pub async fn userinfo(
+        pool: &PgPool,
+        bearer: Option<&str>,
+    ) -> Result<OAuthUserInfoVo, OAuthError> {
+        let token = bearer.ok_or_else(OAuthError::invalid_token)?;
+        let claims =
+            Self::active_access_token(pool, token).await?.ok_or_else(OAuthError::invalid_token)?;
+        if !has_scope(&claims.scope, "openid") {
+            return Err(OAuthError::insufficient_scope("The openid scope is required"));
+        }
+
+        let user_id: i64 = claims.sub.parse().map_err(|_| OAuthError::invalid_token())?;
+        let user = AuthRepository::get_user_by_id(pool, user_id)
+            .await?
+            .ok_or_else(OAuthError::invalid_token)?;
+        let profile = AuthRepository::get_profile(pool, user_id)
+            .await?
+            .ok_or_else(OAuthError::invalid_token)?;
+
+        let with_profile = has_scope(&claims.scope, "profile");
+        let with_email = has_scope(&claims.scope, "email");
+        Ok(OAuthUserInfoVo {
+            sub: claims.sub,
+            preferred_username: with_profile.then(|| profile.username.clone()),
+            name: profile.real_name.filter(|_| with_profile),
+            email: with_email.then_some(profile.email),
+            email_verified: with_email.then_some(profile.email_verified_at.is_some()),
+            permissions: Self::user_permissions(pool, &user).await?,
+        })
+    }
+
+    /// OpenID Connect discovery document
+    pub fn discovery() -> ProviderMetadataVo {
+        let base = CONFIG.oauth_issuer.trim_end_matches('/');
+        ProviderMetadataVo {
+            issuer: CONFIG.oauth_issuer.clone(),
+            authorization_endpoint: CONFIG.oauth_authorize_url.clone(),
+            token_endpoint: format!("{}/token", base),
+            introspection_endpoint: format!("{}/introspect", base),
+            userinfo_endpoint: format!("{}/userinfo", base),
+            jwks_uri: (!JWT_CONFIG.keys.jwks().keys.is_empty())
+                .then(|| format!("{}/.well-known/jwks.json", base)),
+            response_types_supported: vec!["code"],
+            grant_types_supported: SUPPORTED_GRANTS.to_vec(),
+            subject_types_supported: vec!["public"],
+            id_token_signing_alg_values_supported: vec![JWT_CONFIG.keys.algorithm()],
+            scopes_supported: SUPPORTED_SCOPES.to_vec(),
+            token_endpoint_auth_methods_supported: vec![
+                "client_secret_basic",
+                "client_secret_post",
+                "none",
+            ],
+            code_challenge_methods_supported: vec!["S256"],
+            claims_supported: vec![
+                "sub",
+                "preferred_username",
+                "name",
+                "email",
+                "email_verified",
+                "permissions",
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::NaiveDateTime;
+
+    /// PKCE example of RFC 7636 appendix B
+    const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    const REDIRECT_URI: &str = "https://app.example.com/callback";
+
+    fn client(grant_types: &[&str]) -> OAuthClientEntity {
+        OAuthClientEntity {
+            id: 1,
+            client_id: "rzoc_test".to_string(),
+            secret_hash: None,
+            name: "Test".to_string(),
+            redirect_uris: vec![REDIRECT_URI.to_string()],
+            grant_types: grant_types.iter().map(|grant| grant.to_string()).collect(),
+            service_account_id: None,
+            status: 1,
+            created_at: NaiveDateTime::default(),
+            updated_at: NaiveDateTime::default(),
+        }
+    }
+
+    fn authorize_request(scope: &str) -> AuthorizeRequest {
+        AuthorizeRequest {
+            response_type: "code".to_string(),
+            client_id: "rzoc_test".to_string(),
+            redirect_uri: REDIRECT_URI.to_string(),
+            scope: Some(scope.to_string()),
+            state: None,
+            nonce: None,
+            code_challenge: Some(CODE_CHALLENGE.to_string()),
+            code_challenge_method: Some("S256".to_string()),
+        }
+    }
+
+    fn grant() -> AuthorizationCodeEntity {
+        AuthorizationCodeEntity {
+            user_id: 2,
+            redirect_uri: REDIRECT_URI.to_string(),
+            scope: "openid".to_string(),
+            nonce: None,
+            code_challenge: CODE_CHALLENGE.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_code_challenge_matches_rfc_7636() {
+        assert_eq!(oidc::code_challenge(CODE_VERIFIER), CODE_CHALLENGE);
+    }
+
+    #[test]
+    fn test_authorize_scopes_filters_and_dedups() {
+        let client = client(&[GRANT_AUTHORIZATION_CODE]);
+        let scopes = authorize_scopes(&client, &authorize_request("openid admin email openid"));
+        assert_eq!(scopes.unwrap(), ["openid", "email"]);
+    }
+
+    #[test]
+    fn test_authorize_requires_registered_redirect_uri() {
+        let client = client(&[GRANT_AUTHORIZATION_CODE]);
+        for redirect_uri in
+            ["https://evil.example.com/callback", "https://app.example.com/callback/"]
+        {
+            let request = AuthorizeRequest {
+                redirect_uri: redirect_uri.to_string(),
+                ..authorize_request("")
+            };
+            assert!(authorize_scopes(&client, &request).is_err(), "{}", redirect_uri);
+        }
+    }
+
+    #[test]
+    fn test_authorize_requires_code_grant() {
+        let machine_client = client(&[GRANT_CLIENT_CREDENTIALS]);
+        assert!(authorize_scopes(&machine_client, &authorize_request("openid")).is_err());
+
+        let code_client = client(&[GRANT_AUTHORIZATION_CODE]);
+        let request =
+            AuthorizeRequest { response_type: "token".to_string(), ..authorize_request("openid") };
+        assert!(authorize_scopes(&code_client, &request).is_err());
+    }
+
+    #[test]
+    fn test_authorize_requires_s256_challenge() {
+        let client = client(&[GRANT_AUTHORIZATION_CODE]);
+        let plain = AuthorizeRequest {
+            code_challenge_method: Some("plain".to_string()),
+            ..authorize_request("openid")
+        };
+        let missing = AuthorizeRequest { code_challenge: None, ..authorize_request("openid") };
+        let empty =
+            AuthorizeRequest { code_challenge: Some(String::new()), ..authorize_request("openid") };
+        for request in [plain, missing, empty] {
+            assert!(authorize_scopes(&client, &request).is_err());
+        }
+    }
+
+    #[test]
+    fn test_verify_authorization_code() {
+        assert!(verify_authorization_code(&grant(), REDIRECT_URI, CODE_VERIFIER).is_ok());
+    }
+
+    #[test]
+    fn test_verify_authorization_code_rejects_redirect_mismatch() {
+        let err =
+            verify_authorization_code(&grant(), "https://app.example.com/other", CODE_VERIFIER)
+                .unwrap_err();
+        assert_eq!(err.to_string(), "invalid_grant: Redirect URI mismatch");
+    }
+
+    #[test]
+    fn test_verify_authorization_code_rejects_wrong_verifier() {
+        for verifier in ["", CODE_CHALLENGE, "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXK"] {
+            let err = verify_authorization_code(&grant(), REDIRECT_URI, verifier).unwrap_err();
+            assert_eq!(err.to_string(), "invalid_grant: PKCE verification failed");
+        }
+    }
+
+    #[test]
+    fn test_client_secret_matches() {
+        let hash = TokenUtils::hash_token("secret");
+        assert!(client_secret_matches(Some(&hash), Some("secret")));
+        assert!(!client_secret_matches(Some(&hash), Some("wrong")));
+        assert!(!client_secret_matches(Some(&hash), Some("")));
+        // A confidential client must authenticate
+        assert!(!client_secret_matches(Some(&hash), None));
+    }
+
+    #[test]
+    fn test_client_secret_matches_public_client() {
+        assert!(client_secret_matches(None, None));
+        // A secret sent for a public client is a misconfiguration, not ignored
+        assert!(!client_secret_matches(None, Some("secret")));
+    }
+
+    #[test]
+    fn test_basic_credentials_win_over_form_fields() {
+        let basic = ("basic_id".to_string(), "basic_secret".to_string());
+        let credentials =
+            ClientCredentials::new(Some(&basic), Some("form_id"), Some("form_secret"));
+        assert_eq!(credentials.client_id, Some("basic_id"));
+        assert_eq!(credentials.client_secret, Some("basic_secret"));
+
+        let credentials = ClientCredentials::new(None, Some("form_id"), None);
+        assert_eq!(credentials.client_id, Some("form_id"));
+        assert_eq!(credentials.client_secret, None);
+    }
+}
@@ -0,0 +1,139 @@
+use super::entity::OAuthClientEntity;
+
+use chrono::NaiveDateTime;
+use jsonwebtoken::Algorithm;
+use serde::Serialize;
+
+/// OAuth client item for list display
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthClientVo {
+    pub id: i64,
+    pub client_id: String,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub grant_types: Vec<String>,
+    pub confidential: bool,
+    pub service_account_id: Option<i64>,
+    pub status: i16,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<OAuthClientEntity> for OAuthClientVo {
+    fn from(client: OAuthClientEntity) -> Self {
+        Self {
+            id: client.id,
+            client_id: client.client_id,
+            name: client.name,
+            redirect_uris: client.redirect_uris,
+            grant_types: client.grant_types,
+            confidential: client.secret_hash.is_some(),
+            service_account_id: client.service_account_id,
+            status: client.status,
+            created_at: client.created_at,
+            updated_at: client.updated_at,
+        }
+    }
+}
+
+/// Registered client with its secret, which is only shown once
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthClientSecretVo {
+    pub client_secret: Option<String>,
+    pub client: OAuthClientVo,
+}
+
+/// Client and scopes shown on the consent page
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizeInfoVo {
+    pub client_id: String,
+    pub client_name: String,
+    pub scopes: Vec<String>,
+}
+
+/// Client redirect carrying the authorization code
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizeRedirectVo {
+    pub redirect_url: String,
+}
+
+/// Token endpoint response (RFC 6749 section 5.1)
+#[derive(Debug, Serialize)]
+pub struct TokenVo {
+    pub access_token: String,
+    /// Always `Bearer`
+    pub token_type: &'static str,
+    /// Token lifetime in seconds
+    pub expires_in: i64,
+    pub scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
+
+/// Token introspection response (RFC 7662), only `active` for inactive tokens
+#[derive(Debug, Default, Serialize)]
+pub struct IntrospectionVo {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Vec<String>>,
+}
+
+/// OpenID Connect userinfo response
+#[derive(Debug, Serialize)]
+pub struct OAuthUserInfoVo {
+    pub sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+    pub permissions: Vec<String>,
+}
+
+/// OpenID Connect discovery document
+#[derive(Debug, Serialize)]
+pub struct ProviderMetadataVo {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub introspection_endpoint: String,
+    pub userinfo_endpoint: String,
+    /// Only published for asymmetric signing keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwks_uri: Option<String>,
+    pub response_types_supported: Vec<&'static str>,
+    pub grant_types_supported: Vec<&'static str>,
+    pub subject_types_supported: Vec<&'static str>,
+    pub id_token_signing_alg_values_supported: Vec<Algorithm>,
+    pub scopes_supported: Vec<&'static str>,
+    pub token_endpoint_auth_methods_supported: Vec<&'static str>,
+    pub code_challenge_methods_supported: Vec<&'static str>,
+    pub claims_supported: Vec<&'static str>,
+}
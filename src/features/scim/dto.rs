@@ -0,0 +1,80 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// List query parameters (RFC 7644 section 3.4.2)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListQuery {
+    pub filter: Option<String>,
+    /// 1-based index of the first result. Defaults to 1.
+    pub start_index: Option<i64>,
+    /// Maximum number of results. Defaults to 100.
+    pub count: Option<i64>,
+}
+
+/// `name` attribute of a user
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimNameDto {
+    pub formatted: Option<String>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+}
+
+/// One entry of the `emails` attribute
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimEmailDto {
+    pub value: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+/// User resource sent by the provisioning client (POST and PUT)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserDto {
+    pub user_name: String,
+    pub external_id: Option<String>,
+    pub name: Option<ScimNameDto>,
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub emails: Vec<ScimEmailDto>,
+    /// Defaults to true
+    pub active: Option<bool>,
+    /// Initial password; users without one sign in through SSO or LDAP
+    pub password: Option<String>,
+}
+
+/// Member reference of a group
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScimMemberDto {
+    /// User ID
+    pub value: String,
+}
+
+/// Group resource sent by the provisioning client (POST and PUT)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroupDto {
+    pub display_name: String,
+    pub external_id: Option<String>,
+    #[serde(default)]
+    pub members: Vec<ScimMemberDto>,
+}
+
+/// One operation of a PATCH request
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScimPatchOperation {
+    /// add, replace or remove (case-insensitive)
+    pub op: String,
+    pub path: Option<String>,
+    pub value: Option<Value>,
+}
+
+/// PATCH request (RFC 7644 section 3.5.2)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScimPatchDto {
+    #[serde(rename = "Operations")]
+    pub operations: Vec<ScimPatchOperation>,
+}
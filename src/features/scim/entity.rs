@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+
+/// User resource as stored
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ScimUserEntity {
+    pub id: i64,
+    pub username: String,
+    pub email: String,
+    pub real_name: Option<String>,
+    pub status: i16,
+    pub is_system: bool,
+    pub scim_external_id: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Role exposed as a group
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ScimGroupEntity {
+    pub id: i64,
+    pub name: String,
+    pub is_system: bool,
+    pub scim_external_id: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// User to role assignment, with the display names of both sides
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ScimMembershipEntity {
+    pub user_id: i64,
+    pub username: String,
+    pub role_id: i64,
+    pub role_name: String,
+}
@@ -0,0 +1,7 @@
+pub mod dto;
+pub mod entity;
+pub mod error;
+pub mod repo;
+pub mod router;
+pub mod service;
+pub mod vo;
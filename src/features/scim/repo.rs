@@ -0,0 +1,519 @@
+use super::entity::{ScimGroupEntity, ScimMembershipEntity, ScimUserEntity};
+use crate::common::error::ServiceError;
+
+use chrono::Utc;
+use sqlx::{PgPool, QueryBuilder};
+
+const USER_COLUMNS: &str =
+    "u.id, u.username, u.email, u.real_name, u.status, u.is_system, u.scim_external_id,
+     u.created_at, u.updated_at";
+
+const GROUP_COLUMNS: &str =
+    "r.id, r.name, r.is_system, r.scim_external_id, r.created_at, r.updated_at";
+
+/// Filter condition on a column or SQL expression
+#[derive(Debug, Clone)]
+pub enum FilterClause {
+    /// The column is not NULL
+    Present(&'static str),
+    /// Case-insensitive text equality
+    Equals { column: &'static str, value: String, negate: bool },
+    /// Boolean expression equality
+    Flag { column: &'static str, value: bool, negate: bool },
+    /// Case-insensitive `LIKE` pattern, already escaped
+    Like { column: &'static str, pattern: String },
+}
+
+/// Membership changes of a PATCH on a group
+#[derive(Debug, Clone, Default)]
+pub struct GroupMembersChange {
+    /// Replaces every non-system member when set
+    pub replace: Option<Vec<i64>>,
+    pub add: Vec<i64>,
+    pub remove: Vec<i64>,
+}
+
+/// SCIM repository for users and the roles exposed as groups
+pub struct ScimRepository;
+
+impl ScimRepository {
+    fn format_query(
+        clauses: &[FilterClause],
+        query_builder: &mut QueryBuilder<'_, sqlx::Postgres>,
+    ) {
+        for clause in clauses {
+            match clause {
+                FilterClause::Present(column) => {
+                    query_builder.push(format!(" AND {} IS NOT NULL", column));
+                }
+                FilterClause::Equals { column, value, negate } => {
+                    let op = if *negate { "<>" } else { "=" };
+                    query_builder
+                        .push(format!(" AND LOWER({}::text) {} LOWER(", column, op))
+                        .push_bind(value.clone())
+                        .push(")");
+                }
+                FilterClause::Flag { column, value, negate } => {
+                    let op = if *negate { "<>" } else { "=" };
+                    query_builder.push(format!(" AND {} {} ", column, op)).push_bind(*value);
+                }
+                FilterClause::Like { column, pattern } => {
+                    query_builder
+                        .push(format!(" AND {}::text ILIKE ", column))
+                        .push_bind(pattern.clone());
+                }
+            }
+        }
+    }
+
+    /// Queries human users with pagination
+    pub async fn find_users(
+        pool: &PgPool,
+        clauses: &[FilterClause],
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<ScimUserEntity>, i64), ServiceError> {
+        const FROM: &str = " FROM users u WHERE u.deleted_at IS NULL AND u.user_type = 1";
+
+        let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
+            QueryBuilder::new(format!("SELECT COUNT(*){}", FROM));
+        Self::format_query(clauses, &mut query_builder);
+        let total: (i64,) = query_builder.build_query_as().fetch_one(pool).await.map_err(|e| {
+            tracing::error!("Database error counting SCIM users: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        if total.0 == 0 || limit == 0 {
+            return Ok((Vec::new(), total.0));
+        }
+
+        let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
+            QueryBuilder::new(format!("SELECT {}{}", USER_COLUMNS, FROM));
+        Self::format_query(clauses, &mut query_builder);
+        query_builder.push(" ORDER BY u.id");
+        query_builder.push(" LIMIT ").push_bind(limit);
+        query_builder.push(" OFFSET ").push_bind(offset);
+
+        let users = query_builder.build_query_as().fetch_all(pool).await.map_err(|e| {
+            tracing::error!("Database error in SCIM user query: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok((users, total.0))
+    }
+
+    /// Find a human user by ID
+    pub async fn find_user(pool: &PgPool, id: i64) -> Result<Option<ScimUserEntity>, ServiceError> {
+        sqlx::query_as::<_, ScimUserEntity>(&format!(
+            "SELECT {} FROM users u WHERE u.id = $1 AND u.deleted_at IS NULL AND u.user_type = 1",
+            USER_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error finding SCIM user ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Whether another user holds a username or email
+    pub async fn user_conflicts(
+        pool: &PgPool,
+        column: &'static str,
+        value: &str,
+        exclude_id: Option<i64>,
+    ) -> Result<bool, ServiceError> {
+        sqlx::query_scalar(&format!(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE {} = $1 AND deleted_at IS NULL
+                           AND id IS DISTINCT FROM $2)",
+            column
+        ))
+        .bind(value)
+        .bind(exclude_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error checking SCIM user {} conflict: {:?}", column, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Create a provisioned user
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_user(
+        pool: &PgPool,
+        username: &str,
+        email: &str,
+        real_name: Option<&str>,
+        status: i16,
+        password_hash: &str,
+        password_set: bool,
+        external_id: Option<&str>,
+    ) -> Result<i64, ServiceError> {
+        let now = Utc::now().naive_utc();
+        sqlx::query_scalar(
+            "INSERT INTO users (username, email, real_name, status, password_hash,
+                                password_changed_at, scim_external_id, created_at)
+             VALUES ($1, $2, $3, $4, $5, CASE WHEN $6 THEN $8 END, $7, $8)
+             RETURNING id",
+        )
+        .bind(username)
+        .bind(email)
+        .bind(real_name)
+        .bind(status)
+        .bind(password_hash)
+        .bind(password_set)
+        .bind(external_id)
+        .bind(now)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error creating SCIM user '{}': {:?}", username, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Replace the attributes of a provisioned user. The password is kept
+    /// when `password_hash` is `None`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_user(
+        pool: &PgPool,
+        id: i64,
+        username: &str,
+        email: &str,
+        real_name: Option<&str>,
+        status: i16,
+        password_hash: Option<&str>,
+        external_id: Option<&str>,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE users
+             SET username = $2, email = $3, real_name = $4, status = $5,
+                 email_verified_at = CASE WHEN email = $3 THEN email_verified_at END,
+                 password_hash = COALESCE($6, password_hash),
+                 password_changed_at = CASE WHEN $6 IS NULL THEN password_changed_at ELSE $8 END,
+                 scim_external_id = $7
+             WHERE id = $1 AND deleted_at IS NULL AND user_type = 1",
+        )
+        .bind(id)
+        .bind(username)
+        .bind(email)
+        .bind(real_name)
+        .bind(status)
+        .bind(password_hash)
+        .bind(external_id)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error updating SCIM user ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Role memberships of users, limited to grants inside their validity window
+    pub async fn find_user_memberships(
+        pool: &PgPool,
+        user_ids: &[i64],
+    ) -> Result<Vec<ScimMembershipEntity>, ServiceError> {
+        sqlx::query_as::<_, ScimMembershipEntity>(
+            "SELECT ur.user_id, u.username, ur.role_id, r.name AS role_name
+             FROM active_user_roles ur
+             JOIN users u ON u.id = ur.user_id
+             JOIN roles r ON r.id = ur.role_id AND r.deleted_at IS NULL
+             WHERE ur.user_id = ANY($1)
+             ORDER BY r.id",
+        )
+        .bind(user_ids)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_user_memberships: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Members of roles, limited to grants inside their validity window
+    pub async fn find_group_memberships(
+        pool: &PgPool,
+        role_ids: &[i64],
+    ) -> Result<Vec<ScimMembershipEntity>, ServiceError> {
+        sqlx::query_as::<_, ScimMembershipEntity>(
+            "SELECT ur.user_id, u.username, ur.role_id, r.name AS role_name
+             FROM active_user_roles ur
+             JOIN users u ON u.id = ur.user_id AND u.deleted_at IS NULL
+             JOIN roles r ON r.id = ur.role_id
+             WHERE ur.role_id = ANY($1)
+             ORDER BY u.id",
+        )
+        .bind(role_ids)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_group_memberships: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// IDs and system flags of the existing human users among `user_ids`
+    pub async fn find_human_users(
+        pool: &PgPool,
+        user_ids: &[i64],
+    ) -> Result<Vec<(i64, bool)>, ServiceError> {
+        sqlx::query_as(
+            "SELECT id, is_system FROM users
+             WHERE id = ANY($1) AND deleted_at IS NULL AND user_type = 1",
+        )
+        .bind(user_ids)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error in find_human_users: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Queries roles with pagination
+    pub async fn find_groups(
+        pool: &PgPool,
+        clauses: &[FilterClause],
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<ScimGroupEntity>, i64), ServiceError> {
+        const FROM: &str = " FROM roles r WHERE r.deleted_at IS NULL";
+
+        let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
+            QueryBuilder::new(format!("SELECT COUNT(*){}", FROM));
+        Self::format_query(clauses, &mut query_builder);
+        let total: (i64,) = query_builder.build_query_as().fetch_one(pool).await.map_err(|e| {
+            tracing::error!("Database error counting SCIM groups: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        if total.0 == 0 || limit == 0 {
+            return Ok((Vec::new(), total.0));
+        }
+
+        let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
+            QueryBuilder::new(format!("SELECT {}{}", GROUP_COLUMNS, FROM));
+        Self::format_query(clauses, &mut query_builder);
+        query_builder.push(" ORDER BY r.id");
+        query_builder.push(" LIMIT ").push_bind(limit);
+        query_builder.push(" OFFSET ").push_bind(offset);
+
+        let groups = query_builder.build_query_as().fetch_all(pool).await.map_err(|e| {
+            tracing::error!("Database error in SCIM group query: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok((groups, total.0))
+    }
+
+    /// Find a role by ID
+    pub async fn find_group(
+        pool: &PgPool,
+        id: i64,
+    ) -> Result<Option<ScimGroupEntity>, ServiceError> {
+        sqlx::query_as::<_, ScimGroupEntity>(&format!(
+            "SELECT {} FROM roles r WHERE r.id = $1 AND r.deleted_at IS NULL",
+            GROUP_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error finding SCIM group ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Whether another role holds a name or code
+    pub async fn role_conflicts(
+        pool: &PgPool,
+        column: &'static str,
+        value: &str,
+        exclude_id: Option<i64>,
+    ) -> Result<bool, ServiceError> {
+        sqlx::query_scalar(&format!(
+            "SELECT EXISTS(SELECT 1 FROM roles WHERE {} = $1 AND deleted_at IS NULL
+                           AND id IS DISTINCT FROM $2)",
+            column
+        ))
+        .bind(value)
+        .bind(exclude_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error checking SCIM role {} conflict: {:?}", column, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Create a role for a provisioned group, with its members
+    pub async fn create_group(
+        pool: &PgPool,
+        name: &str,
+        code: &str,
+        external_id: Option<&str>,
+        member_ids: &[i64],
+    ) -> Result<i64, ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for SCIM group: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        let role_id: i64 = sqlx::query_scalar(
+            "INSERT INTO roles (name, code, description, status, scim_external_id, created_at)
+             VALUES ($1, $2, 'Provisioned by SCIM', 1, $3, $4)
+             RETURNING id",
+        )
+        .bind(name)
+        .bind(code)
+        .bind(external_id)
+        .bind(Utc::now().naive_utc())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error creating SCIM group '{}': {:?}", name, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Self::add_members(&mut tx, role_id, member_ids).await?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing SCIM group creation: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(role_id)
+    }
+
+    /// Update the name, external ID and members of a role. Returns the IDs
+    /// of the users whose membership changed.
+    pub async fn update_group(
+        pool: &PgPool,
+        id: i64,
+        name: &str,
+        external_id: Option<&str>,
+        members: &GroupMembersChange,
+    ) -> Result<Vec<i64>, ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for SCIM group: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        sqlx::query("UPDATE roles SET name = $2, scim_external_id = $3 WHERE id = $1")
+            .bind(id)
+            .bind(name)
+            .bind(external_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error updating SCIM group ID {}: {:?}", id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+
+        let mut changed = Vec::new();
+        if let Some(member_ids) = &members.replace {
+            // System users keep their roles; SCIM cannot manage them
+            let removed: Vec<i64> = sqlx::query_scalar(
+                "DELETE FROM user_roles
+                 WHERE role_id = $1 AND user_id <> ALL($2)
+                   AND user_id IN (SELECT id FROM users WHERE is_system = FALSE)
+                 RETURNING user_id",
+            )
+            .bind(id)
+            .bind(member_ids)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error replacing SCIM group members: {:?}", e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+            changed.extend(removed);
+            changed.extend(Self::add_members(&mut tx, id, member_ids).await?);
+        }
+        changed.extend(Self::add_members(&mut tx, id, &members.add).await?);
+        if !members.remove.is_empty() {
+            let removed: Vec<i64> = sqlx::query_scalar(
+                "DELETE FROM user_roles WHERE role_id = $1 AND user_id = ANY($2) RETURNING user_id",
+            )
+            .bind(id)
+            .bind(&members.remove)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error removing SCIM group members: {:?}", e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+            changed.extend(removed);
+        }
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing SCIM group update: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        changed.sort_unstable();
+        changed.dedup();
+        Ok(changed)
+    }
+
+    /// Add users to a role as permanent members, returning the users that were
+    /// not members yet or only held a time-bound grant of it
+    async fn add_members(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        role_id: i64,
+        user_ids: &[i64],
+    ) -> Result<Vec<i64>, ServiceError> {
+        if user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        sqlx::query_scalar(
+            "INSERT INTO user_roles (user_id, role_id, created_at)
+             SELECT user_id, $1, $3 FROM UNNEST($2::BIGINT[]) AS user_id
+             ON CONFLICT (user_id, role_id) DO UPDATE
+             SET valid_from = NULL, valid_until = NULL, state = 2, created_at = EXCLUDED.created_at
+             WHERE user_roles.valid_from IS NOT NULL OR user_roles.valid_until IS NOT NULL
+             RETURNING user_id",
+        )
+        .bind(role_id)
+        .bind(user_ids)
+        .bind(Utc::now().naive_utc())
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error adding SCIM group members: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Soft delete a role and drop its memberships. Returns the IDs of its former members.
+    pub async fn delete_group(pool: &PgPool, id: i64) -> Result<Vec<i64>, ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for SCIM group: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        sqlx::query("UPDATE roles SET deleted_at = $2 WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .bind(Utc::now().naive_utc())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error deleting SCIM group ID {}: {:?}", id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+        let members: Vec<i64> =
+            sqlx::query_scalar("DELETE FROM user_roles WHERE role_id = $1 RETURNING user_id")
+                .bind(id)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Database error dropping SCIM group memberships: {:?}", e);
+                    ServiceError::DatabaseQueryFailed
+                })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing SCIM group deletion: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(members)
+    }
+}
@@ -0,0 +1,388 @@
+use super::{
+    dto::{ScimGroupDto, ScimListQuery, ScimPatchDto, ScimUserDto},
+    error::{SCIM_CONTENT_TYPE, ScimError},
+    service::ScimService,
+};
+use crate::{
+    core::config::CONFIG, features::system::log::service::LogService,
+    middleware::scim::scim_auth_middleware,
+};
+
+use axum::{
+    Json, Router,
+    extract::{ConnectInfo, Path, Query, State, rejection::JsonRejection},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::PgPool;
+use std::{net::SocketAddr, time::Instant};
+
+/// SCIM 2.0 provisioning endpoints, authenticated by the configured bearer token
+pub fn scim_routes() -> Router<PgPool> {
+    Router::new()
+        .route("/ServiceProviderConfig", get(service_provider_config))
+        .route("/Users", get(list_users).post(create_user))
+        .route("/Users/{id}", get(get_user).put(replace_user).patch(patch_user).delete(delete_user))
+        .route("/Groups", get(list_groups).post(create_group))
+        .route(
+            "/Groups/{id}",
+            get(get_group).put(replace_group).patch(patch_group).delete(delete_group),
+        )
+        .route_layer(middleware::from_fn(scim_auth_middleware))
+}
+
+/// JSON body with the SCIM media type
+fn scim_json<T: Serialize>(status: StatusCode, body: T) -> Response {
+    let mut response = (status, Json(body)).into_response();
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(SCIM_CONTENT_TYPE));
+    response
+}
+
+/// `201 Created` with the resource location
+fn created<T: Serialize>(body: T, resource: &str, id: &str) -> Response {
+    let mut response = scim_json(StatusCode::CREATED, body);
+    let location = format!("{}/{}/{}", CONFIG.scim_base_url.trim_end_matches('/'), resource, id);
+    if let Ok(location) = HeaderValue::from_str(&location) {
+        response.headers_mut().insert(header::LOCATION, location);
+    }
+    response
+}
+
+/// Request body, with parse failures reported as SCIM errors
+fn body<T>(json: Result<Json<T>, JsonRejection>) -> Result<T, ScimError> {
+    json.map(|Json(body)| body).map_err(|e| ScimError::invalid_syntax(e.body_text()))
+}
+
+/// Record a provisioning change in the operation log
+#[allow(clippy::too_many_arguments)]
+async fn audit<T>(
+    pool: &PgPool,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    action: &str,
+    description: &str,
+    resource_id: Option<&str>,
+    start_time: Instant,
+    result: &Result<T, ScimError>,
+) {
+    let (description, status) = match result {
+        Ok(_) => (description.to_string(), "SUCCESS"),
+        Err(err) => (err.to_string(), "FAIL"),
+    };
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+    if let Err(e) = LogService::log_business_operation(
+        pool,
+        0,
+        "scim",
+        action,
+        &description,
+        json!({ "id": resource_id }),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &addr.ip().to_string(),
+        user_agent,
+        None,
+    )
+    .await
+    {
+        tracing::error!("Failed to log SCIM operation: {:?}", e);
+    }
+}
+
+/// Supported SCIM features (RFC 7643 section 5)
+async fn service_provider_config() -> Response {
+    scim_json(
+        StatusCode::OK,
+        json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig"],
+            "patch": { "supported": true },
+            "bulk": { "supported": false, "maxOperations": 0, "maxPayloadSize": 0 },
+            "filter": { "supported": true, "maxResults": 200 },
+            "changePassword": { "supported": true },
+            "sort": { "supported": false },
+            "etag": { "supported": false },
+            "authenticationSchemes": [{
+                "type": "oauthbearertoken",
+                "name": "Bearer Token",
+                "description": "Static bearer token configured in RUSTZEN_SCIM_TOKEN",
+                "primary": true,
+            }],
+        }),
+    )
+}
+
+/// Query users
+#[tracing::instrument(name = "scim_list_users", skip(pool))]
+async fn list_users(
+    State(pool): State<PgPool>,
+    Query(query): Query<ScimListQuery>,
+) -> Result<Response, ScimError> {
+    let users = ScimService::list_users(&pool, query).await?;
+    Ok(scim_json(StatusCode::OK, users))
+}
+
+/// Get a user
+#[tracing::instrument(name = "scim_get_user", skip(pool))]
+async fn get_user(
+    State(pool): State<PgPool>,
+    Path(id): Path<String>,
+) -> Result<Response, ScimError> {
+    let user = ScimService::get_user(&pool, &id).await?;
+    Ok(scim_json(StatusCode::OK, user))
+}
+
+/// Provision a user
+#[tracing::instrument(name = "scim_create_user", skip(pool, addr, headers, json))]
+async fn create_user(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    json: Result<Json<ScimUserDto>, JsonRejection>,
+) -> Result<Response, ScimError> {
+    let start_time = Instant::now();
+    let result = match body(json) {
+        Ok(dto) => ScimService::create_user(&pool, dto).await,
+        Err(err) => Err(err),
+    };
+    let id = result.as_ref().ok().map(|user| user.id.clone());
+    audit(
+        &pool,
+        addr,
+        &headers,
+        "SCIM_USER_CREATE",
+        "User provisioned",
+        id.as_deref(),
+        start_time,
+        &result,
+    )
+    .await;
+
+    let user = result?;
+    let id = user.id.clone();
+    Ok(created(user, "Users", &id))
+}
+
+/// Replace a user
+#[tracing::instrument(name = "scim_replace_user", skip(pool, addr, headers, json))]
+async fn replace_user(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    json: Result<Json<ScimUserDto>, JsonRejection>,
+) -> Result<Response, ScimError> {
+    let start_time = Instant::now();
+    let result = match body(json) {
+        Ok(dto) => ScimService::replace_user(&pool, &id, dto).await,
+        Err(err) => Err(err),
+    };
+    audit(
+        &pool,
+        addr,
+        &headers,
+        "SCIM_USER_UPDATE",
+        "User updated",
+        Some(&id),
+        start_time,
+        &result,
+    )
+    .await;
+
+    Ok(scim_json(StatusCode::OK, result?))
+}
+
+/// Apply PATCH operations to a user
+#[tracing::instrument(name = "scim_patch_user", skip(pool, addr, headers, json))]
+async fn patch_user(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    json: Result<Json<ScimPatchDto>, JsonRejection>,
+) -> Result<Response, ScimError> {
+    let start_time = Instant::now();
+    let result = match body(json) {
+        Ok(patch) => ScimService::patch_user(&pool, &id, patch).await,
+        Err(err) => Err(err),
+    };
+    audit(
+        &pool,
+        addr,
+        &headers,
+        "SCIM_USER_UPDATE",
+        "User updated",
+        Some(&id),
+        start_time,
+        &result,
+    )
+    .await;
+
+    Ok(scim_json(StatusCode::OK, result?))
+}
+
+/// Delete a user
+#[tracing::instrument(name = "scim_delete_user", skip(pool, addr, headers))]
+async fn delete_user(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ScimError> {
+    let start_time = Instant::now();
+    let result = ScimService::delete_user(&pool, &id).await;
+    audit(
+        &pool,
+        addr,
+        &headers,
+        "SCIM_USER_DELETE",
+        "User deprovisioned",
+        Some(&id),
+        start_time,
+        &result,
+    )
+    .await;
+
+    result?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Query groups
+#[tracing::instrument(name = "scim_list_groups", skip(pool))]
+async fn list_groups(
+    State(pool): State<PgPool>,
+    Query(query): Query<ScimListQuery>,
+) -> Result<Response, ScimError> {
+    let groups = ScimService::list_groups(&pool, query).await?;
+    Ok(scim_json(StatusCode::OK, groups))
+}
+
+/// Get a group
+#[tracing::instrument(name = "scim_get_group", skip(pool))]
+async fn get_group(
+    State(pool): State<PgPool>,
+    Path(id): Path<String>,
+) -> Result<Response, ScimError> {
+    let group = ScimService::get_group(&pool, &id).await?;
+    Ok(scim_json(StatusCode::OK, group))
+}
+
+/// Provision a group
+#[tracing::instrument(name = "scim_create_group", skip(pool, addr, headers, json))]
+async fn create_group(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    json: Result<Json<ScimGroupDto>, JsonRejection>,
+) -> Result<Response, ScimError> {
+    let start_time = Instant::now();
+    let result = match body(json) {
+        Ok(dto) => ScimService::create_group(&pool, dto).await,
+        Err(err) => Err(err),
+    };
+    let id = result.as_ref().ok().map(|group| group.id.clone());
+    audit(
+        &pool,
+        addr,
+        &headers,
+        "SCIM_GROUP_CREATE",
+        "Group provisioned",
+        id.as_deref(),
+        start_time,
+        &result,
+    )
+    .await;
+
+    let group = result?;
+    let id = group.id.clone();
+    Ok(created(group, "Groups", &id))
+}
+
+/// Replace a group
+#[tracing::instrument(name = "scim_replace_group", skip(pool, addr, headers, json))]
+async fn replace_group(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    json: Result<Json<ScimGroupDto>, JsonRejection>,
+) -> Result<Response, ScimError> {
+    let start_time = Instant::now();
+    let result = match body(json) {
+        Ok(dto) => ScimService::replace_group(&pool, &id, dto).await,
+        Err(err) => Err(err),
+    };
+    audit(
+        &pool,
+        addr,
+        &headers,
+        "SCIM_GROUP_UPDATE",
+        "Group updated",
+        Some(&id),
+        start_time,
+        &result,
+    )
+    .await;
+
+    Ok(scim_json(StatusCode::OK, result?))
+}
+
+/// Apply PATCH operations to a group
+#[tracing::instrument(name = "scim_patch_group", skip(pool, addr, headers, json))]
+async fn patch_group(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    json: Result<Json<ScimPatchDto>, JsonRejection>,
+) -> Result<Response, ScimError> {
+    let start_time = Instant::now();
+    let result = match body(json) {
+        Ok(patch) => ScimService::patch_group(&pool, &id, patch).await,
+        Err(err) => Err(err),
+    };
+    audit(
+        &pool,
+        addr,
+        &headers,
+        "SCIM_GROUP_UPDATE",
+        "Group updated",
+        Some(&id),
+        start_time,
+        &result,
+    )
+    .await;
+
+    Ok(scim_json(StatusCode::OK, result?))
+}
+
+/// Delete a group
+#[tracing::instrument(name = "scim_delete_group", skip(pool, addr, headers))]
+async fn delete_group(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ScimError> {
+    let start_time = Instant::now();
+    let result = ScimService::delete_group(&pool, &id).await;
+    audit(
+        &pool,
+        addr,
+        &headers,
+        "SCIM_GROUP_DELETE",
+        "Group deprovisioned",
+        Some(&id),
+        start_time,
+        &result,
+    )
+    .await;
+
+    result?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,1055 @@
+use super::{
+    dto::{ScimGroupDto, ScimListQuery, ScimPatchDto, ScimUserDto},
+    entity::{ScimGroupEntity, ScimMembershipEntity, ScimUserEntity},
+    error::ScimError,
+    repo::{FilterClause, GroupMembersChange, ScimRepository},
+    vo::{ScimGroupVo, ScimListResponseVo, ScimReferenceVo, ScimUserVo},
+};
+use crate::{
+    common::email::is_valid_email,
+    core::{
+        password::{NO_PASSWORD_HASH, PASSWORD_POLICY, PasswordUtils},
+        scim::{self, AttributePath, CompareOp, Comparison},
+        token::TokenUtils,
+    },
+    features::auth::{entity::UserStatus, service::AuthService},
+};
+
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// Page size when the client does not ask for one
+const DEFAULT_COUNT: i64 = 100;
+/// Largest page size served
+const MAX_COUNT: i64 = 200;
+
+/// Filterable user attributes and the matching column, `None` for `active`
+fn user_filter_column(attribute: &str) -> Option<Option<&'static str>> {
+    match attribute {
+        "id" => Some(Some("u.id")),
+        "username" => Some(Some("u.username")),
+        "externalid" => Some(Some("u.scim_external_id")),
+        "emails" | "emails.value" => Some(Some("u.email")),
+        "displayname" | "name.formatted" => Some(Some("u.real_name")),
+        "active" => Some(None),
+        _ => None,
+    }
+}
+
+/// Filterable group attributes and the matching column
+fn group_filter_column(attribute: &str) -> Option<&'static str> {
+    match attribute {
+        "id" => Some("r.id"),
+        "displayname" => Some("r.name"),
+        "externalid" => Some("r.scim_external_id"),
+        _ => None,
+    }
+}
+
+/// Escapes `LIKE` wildcards of a filter value
+fn like_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Text of a string or number filter value
+fn filter_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// SQL condition of a comparison on a text column
+fn text_clause(column: &'static str, comparison: &Comparison) -> Result<FilterClause, ScimError> {
+    let invalid =
+        || ScimError::invalid_filter(format!("invalid value for {}", comparison.attribute));
+    Ok(match comparison.op {
+        CompareOp::Pr => FilterClause::Present(column),
+        CompareOp::Eq | CompareOp::Ne => FilterClause::Equals {
+            column,
+            value: filter_text(&comparison.value).ok_or_else(invalid)?,
+            negate: comparison.op == CompareOp::Ne,
+        },
+        CompareOp::Co | CompareOp::Sw | CompareOp::Ew => {
+            let text = like_escape(&filter_text(&comparison.value).ok_or_else(invalid)?);
+            let pattern = match comparison.op {
+                CompareOp::Co => format!("%{}%", text),
+                CompareOp::Sw => format!("{}%", text),
+                _ => format!("%{}", text),
+            };
+            FilterClause::Like { column, pattern }
+        }
+    })
+}
+
+/// SQL conditions of a user filter
+fn user_clauses(filter: Option<&str>) -> Result<Vec<FilterClause>, ScimError> {
+    let Some(filter) = filter.filter(|filter| !filter.trim().is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let comparisons =
+        scim::parse_filter(filter).map_err(|e| ScimError::invalid_filter(e.to_string()))?;
+    comparisons
+        .iter()
+        .map(|comparison| match user_filter_column(&comparison.attribute) {
+            Some(Some(column)) => text_clause(column, comparison),
+            Some(None) => match (comparison.op, &comparison.value) {
+                (CompareOp::Eq | CompareOp::Ne, Value::Bool(value)) => Ok(FilterClause::Flag {
+                    column: "(u.status = 1)",
+                    value: *value,
+                    negate: comparison.op == CompareOp::Ne,
+                }),
+                _ => {
+                    Err(ScimError::invalid_filter("active only supports eq and ne with a boolean"))
+                }
+            },
+            None => Err(ScimError::invalid_filter(format!(
+                "unsupported attribute '{}'",
+                comparison.attribute
+            ))),
+        })
+        .collect()
+}
+
+/// SQL conditions of a group filter
+fn group_clauses(filter: Option<&str>) -> Result<Vec<FilterClause>, ScimError> {
+    let Some(filter) = filter.filter(|filter| !filter.trim().is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let comparisons =
+        scim::parse_filter(filter).map_err(|e| ScimError::invalid_filter(e.to_string()))?;
+    comparisons
+        .iter()
+        .map(|comparison| match group_filter_column(&comparison.attribute) {
+            Some(column) => text_clause(column, comparison),
+            None => Err(ScimError::invalid_filter(format!(
+                "unsupported attribute '{}'",
+                comparison.attribute
+            ))),
+        })
+        .collect()
+}
+
+/// `(offset, limit, start_index)` of a list query
+fn page(query: &ScimListQuery) -> (i64, i64, i64) {
+    let start_index = query.start_index.unwrap_or(1).max(1);
+    let count = query.count.unwrap_or(DEFAULT_COUNT).clamp(0, MAX_COUNT);
+    (start_index - 1, count, start_index)
+}
+
+/// Resource IDs are user and role IDs
+fn parse_id(id: &str, resource: &str) -> Result<i64, ScimError> {
+    id.parse().map_err(|_| ScimError::not_found(resource))
+}
+
+/// String value of a PATCH operation or resource attribute
+fn string_value(value: &Value, attribute: &str) -> Result<String, ScimError> {
+    value
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| ScimError::invalid_value(format!("{} must be a string", attribute)))
+}
+
+/// Boolean value; some identity providers send booleans as strings
+fn bool_value(value: &Value, attribute: &str) -> Result<bool, ScimError> {
+    match value {
+        Value::Bool(value) => Ok(*value),
+        Value::String(text) if text.eq_ignore_ascii_case("true") => Ok(true),
+        Value::String(text) if text.eq_ignore_ascii_case("false") => Ok(false),
+        _ => Err(ScimError::invalid_value(format!("{} must be a boolean", attribute))),
+    }
+}
+
+/// Display name from a `name` object
+fn name_value(name: &Value) -> Option<String> {
+    if let Some(formatted) = name.get("formatted").and_then(Value::as_str) {
+        return Some(formatted.to_string());
+    }
+    let parts: Vec<&str> = ["givenName", "familyName"]
+        .iter()
+        .filter_map(|part| name.get(part).and_then(Value::as_str))
+        .filter(|part| !part.trim().is_empty())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Primary (or first) address of an `emails` array
+fn email_value(emails: &Value) -> Result<String, ScimError> {
+    let emails =
+        emails.as_array().ok_or_else(|| ScimError::invalid_value("emails must be an array"))?;
+    emails
+        .iter()
+        .find(|email| email.get("primary").and_then(Value::as_bool) == Some(true))
+        .or_else(|| emails.first())
+        .and_then(|email| email.get("value"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| ScimError::invalid_value("emails must contain a value"))
+}
+
+/// PATCH operation kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PatchOp {
+    Add,
+    Replace,
+    Remove,
+}
+
+impl PatchOp {
+    fn parse(op: &str) -> Result<Self, ScimError> {
+        match op.to_ascii_lowercase().as_str() {
+            "add" => Ok(Self::Add),
+            "replace" => Ok(Self::Replace),
+            "remove" => Ok(Self::Remove),
+            other => Err(ScimError::invalid_syntax(format!("unsupported operation '{}'", other))),
+        }
+    }
+}
+
+/// One PATCH operation with its parsed path
+type PatchOperation = (PatchOp, Option<AttributePath>, Option<Value>);
+
+/// Operations of a PATCH request with parsed paths
+fn patch_operations(patch: ScimPatchDto) -> Result<Vec<PatchOperation>, ScimError> {
+    patch
+        .operations
+        .into_iter()
+        .map(|operation| {
+            let op = PatchOp::parse(&operation.op)?;
+            let path = match operation.path.as_deref().filter(|path| !path.trim().is_empty()) {
+                Some(path) => Some(
+                    scim::parse_path(path).map_err(|e| ScimError::invalid_path(e.to_string()))?,
+                ),
+                None => None,
+            };
+            if path.is_none() && (op == PatchOp::Remove || operation.value.is_none()) {
+                return Err(ScimError::invalid_path("a path is required"));
+            }
+            Ok((op, path, operation.value))
+        })
+        .collect()
+}
+
+/// User IDs of a `members[value eq "42"]` path, which only a remove may use
+fn member_filter_ids(op: PatchOp, path: &AttributePath) -> Result<Vec<i64>, ScimError> {
+    let member_ids: Vec<i64> = path
+        .filter
+        .iter()
+        .filter(|c| c.attribute == "value" && c.op == CompareOp::Eq)
+        .filter_map(|c| filter_text(&c.value)?.parse().ok())
+        .collect();
+    if op != PatchOp::Remove || member_ids.len() != path.filter.len() {
+        return Err(ScimError::invalid_path("unsupported members filter"));
+    }
+    Ok(member_ids)
+}
+
+/// User IDs of a `members` value, e.g. `[{"value": "42"}]`
+fn member_values(value: &Value) -> Result<Vec<i64>, ScimError> {
+    value
+        .as_array()
+        .ok_or_else(|| ScimError::invalid_value("members must be an array"))?
+        .iter()
+        .map(|member| {
+            member
+                .get("value")
+                .and_then(|value| filter_text(value)?.parse::<i64>().ok())
+                .ok_or_else(|| ScimError::invalid_value("Unknown member"))
+        })
+        .collect()
+}
+
+/// Writable attributes of a user
+#[derive(Debug, Clone)]
+struct UserAttributes {
+    username: String,
+    email: String,
+    real_name: Option<String>,
+    active: bool,
+    password: Option<String>,
+    external_id: Option<String>,
+}
+
+impl UserAttributes {
+    fn from_dto(dto: ScimUserDto) -> Result<Self, ScimError> {
+        let email = dto
+            .emails
+            .iter()
+            .find(|email| email.primary)
+            .or_else(|| dto.emails.first())
+            .map(|email| email.value.clone())
+            .ok_or_else(|| ScimError::invalid_value("An email is required"))?;
+        let real_name = dto.display_name.or_else(|| {
+            dto.name.and_then(|name| {
+                name.formatted.or_else(|| {
+                    let parts: Vec<String> =
+                        [name.given_name, name.family_name].into_iter().flatten().collect();
+                    (!parts.is_empty()).then(|| parts.join(" "))
+                })
+            })
+        });
+        Ok(Self {
+            username: dto.user_name,
+            email,
+            real_name,
+            active: dto.active.unwrap_or(true),
+            password: dto.password,
+            external_id: dto.external_id,
+        })
+    }
+
+    fn from_entity(user: &ScimUserEntity) -> Self {
+        Self {
+            username: user.username.clone(),
+            email: user.email.clone(),
+            real_name: user.real_name.clone(),
+            active: user.status == UserStatus::Normal as i16,
+            password: None,
+            external_id: user.scim_external_id.clone(),
+        }
+    }
+
+    /// Apply the operations of a PATCH request in order
+    fn patch(&mut self, operations: Vec<PatchOperation>) -> Result<(), ScimError> {
+        for (op, path, value) in operations {
+            match path {
+                Some(path) => {
+                    // emails[type eq "work"].value: there is a single email
+                    let attribute = match (&path.filter[..], &path.sub_attribute) {
+                        ([], _) => path.attribute.clone(),
+                        (_, Some(sub)) if path.attribute == "emails" => format!("emails.{}", sub),
+                        _ => return Err(ScimError::invalid_path("unsupported value filter")),
+                    };
+                    self.apply(op, &attribute, value.as_ref())?;
+                }
+                None => {
+                    let Some(Value::Object(object)) = value else {
+                        return Err(ScimError::invalid_value("value must be an object"));
+                    };
+                    for (attribute, value) in object {
+                        self.apply(op, &attribute.to_ascii_lowercase(), Some(&value))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply an add, replace or remove of one attribute. Attributes the
+    /// user model has no place for are ignored.
+    fn apply(
+        &mut self,
+        op: PatchOp,
+        attribute: &str,
+        value: Option<&Value>,
+    ) -> Result<(), ScimError> {
+        let value = match (op, value) {
+            (PatchOp::Remove, _) => None,
+            (_, Some(value)) => Some(value),
+            (_, None) => {
+                return Err(ScimError::invalid_value(format!("{} needs a value", attribute)));
+            }
+        };
+        match (attribute, value) {
+            ("username", Some(value)) => self.username = string_value(value, "userName")?,
+            ("emails", Some(value)) => self.email = email_value(value)?,
+            ("emails.value", Some(value)) => self.email = string_value(value, "emails")?,
+            ("username" | "emails" | "emails.value", None) => {
+                return Err(ScimError::mutability(format!("{} is required", attribute)));
+            }
+            ("active", Some(value)) => self.active = bool_value(value, "active")?,
+            ("active", None) => self.active = false,
+            ("externalid", value) => {
+                self.external_id = value.map(|v| string_value(v, "externalId")).transpose()?
+            }
+            ("displayname" | "name.formatted", value) => {
+                self.real_name = value.map(|v| string_value(v, attribute)).transpose()?
+            }
+            ("name", value) => self.real_name = value.and_then(name_value),
+            ("password", Some(value)) => self.password = Some(string_value(value, "password")?),
+            _ => tracing::debug!("SCIM user attribute '{}' ignored", attribute),
+        }
+        Ok(())
+    }
+
+    fn validate(&mut self) -> Result<(), ScimError> {
+        self.username = self.username.trim().to_string();
+        self.email = self.email.trim().to_string();
+        self.real_name =
+            self.real_name.take().map(|name| name.trim().to_string()).filter(|n| !n.is_empty());
+        if self.username.is_empty() || self.username.chars().count() > 50 {
+            return Err(ScimError::invalid_value("userName must be 1 to 50 characters"));
+        }
+        if !is_valid_email(&self.email) {
+            return Err(ScimError::invalid_value("Invalid email"));
+        }
+        if self.real_name.as_ref().is_some_and(|name| name.chars().count() > 50) {
+            return Err(ScimError::invalid_value("displayName must be at most 50 characters"));
+        }
+        Ok(())
+    }
+}
+
+/// Status after the provisioning client sets `active`.
+///
+/// Deactivation disables the user. Activation enables disabled and pending
+/// users but leaves a lockout from failed logins in place.
+fn provisioned_status(current: i16, active: bool) -> i16 {
+    match (active, UserStatus::try_from(current)) {
+        (false, _) => UserStatus::Disabled as i16,
+        (true, Ok(UserStatus::Disabled | UserStatus::Pending)) => UserStatus::Normal as i16,
+        (true, _) => current,
+    }
+}
+
+/// Role code generated from a group name, e.g. "Sales Team" -> "SALES_TEAM"
+fn role_code(name: &str) -> String {
+    let code: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    let code: Vec<&str> = code.split('_').filter(|part| !part.is_empty()).collect();
+    let code: String = code.join("_").chars().take(40).collect();
+    if code.is_empty() { "SCIM_GROUP".to_string() } else { code }
+}
+
+/// Group references of users, keyed by user ID
+fn groups_by_user(memberships: Vec<ScimMembershipEntity>) -> HashMap<i64, Vec<ScimReferenceVo>> {
+    let mut groups: HashMap<i64, Vec<ScimReferenceVo>> = HashMap::new();
+    for membership in memberships {
+        groups.entry(membership.user_id).or_default().push(ScimReferenceVo {
+            value: membership.role_id.to_string(),
+            display: membership.role_name,
+        });
+    }
+    groups
+}
+
+/// Member references of groups, keyed by role ID
+fn members_by_group(memberships: Vec<ScimMembershipEntity>) -> HashMap<i64, Vec<ScimReferenceVo>> {
+    let mut members: HashMap<i64, Vec<ScimReferenceVo>> = HashMap::new();
+    for membership in memberships {
+        members.entry(membership.role_id).or_default().push(ScimReferenceVo {
+            value: membership.user_id.to_string(),
+            display: membership.username,
+        });
+    }
+    members
+}
+
+/// Writable attributes of a group
+#[derive(Debug, Clone)]
+struct GroupAttributes {
+    name: String,
+    external_id: Option<String>,
+    members: GroupMembersChange,
+}
+
+pub struct ScimService;
+
+impl ScimService {
+    /// Query users
+    pub async fn list_users(
+        pool: &PgPool,
+        query: ScimListQuery,
+    ) -> Result<ScimListResponseVo<ScimUserVo>, ScimError> {
+        let clauses = user_clauses(query.filter.as_deref())?;
+        let (offset, limit, start_index) = page(&query);
+
+        let (users, total) = ScimRepository::find_users(pool, &clauses, offset, limit).await?;
+        let resources = Self::user_vos(pool, users).await?;
+
+        Ok(ScimListResponseVo::new(resources, total, start_index))
+    }
+
+    /// Get a user
+    pub async fn get_user(pool: &PgPool, id: &str) -> Result<ScimUserVo, ScimError> {
+        let user = Self::find_user(pool, id).await?;
+        Self::user_vo(pool, user).await
+    }
+
+    /// Provision a user
+    pub async fn create_user(pool: &PgPool, dto: ScimUserDto) -> Result<ScimUserVo, ScimError> {
+        let attributes = UserAttributes::from_dto(dto)?;
+        let id = Self::save_user(pool, None, attributes).await?;
+        tracing::info!("SCIM provisioned user_id={}", id);
+        Self::get_user(pool, &id.to_string()).await
+    }
+
+    /// Replace a user
+    pub async fn replace_user(
+        pool: &PgPool,
+        id: &str,
+        dto: ScimUserDto,
+    ) -> Result<ScimUserVo, ScimError> {
+        let user = Self::find_managed_user(pool, id).await?;
+        let attributes = UserAttributes::from_dto(dto)?;
+        Self::save_user(pool, Some(&user), attributes).await?;
+        Self::get_user(pool, id).await
+    }
+
+    /// Apply PATCH operations to a user
+    pub async fn patch_user(
+        pool: &PgPool,
+        id: &str,
+        patch: ScimPatchDto,
+    ) -> Result<ScimUserVo, ScimError> {
+        let user = Self::find_managed_user(pool, id).await?;
+        let mut attributes = UserAttributes::from_entity(&user);
+        attributes.patch(patch_operations(patch)?)?;
+
+        Self::save_user(pool, Some(&user), attributes).await?;
+        Self::get_user(pool, id).await
+    }
+
+    /// Delete a user (soft delete)
+    pub async fn delete_user(pool: &PgPool, id: &str) -> Result<(), ScimError> {
+        let user = Self::find_managed_user(pool, id).await?;
+        if !crate::features::system::user::repo::UserRepository::soft_delete(pool, user.id).await? {
+            return Err(ScimError::not_found("User"));
+        }
+        AuthService::revoke_all_sessions(pool, user.id).await?;
+        tracing::info!("SCIM deleted user_id={}", user.id);
+        Ok(())
+    }
+
+    async fn find_user(pool: &PgPool, id: &str) -> Result<ScimUserEntity, ScimError> {
+        ScimRepository::find_user(pool, parse_id(id, "User")?)
+            .await?
+            .ok_or_else(|| ScimError::not_found("User"))
+    }
+
+    /// Find a user the provisioning client may change
+    async fn find_managed_user(pool: &PgPool, id: &str) -> Result<ScimUserEntity, ScimError> {
+        let user = Self::find_user(pool, id).await?;
+        if user.is_system {
+            return Err(ScimError::forbidden("System users are not managed by SCIM"));
+        }
+        Ok(user)
+    }
+
+    /// Create or update a user, returning its ID
+    async fn save_user(
+        pool: &PgPool,
+        user: Option<&ScimUserEntity>,
+        mut attributes: UserAttributes,
+    ) -> Result<i64, ScimError> {
+        attributes.validate()?;
+        let exclude_id = user.map(|user| user.id);
+
+        if ScimRepository::user_conflicts(pool, "username", &attributes.username, exclude_id)
+            .await?
+        {
+            return Err(ScimError::uniqueness("userName is already taken"));
+        }
+        if ScimRepository::user_conflicts(pool, "email", &attributes.email, exclude_id).await? {
+            return Err(ScimError::uniqueness("email is already taken"));
+        }
+        if let Some(external_id) = &attributes.external_id
+            && ScimRepository::user_conflicts(pool, "scim_external_id", external_id, exclude_id)
+                .await?
+        {
+            return Err(ScimError::uniqueness("externalId is already taken"));
+        }
+
+        let password_hash = match &attributes.password {
+            Some(password) => {
+                PASSWORD_POLICY.validate(password, &attributes.username)?;
+                Some(PasswordUtils::hash_password(password)?)
+            }
+            None => None,
+        };
+
+        let Some(user) = user else {
+            let status = provisioned_status(UserStatus::Pending as i16, attributes.active);
+            return Ok(ScimRepository::create_user(
+                pool,
+                &attributes.username,
+                &attributes.email,
+                attributes.real_name.as_deref(),
+                status,
+                password_hash.as_deref().unwrap_or(NO_PASSWORD_HASH),
+                password_hash.is_some(),
+                attributes.external_id.as_deref(),
+            )
+            .await?);
+        };
+
+        let status = provisioned_status(user.status, attributes.active);
+        if !ScimRepository::update_user(
+            pool,
+            user.id,
+            &attributes.username,
+            &attributes.email,
+            attributes.real_name.as_deref(),
+            status,
+            password_hash.as_deref(),
+            attributes.external_id.as_deref(),
+        )
+        .await?
+        {
+            return Err(ScimError::not_found("User"));
+        }
+
+        if status == UserStatus::Disabled as i16 && user.status != status {
+            AuthService::revoke_all_sessions(pool, user.id).await?;
+            tracing::info!("SCIM deactivated user_id={}", user.id);
+        }
+        Ok(user.id)
+    }
+
+    async fn user_vo(pool: &PgPool, user: ScimUserEntity) -> Result<ScimUserVo, ScimError> {
+        let mut vos = Self::user_vos(pool, vec![user]).await?;
+        vos.pop().ok_or_else(|| ScimError::not_found("User"))
+    }
+
+    async fn user_vos(
+        pool: &PgPool,
+        users: Vec<ScimUserEntity>,
+    ) -> Result<Vec<ScimUserVo>, ScimError> {
+        let ids: Vec<i64> = users.iter().map(|user| user.id).collect();
+        let mut groups = groups_by_user(ScimRepository::find_user_memberships(pool, &ids).await?);
+        Ok(users
+            .into_iter()
+            .map(|user| {
+                let user_groups = groups.remove(&user.id).unwrap_or_default();
+                ScimUserVo::new(user, user_groups)
+            })
+            .collect())
+    }
+
+    /// Query groups
+    pub async fn list_groups(
+        pool: &PgPool,
+        query: ScimListQuery,
+    ) -> Result<ScimListResponseVo<ScimGroupVo>, ScimError> {
+        let clauses = group_clauses(query.filter.as_deref())?;
+        let (offset, limit, start_index) = page(&query);
+
+        let (groups, total) = ScimRepository::find_groups(pool, &clauses, offset, limit).await?;
+        let resources = Self::group_vos(pool, groups).await?;
+
+        Ok(ScimListResponseVo::new(resources, total, start_index))
+    }
+
+    /// Get a group
+    pub async fn get_group(pool: &PgPool, id: &str) -> Result<ScimGroupVo, ScimError> {
+        let group = Self::find_group(pool, id).await?;
+        let mut vos = Self::group_vos(pool, vec![group]).await?;
+        vos.pop().ok_or_else(|| ScimError::not_found("Group"))
+    }
+
+    /// Provision a group as a new role
+    pub async fn create_group(pool: &PgPool, dto: ScimGroupDto) -> Result<ScimGroupVo, ScimError> {
+        let name =
+            Self::validate_group(pool, &dto.display_name, dto.external_id.as_deref(), None).await?;
+        let member_ids = Self::member_ids(pool, &dto.members).await?;
+
+        let mut code = role_code(&name);
+        if ScimRepository::role_conflicts(pool, "code", &code, None).await? {
+            code = format!("{}_{}", code, &TokenUtils::generate_token()[..6]).to_ascii_uppercase();
+        }
+        let id = ScimRepository::create_group(
+            pool,
+            &name,
+            &code,
+            dto.external_id.as_deref(),
+            &member_ids,
+        )
+        .await?;
+        AuthService::sync_permission_caches(pool, &member_ids).await;
+
+        tracing::info!("SCIM provisioned group as role_id={} ({})", id, code);
+        Self::get_group(pool, &id.to_string()).await
+    }
+
+    /// Replace a group's name and members
+    pub async fn replace_group(
+        pool: &PgPool,
+        id: &str,
+        dto: ScimGroupDto,
+    ) -> Result<ScimGroupVo, ScimError> {
+        let group = Self::find_group(pool, id).await?;
+        let attributes = GroupAttributes {
+            name: dto.display_name,
+            external_id: dto.external_id,
+            members: GroupMembersChange {
+                replace: Some(Self::member_ids(pool, &dto.members).await?),
+                ..Default::default()
+            },
+        };
+        Self::save_group(pool, &group, attributes).await?;
+        Self::get_group(pool, id).await
+    }
+
+    /// Apply PATCH operations to a group
+    pub async fn patch_group(
+        pool: &PgPool,
+        id: &str,
+        patch: ScimPatchDto,
+    ) -> Result<ScimGroupVo, ScimError> {
+        let group = Self::find_group(pool, id).await?;
+        let mut attributes = GroupAttributes {
+            name: group.name.clone(),
+            external_id: group.scim_external_id.clone(),
+            members: GroupMembersChange::default(),
+        };
+
+        for (op, path, value) in patch_operations(patch)? {
+            match path {
+                Some(path) if path.attribute == "members" && !path.filter.is_empty() => {
+                    let member_ids = member_filter_ids(op, &path)?;
+                    Self::change_members(&mut attributes.members, op, member_ids);
+                }
+                Some(path) => {
+                    let value = value.unwrap_or(Value::Null);
+                    Self::apply_group(pool, &mut attributes, op, &path.attribute, value).await?;
+                }
+                None => {
+                    let Some(Value::Object(object)) = value else {
+                        return Err(ScimError::invalid_value("value must be an object"));
+                    };
+                    for (attribute, value) in object {
+                        let attribute = attribute.to_ascii_lowercase();
+                        Self::apply_group(pool, &mut attributes, op, &attribute, value).await?;
+                    }
+                }
+            }
+        }
+
+        Self::save_group(pool, &group, attributes).await?;
+        Self::get_group(pool, id).await
+    }
+
+    /// Delete the role of a group
+    pub async fn delete_group(pool: &PgPool, id: &str) -> Result<(), ScimError> {
+        let group = Self::find_group(pool, id).await?;
+        if group.is_system {
+            return Err(ScimError::forbidden("System roles are not managed by SCIM"));
+        }
+        let members = ScimRepository::delete_group(pool, group.id).await?;
+        AuthService::sync_permission_caches(pool, &members).await;
+        tracing::info!(
+            "SCIM deleted group role_id={}, {} member(s) removed",
+            group.id,
+            members.len()
+        );
+        Ok(())
+    }
+
+    async fn find_group(pool: &PgPool, id: &str) -> Result<ScimGroupEntity, ScimError> {
+        ScimRepository::find_group(pool, parse_id(id, "Group")?)
+            .await?
+            .ok_or_else(|| ScimError::not_found("Group"))
+    }
+
+    /// Check a group name and external ID, returning the trimmed name
+    async fn validate_group(
+        pool: &PgPool,
+        name: &str,
+        external_id: Option<&str>,
+        exclude_id: Option<i64>,
+    ) -> Result<String, ScimError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > 50 {
+            return Err(ScimError::invalid_value("displayName must be 1 to 50 characters"));
+        }
+        if ScimRepository::role_conflicts(pool, "name", name, exclude_id).await? {
+            return Err(ScimError::uniqueness("displayName is already taken"));
+        }
+        if let Some(external_id) = external_id
+            && ScimRepository::role_conflicts(pool, "scim_external_id", external_id, exclude_id)
+                .await?
+        {
+            return Err(ScimError::uniqueness("externalId is already taken"));
+        }
+        Ok(name.to_string())
+    }
+
+    /// User IDs of member references. System users are skipped: their roles
+    /// are not managed by SCIM.
+    async fn member_ids(
+        pool: &PgPool,
+        members: &[super::dto::ScimMemberDto],
+    ) -> Result<Vec<i64>, ScimError> {
+        let ids = members
+            .iter()
+            .map(|member| member.value.parse::<i64>())
+            .collect::<Result<Vec<i64>, _>>()
+            .map_err(|_| ScimError::invalid_value("Unknown member"))?;
+        Self::manageable_ids(pool, ids).await
+    }
+
+    async fn manageable_ids(pool: &PgPool, mut ids: Vec<i64>) -> Result<Vec<i64>, ScimError> {
+        ids.sort_unstable();
+        ids.dedup();
+        let users = ScimRepository::find_human_users(pool, &ids).await?;
+        if users.len() != ids.len() {
+            return Err(ScimError::invalid_value("Unknown member"));
+        }
+        Ok(users.into_iter().filter(|(_, is_system)| !is_system).map(|(id, _)| id).collect())
+    }
+
+    /// Fold a members operation into the pending change
+    fn change_members(members: &mut GroupMembersChange, op: PatchOp, ids: Vec<i64>) {
+        match (op, &mut members.replace) {
+            (PatchOp::Replace, replace) => {
+                *replace = Some(ids);
+                members.add.clear();
+                members.remove.clear();
+            }
+            (PatchOp::Add, Some(replace)) => replace.extend(ids),
+            (PatchOp::Remove, Some(replace)) => replace.retain(|id| !ids.contains(id)),
+            (PatchOp::Add, None) => {
+                members.remove.retain(|id| !ids.contains(id));
+                members.add.extend(ids);
+            }
+            (PatchOp::Remove, None) => {
+                members.add.retain(|id| !ids.contains(id));
+                members.remove.extend(ids);
+            }
+        }
+    }
+
+    /// Apply an add, replace or remove of one group attribute
+    async fn apply_group(
+        pool: &PgPool,
+        attributes: &mut GroupAttributes,
+        op: PatchOp,
+        attribute: &str,
+        value: Value,
+    ) -> Result<(), ScimError> {
+        match (attribute, op) {
+            ("displayname", PatchOp::Remove) => {
+                return Err(ScimError::mutability("displayName is required"));
+            }
+            ("displayname", _) => attributes.name = string_value(&value, "displayName")?,
+            ("externalid", PatchOp::Remove) => attributes.external_id = None,
+            ("externalid", _) => attributes.external_id = Some(string_value(&value, "externalId")?),
+            ("members", PatchOp::Remove) if value.is_null() => {
+                Self::change_members(&mut attributes.members, PatchOp::Replace, Vec::new())
+            }
+            ("members", op) => {
+                let ids = member_values(&value)?;
+                // Removing a user that is gone is not an error
+                let ids = if op == PatchOp::Remove {
+                    ids
+                } else {
+                    Self::manageable_ids(pool, ids).await?
+                };
+                Self::change_members(&mut attributes.members, op, ids);
+            }
+            _ => tracing::debug!("SCIM group attribute '{}' ignored", attribute),
+        }
+        Ok(())
+    }
+
+    /// Update a group's role and refresh the permissions of changed members
+    async fn save_group(
+        pool: &PgPool,
+        group: &ScimGroupEntity,
+        attributes: GroupAttributes,
+    ) -> Result<(), ScimError> {
+        let name = Self::validate_group(
+            pool,
+            &attributes.name,
+            attributes.external_id.as_deref(),
+            Some(group.id),
+        )
+        .await?;
+        if group.is_system && name != group.name {
+            return Err(ScimError::mutability("System roles cannot be renamed"));
+        }
+
+        let changed = ScimRepository::update_group(
+            pool,
+            group.id,
+            &name,
+            attributes.external_id.as_deref(),
+            &attributes.members,
+        )
+        .await?;
+        AuthService::sync_permission_caches(pool, &changed).await;
+
+        tracing::info!(
+            "SCIM updated group role_id={}, {} member(s) changed",
+            group.id,
+            changed.len()
+        );
+        Ok(())
+    }
+
+    async fn group_vos(
+        pool: &PgPool,
+        groups: Vec<ScimGroupEntity>,
+    ) -> Result<Vec<ScimGroupVo>, ScimError> {
+        let ids: Vec<i64> = groups.iter().map(|group| group.id).collect();
+        let mut members =
+            members_by_group(ScimRepository::find_group_memberships(pool, &ids).await?);
+        Ok(groups
+            .into_iter()
+            .map(|group| {
+                let group_members = members.remove(&group.id).unwrap_or_default();
+                ScimGroupVo::new(group, group_members)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn operations(patch: Value) -> Result<Vec<PatchOperation>, ScimError> {
+        patch_operations(serde_json::from_value(patch).unwrap())
+    }
+
+    fn user(active: bool) -> UserAttributes {
+        UserAttributes {
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            real_name: None,
+            active,
+            password: None,
+            external_id: None,
+        }
+    }
+
+    fn patch_user(active: bool, patch: Value) -> Result<UserAttributes, ScimError> {
+        let mut attributes = user(active);
+        attributes.patch(operations(patch)?)?;
+        Ok(attributes)
+    }
+
+    /// Fold the member operations of a group PATCH like `patch_group` does,
+    /// with every referenced user manageable
+    fn patch_members(patch: Value) -> Result<GroupMembersChange, ScimError> {
+        let mut members = GroupMembersChange::default();
+        for (op, path, value) in operations(patch)? {
+            let path = path.expect("member operations have a path");
+            let ids = if path.filter.is_empty() {
+                member_values(&value.unwrap_or(Value::Null))?
+            } else {
+                member_filter_ids(op, &path)?
+            };
+            ScimService::change_members(&mut members, op, ids);
+        }
+        Ok(members)
+    }
+
+    #[test]
+    fn test_patch_operations_need_a_path_to_remove() {
+        let err = operations(json!({"Operations": [{"op": "remove"}]})).unwrap_err();
+        assert_eq!(err.to_string(), "invalidPath: a path is required");
+
+        let err =
+            operations(json!({"Operations": [{"op": "move", "path": "active"}]})).unwrap_err();
+        assert_eq!(err.to_string(), "invalidSyntax: unsupported operation 'move'");
+    }
+
+    #[test]
+    fn test_patch_active_replace() {
+        // Azure AD sends booleans as strings and capitalizes the op
+        let patch = json!({"Operations": [{"op": "Replace", "path": "active", "value": "False"}]});
+        assert!(!patch_user(true, patch).unwrap().active);
+
+        let patch = json!({"Operations": [{"op": "replace", "value": {"active": true}}]});
+        assert!(patch_user(false, patch).unwrap().active);
+    }
+
+    #[test]
+    fn test_patch_active_remove_deactivates() {
+        let patch = json!({"Operations": [{"op": "remove", "path": "active"}]});
+        assert!(!patch_user(true, patch).unwrap().active);
+    }
+
+    #[test]
+    fn test_patch_active_rejects_non_boolean() {
+        let patch = json!({"Operations": [{"op": "replace", "path": "active", "value": "yes"}]});
+        assert!(patch_user(true, patch).is_err());
+    }
+
+    #[test]
+    fn test_patch_required_attribute_cannot_be_removed() {
+        let patch = json!({"Operations": [{"op": "remove", "path": "userName"}]});
+        assert_eq!(
+            patch_user(true, patch).unwrap_err().to_string(),
+            "mutability: username is required"
+        );
+    }
+
+    #[test]
+    fn test_provisioned_status() {
+        let normal = UserStatus::Normal as i16;
+        let disabled = UserStatus::Disabled as i16;
+        let pending = UserStatus::Pending as i16;
+        let locked = UserStatus::Locked as i16;
+
+        assert_eq!(provisioned_status(normal, false), disabled);
+        assert_eq!(provisioned_status(locked, false), disabled);
+        assert_eq!(provisioned_status(disabled, true), normal);
+        assert_eq!(provisioned_status(pending, true), normal);
+        assert_eq!(provisioned_status(normal, true), normal);
+        // Reactivation does not lift a lockout from failed logins
+        assert_eq!(provisioned_status(locked, true), locked);
+    }
+
+    #[test]
+    fn test_patch_members_add_and_remove() {
+        let members = patch_members(json!({"Operations": [
+            {"op": "add", "path": "members", "value": [{"value": "2"}, {"value": "3"}]},
+            {"op": "remove", "path": "members[value eq \"3\"]"},
+            {"op": "remove", "path": "members", "value": [{"value": "4"}]},
+        ]}))
+        .unwrap();
+        assert_eq!(members.replace, None);
+        assert_eq!(members.add, [2]);
+        assert_eq!(members.remove, [3, 4]);
+    }
+
+    #[test]
+    fn test_patch_members_remove_then_add() {
+        let members = patch_members(json!({"Operations": [
+            {"op": "remove", "path": "members[value eq \"2\"]"},
+            {"op": "add", "path": "members", "value": [{"value": "2"}]},
+        ]}))
+        .unwrap();
+        assert_eq!(members.add, [2]);
+        assert!(members.remove.is_empty());
+    }
+
+    #[test]
+    fn test_patch_members_after_replace() {
+        let members = patch_members(json!({"Operations": [
+            {"op": "replace", "path": "members", "value": [{"value": "2"}, {"value": "3"}]},
+            {"op": "add", "path": "members", "value": [{"value": "4"}]},
+            {"op": "remove", "path": "members[value eq \"2\"]"},
+        ]}))
+        .unwrap();
+        assert_eq!(members.replace, Some(vec![3, 4]));
+        assert!(members.add.is_empty() && members.remove.is_empty());
+    }
+
+    #[test]
+    fn test_patch_members_filter_only_removes() {
+        let patch = json!({"Operations": [
+            {"op": "add", "path": "members[value eq \"2\"]", "value": [{"value": "2"}]},
+        ]});
+        assert!(patch_members(patch).is_err());
+
+        let patch =
+            json!({"Operations": [{"op": "remove", "path": "members[display eq \"bob\"]"}]});
+        assert!(patch_members(patch).is_err());
+    }
+
+    #[test]
+    fn test_member_values_rejects_unknown_reference() {
+        assert_eq!(member_values(&json!([{"value": 5}, {"value": "6"}])).unwrap(), [5, 6]);
+        assert!(member_values(&json!([{"value": "bob"}])).is_err());
+        assert!(member_values(&json!({"value": "2"})).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use super::entity::{ScimGroupEntity, ScimUserEntity};
+use crate::core::config::CONFIG;
+
+use chrono::{NaiveDateTime, SecondsFormat};
+use serde::Serialize;
+
+pub const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const GROUP_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+pub const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+
+/// Resource metadata
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMetaVo {
+    pub resource_type: &'static str,
+    pub created: String,
+    pub last_modified: String,
+    pub location: String,
+}
+
+impl ScimMetaVo {
+    fn new(
+        resource_type: &'static str,
+        endpoint: &str,
+        id: i64,
+        created: NaiveDateTime,
+        last_modified: NaiveDateTime,
+    ) -> Self {
+        let timestamp = |t: NaiveDateTime| t.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true);
+        Self {
+            resource_type,
+            created: timestamp(created),
+            last_modified: timestamp(last_modified),
+            location: format!("{}/{}/{}", CONFIG.scim_base_url.trim_end_matches('/'), endpoint, id),
+        }
+    }
+}
+
+/// `name` attribute of a user
+#[derive(Debug, Serialize)]
+pub struct ScimNameVo {
+    pub formatted: String,
+}
+
+/// One entry of the `emails` attribute
+#[derive(Debug, Serialize)]
+pub struct ScimEmailVo {
+    pub value: String,
+    #[serde(rename = "type")]
+    pub email_type: &'static str,
+    pub primary: bool,
+}
+
+/// Group or member reference
+#[derive(Debug, Serialize)]
+pub struct ScimReferenceVo {
+    pub value: String,
+    pub display: String,
+}
+
+/// User resource
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserVo {
+    pub schemas: Vec<&'static str>,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    pub user_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<ScimNameVo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    pub emails: Vec<ScimEmailVo>,
+    pub active: bool,
+    /// Roles of the user, read-only
+    pub groups: Vec<ScimReferenceVo>,
+    pub meta: ScimMetaVo,
+}
+
+impl ScimUserVo {
+    pub fn new(user: ScimUserEntity, groups: Vec<ScimReferenceVo>) -> Self {
+        Self {
+            schemas: vec![USER_SCHEMA],
+            id: user.id.to_string(),
+            external_id: user.scim_external_id,
+            user_name: user.username,
+            name: user.real_name.clone().map(|formatted| ScimNameVo { formatted }),
+            display_name: user.real_name,
+            emails: vec![ScimEmailVo { value: user.email, email_type: "work", primary: true }],
+            active: user.status == 1,
+            groups,
+            meta: ScimMetaVo::new("User", "Users", user.id, user.created_at, user.updated_at),
+        }
+    }
+}
+
+/// Group resource, backed by a role
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroupVo {
+    pub schemas: Vec<&'static str>,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    pub display_name: String,
+    pub members: Vec<ScimReferenceVo>,
+    pub meta: ScimMetaVo,
+}
+
+impl ScimGroupVo {
+    pub fn new(group: ScimGroupEntity, members: Vec<ScimReferenceVo>) -> Self {
+        Self {
+            schemas: vec![GROUP_SCHEMA],
+            id: group.id.to_string(),
+            external_id: group.scim_external_id,
+            display_name: group.name,
+            members,
+            meta: ScimMetaVo::new("Group", "Groups", group.id, group.created_at, group.updated_at),
+        }
+    }
+}
+
+/// Query result (RFC 7644 section 3.4.2)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListResponseVo<T> {
+    pub schemas: Vec<&'static str>,
+    pub total_results: i64,
+    pub start_index: i64,
+    pub items_per_page: i64,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<T>,
+}
+
+impl<T> ScimListResponseVo<T> {
+    pub fn new(resources: Vec<T>, total_results: i64, start_index: i64) -> Self {
+        Self {
+            schemas: vec![LIST_RESPONSE_SCHEMA],
+            total_results,
+            start_index,
+            items_per_page: resources.len() as i64,
+            resources,
+        }
+    }
+}
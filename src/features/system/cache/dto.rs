@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+/// Cache flush parameters
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlushCacheDto {
+    /// Key prefix to flush, e.g. `perm:` or `dict:user_status`
+    pub prefix: String,
+}
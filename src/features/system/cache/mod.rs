@@ -0,0 +1,4 @@
+pub mod dto;
+pub mod router;
+pub mod service;
+pub mod vo;
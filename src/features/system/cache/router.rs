@@ -0,0 +1,87 @@
+use super::{
+    dto::FlushCacheDto,
+    service::CacheService,
+    vo::{CacheStatsVo, FlushCacheVo},
+};
+use crate::{
+    common::{
+        api::{ApiResponse, AppResult},
+        router_ext::RouterExt,
+    },
+    core::{extractor::CurrentUser, permission::PermissionsCheck},
+    features::system::log::service::LogService,
+};
+
+use axum::{
+    Router,
+    extract::{ConnectInfo, Query, State},
+    http::HeaderMap,
+    routing::{delete, get},
+};
+use sqlx::PgPool;
+use std::{net::SocketAddr, time::Instant};
+use tracing::instrument;
+
+/// Defines the routes for the cache monitor
+pub fn cache_routes() -> Router<PgPool> {
+    Router::new()
+        .route_with_permission(
+            "/",
+            get(get_cache_stats),
+            PermissionsCheck::Single("system:cache:list"),
+        )
+        .route_with_permission(
+            "/",
+            delete(flush_cache),
+            PermissionsCheck::Single("system:cache:flush"),
+        )
+}
+
+/// Handles the request to get cache statistics
+pub async fn get_cache_stats() -> AppResult<CacheStatsVo> {
+    let stats = CacheService::get_stats().await?;
+    Ok(ApiResponse::success(stats))
+}
+
+/// Flush cache entries by key prefix
+#[instrument(skip(current_user, pool, addr, headers))]
+pub async fn flush_cache(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(query): Query<FlushCacheDto>,
+) -> AppResult<FlushCacheVo> {
+    let start_time = Instant::now();
+    tracing::info!("Flushing cache prefix: {}", query.prefix);
+
+    let result = CacheService::flush(&query.prefix).await;
+    let (description, deleted, status) = match &result {
+        Ok(vo) => (
+            format!("Flushed {} cache entries with prefix '{}'", vo.deleted, vo.prefix),
+            Some(vo.deleted),
+            "SUCCESS",
+        ),
+        Err(err) => (err.to_string(), None, "FAIL"),
+    };
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+    if let Err(e) = LogService::log_business_operation(
+        &pool,
+        current_user.user_id,
+        &current_user.username,
+        "CACHE_FLUSH",
+        &description,
+        serde_json::json!({ "prefix": query.prefix, "deleted": deleted }),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &addr.ip().to_string(),
+        user_agent,
+        current_user.actor.as_ref(),
+    )
+    .await
+    {
+        tracing::error!("Failed to log CACHE_FLUSH: {:?}", e);
+    }
+
+    Ok(ApiResponse::success(result?))
+}
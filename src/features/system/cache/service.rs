@@ -0,0 +1,57 @@
+use super::vo::{CachePrefixStatsVo, CacheStatsVo, FlushCacheVo, hit_ratio};
+use crate::{common::error::ServiceError, core::cache::CACHE};
+
+use std::collections::BTreeSet;
+
+pub struct CacheService;
+
+impl CacheService {
+    /// Entry counts and hit/miss ratios per key prefix.
+    ///
+    /// Entry counts come from the backend; hits and misses are counted by this
+    /// instance since it started.
+    pub async fn get_stats() -> Result<CacheStatsVo, ServiceError> {
+        let stats = CACHE.stats().await.map_err(|e| {
+            tracing::error!("Failed to read cache statistics: {}", e);
+            ServiceError::CacheUnavailable
+        })?;
+
+        let prefixes: BTreeSet<&String> =
+            stats.entries.keys().chain(stats.counters.keys()).collect();
+        let prefixes: Vec<CachePrefixStatsVo> = prefixes
+            .into_iter()
+            .map(|prefix| {
+                CachePrefixStatsVo::new(
+                    prefix.clone(),
+                    stats.entries.get(prefix).copied().unwrap_or(0),
+                    stats.counters.get(prefix).copied().unwrap_or_default(),
+                )
+            })
+            .collect();
+
+        let hits = prefixes.iter().map(|p| p.hits).sum();
+        let misses = prefixes.iter().map(|p| p.misses).sum();
+        Ok(CacheStatsVo {
+            backend: stats.backend,
+            capacity: stats.capacity,
+            entries: prefixes.iter().map(|p| p.entries).sum(),
+            hits,
+            misses,
+            hit_ratio: hit_ratio(hits, misses),
+            prefixes,
+        })
+    }
+
+    /// Deletes the cache entries whose key starts with `prefix`
+    pub async fn flush(prefix: &str) -> Result<FlushCacheVo, ServiceError> {
+        if prefix.trim().is_empty() {
+            return Err(ServiceError::InvalidOperation("Cache key prefix is required".to_string()));
+        }
+
+        let deleted = CACHE.flush_prefix(prefix).await.map_err(|e| {
+            tracing::error!("Failed to flush cache prefix '{}': {}", prefix, e);
+            ServiceError::CacheUnavailable
+        })?;
+        Ok(FlushCacheVo { prefix: prefix.to_string(), deleted })
+    }
+}
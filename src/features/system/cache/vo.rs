@@ -0,0 +1,54 @@
+use crate::core::cache::PrefixCounters;
+
+use serde::Serialize;
+
+/// Cache statistics for the cache monitor
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStatsVo {
+    pub backend: &'static str,
+    /// Maximum number of entries, `None` when managed by the backend
+    pub capacity: Option<u64>,
+    pub entries: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// Hits per lookup, `None` before the first lookup
+    pub hit_ratio: Option<f64>,
+    pub prefixes: Vec<CachePrefixStatsVo>,
+}
+
+/// Statistics of one key prefix, e.g. `perm` or `dict`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachePrefixStatsVo {
+    pub prefix: String,
+    pub entries: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_ratio: Option<f64>,
+}
+
+impl CachePrefixStatsVo {
+    pub fn new(prefix: String, entries: u64, counters: PrefixCounters) -> Self {
+        Self {
+            prefix,
+            entries,
+            hits: counters.hits,
+            misses: counters.misses,
+            hit_ratio: hit_ratio(counters.hits, counters.misses),
+        }
+    }
+}
+
+pub fn hit_ratio(hits: u64, misses: u64) -> Option<f64> {
+    let lookups = hits + misses;
+    (lookups > 0).then(|| hits as f64 / lookups as f64)
+}
+
+/// Result of a cache flush
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlushCacheVo {
+    pub prefix: String,
+    pub deleted: u64,
+}
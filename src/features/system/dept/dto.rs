@@ -0,0 +1,39 @@
+use serde::Deserialize;
+
+/// Create department request parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDeptDto {
+    /// Parent department ID, 0 for a root department
+    #[serde(default)]
+    pub parent_id: i64,
+    pub name: String,
+    pub code: String,
+    pub sort_order: i32,
+    pub status: i16,
+}
+
+/// Update department request parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDeptDto {
+    /// Parent department ID, 0 for a root department
+    #[serde(default)]
+    pub parent_id: i64,
+    pub name: String,
+    pub code: String,
+    pub sort_order: i32,
+    pub status: i16,
+}
+
+/// Department query parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeptQueryDto {
+    /// Filter by name (case-insensitive search).
+    pub name: Option<String>,
+    /// Filter by code (case-insensitive search).
+    pub code: Option<String>,
+    /// Filter by status. Accepts "1", "2"; all statuses when omitted.
+    pub status: Option<String>,
+}
@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Department entity (single table)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DeptEntity {
+    pub id: i64,
+    pub parent_id: i64,
+    pub name: String,
+    pub code: String,
+    pub status: i16,
+    pub sort_order: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// What a user's data scope is resolved from
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DataScopeSourceEntity {
+    pub dept_id: Option<i64>,
+    pub is_system: bool,
+    /// Data scopes of the user's enabled roles
+    pub data_scopes: Vec<i16>,
+}
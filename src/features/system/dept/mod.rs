@@ -0,0 +1,7 @@
+pub mod dto;
+pub mod entity;
+pub mod repo;
+pub mod router;
+pub mod scope;
+pub mod service;
+pub mod vo;
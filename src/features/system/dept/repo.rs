@@ -0,0 +1,267 @@
+use super::{
+    dto::DeptQueryDto,
+    entity::{DataScopeSourceEntity, DeptEntity},
+};
+use crate::common::error::ServiceError;
+
+use chrono::Utc;
+use sqlx::{PgPool, QueryBuilder};
+
+/// Department data access layer
+pub struct DeptRepository;
+
+impl DeptRepository {
+    fn format_query(query: &DeptQueryDto, query_builder: &mut QueryBuilder<'_, sqlx::Postgres>) {
+        if let Some(name) = &query.name
+            && !name.trim().is_empty()
+        {
+            query_builder.push(" AND name ILIKE ").push_bind(format!("%{}%", name));
+        }
+        if let Some(code) = &query.code
+            && !code.trim().is_empty()
+        {
+            query_builder.push(" AND code ILIKE ").push_bind(format!("%{}%", code));
+        }
+        if let Some(status) = &query.status
+            && let Ok(status_num) = status.parse::<i16>()
+        {
+            query_builder.push(" AND status = ").push_bind(status_num);
+        }
+    }
+
+    /// Queries departments based on conditions
+    pub async fn find_all(
+        pool: &PgPool,
+        query: DeptQueryDto,
+    ) -> Result<Vec<DeptEntity>, ServiceError> {
+        let mut query_builder: QueryBuilder<'_, sqlx::Postgres> = QueryBuilder::new(
+            "SELECT id, parent_id, name, code, status, sort_order, created_at, updated_at FROM depts WHERE deleted_at IS NULL",
+        );
+
+        Self::format_query(&query, &mut query_builder);
+
+        query_builder.push(" ORDER BY sort_order ASC, id ASC");
+
+        let depts = query_builder.build_query_as().fetch_all(pool).await.map_err(|e| {
+            tracing::error!("Database error finding departments with conditions: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Ok(depts)
+    }
+
+    /// Creates a new department
+    pub async fn create(
+        pool: &PgPool,
+        parent_id: i64,
+        name: &str,
+        code: &str,
+        sort_order: i32,
+        status: i16,
+    ) -> Result<i64, ServiceError> {
+        sqlx::query_scalar::<_, i64>(
+            "INSERT INTO depts (parent_id, name, code, sort_order, status, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id",
+        )
+        .bind(parent_id)
+        .bind(name)
+        .bind(code)
+        .bind(sort_order)
+        .bind(status)
+        .bind(Utc::now().naive_utc())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error creating department: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Updates an existing department
+    pub async fn update(
+        pool: &PgPool,
+        id: i64,
+        parent_id: i64,
+        name: &str,
+        code: &str,
+        sort_order: i32,
+        status: i16,
+    ) -> Result<i64, ServiceError> {
+        let dept_id = sqlx::query_scalar::<_, i64>(
+            "UPDATE depts
+             SET parent_id = $2, name = $3, code = $4, sort_order = $5, status = $6, updated_at = $7
+             WHERE id = $1 AND deleted_at IS NULL
+             RETURNING id",
+        )
+        .bind(id)
+        .bind(parent_id)
+        .bind(name)
+        .bind(code)
+        .bind(sort_order)
+        .bind(status)
+        .bind(Utc::now().naive_utc())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error updating department: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        dept_id.ok_or_else(|| ServiceError::NotFound("Department".to_string()))
+    }
+
+    /// Soft deletes a department
+    pub async fn soft_delete(pool: &PgPool, id: i64) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE depts SET deleted_at = $1, updated_at = $1 WHERE id = $2 AND deleted_at IS NULL",
+        )
+        .bind(Utc::now().naive_utc())
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error soft deleting department {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Retrieves department list for Options API
+    pub async fn find_options(
+        pool: &PgPool,
+        search_query: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<Vec<(i64, String)>, ServiceError> {
+        let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
+            QueryBuilder::new("SELECT id, name FROM depts WHERE status = 1 AND deleted_at IS NULL");
+
+        if let Some(keyword) = search_query
+            && !keyword.trim().is_empty()
+        {
+            query_builder.push(" AND name ILIKE ").push_bind(format!("%{}%", keyword));
+        }
+
+        query_builder.push(" ORDER BY sort_order ASC, name ASC");
+
+        if let Some(limit) = limit {
+            query_builder.push(" LIMIT ").push_bind(limit);
+        }
+
+        query_builder.build_query_as().fetch_all(pool).await.map_err(|e| {
+            tracing::error!("Database error finding department options: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Whether another department already uses the code
+    pub async fn code_exists(
+        pool: &PgPool,
+        code: &str,
+        exclude_id: Option<i64>,
+    ) -> Result<bool, ServiceError> {
+        sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM depts WHERE code = $1 AND id <> COALESCE($2, 0))",
+        )
+        .bind(code)
+        .bind(exclude_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error checking department code: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// IDs of the department and every department above it, empty when the department does not exist
+    pub async fn find_lineage(pool: &PgPool, dept_id: i64) -> Result<Vec<i64>, ServiceError> {
+        sqlx::query_scalar(
+            "WITH RECURSIVE lineage(id, parent_id) AS (
+                 SELECT id, parent_id FROM depts WHERE id = $1 AND deleted_at IS NULL
+                 UNION
+                 SELECT d.id, d.parent_id
+                 FROM depts d
+                 JOIN lineage l ON d.id = l.parent_id
+                 WHERE d.deleted_at IS NULL
+             )
+             SELECT id FROM lineage",
+        )
+        .bind(dept_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error finding lineage of department {}: {:?}", dept_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// IDs of the department and every department below it
+    pub async fn find_descendant_ids(
+        pool: &PgPool,
+        dept_id: i64,
+    ) -> Result<Vec<i64>, ServiceError> {
+        sqlx::query_scalar("SELECT dept_id FROM dept_descendants($1)")
+            .bind(dept_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    "Database error finding descendants of department {}: {:?}",
+                    dept_id,
+                    e
+                );
+                ServiceError::DatabaseQueryFailed
+            })
+    }
+
+    /// Count the departments directly below a department
+    pub async fn get_child_dept_count(pool: &PgPool, dept_id: i64) -> Result<i64, ServiceError> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM depts WHERE parent_id = $1 AND deleted_at IS NULL")
+            .bind(dept_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error getting child department count: {:?}", e);
+                ServiceError::DatabaseQueryFailed
+            })
+    }
+
+    /// Count the users of a department
+    pub async fn get_dept_user_count(pool: &PgPool, dept_id: i64) -> Result<i64, ServiceError> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE dept_id = $1 AND deleted_at IS NULL")
+            .bind(dept_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error getting department user count: {:?}", e);
+                ServiceError::DatabaseQueryFailed
+            })
+    }
+
+    /// Department, system flag and data scopes of a user's roles and the roles they inherit from
+    pub async fn find_data_scope_source(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Option<DataScopeSourceEntity>, ServiceError> {
+        sqlx::query_as::<_, DataScopeSourceEntity>(
+            "SELECT u.dept_id,
+                    COALESCE(u.is_system, FALSE) AS is_system,
+                    COALESCE(ARRAY_AGG(DISTINCT a.data_scope) FILTER (WHERE a.id IS NOT NULL), '{}') AS data_scopes
+             FROM users u
+             LEFT JOIN active_user_roles ur ON ur.user_id = u.id
+             LEFT JOIN roles r ON r.id = ur.role_id AND r.status = 1 AND r.deleted_at IS NULL
+             LEFT JOIN role_closure rc ON rc.role_id = r.id
+             LEFT JOIN roles a ON a.id = rc.ancestor_id
+             WHERE u.id = $1 AND u.deleted_at IS NULL
+             GROUP BY u.id",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error finding data scope of user {}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use super::{
+    dto::{CreateDeptDto, DeptQueryDto, UpdateDeptDto},
+    service::DeptService,
+    vo::DeptItemVo,
+};
+use crate::{
+    common::{
+        api::{ApiResponse, AppResult, OptionItem, OptionsQuery},
+        router_ext::RouterExt,
+    },
+    core::permission::PermissionsCheck,
+};
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{delete, get, post, put},
+};
+use sqlx::PgPool;
+
+/// Department management routes
+pub fn dept_routes() -> Router<PgPool> {
+    Router::new()
+        .route_with_permission(
+            "/",
+            get(get_dept_list),
+            PermissionsCheck::Single("system:dept:list"),
+        )
+        .route_with_permission(
+            "/",
+            post(create_dept),
+            PermissionsCheck::Single("system:dept:create"),
+        )
+        .route_with_permission(
+            "/{id}",
+            put(update_dept),
+            PermissionsCheck::Single("system:dept:update"),
+        )
+        .route_with_permission(
+            "/{id}",
+            delete(delete_dept),
+            PermissionsCheck::Single("system:dept:delete"),
+        )
+        .route_with_permission(
+            "/options",
+            get(get_dept_options),
+            PermissionsCheck::Single("system:dept:options"),
+        )
+}
+
+/// Get department list with optional filtering
+/// Not paginated, the client builds the tree from parent_id
+async fn get_dept_list(
+    State(pool): State<PgPool>,
+    Query(params): Query<DeptQueryDto>,
+) -> AppResult<Vec<DeptItemVo>> {
+    let (dept_list, total) = DeptService::get_dept_list(&pool, params).await?;
+    Ok(ApiResponse::page(dept_list, total))
+}
+
+/// Create new department
+async fn create_dept(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateDeptDto>,
+) -> AppResult<i64> {
+    let dept_id = DeptService::create_dept(&pool, request).await?;
+    Ok(ApiResponse::success(dept_id))
+}
+
+/// Update department
+async fn update_dept(
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    Json(request): Json<UpdateDeptDto>,
+) -> AppResult<i64> {
+    let dept_id = DeptService::update_dept(&pool, id, request).await?;
+    Ok(ApiResponse::success(dept_id))
+}
+
+/// Delete department (rejected while it has children or users)
+async fn delete_dept(State(pool): State<PgPool>, Path(id): Path<i64>) -> AppResult<()> {
+    DeptService::delete_dept(&pool, id).await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// Get department options for dropdowns
+async fn get_dept_options(
+    State(pool): State<PgPool>,
+    Query(query): Query<OptionsQuery>,
+) -> AppResult<Vec<OptionItem<i64>>> {
+    let options = DeptService::get_dept_options(&pool, query).await?;
+    Ok(ApiResponse::success(options))
+}
@@ -0,0 +1,128 @@
+use super::service::DeptService;
+use crate::{common::error::AppError, core::extractor::CurrentUser};
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+/// Data scope of a role: whose rows its holders see in scoped lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataScopeKind {
+    All = 1,
+    Dept = 2,
+    DeptAndChildren = 3,
+    SelfOnly = 4,
+}
+
+impl DataScopeKind {
+    pub fn from_i16(value: i16) -> Option<Self> {
+        match value {
+            1 => Some(Self::All),
+            2 => Some(Self::Dept),
+            3 => Some(Self::DeptAndChildren),
+            4 => Some(Self::SelfOnly),
+            _ => None,
+        }
+    }
+
+    /// Higher is broader
+    fn breadth(self) -> u8 {
+        match self {
+            Self::SelfOnly => 0,
+            Self::Dept => 1,
+            Self::DeptAndChildren => 2,
+            Self::All => 3,
+        }
+    }
+
+    /// The broadest of the scopes of a user's roles, self only without roles
+    pub fn broadest(kinds: impl IntoIterator<Item = Self>) -> Self {
+        kinds.into_iter().max_by_key(|kind| kind.breadth()).unwrap_or(Self::SelfOnly)
+    }
+}
+
+/// Rows the current user may see in scoped list queries (users, logs)
+///
+/// Usage: async fn handler(scope: DataScope) -> Response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataScope {
+    /// No restriction
+    All,
+    /// Rows owned by the user or by a user of one of these departments
+    Restricted { user_id: i64, dept_ids: Vec<i64> },
+}
+
+impl DataScope {
+    /// Appends the scope as an `AND` condition on the column holding the owning user id
+    pub fn push_filter(
+        &self,
+        query_builder: &mut QueryBuilder<'_, Postgres>,
+        user_id_column: &str,
+    ) {
+        let Self::Restricted { user_id, dept_ids } = self else {
+            return;
+        };
+        query_builder.push(format!(" AND ({} = ", user_id_column)).push_bind(*user_id);
+        if !dept_ids.is_empty() {
+            query_builder
+                .push(format!(
+                    " OR {} IN (SELECT id FROM users WHERE dept_id = ANY(",
+                    user_id_column
+                ))
+                .push_bind(dept_ids.clone())
+                .push("))");
+        }
+        query_builder.push(")");
+    }
+}
+
+impl FromRequestParts<PgPool> for DataScope {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, pool: &PgPool) -> Result<Self, Self::Rejection> {
+        let current_user = CurrentUser::from_request_parts(parts, pool).await?;
+        let scope = DeptService::resolve_data_scope(pool, current_user.user_id).await?;
+        tracing::debug!("Data scope of user_id={}: {:?}", current_user.user_id, scope);
+        Ok(scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_broadest_scope_wins() {
+        use DataScopeKind::*;
+        assert_eq!(DataScopeKind::broadest([SelfOnly, Dept]), Dept);
+        assert_eq!(DataScopeKind::broadest([DeptAndChildren, Dept, SelfOnly]), DeptAndChildren);
+        assert_eq!(DataScopeKind::broadest([Dept, All]), All);
+        assert_eq!(DataScopeKind::broadest([]), SelfOnly);
+    }
+
+    #[test]
+    fn test_restricted_scope_filters_on_user_and_departments() {
+        let mut query_builder: QueryBuilder<'_, Postgres> =
+            QueryBuilder::new("SELECT * FROM operation_logs WHERE 1=1");
+        DataScope::Restricted { user_id: 7, dept_ids: vec![1, 2] }
+            .push_filter(&mut query_builder, "user_id");
+        assert_eq!(
+            query_builder.sql(),
+            "SELECT * FROM operation_logs WHERE 1=1 AND (user_id = $1 OR user_id IN \
+             (SELECT id FROM users WHERE dept_id = ANY($2)))"
+        );
+
+        let mut query_builder: QueryBuilder<'_, Postgres> =
+            QueryBuilder::new("SELECT * FROM operation_logs WHERE 1=1");
+        DataScope::Restricted { user_id: 7, dept_ids: vec![] }
+            .push_filter(&mut query_builder, "user_id");
+        assert_eq!(
+            query_builder.sql(),
+            "SELECT * FROM operation_logs WHERE 1=1 AND (user_id = $1)"
+        );
+
+        let mut query_builder: QueryBuilder<'_, Postgres> =
+            QueryBuilder::new("SELECT * FROM operation_logs WHERE 1=1");
+        DataScope::All.push_filter(&mut query_builder, "user_id");
+        assert_eq!(query_builder.sql(), "SELECT * FROM operation_logs WHERE 1=1");
+    }
+}
@@ -0,0 +1,200 @@
+use super::{
+    dto::{CreateDeptDto, DeptQueryDto, UpdateDeptDto},
+    repo::DeptRepository,
+    scope::{DataScope, DataScopeKind},
+    vo::DeptItemVo,
+};
+use crate::common::{
+    api::{OptionItem, OptionsQuery},
+    error::ServiceError,
+};
+
+use sqlx::PgPool;
+
+pub struct DeptService;
+
+impl DeptService {
+    /// Get department list as a flat tree (parent_id 0 is a root) with optional filtering
+    pub async fn get_dept_list(
+        pool: &PgPool,
+        query: DeptQueryDto,
+    ) -> Result<(Vec<DeptItemVo>, i64), ServiceError> {
+        tracing::info!("Fetching department list with query: {:?}", query);
+
+        let depts = DeptRepository::find_all(pool, query).await?;
+
+        let list: Vec<DeptItemVo> = depts.into_iter().map(DeptItemVo::from).collect();
+        let count = list.len() as i64;
+
+        Ok((list, count))
+    }
+
+    /// Check the parent exists and is not the department itself or below it
+    async fn validate_parent(
+        pool: &PgPool,
+        dept_id: Option<i64>,
+        parent_id: i64,
+    ) -> Result<(), ServiceError> {
+        if parent_id == 0 {
+            return Ok(());
+        }
+        let lineage = DeptRepository::find_lineage(pool, parent_id).await?;
+        if lineage.is_empty() {
+            return Err(ServiceError::NotFound("Parent department".to_string()));
+        }
+        if let Some(dept_id) = dept_id
+            && lineage.contains(&dept_id)
+        {
+            tracing::warn!(
+                "Rejected department cycle: department {} cannot move below department {}",
+                dept_id,
+                parent_id
+            );
+            return Err(ServiceError::InvalidOperation(
+                "A department cannot be moved below itself or one of its children".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the department code is not taken by another department
+    async fn validate_code(
+        pool: &PgPool,
+        dept_id: Option<i64>,
+        code: &str,
+    ) -> Result<(), ServiceError> {
+        if DeptRepository::code_exists(pool, code, dept_id).await? {
+            return Err(ServiceError::InvalidOperation(format!(
+                "Department code '{}' already exists",
+                code
+            )));
+        }
+        Ok(())
+    }
+
+    /// Create new department with validation
+    pub async fn create_dept(pool: &PgPool, request: CreateDeptDto) -> Result<i64, ServiceError> {
+        tracing::info!("Attempting to create department with name: {}", request.name);
+
+        Self::validate_parent(pool, None, request.parent_id).await?;
+        Self::validate_code(pool, None, &request.code).await?;
+
+        let dept_id = DeptRepository::create(
+            pool,
+            request.parent_id,
+            &request.name,
+            &request.code,
+            request.sort_order,
+            request.status,
+        )
+        .await?;
+
+        tracing::info!("Successfully created department: {}", dept_id);
+        Ok(dept_id)
+    }
+
+    /// Update existing department with validation
+    pub async fn update_dept(
+        pool: &PgPool,
+        id: i64,
+        request: UpdateDeptDto,
+    ) -> Result<i64, ServiceError> {
+        tracing::info!("Attempting to update department: {}", id);
+
+        Self::validate_parent(pool, Some(id), request.parent_id).await?;
+        Self::validate_code(pool, Some(id), &request.code).await?;
+
+        let dept_id = DeptRepository::update(
+            pool,
+            id,
+            request.parent_id,
+            &request.name,
+            &request.code,
+            request.sort_order,
+            request.status,
+        )
+        .await?;
+
+        tracing::info!("Successfully updated department: {}", dept_id);
+        Ok(dept_id)
+    }
+
+    /// Delete a department without children or users
+    pub async fn delete_dept(pool: &PgPool, id: i64) -> Result<(), ServiceError> {
+        tracing::info!("Attempting to delete department: {}", id);
+
+        let child_count = DeptRepository::get_child_dept_count(pool, id).await?;
+        if child_count > 0 {
+            tracing::warn!("Cannot delete department {} - it has {} children", id, child_count);
+            return Err(ServiceError::InvalidOperation(format!(
+                "Cannot delete department '{}' - it has {} child department(s). Please move or delete them first.",
+                id, child_count
+            )));
+        }
+
+        let user_count = DeptRepository::get_dept_user_count(pool, id).await?;
+        if user_count > 0 {
+            tracing::warn!("Cannot delete department {} - it has {} users", id, user_count);
+            return Err(ServiceError::InvalidOperation(format!(
+                "Cannot delete department '{}' - it still has {} user(s). Please move them to another department first.",
+                id, user_count
+            )));
+        }
+
+        if DeptRepository::soft_delete(pool, id).await? {
+            tracing::info!("Successfully deleted department: {}", id);
+            Ok(())
+        } else {
+            Err(ServiceError::NotFound("Department".to_string()))
+        }
+    }
+
+    /// Get department options for dropdowns
+    pub async fn get_dept_options(
+        pool: &PgPool,
+        query: OptionsQuery,
+    ) -> Result<Vec<OptionItem<i64>>, ServiceError> {
+        tracing::info!("Fetching department options: {:?}", query);
+
+        let depts = DeptRepository::find_options(pool, query.q.as_deref(), query.limit).await?;
+
+        Ok(depts.into_iter().map(|(id, name)| OptionItem { label: name, value: id }).collect())
+    }
+
+    /// Ensure a department assigned to a user exists
+    pub async fn validate_dept_exists(pool: &PgPool, dept_id: i64) -> Result<(), ServiceError> {
+        if DeptRepository::find_lineage(pool, dept_id).await?.is_empty() {
+            return Err(ServiceError::NotFound("Department".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Resolve the data scope of a user from the broadest scope of their enabled roles.
+    /// System users see everything; users without a department only see their own rows
+    /// unless a role grants all data.
+    pub async fn resolve_data_scope(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<DataScope, ServiceError> {
+        let Some(source) = DeptRepository::find_data_scope_source(pool, user_id).await? else {
+            return Ok(DataScope::Restricted { user_id, dept_ids: Vec::new() });
+        };
+        if source.is_system {
+            return Ok(DataScope::All);
+        }
+
+        let kind = DataScopeKind::broadest(
+            source.data_scopes.into_iter().filter_map(DataScopeKind::from_i16),
+        );
+        let dept_ids = match (kind, source.dept_id) {
+            (DataScopeKind::All, _) => return Ok(DataScope::All),
+            (DataScopeKind::DeptAndChildren, Some(dept_id)) => {
+                DeptRepository::find_descendant_ids(pool, dept_id).await?
+            }
+            (DataScopeKind::Dept, Some(dept_id)) => vec![dept_id],
+            _ => Vec::new(),
+        };
+
+        Ok(DataScope::Restricted { user_id, dept_ids })
+    }
+}
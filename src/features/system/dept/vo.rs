@@ -0,0 +1,35 @@
+use super::entity::DeptEntity;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Department item for tree list display
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeptItemVo {
+    pub id: i64,
+    pub parent_id: i64,
+    pub name: String,
+    pub code: String,
+    pub status: i16,
+    pub sort_order: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub children: Option<Vec<DeptItemVo>>,
+}
+
+impl From<DeptEntity> for DeptItemVo {
+    fn from(entity: DeptEntity) -> Self {
+        Self {
+            id: entity.id,
+            parent_id: entity.parent_id,
+            name: entity.name,
+            code: entity.code,
+            status: entity.status,
+            sort_order: entity.sort_order,
+            created_at: entity.created_at,
+            updated_at: entity.updated_at,
+            children: None,
+        }
+    }
+}
@@ -9,25 +9,25 @@ pub struct DictRepository;
 impl DictRepository {
     /// Formats the query for the dictionary items
     fn format_query(query: &DictQueryDto, query_builder: &mut QueryBuilder<'_, sqlx::Postgres>) {
-        if let Some(dict_type) = &query.dict_type {
-            if !dict_type.trim().is_empty() {
-                query_builder.push(" AND dict_type ILIKE  ").push_bind(format!("%{}%", dict_type));
-            }
+        if let Some(dict_type) = &query.dict_type
+            && !dict_type.trim().is_empty()
+        {
+            query_builder.push(" AND dict_type ILIKE  ").push_bind(format!("%{}%", dict_type));
         }
-        if let Some(label) = &query.label {
-            if !label.trim().is_empty() {
-                query_builder.push(" AND label ILIKE  ").push_bind(format!("%{}%", label));
-            }
+        if let Some(label) = &query.label
+            && !label.trim().is_empty()
+        {
+            query_builder.push(" AND label ILIKE  ").push_bind(format!("%{}%", label));
         }
-        if let Some(value) = &query.value {
-            if !value.trim().is_empty() {
-                query_builder.push(" AND value ILIKE  ").push_bind(format!("%{}%", value));
-            }
+        if let Some(value) = &query.value
+            && !value.trim().is_empty()
+        {
+            query_builder.push(" AND value ILIKE  ").push_bind(format!("%{}%", value));
         }
-        if let Some(status) = &query.status {
-            if let Ok(status_num) = status.parse::<i16>() {
-                query_builder.push(" AND status = ").push_bind(status_num);
-            }
+        if let Some(status) = &query.status
+            && let Ok(status_num) = status.parse::<i16>()
+        {
+            query_builder.push(" AND status = ").push_bind(status_num);
         }
     }
 
@@ -36,7 +36,7 @@ impl DictRepository {
         let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
             QueryBuilder::new("SELECT COUNT(*) FROM dicts WHERE 1=1");
 
-        Self::format_query(&query, &mut query_builder);
+        Self::format_query(query, &mut query_builder);
 
         let count: (i64,) = query_builder.build_query_as().fetch_one(pool).await.map_err(|e| {
             tracing::error!("Database error counting users: {:?}", e);
@@ -186,6 +186,7 @@ impl DictRepository {
     }
 
     /// Updates an existing dictionary item
+    #[allow(clippy::too_many_arguments)]
     pub async fn update(
         pool: &PgPool,
         id: i64,
@@ -24,37 +24,37 @@ pub fn dict_routes() -> Router<PgPool> {
         .route_with_permission(
             "/",
             get(get_dict_list),
-            PermissionsCheck::Any(vec!["system:*", "system:dict:*", "system:dict:list"]),
+            PermissionsCheck::Single("system:dict:list"),
         )
         .route_with_permission(
             "/",
             post(create_dict),
-            PermissionsCheck::Any(vec!["system:*", "system:dict:*", "system:dict:create"]),
+            PermissionsCheck::Single("system:dict:create"),
         )
         .route_with_permission(
             "/{id}",
             put(update_dict),
-            PermissionsCheck::Any(vec!["system:*", "system:dict:*", "system:dict:update"]),
+            PermissionsCheck::Single("system:dict:update"),
         )
         .route_with_permission(
             "/{id}",
             delete(delete_dict),
-            PermissionsCheck::Any(vec!["system:*", "system:dict:*", "system:dict:delete"]),
+            PermissionsCheck::Single("system:dict:delete"),
         )
         .route_with_permission(
             "/{id}/status",
             patch(update_dict_status),
-            PermissionsCheck::Any(vec!["system:*", "system:dict:*", "system:dict:update"]),
+            PermissionsCheck::Single("system:dict:update"),
         )
         .route_with_permission(
             "/options",
             get(get_dict_options),
-            PermissionsCheck::Any(vec!["system:*", "system:dict:*", "system:dict:options"]),
+            PermissionsCheck::Single("system:dict:options"),
         )
         .route_with_permission(
             "/type/{type}",
             get(get_dict_by_type),
-            PermissionsCheck::Any(vec!["system:*", "system:dict:*", "system:dict:options"]),
+            PermissionsCheck::Single("system:dict:options"),
         )
 }
 
@@ -3,13 +3,27 @@ use super::{
     repo::DictRepository,
     vo::DictItemVo,
 };
-use crate::common::{api::OptionItem, error::ServiceError, pagination::Pagination};
+use crate::{
+    common::{api::OptionItem, error::ServiceError, pagination::Pagination},
+    core::{cache::CACHE, config::CONFIG},
+};
 
 use sqlx::PgPool;
+use std::time::Duration;
+
+/// Cache key prefix of dictionary items by type
+const DICT_KEY_PREFIX: &str = "dict:";
 
 pub struct DictService;
 
 impl DictService {
+    /// Drops cached dictionary items, after any change since items can move between types
+    async fn invalidate_cache() {
+        if let Err(e) = CACHE.flush_prefix(DICT_KEY_PREFIX).await {
+            tracing::error!("Failed to invalidate dictionary cache: {}", e);
+        }
+    }
+
     /// Retrieves a list of dictionary items with optional filtering
     pub async fn get_dict_list(
         pool: &PgPool,
@@ -52,6 +66,7 @@ impl DictService {
             ServiceError::DatabaseQueryFailed
         })?;
 
+        Self::invalidate_cache().await;
         Ok(dict_id)
     }
 
@@ -79,6 +94,7 @@ impl DictService {
             ServiceError::DatabaseQueryFailed
         })?;
 
+        Self::invalidate_cache().await;
         Ok(updated_dict)
     }
 
@@ -90,6 +106,7 @@ impl DictService {
 
         if success {
             tracing::info!("Successfully deleted dictionary item: {}", id);
+            Self::invalidate_cache().await;
             Ok(())
         } else {
             tracing::warn!("Dictionary item not found during deletion: {}", id);
@@ -131,14 +148,21 @@ impl DictService {
         Ok(result)
     }
 
-    /// Retrieves dictionary items by type
+    /// Retrieves dictionary items by type, cached for `dict_cache_ttl` seconds
     pub async fn get_dict_by_type(
         pool: &PgPool,
         dict_type: &str,
     ) -> Result<Vec<OptionItem<String>>, ServiceError> {
         tracing::info!("Retrieving dictionary items by type: {}", dict_type);
 
+        let key = format!("{}{}", DICT_KEY_PREFIX, dict_type);
+        if let Some(dicts) = CACHE.get_json::<Vec<OptionItem<String>>>(&key).await {
+            tracing::debug!("Dictionary items for type {} served from cache", dict_type);
+            return Ok(dicts);
+        }
+
         let dicts = DictRepository::find_by_type(pool, dict_type).await?;
+        CACHE.set_json(&key, &dicts, Duration::from_secs(CONFIG.dict_cache_ttl)).await;
 
         tracing::info!(
             "Successfully retrieved {} dictionary items for type {}",
@@ -167,6 +191,7 @@ impl DictService {
 
         if success {
             tracing::info!("Successfully updated dictionary item {} status to {}", id, status);
+            Self::invalidate_cache().await;
             Ok(())
         } else {
             tracing::warn!("Dictionary item not found for status update: {}", id);
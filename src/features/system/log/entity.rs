@@ -17,5 +17,7 @@ pub struct LogEntity {
     pub duration_ms: i32,
     pub ip_address: IpAddr,
     pub user_agent: String,
+    pub actor_user_id: Option<i64>,
+    pub actor_username: Option<String>,
     pub created_at: NaiveDateTime,
 }
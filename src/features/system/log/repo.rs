@@ -1,5 +1,7 @@
 use super::{dto::LogQueryDto, entity::LogEntity};
-use crate::common::error::ServiceError;
+use crate::{
+    common::error::ServiceError, core::jwt::ActorClaims, features::system::dept::scope::DataScope,
+};
 
 use sqlx::{PgPool, QueryBuilder};
 
@@ -8,38 +10,41 @@ pub struct LogRepository;
 
 impl LogRepository {
     fn format_query(query: &LogQueryDto, query_builder: &mut QueryBuilder<'_, sqlx::Postgres>) {
-        if let Some(username) = &query.username {
-            if !username.trim().is_empty() {
-                query_builder.push(" AND username ILIKE ").push_bind(format!("%{}%", username));
-            }
+        if let Some(username) = &query.username
+            && !username.trim().is_empty()
+        {
+            query_builder.push(" AND username ILIKE ").push_bind(format!("%{}%", username));
         }
-        if let Some(action) = &query.action {
-            if !action.trim().is_empty() {
-                query_builder.push(" AND action ILIKE ").push_bind(format!("%{}%", action));
-            }
+        if let Some(action) = &query.action
+            && !action.trim().is_empty()
+        {
+            query_builder.push(" AND action ILIKE ").push_bind(format!("%{}%", action));
         }
-        if let Some(description) = &query.description {
-            if !description.trim().is_empty() {
-                query_builder
-                    .push(" AND description ILIKE ")
-                    .push_bind(format!("%{}%", description));
-            }
+        if let Some(description) = &query.description
+            && !description.trim().is_empty()
+        {
+            query_builder.push(" AND description ILIKE ").push_bind(format!("%{}%", description));
         }
-        if let Some(ip_address) = &query.ip_address {
-            if !ip_address.trim().is_empty() {
-                query_builder
-                    .push(" AND ip_address::text ILIKE ")
-                    .push_bind(format!("%{}%", ip_address));
-            }
+        if let Some(ip_address) = &query.ip_address
+            && !ip_address.trim().is_empty()
+        {
+            query_builder
+                .push(" AND ip_address::text ILIKE ")
+                .push_bind(format!("%{}%", ip_address));
         }
     }
 
-    /// Count logs matching filters
-    async fn count_logs(pool: &PgPool, query: &LogQueryDto) -> Result<i64, ServiceError> {
+    /// Count logs matching filters within a data scope
+    async fn count_logs(
+        pool: &PgPool,
+        query: &LogQueryDto,
+        scope: &DataScope,
+    ) -> Result<i64, ServiceError> {
         let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
             QueryBuilder::new("SELECT COUNT(*) FROM operation_logs WHERE 1=1");
 
-        Self::format_query(&query, &mut query_builder);
+        Self::format_query(query, &mut query_builder);
+        scope.push_filter(&mut query_builder, "user_id");
 
         let count: (i64,) = query_builder.build_query_as().fetch_one(pool).await.map_err(|e| {
             tracing::error!("Database error counting users: {:?}", e);
@@ -50,15 +55,16 @@ impl LogRepository {
         Ok(count.0)
     }
 
-    /// Find logs with pagination and filters
+    /// Find logs with pagination and filters within a data scope
     pub async fn find_with_pagination(
         pool: &PgPool,
         offset: i64,
         limit: i64,
         query: LogQueryDto,
+        scope: &DataScope,
     ) -> Result<(Vec<LogEntity>, i64), ServiceError> {
         tracing::debug!("Finding users with pagination and filters: {:?}", query);
-        let total = Self::count_logs(pool, &query).await?;
+        let total = Self::count_logs(pool, &query, scope).await?;
         if total == 0 {
             return Ok((Vec::new(), total));
         }
@@ -67,6 +73,7 @@ impl LogRepository {
             QueryBuilder::new("SELECT * FROM operation_logs WHERE 1=1");
 
         Self::format_query(&query, &mut query_builder);
+        scope.push_filter(&mut query_builder, "user_id");
 
         query_builder.push(" ORDER BY created_at DESC");
         query_builder.push(" LIMIT ").push_bind(limit);
@@ -81,6 +88,7 @@ impl LogRepository {
     }
 
     /// Creates a new log entry with full details (for business operations)
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_with_details(
         pool: &PgPool,
         user_id: i64,
@@ -92,11 +100,12 @@ impl LogRepository {
         duration_ms: Option<i32>,
         ip_address: Option<&str>,
         user_agent: Option<&str>,
+        actor: Option<&ActorClaims>,
     ) -> Result<i64, ServiceError> {
         tracing::debug!("Creating detailed log entry with action: {:?}", action);
 
         let log_id = sqlx::query_scalar::<_, i64>(
-            "SELECT log_operation($1, $2, $3, $4, $5, $6::inet, $7, $8, $9)",
+            "SELECT log_operation($1, $2, $3, $4, $5, $6::inet, $7, $8, $9, $10, $11)",
         )
         .bind(user_id)
         .bind(username)
@@ -107,6 +116,8 @@ impl LogRepository {
         .bind(user_agent)
         .bind(status)
         .bind(duration_ms)
+        .bind(actor.map(|actor| actor.user_id))
+        .bind(actor.map(|actor| actor.username.as_str()))
         .fetch_one(pool)
         .await
         .map_err(|e| {
@@ -120,11 +131,13 @@ impl LogRepository {
     pub async fn find_all(
         pool: &PgPool,
         query: LogQueryDto,
+        scope: &DataScope,
     ) -> Result<Vec<LogEntity>, ServiceError> {
         let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
             QueryBuilder::new("SELECT * FROM operation_logs WHERE 1=1");
 
         Self::format_query(&query, &mut query_builder);
+        scope.push_filter(&mut query_builder, "user_id");
 
         let logs = query_builder.build_query_as().fetch_all(pool).await.map_err(|e| {
             tracing::error!("Database error in operation_logs pagination: {:?}", e);
@@ -5,6 +5,7 @@ use crate::{
         router_ext::RouterExt,
     },
     core::permission::PermissionsCheck,
+    features::system::dept::scope::DataScope,
 };
 
 use axum::{
@@ -20,32 +21,32 @@ use sqlx::PgPool;
 /// Defines the routes for log management
 pub fn log_routes() -> Router<PgPool> {
     Router::new()
-        .route_with_permission(
-            "/",
-            get(get_log_list),
-            PermissionsCheck::Any(vec!["system:*", "system:log:*", "system:log:list"]),
-        )
+        .route_with_permission("/", get(get_log_list), PermissionsCheck::Single("system:log:list"))
         .route_with_permission(
             "/export",
             get(export_log_list),
-            PermissionsCheck::Any(vec!["system:*", "system:log:*", "system:log:export"]),
+            PermissionsCheck::Single("system:log:export"),
         )
 }
 
 /// Handles the request to get a paginated list of logs
 pub async fn get_log_list(
     State(pool): State<PgPool>,
+    scope: DataScope,
     Query(query): Query<LogQueryDto>,
 ) -> AppResult<Vec<LogItemVo>> {
-    let (logs, total) = LogService::get_log_list(&pool, query).await?;
+    let (logs, total) = LogService::get_log_list(&pool, query, &scope).await?;
     Ok(ApiResponse::page(logs, total))
 }
 
 pub async fn export_log_list(
     State(pool): State<PgPool>,
+    scope: DataScope,
     Query(query): Query<LogQueryDto>,
 ) -> Result<Response, (StatusCode, String)> {
-    let content = LogService::get_all_log_csv(&pool, query).await.unwrap();
+    let content = LogService::get_all_log_csv(&pool, query, &scope)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     let filename = format!("log_{}.csv", get_timestamp());
     let disposition = format!("attachment; filename={}", filename);
@@ -1,5 +1,9 @@
 use super::{dto::LogQueryDto, entity::LogEntity, repo::LogRepository, vo::LogItemVo};
-use crate::common::{error::ServiceError, pagination::Pagination};
+use crate::{
+    common::{error::ServiceError, pagination::Pagination},
+    core::jwt::ActorClaims,
+    features::system::dept::scope::DataScope,
+};
 
 use sqlx::PgPool;
 /// A service for log-related operations
@@ -10,10 +14,12 @@ impl LogService {
     pub async fn get_log_list(
         pool: &PgPool,
         query: LogQueryDto,
+        scope: &DataScope,
     ) -> Result<(Vec<LogItemVo>, i64), ServiceError> {
         let (limit, offset, _) = Pagination::normalize(query.current, query.page_size);
 
-        let (logs, total) = LogRepository::find_with_pagination(pool, offset, limit, query).await?;
+        let (logs, total) =
+            LogRepository::find_with_pagination(pool, offset, limit, query, scope).await?;
         let list: Vec<LogItemVo> = logs.into_iter().map(LogItemVo::from).collect();
 
         Ok((list, total))
@@ -21,6 +27,8 @@ impl LogService {
 
     /// Logs an HTTP request (for middleware use only)
     /// This should be called by the logging middleware, not by business logic.
+    /// `actor` is the real user when `user_id` is impersonated.
+    #[allow(clippy::too_many_arguments)]
     pub async fn log_http_request(
         pool: &PgPool,
         method: &str,
@@ -31,6 +39,7 @@ impl LogService {
         user_agent: &str,
         status_code: u16,
         duration_ms: i32,
+        actor: Option<&ActorClaims>,
     ) -> Result<(), ServiceError> {
         let action = format!("HTTP_{}", method);
         let status = if status_code < 400 { "SUCCESS" } else { "ERROR" };
@@ -50,6 +59,7 @@ impl LogService {
             Some(duration_ms),
             Some(ip_address),
             Some(user_agent),
+            actor,
         )
         .await?;
 
@@ -57,6 +67,8 @@ impl LogService {
     }
 
     /// Logs a business operation (for explicit CRUD, not for HTTP middleware)
+    /// `actor` is the real user when `user_id` is impersonated.
+    #[allow(clippy::too_many_arguments)]
     pub async fn log_business_operation(
         pool: &PgPool,
         user_id: i64,
@@ -68,6 +80,7 @@ impl LogService {
         duration_ms: i32,
         ip_address: &str,
         user_agent: &str,
+        actor: Option<&ActorClaims>,
     ) -> Result<(), ServiceError> {
         let _ = LogRepository::create_with_details(
             pool,
@@ -80,6 +93,7 @@ impl LogService {
             Some(duration_ms),
             Some(ip_address),
             Some(user_agent),
+            actor,
         )
         .await?;
 
@@ -89,8 +103,9 @@ impl LogService {
     pub async fn get_all_log_csv(
         pool: &PgPool,
         query: LogQueryDto,
+        scope: &DataScope,
     ) -> Result<String, ServiceError> {
-        let logs = LogRepository::find_all(pool, query).await?;
+        let logs = LogRepository::find_all(pool, query, scope).await?;
         Self::create_csv_chunk(logs, true).await
     }
 
@@ -104,16 +119,18 @@ impl LogService {
         // Add CSV header if this is the first batch
         if include_header {
             csv_content
-                .push_str("ID,user_id,username,action,description,status,duration_ms,ip_address,user_agent,created_at\n");
+                .push_str("ID,user_id,username,actor_user_id,actor_username,action,description,status,duration_ms,ip_address,user_agent,created_at\n");
         }
 
         // Add data rows
         for log in logs {
             let row = format!(
-                "{},{},{},{},{},{},{},{},{},{}\n",
+                "{},{},{},{},{},{},{},{},{},{},{},{}\n",
                 log.id,
                 log.user_id,
                 Self::escape_csv_field(&log.username),
+                log.actor_user_id.map(|id| id.to_string()).unwrap_or_default(),
+                Self::escape_csv_field(log.actor_username.as_deref().unwrap_or("")),
                 Self::escape_csv_field(&log.action),
                 Self::escape_csv_field(log.description.as_deref().unwrap_or("")),
                 Self::escape_csv_field(&log.status),
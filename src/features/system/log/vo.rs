@@ -18,6 +18,9 @@ pub struct LogItemVo {
     pub duration_ms: i32,
     pub ip_address: String,
     pub user_agent: String,
+    /// Real user when `user_id` was impersonated
+    pub actor_user_id: Option<i64>,
+    pub actor_username: Option<String>,
     pub created_at: NaiveDateTime,
 }
 
@@ -32,6 +35,8 @@ impl From<LogEntity> for LogItemVo {
             data: entity.data,
             ip_address: entity.ip_address.to_string(),
             user_agent: entity.user_agent,
+            actor_user_id: entity.actor_user_id,
+            actor_username: entity.actor_username,
             status: entity.status,
             duration_ms: entity.duration_ms,
             created_at: entity.created_at,
@@ -9,15 +9,15 @@ pub struct MenuRepository;
 
 impl MenuRepository {
     fn format_query(query: &MenuQueryDto, query_builder: &mut QueryBuilder<'_, sqlx::Postgres>) {
-        if let Some(name) = &query.name {
-            if !name.trim().is_empty() {
-                query_builder.push(" AND name ILIKE  ").push_bind(format!("%{}%", name));
-            }
+        if let Some(name) = &query.name
+            && !name.trim().is_empty()
+        {
+            query_builder.push(" AND name ILIKE  ").push_bind(format!("%{}%", name));
         }
-        if let Some(code) = &query.code {
-            if !code.trim().is_empty() {
-                query_builder.push(" AND code ILIKE  ").push_bind(format!("%{}%", code));
-            }
+        if let Some(code) = &query.code
+            && !code.trim().is_empty()
+        {
+            query_builder.push(" AND code ILIKE  ").push_bind(format!("%{}%", code));
         }
         if let Some(status) = &query.status {
             if let Ok(status_num) = status.parse::<i16>() {
@@ -83,6 +83,7 @@ impl MenuRepository {
     }
 
     /// Updates an existing menu
+    #[allow(clippy::too_many_arguments)]
     pub async fn update(
         pool: &PgPool,
         id: i64,
@@ -24,27 +24,27 @@ pub fn menu_routes() -> Router<PgPool> {
         .route_with_permission(
             "/",
             get(get_menu_list),
-            PermissionsCheck::Any(vec!["system:*", "system:menu:*", "system:menu:list"]),
+            PermissionsCheck::Single("system:menu:list"),
         )
         .route_with_permission(
             "/",
             post(create_menu),
-            PermissionsCheck::Any(vec!["system:*", "system:menu:*", "system:menu:create"]),
+            PermissionsCheck::Single("system:menu:create"),
         )
         .route_with_permission(
             "/{id}",
             put(update_menu),
-            PermissionsCheck::Any(vec!["system:*", "system:menu:*", "system:menu:update"]),
+            PermissionsCheck::Single("system:menu:update"),
         )
         .route_with_permission(
             "/{id}",
             delete(delete_menu),
-            PermissionsCheck::Any(vec!["system:*", "system:menu:*", "system:menu:delete"]),
+            PermissionsCheck::Single("system:menu:delete"),
         )
         .route_with_permission(
             "/options",
             get(get_menu_options),
-            PermissionsCheck::Any(vec!["system:*", "system:menu:*", "system:menu:options"]),
+            PermissionsCheck::Single("system:menu:options"),
         )
 }
 
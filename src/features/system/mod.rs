@@ -1,3 +1,5 @@
+pub mod cache;
+pub mod dept;
 pub mod dict;
 pub mod log;
 pub mod menu;
@@ -7,6 +9,10 @@ pub mod user;
 use axum::Router;
 use sqlx::PgPool;
 
+use crate::features::oauth::router::oauth_client_routes;
+
+use cache::router::cache_routes;
+use dept::router::dept_routes;
 use dict::router::dict_routes;
 use log::router::log_routes;
 use menu::router::menu_routes;
@@ -19,6 +25,9 @@ pub fn system_routes() -> Router<PgPool> {
         .nest("/users", user_routes())
         .nest("/menus", menu_routes())
         .nest("/roles", role_routes())
+        .nest("/depts", dept_routes())
         .nest("/dicts", dict_routes())
         .nest("/logs", log_routes())
+        .nest("/cache", cache_routes())
+        .nest("/oauth-clients", oauth_client_routes())
 }
@@ -8,6 +8,16 @@ pub struct CreateRoleDto {
     pub code: String,
     pub status: i16,
     pub menu_ids: Vec<i64>,
+    /// Menus whose permission codes are denied, overriding grants from any role
+    #[serde(default)]
+    pub deny_menu_ids: Vec<i64>,
+    /// Role whose granted and denied menus are inherited
+    #[serde(default)]
+    pub parent_role_id: Option<i64>,
+    /// Rows visible in scoped lists: 1 all, 2 own department, 3 department and children,
+    /// 4 self only. Defaults to 1 on create and to the current scope on update.
+    #[serde(default)]
+    pub data_scope: Option<i16>,
     pub description: Option<String>,
 }
 
@@ -20,6 +30,16 @@ pub struct UpdateRoleDto {
     pub code: String,
     pub status: i16,
     pub menu_ids: Vec<i64>,
+    /// Menus whose permission codes are denied, overriding grants from any role
+    #[serde(default)]
+    pub deny_menu_ids: Vec<i64>,
+    /// Role whose granted and denied menus are inherited
+    #[serde(default)]
+    pub parent_role_id: Option<i64>,
+    /// Rows visible in scoped lists: 1 all, 2 own department, 3 department and children,
+    /// 4 self only. Defaults to 1 on create and to the current scope on update.
+    #[serde(default)]
+    pub data_scope: Option<i16>,
     pub description: Option<String>,
 }
 
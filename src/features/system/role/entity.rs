@@ -12,4 +12,9 @@ pub struct RoleWithMenuEntity {
     pub updated_at: NaiveDateTime,
     pub is_system: Option<bool>,
     pub menus: serde_json::Value,
+    pub deny_menus: serde_json::Value,
+    pub parent_role_id: Option<i64>,
+    pub inherited_menus: serde_json::Value,
+    pub inherited_deny_menus: serde_json::Value,
+    pub data_scope: i16,
 }
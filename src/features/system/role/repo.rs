@@ -8,20 +8,20 @@ pub struct RoleRepository;
 
 impl RoleRepository {
     fn format_query(query: &RoleQueryDto, query_builder: &mut QueryBuilder<'_, sqlx::Postgres>) {
-        if let Some(role_name) = &query.role_name {
-            if !role_name.trim().is_empty() {
-                query_builder.push(" AND role_name ILIKE  ").push_bind(format!("%{}%", role_name));
-            }
+        if let Some(role_name) = &query.role_name
+            && !role_name.trim().is_empty()
+        {
+            query_builder.push(" AND role_name ILIKE  ").push_bind(format!("%{}%", role_name));
         }
-        if let Some(role_code) = &query.role_code {
-            if !role_code.trim().is_empty() {
-                query_builder.push(" AND role_code ILIKE  ").push_bind(format!("%{}%", role_code));
-            }
+        if let Some(role_code) = &query.role_code
+            && !role_code.trim().is_empty()
+        {
+            query_builder.push(" AND role_code ILIKE  ").push_bind(format!("%{}%", role_code));
         }
-        if let Some(status) = &query.status {
-            if let Ok(status_num) = status.parse::<i16>() {
-                query_builder.push(" AND status = ").push_bind(status_num);
-            }
+        if let Some(status) = &query.status
+            && let Ok(status_num) = status.parse::<i16>()
+        {
+            query_builder.push(" AND status = ").push_bind(status_num);
         }
     }
 
@@ -30,7 +30,7 @@ impl RoleRepository {
         let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
             QueryBuilder::new("SELECT COUNT(*) FROM role_with_menus WHERE 1=1");
 
-        Self::format_query(&query, &mut query_builder);
+        Self::format_query(query, &mut query_builder);
 
         let count: (i64,) = query_builder.build_query_as().fetch_one(pool).await.map_err(|e| {
             tracing::error!("Database error counting users: {:?}", e);
@@ -69,13 +69,17 @@ impl RoleRepository {
     }
 
     /// Creates a new role
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
         pool: &PgPool,
         role_name: &str,
         role_code: &str,
         description: Option<&str>,
         status: i16,
+        parent_role_id: Option<i64>,
+        data_scope: Option<i16>,
         menu_ids: &[i64],
+        deny_menu_ids: &[i64],
     ) -> Result<i64, ServiceError> {
         let mut tx = pool.begin().await.map_err(|e| {
             tracing::error!("Database error starting transaction for role creation: {:?}", e);
@@ -83,15 +87,17 @@ impl RoleRepository {
         })?;
 
         let role_id = sqlx::query_scalar::<_, i64>(
-            "INSERT INTO roles (name, code, description, status, created_at)
-             VALUES ($1, $2, $3, $4, $5)
+            "INSERT INTO roles (name, code, description, status, parent_role_id, created_at, data_scope)
+             VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, 1))
              RETURNING id",
         )
         .bind(role_name)
         .bind(role_code)
         .bind(description)
         .bind(status)
+        .bind(parent_role_id)
         .bind(Utc::now().naive_utc())
+        .bind(data_scope)
         .fetch_one(&mut *tx)
         .await
         .map_err(|e| {
@@ -99,7 +105,7 @@ impl RoleRepository {
             ServiceError::DatabaseQueryFailed
         })?;
 
-        Self::insert_role_menus(&mut tx, role_id, menu_ids).await?;
+        Self::insert_role_menus(&mut tx, role_id, menu_ids, deny_menu_ids).await?;
 
         tx.commit().await.map_err(|e| {
             tracing::error!("Database error committing role creation transaction: {:?}", e);
@@ -109,7 +115,12 @@ impl RoleRepository {
         Ok(role_id)
     }
 
-    /// Updates an existing role
+    /// Updates an existing role.
+    ///
+    /// The new parent and its ancestors are locked and their IDs passed to
+    /// `check_parent_lineage` before the update, so that concurrent updates
+    /// cannot create an inheritance cycle.
+    #[allow(clippy::too_many_arguments)]
     pub async fn update(
         pool: &PgPool,
         id: i64,
@@ -117,25 +128,37 @@ impl RoleRepository {
         role_code: &str,
         description: Option<&str>,
         status: i16,
+        parent_role_id: Option<i64>,
+        data_scope: Option<i16>,
         menu_ids: &[i64],
+        deny_menu_ids: &[i64],
+        check_parent_lineage: impl FnOnce(i64, &[i64]) -> Result<(), ServiceError>,
     ) -> Result<i64, ServiceError> {
         let mut tx = pool.begin().await.map_err(|e| {
             tracing::error!("Database error starting transaction for role update: {:?}", e);
             ServiceError::DatabaseQueryFailed
         })?;
 
+        if let Some(parent_role_id) = parent_role_id {
+            let lineage = Self::lock_lineage(&mut tx, parent_role_id).await?;
+            check_parent_lineage(parent_role_id, &lineage)?;
+        }
+
         // update role
         let id_opt = sqlx::query_scalar::<_, i64>(
             "UPDATE roles
-                 SET name = $1, code = $2, description = $3, status = $4
-                 WHERE id = $5 AND deleted_at IS NULL
+                 SET name = $1, code = $2, description = $3, status = $4, parent_role_id = $5,
+                     data_scope = COALESCE($7, data_scope)
+                 WHERE id = $6 AND deleted_at IS NULL
                  RETURNING id",
         )
         .bind(role_name)
         .bind(role_code)
         .bind(description)
         .bind(status)
+        .bind(parent_role_id)
         .bind(id)
+        .bind(data_scope)
         .fetch_optional(&mut *tx)
         .await
         .map_err(|e| {
@@ -145,7 +168,7 @@ impl RoleRepository {
 
         if let Some(id) = id_opt {
             // update role_menus
-            Self::insert_role_menus(&mut tx, id, menu_ids).await?;
+            Self::insert_role_menus(&mut tx, id, menu_ids, deny_menu_ids).await?;
             tx.commit().await.map_err(|e| {
                 tracing::error!("Database error committing role update transaction: {:?}", e);
                 ServiceError::DatabaseQueryFailed
@@ -172,11 +195,43 @@ impl RoleRepository {
         Ok(result.rows_affected() > 0)
     }
 
-    /// insert role_menus
+    /// Lock a role and its ancestors, returning their IDs from the role up.
+    /// Empty when the role does not exist.
+    async fn lock_lineage(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        role_id: i64,
+    ) -> Result<Vec<i64>, ServiceError> {
+        let mut lineage = Vec::new();
+        let mut next = Some(role_id);
+        // Each row is read after it is locked, so the walk sees committed parents
+        while let Some(id) = next
+            && !lineage.contains(&id)
+        {
+            let parent: Option<Option<i64>> = sqlx::query_scalar(
+                "SELECT parent_role_id FROM roles WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+            )
+            .bind(id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error locking lineage of role {}: {:?}", role_id, e);
+                ServiceError::DatabaseQueryFailed
+            })?;
+            let Some(parent) = parent else {
+                break;
+            };
+            lineage.push(id);
+            next = parent;
+        }
+        Ok(lineage)
+    }
+
+    /// insert role_menus, granted and denied
     async fn insert_role_menus(
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         role_id: i64,
         menu_ids: &[i64],
+        deny_menu_ids: &[i64],
     ) -> Result<(), ServiceError> {
         sqlx::query("DELETE FROM role_menus WHERE role_id = $1")
             .bind(role_id)
@@ -186,17 +241,19 @@ impl RoleRepository {
                 tracing::error!("Database error deleting existing role_menus: {:?}", e);
                 ServiceError::DatabaseQueryFailed
             })?;
-        if menu_ids.is_empty() {
+        if menu_ids.is_empty() && deny_menu_ids.is_empty() {
             return Ok(());
         }
         let now = Utc::now().naive_utc();
         let mut query_builder =
-            String::from("INSERT INTO role_menus (role_id, menu_id, created_at) VALUES ");
-        for (i, menu_id) in menu_ids.iter().enumerate() {
+            String::from("INSERT INTO role_menus (role_id, menu_id, is_deny, created_at) VALUES ");
+        let granted = menu_ids.iter().map(|menu_id| (menu_id, false));
+        let denied = deny_menu_ids.iter().map(|menu_id| (menu_id, true));
+        for (i, (menu_id, is_deny)) in granted.chain(denied).enumerate() {
             if i > 0 {
                 query_builder.push_str(", ");
             }
-            query_builder.push_str(&format!("({}, {}, '{}')", role_id, menu_id, now));
+            query_builder.push_str(&format!("({}, {}, {}, '{}')", role_id, menu_id, is_deny, now));
         }
         sqlx::query(&query_builder).execute(&mut **tx).await.map_err(|e| {
             tracing::error!("Database error inserting role_menus: {:?}", e);
@@ -232,6 +289,42 @@ impl RoleRepository {
         Ok(results)
     }
 
+    /// IDs of the role and every role above it, empty when the role does not exist
+    pub async fn find_lineage(pool: &PgPool, role_id: i64) -> Result<Vec<i64>, ServiceError> {
+        sqlx::query_scalar(
+            "WITH RECURSIVE lineage(id, parent_role_id) AS (
+                 SELECT id, parent_role_id FROM roles WHERE id = $1 AND deleted_at IS NULL
+                 UNION
+                 SELECT r.id, r.parent_role_id
+                 FROM roles r
+                 JOIN lineage l ON r.id = l.parent_role_id
+                 WHERE r.deleted_at IS NULL
+             )
+             SELECT id FROM lineage",
+        )
+        .bind(role_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error finding lineage of role {}: {:?}", role_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Count the roles inheriting directly from a role
+    pub async fn get_child_role_count(pool: &PgPool, role_id: i64) -> Result<i64, ServiceError> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM roles WHERE parent_role_id = $1 AND deleted_at IS NULL",
+        )
+        .bind(role_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error getting child role count: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
     pub async fn get_role_user_count(pool: &PgPool, role_id: i64) -> Result<i64, ServiceError> {
         let result =
             sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM user_roles WHERE role_id = $1")
@@ -244,4 +337,21 @@ impl RoleRepository {
                 })?;
         Ok(result)
     }
+
+    /// Find the ID of an enabled role by its code
+    pub async fn find_active_id_by_code(
+        pool: &PgPool,
+        code: &str,
+    ) -> Result<Option<i64>, ServiceError> {
+        sqlx::query_scalar(
+            "SELECT id FROM roles WHERE code = $1 AND status = 1 AND deleted_at IS NULL",
+        )
+        .bind(code)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error finding role by code '{}': {:?}", code, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
 }
@@ -24,27 +24,27 @@ pub fn role_routes() -> Router<PgPool> {
         .route_with_permission(
             "/",
             get(get_role_list),
-            PermissionsCheck::Any(vec!["system:*", "system:role:*", "system:role:list"]),
+            PermissionsCheck::Single("system:role:list"),
         )
         .route_with_permission(
             "/",
             post(create_role),
-            PermissionsCheck::Any(vec!["system:*", "system:role:*", "system:role:create"]),
+            PermissionsCheck::Single("system:role:create"),
         )
         .route_with_permission(
             "/{id}",
             put(update_role),
-            PermissionsCheck::Any(vec!["system:*", "system:role:*", "system:role:update"]),
+            PermissionsCheck::Single("system:role:update"),
         )
         .route_with_permission(
             "/{id}",
             delete(delete_role),
-            PermissionsCheck::Any(vec!["system:*", "system:role:*", "system:role:delete"]),
+            PermissionsCheck::Single("system:role:delete"),
         )
         .route_with_permission(
             "/options",
             get(get_role_options),
-            PermissionsCheck::Any(vec!["system:*", "system:role:*", "system:role:options"]),
+            PermissionsCheck::Single("system:role:options"),
         )
 }
 
@@ -82,7 +82,13 @@ async fn update_role(
     Path(id): Path<i64>,
     Json(request): Json<UpdateRoleDto>,
 ) -> AppResult<()> {
-    tracing::info!("Update role {}: name={:?}, menus={:?}", id, request.name, request.menu_ids);
+    tracing::info!(
+        "Update role {}: name={:?}, menus={:?}, deny_menus={:?}",
+        id,
+        request.name,
+        request.menu_ids,
+        request.deny_menu_ids
+    );
 
     RoleService::update_role(&pool, id, request).await?;
 
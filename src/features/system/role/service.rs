@@ -3,10 +3,13 @@ use super::{
     repo::RoleRepository,
     vo::RoleItemVo,
 };
-use crate::common::{
-    api::{OptionItem, OptionsQuery},
-    error::ServiceError,
-    pagination::Pagination,
+use crate::{
+    common::{
+        api::{OptionItem, OptionsQuery},
+        error::ServiceError,
+        pagination::Pagination,
+    },
+    features::system::dept::scope::DataScopeKind,
 };
 
 use sqlx::PgPool;
@@ -31,17 +34,86 @@ impl RoleService {
         Ok((list, total))
     }
 
+    /// A menu can be granted or denied by a role, not both
+    fn validate_menus(menu_ids: &[i64], deny_menu_ids: &[i64]) -> Result<(), ServiceError> {
+        if let Some(menu_id) = deny_menu_ids.iter().find(|id| menu_ids.contains(id)) {
+            return Err(ServiceError::InvalidOperation(format!(
+                "Menu {} cannot be both granted and denied",
+                menu_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// The data scope, when given, must be one of the known scopes
+    fn validate_data_scope(data_scope: Option<i16>) -> Result<(), ServiceError> {
+        if let Some(data_scope) = data_scope
+            && DataScopeKind::from_i16(data_scope).is_none()
+        {
+            return Err(ServiceError::InvalidOperation(format!(
+                "Unknown data scope {}",
+                data_scope
+            )));
+        }
+        Ok(())
+    }
+
+    /// The parent role of a new role must exist
+    async fn validate_parent(
+        pool: &PgPool,
+        parent_role_id: Option<i64>,
+    ) -> Result<(), ServiceError> {
+        let Some(parent_role_id) = parent_role_id else {
+            return Ok(());
+        };
+        let lineage = RoleRepository::find_lineage(pool, parent_role_id).await?;
+        Self::check_parent_lineage(None, parent_role_id, &lineage)
+    }
+
+    /// Check the lineage of a parent role, from the parent up: it is empty when
+    /// the parent does not exist, and must not contain the role itself, which
+    /// would make the role inherit from itself or from a role inheriting from it
+    fn check_parent_lineage(
+        role_id: Option<i64>,
+        parent_role_id: i64,
+        lineage: &[i64],
+    ) -> Result<(), ServiceError> {
+        if lineage.is_empty() {
+            return Err(ServiceError::NotFound("Parent role".to_string()));
+        }
+        if let Some(role_id) = role_id
+            && lineage.contains(&role_id)
+        {
+            tracing::warn!(
+                "Rejected inheritance cycle: role {} cannot inherit from role {}",
+                role_id,
+                parent_role_id
+            );
+            return Err(ServiceError::InvalidOperation(
+                "A role cannot inherit from itself or from a role inheriting from it".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Create new role with validation
     pub async fn create_role(pool: &PgPool, request: CreateRoleDto) -> Result<(), ServiceError> {
         tracing::info!("Creating role: {}", request.name);
 
+        Self::validate_menus(&request.menu_ids, &request.deny_menu_ids)?;
+        Self::validate_parent(pool, request.parent_role_id).await?;
+        Self::validate_data_scope(request.data_scope)?;
+
         let id: i64 = RoleRepository::create(
             pool,
             &request.name,
             &request.code,
             request.description.as_deref(),
             request.status,
+            request.parent_role_id,
+            request.data_scope,
             &request.menu_ids,
+            &request.deny_menu_ids,
         )
         .await?;
 
@@ -57,6 +129,10 @@ impl RoleService {
     ) -> Result<(), ServiceError> {
         tracing::info!("Updating role: {}", id);
 
+        Self::validate_menus(&request.menu_ids, &request.deny_menu_ids)?;
+        Self::validate_data_scope(request.data_scope)?;
+
+        // The parent lineage is checked while it is locked by the update
         let new_id: i64 = RoleRepository::update(
             pool,
             id,
@@ -64,7 +140,11 @@ impl RoleService {
             &request.code,
             request.description.as_deref(),
             request.status,
+            request.parent_role_id,
+            request.data_scope,
             &request.menu_ids,
+            &request.deny_menu_ids,
+            |parent_role_id, lineage| Self::check_parent_lineage(Some(id), parent_role_id, lineage),
         )
         .await?;
 
@@ -86,6 +166,16 @@ impl RoleService {
             )));
         }
 
+        // Check if other roles inherit from it
+        let child_count = RoleRepository::get_child_role_count(pool, id).await?;
+        if child_count > 0 {
+            tracing::warn!("Cannot delete role {} - {} roles inherit from it", id, child_count);
+            return Err(ServiceError::InvalidOperation(format!(
+                "Cannot delete role '{}' - {} role(s) inherit from it. Please change their parent role before deleting the role.",
+                id, child_count
+            )));
+        }
+
         // Perform the deletion
         let success = RoleRepository::soft_delete(pool, id).await?;
 
@@ -114,3 +204,39 @@ impl RoleService {
         Ok(options)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_parent_lineage_accepts_unrelated_parent() {
+        assert!(RoleService::check_parent_lineage(Some(1), 3, &[3, 2]).is_ok());
+        assert!(RoleService::check_parent_lineage(None, 3, &[3, 2]).is_ok());
+    }
+
+    #[test]
+    fn test_check_parent_lineage_rejects_missing_parent() {
+        assert!(matches!(
+            RoleService::check_parent_lineage(Some(1), 3, &[]),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_parent_lineage_rejects_self_parent() {
+        assert!(matches!(
+            RoleService::check_parent_lineage(Some(3), 3, &[3]),
+            Err(ServiceError::InvalidOperation(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_parent_lineage_rejects_descendant_parent() {
+        // Role 3 inherits from 2, which inherits from 1: 1 cannot inherit from 3
+        assert!(matches!(
+            RoleService::check_parent_lineage(Some(1), 3, &[3, 2, 1]),
+            Err(ServiceError::InvalidOperation(_))
+        ));
+    }
+}
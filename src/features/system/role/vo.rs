@@ -16,6 +16,15 @@ pub struct RoleItemVo {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub menus: Vec<OptionItem<i64>>,
+    /// Menus whose permission codes the role denies
+    pub deny_menus: Vec<OptionItem<i64>>,
+    pub parent_role_id: Option<i64>,
+    /// Menus granted through the parent roles
+    pub inherited_menus: Vec<OptionItem<i64>>,
+    /// Menus denied through the parent roles
+    pub inherited_deny_menus: Vec<OptionItem<i64>>,
+    /// 1: all, 2: own department, 3: department and children, 4: self only
+    pub data_scope: i16,
 }
 
 impl From<RoleWithMenuEntity> for RoleItemVo {
@@ -29,6 +38,16 @@ impl From<RoleWithMenuEntity> for RoleItemVo {
             created_at: role.created_at,
             updated_at: role.updated_at,
             menus: serde_json::from_value::<Vec<OptionItem<i64>>>(role.menus).unwrap_or_default(),
+            deny_menus: serde_json::from_value::<Vec<OptionItem<i64>>>(role.deny_menus)
+                .unwrap_or_default(),
+            parent_role_id: role.parent_role_id,
+            inherited_menus: serde_json::from_value::<Vec<OptionItem<i64>>>(role.inherited_menus)
+                .unwrap_or_default(),
+            inherited_deny_menus: serde_json::from_value::<Vec<OptionItem<i64>>>(
+                role.inherited_deny_menus,
+            )
+            .unwrap_or_default(),
+            data_scope: role.data_scope,
         }
     }
 }
@@ -1,10 +1,25 @@
+use crate::common::email::deserialize_email;
+
+use chrono::NaiveDateTime;
 use serde::Deserialize;
 
+/// Time-bound grant of a role, with at least one bound
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleGrantDto {
+    pub role_id: i64,
+    /// Start of the grant (UTC), immediately when omitted
+    pub valid_from: Option<NaiveDateTime>,
+    /// End of the grant (UTC), never when omitted
+    pub valid_until: Option<NaiveDateTime>,
+}
+
 /// Create user request parameters
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateUserDto {
     pub username: String,
+    #[serde(deserialize_with = "deserialize_email")]
     pub email: String,
     pub password: String,
     pub real_name: Option<String>,
@@ -13,16 +28,56 @@ pub struct CreateUserDto {
     /// A list of role IDs to assign to the user. If empty, will use default role.
     #[serde(default)]
     pub role_ids: Vec<i64>,
+    /// Require a password change on the next login. Defaults to false.
+    #[serde(default)]
+    pub must_change_password: bool,
+    /// Department of the user
+    #[serde(default)]
+    pub dept_id: Option<i64>,
+    /// Time-bound role grants, in addition to the permanent `role_ids`
+    #[serde(default)]
+    pub role_grants: Vec<RoleGrantDto>,
+}
+
+/// Create service account request parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateServiceAccountDto {
+    pub username: String,
+    /// Display name
+    pub real_name: Option<String>,
+    /// Human user accountable for the service account
+    pub owner_id: i64,
+    #[serde(default)]
+    pub role_ids: Vec<i64>,
+}
+
+/// Update service account request parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateServiceAccountDto {
+    pub real_name: Option<String>,
+    pub owner_id: i64,
+    /// Replaces all existing roles
+    pub role_ids: Vec<i64>,
 }
 
 /// Update user request parameters
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserDto {
+    #[serde(deserialize_with = "deserialize_email")]
     pub email: String,
     pub real_name: String,
-    /// A list of role IDs to assign to the user. If provided, replaces all existing roles.
+    /// A list of role IDs to assign to the user permanently. Replaces the existing permanent roles.
     pub role_ids: Vec<i64>,
+    /// Department of the user, none when omitted
+    #[serde(default)]
+    pub dept_id: Option<i64>,
+    /// Time-bound role grants. If provided, replaces all existing time-bound grants,
+    /// including expired ones; kept unchanged when omitted.
+    #[serde(default)]
+    pub role_grants: Option<Vec<RoleGrantDto>>,
 }
 
 /// User query parameters
@@ -56,8 +111,19 @@ pub struct UserOptionsDto {
 }
 
 #[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UpdateUserPasswordDto {
     pub password: String,
+    /// Require the user to change this password on the next login. Defaults to false.
+    #[serde(default)]
+    pub must_change_password: bool,
+}
+
+/// Approve/reject a self-registered user
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReviewUserDto {
+    /// Optional reason, recorded in the audit log
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
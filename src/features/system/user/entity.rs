@@ -15,4 +15,45 @@ pub struct UserWithRolesEntity {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub roles: serde_json::Value,
+    /// 1: human, 2: service account
+    pub user_type: i16,
+    /// Accountable human user of a service account
+    pub owner_id: Option<i64>,
+    pub dept_id: Option<i64>,
+    pub dept_name: Option<String>,
+    /// Time-bound role grants in any state
+    pub role_grants: serde_json::Value,
+}
+
+/// Service account client credential (secret hash never selected)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ServiceCredentialEntity {
+    pub id: i64,
+    pub client_id: String,
+    pub created_by: Option<i64>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Kind of user account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserType {
+    Human = 1,
+    ServiceAccount = 2,
+}
+
+/// State of a role grant relative to its validity window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleGrantState {
+    Upcoming = 1,
+    Active = 2,
+    Expired = 3,
+}
+
+/// A role grant whose state was advanced by the sweeper
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SweptRoleGrantEntity {
+    pub user_id: i64,
+    pub role_id: i64,
+    pub state: i16,
 }
@@ -1,7 +1,13 @@
-use super::{dto::CreateUserDto, entity::UserWithRolesEntity};
-use crate::{common::error::ServiceError, features::system::user::dto::UserQueryDto};
-
-use chrono::Utc;
+use super::{
+    dto::{CreateUserDto, RoleGrantDto},
+    entity::{ServiceCredentialEntity, SweptRoleGrantEntity, UserType, UserWithRolesEntity},
+};
+use crate::{
+    common::error::ServiceError,
+    features::system::{dept::scope::DataScope, user::dto::UserQueryDto},
+};
+
+use chrono::{NaiveDateTime, Utc};
 use sqlx::{PgPool, QueryBuilder};
 
 /// User repository for database operations
@@ -9,34 +15,49 @@ pub struct UserRepository;
 
 impl UserRepository {
     fn format_query(query: &UserQueryDto, query_builder: &mut QueryBuilder<'_, sqlx::Postgres>) {
-        if let Some(username) = &query.username {
-            if !username.trim().is_empty() {
-                query_builder.push(" AND username ILIKE ").push_bind(format!("%{}%", username));
-            }
+        if let Some(username) = &query.username
+            && !username.trim().is_empty()
+        {
+            query_builder.push(" AND username ILIKE ").push_bind(format!("%{}%", username));
         }
-        if let Some(real_name) = &query.real_name {
-            if !real_name.trim().is_empty() {
-                query_builder.push(" AND real_name ILIKE ").push_bind(format!("%{}%", real_name));
-            }
+        if let Some(real_name) = &query.real_name
+            && !real_name.trim().is_empty()
+        {
+            query_builder.push(" AND real_name ILIKE ").push_bind(format!("%{}%", real_name));
         }
-        if let Some(email) = &query.email {
-            if !email.trim().is_empty() {
-                query_builder.push(" AND email ILIKE ").push_bind(format!("%{}%", email));
-            }
+        if let Some(email) = &query.email
+            && !email.trim().is_empty()
+        {
+            query_builder.push(" AND email ILIKE ").push_bind(format!("%{}%", email));
         }
-        if let Some(status) = &query.status {
-            if let Ok(status_num) = status.parse::<i16>() {
-                query_builder.push(" AND status = ").push_bind(status_num);
-            }
+        if let Some(status) = &query.status
+            && let Ok(status_num) = status.parse::<i16>()
+        {
+            query_builder.push(" AND status = ").push_bind(status_num);
+        }
+    }
+
+    /// Column holding the user a row of a type is scoped by: service accounts follow their owner
+    fn scope_column(user_type: UserType) -> &'static str {
+        match user_type {
+            UserType::Human => "id",
+            UserType::ServiceAccount => "owner_id",
         }
     }
 
-    /// Count users matching filters
-    async fn count_users(pool: &PgPool, query: &UserQueryDto) -> Result<i64, ServiceError> {
+    /// Count users of a type matching filters within a data scope
+    async fn count_users(
+        pool: &PgPool,
+        user_type: UserType,
+        query: &UserQueryDto,
+        scope: &DataScope,
+    ) -> Result<i64, ServiceError> {
         let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
-            QueryBuilder::new("SELECT COUNT(*) FROM user_with_roles WHERE 1=1");
+            QueryBuilder::new("SELECT COUNT(*) FROM user_with_roles WHERE user_type = ");
+        query_builder.push_bind(user_type as i16);
 
-        Self::format_query(&query, &mut query_builder);
+        Self::format_query(query, &mut query_builder);
+        scope.push_filter(&mut query_builder, Self::scope_column(user_type));
 
         let count: (i64,) = query_builder.build_query_as().fetch_one(pool).await.map_err(|e| {
             tracing::error!("Database error counting users: {:?}", e);
@@ -47,23 +68,27 @@ impl UserRepository {
         Ok(count.0)
     }
 
-    /// Find users with pagination and filters
+    /// Find users of a type with pagination and filters within a data scope
     pub async fn find_with_pagination(
         pool: &PgPool,
+        user_type: UserType,
         offset: i64,
         limit: i64,
         query: UserQueryDto,
+        scope: &DataScope,
     ) -> Result<(Vec<UserWithRolesEntity>, i64), ServiceError> {
         tracing::debug!("Finding users with pagination and filters: {:?}", query);
-        let total = Self::count_users(pool, &query).await?;
+        let total = Self::count_users(pool, user_type, &query, scope).await?;
         if total == 0 {
             return Ok((Vec::new(), total));
         }
 
         let mut query_builder: QueryBuilder<'_, sqlx::Postgres> =
-            QueryBuilder::new("SELECT * FROM user_with_roles WHERE 1=1");
+            QueryBuilder::new("SELECT * FROM user_with_roles WHERE user_type = ");
+        query_builder.push_bind(user_type as i16);
 
         Self::format_query(&query, &mut query_builder);
+        scope.push_filter(&mut query_builder, Self::scope_column(user_type));
 
         query_builder.push(" ORDER BY created_at DESC");
         query_builder.push(" LIMIT ").push_bind(limit);
@@ -77,7 +102,7 @@ impl UserRepository {
         Ok((users, total))
     }
 
-    /// Find users for dropdown options
+    /// Find human users for dropdown options
     pub async fn find_options(
         pool: &PgPool,
         status: Option<i16>, // 1, 2, or None (all users)
@@ -87,7 +112,7 @@ impl UserRepository {
         let mut query = String::from(
             "SELECT id, COALESCE(real_name, username) as display_name
              FROM users
-             WHERE deleted_at IS NULL",
+             WHERE deleted_at IS NULL AND user_type = 1",
         );
 
         // Handle status filter
@@ -96,14 +121,14 @@ impl UserRepository {
         }
 
         // Handle search query
-        if let Some(search_term) = q {
-            if !search_term.trim().is_empty() {
-                query.push_str(&format!(
-                    " AND (username ILIKE '%{}%' OR real_name ILIKE '%{}%')",
-                    search_term.replace("'", "''"),
-                    search_term.replace("'", "''")
-                ));
-            }
+        if let Some(search_term) = q
+            && !search_term.trim().is_empty()
+        {
+            query.push_str(&format!(
+                " AND (username ILIKE '%{}%' OR real_name ILIKE '%{}%')",
+                search_term.replace("'", "''"),
+                search_term.replace("'", "''")
+            ));
         }
 
         query.push_str(" ORDER BY display_name");
@@ -149,8 +174,9 @@ impl UserRepository {
 
         // Create user
         let user_id = sqlx::query_scalar::<_, i64>(
-            "INSERT INTO users (username, email, password_hash, real_name, status, created_at)
-             VALUES ($1, $2, $3, $4, $5, $6)
+            "INSERT INTO users (username, email, password_hash, real_name, status, created_at,
+                                password_changed_at, must_change_password, dept_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $6, $7, $8)
              RETURNING id",
         )
         .bind(&dto.username)
@@ -159,6 +185,8 @@ impl UserRepository {
         .bind(dto.real_name.as_deref())
         .bind(dto.status.unwrap_or(1))
         .bind(Utc::now().naive_utc())
+        .bind(dto.must_change_password)
+        .bind(dto.dept_id)
         .fetch_one(&mut *tx)
         .await
         .map_err(|e| {
@@ -167,6 +195,7 @@ impl UserRepository {
         })?;
 
         Self::insert_user_roles(&mut tx, user_id, &dto.role_ids).await?;
+        Self::insert_role_grants(&mut tx, user_id, &dto.role_grants).await?;
 
         tx.commit().await.map_err(|e| {
             tracing::error!("Database error committing user creation transaction: {:?}", e);
@@ -183,6 +212,8 @@ impl UserRepository {
         email: &str,
         real_name: &str,
         role_ids: &[i64],
+        role_grants: Option<&[RoleGrantDto]>,
+        dept_id: Option<i64>,
     ) -> Result<i64, ServiceError> {
         let mut tx = pool.begin().await.map_err(|e| {
             tracing::error!("Database error starting transaction for user update: {:?}", e);
@@ -191,13 +222,15 @@ impl UserRepository {
 
         let user_id = sqlx::query_scalar::<_, i64>(
             "UPDATE users
-             SET email = $1, real_name = $2
+             SET email = $1, real_name = $2, dept_id = $4,
+                 email_verified_at = CASE WHEN email = $1 THEN email_verified_at END
              WHERE id = $3 AND deleted_at IS NULL
              RETURNING id",
         )
         .bind(email)
         .bind(real_name)
         .bind(id)
+        .bind(dept_id)
         .fetch_optional(&mut *tx)
         .await
         .map_err(|e| {
@@ -207,6 +240,9 @@ impl UserRepository {
 
         if let Some(id) = user_id {
             Self::insert_user_roles(&mut tx, id, role_ids).await?;
+            if let Some(role_grants) = role_grants {
+                Self::insert_role_grants(&mut tx, id, role_grants).await?;
+            }
             tx.commit().await.map_err(|e| {
                 tracing::error!("Database error committing user update transaction: {:?}", e);
                 ServiceError::DatabaseQueryFailed
@@ -233,40 +269,110 @@ impl UserRepository {
         Ok(result.rows_affected() > 0)
     }
 
-    /// Set user roles (replace all existing roles)
+    /// Set the permanent roles of a user (replace all existing permanent roles).
+    /// Time-bound grants are kept, unless their role is now granted permanently.
     pub async fn insert_user_roles(
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         user_id: i64,
         role_ids: &[i64],
     ) -> Result<(), ServiceError> {
-        sqlx::query("DELETE FROM user_roles WHERE user_id = $1")
-            .bind(user_id)
-            .execute(&mut **tx)
-            .await
-            .map_err(|e| {
-                tracing::error!("Database error deleting existing user_roles: {:?}", e);
-                ServiceError::DatabaseQueryFailed
-            })?;
+        sqlx::query(
+            "DELETE FROM user_roles
+             WHERE user_id = $1 AND valid_from IS NULL AND valid_until IS NULL AND role_id <> ALL($2)",
+        )
+        .bind(user_id)
+        .bind(role_ids)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error deleting existing user_roles: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
 
         if role_ids.is_empty() {
             return Ok(());
         }
-        let now = Utc::now().naive_utc();
-        let mut query_builder =
-            String::from("INSERT INTO user_roles (user_id, role_id, created_at) VALUES ");
-        for (i, role_id) in role_ids.iter().enumerate() {
-            if i > 0 {
-                query_builder.push_str(", ");
-            }
-            query_builder.push_str(&format!("({}, {}, '{}')", user_id, role_id, now));
-        }
-        sqlx::query(&query_builder).execute(&mut **tx).await.map_err(|e| {
+        sqlx::query(
+            "INSERT INTO user_roles (user_id, role_id, created_at)
+             SELECT $1, role_id, $3 FROM UNNEST($2::BIGINT[]) AS role_id
+             ON CONFLICT (user_id, role_id) DO UPDATE
+             SET valid_from = NULL, valid_until = NULL, created_at = EXCLUDED.created_at
+             WHERE user_roles.valid_from IS NOT NULL OR user_roles.valid_until IS NOT NULL",
+        )
+        .bind(user_id)
+        .bind(role_ids)
+        .bind(Utc::now().naive_utc())
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
             tracing::error!("Database error inserting user_roles: {:?}", e);
             ServiceError::DatabaseQueryFailed
         })?;
         Ok(())
     }
 
+    /// Set the time-bound role grants of a user (replace all existing ones, expired included)
+    pub async fn insert_role_grants(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i64,
+        grants: &[RoleGrantDto],
+    ) -> Result<(), ServiceError> {
+        sqlx::query(
+            "DELETE FROM user_roles
+             WHERE user_id = $1 AND (valid_from IS NOT NULL OR valid_until IS NOT NULL)",
+        )
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error deleting existing role grants: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        if grants.is_empty() {
+            return Ok(());
+        }
+        let mut query_builder: QueryBuilder<'_, sqlx::Postgres> = QueryBuilder::new(
+            "INSERT INTO user_roles (user_id, role_id, valid_from, valid_until, created_at) ",
+        );
+        let now = Utc::now().naive_utc();
+        query_builder.push_values(grants, |mut row, grant| {
+            row.push_bind(user_id)
+                .push_bind(grant.role_id)
+                .push_bind(grant.valid_from)
+                .push_bind(grant.valid_until)
+                .push_bind(now);
+        });
+        query_builder.build().execute(&mut **tx).await.map_err(|e| {
+            tracing::error!("Database error inserting role grants: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        Ok(())
+    }
+
+    /// Bring the state of time-bound grants in line with their validity window at `now`.
+    /// Returns the grants that started or expired.
+    pub async fn sweep_role_grants(
+        pool: &PgPool,
+        now: NaiveDateTime,
+    ) -> Result<Vec<SweptRoleGrantEntity>, ServiceError> {
+        sqlx::query_as::<_, SweptRoleGrantEntity>(
+            "UPDATE user_roles
+             SET state = user_role_state(valid_from, valid_until, $1)
+             WHERE state <> 3
+               AND (valid_from IS NOT NULL OR valid_until IS NOT NULL)
+               AND state <> user_role_state(valid_from, valid_until, $1)
+             RETURNING user_id, role_id, state",
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error sweeping role grants: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
     /// Check if email exists
     pub async fn email_exists(pool: &PgPool, email: &str) -> Result<bool, ServiceError> {
         let exists: bool = sqlx::query_scalar(
@@ -299,38 +405,234 @@ impl UserRepository {
         Ok(exists)
     }
 
-    pub async fn update_user_password(
+    pub async fn update_user_status(
         pool: &PgPool,
         id: i64,
-        password_hash: &str,
+        status: i16,
     ) -> Result<bool, ServiceError> {
-        let result = sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
-            .bind(password_hash)
+        let result = sqlx::query(
+            "UPDATE users SET status = $1, failed_login_count = 0, locked_until = NULL WHERE id = $2",
+        )
+            .bind(status)
             .bind(id)
             .execute(pool)
             .await
             .map_err(|e| {
-                tracing::error!("Database error updating user password for ID {}: {:?}", id, e);
+                tracing::error!("Database error updating user status for ID {}: {:?}", id, e);
                 ServiceError::DatabaseQueryFailed
             })?;
 
         Ok(result.rows_affected() > 0)
     }
 
-    pub async fn update_user_status(
+    /// Unlock a locked user and reset its failed login counter
+    pub async fn unlock_user(pool: &PgPool, id: i64) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE users
+             SET status = 1, failed_login_count = 0, locked_until = NULL, updated_at = $2
+             WHERE id = $1 AND status = 4 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error unlocking user ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Activate a pending (self-registered) user
+    pub async fn approve_user(pool: &PgPool, id: i64) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE users SET status = 1, updated_at = $2
+             WHERE id = $1 AND status = 3 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error approving user ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Soft delete a pending (self-registered) user, freeing its username and email
+    pub async fn reject_user(pool: &PgPool, id: i64) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE users SET deleted_at = $2
+             WHERE id = $1 AND status = 3 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error rejecting user ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Create a service account with optional roles
+    pub async fn create_service_account(
+        pool: &PgPool,
+        username: &str,
+        email: &str,
+        password_hash: &str,
+        real_name: Option<&str>,
+        owner_id: i64,
+        role_ids: &[i64],
+    ) -> Result<i64, ServiceError> {
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for service account: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        let user_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO users (username, email, password_hash, real_name, status, user_type, owner_id, created_at)
+             VALUES ($1, $2, $3, $4, 1, 2, $5, $6)
+             RETURNING id",
+        )
+        .bind(username)
+        .bind(email)
+        .bind(password_hash)
+        .bind(real_name)
+        .bind(owner_id)
+        .bind(Utc::now().naive_utc())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error creating service account '{}': {:?}", username, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Self::insert_user_roles(&mut tx, user_id, role_ids).await?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing service account transaction: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Ok(user_id)
+    }
+
+    /// Update a service account. Returns false if it does not exist.
+    pub async fn update_service_account(
         pool: &PgPool,
         id: i64,
-        status: i16,
+        real_name: Option<&str>,
+        owner_id: i64,
+        role_ids: &[i64],
     ) -> Result<bool, ServiceError> {
-        let result = sqlx::query("UPDATE users SET status = $1 WHERE id = $2")
-            .bind(status)
-            .bind(id)
-            .execute(pool)
-            .await
-            .map_err(|e| {
-                tracing::error!("Database error updating user status for ID {}: {:?}", id, e);
-                ServiceError::DatabaseQueryFailed
-            })?;
+        let mut tx = pool.begin().await.map_err(|e| {
+            tracing::error!("Database error starting transaction for service account: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        let result = sqlx::query(
+            "UPDATE users SET real_name = $2, owner_id = $3, updated_at = $4
+             WHERE id = $1 AND user_type = 2 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(real_name)
+        .bind(owner_id)
+        .bind(Utc::now().naive_utc())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error updating service account ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::insert_user_roles(&mut tx, id, role_ids).await?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Database error committing service account transaction: {:?}", e);
+            ServiceError::DatabaseQueryFailed
+        })?;
+
+        Ok(true)
+    }
+
+    /// Store a new client credential of a service account
+    pub async fn create_service_credential(
+        pool: &PgPool,
+        user_id: i64,
+        client_id: &str,
+        secret_hash: &str,
+        created_by: i64,
+    ) -> Result<ServiceCredentialEntity, ServiceError> {
+        sqlx::query_as::<_, ServiceCredentialEntity>(
+            "INSERT INTO service_account_credentials (user_id, client_id, secret_hash, created_by, created_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id, client_id, created_by, last_used_at, created_at",
+        )
+        .bind(user_id)
+        .bind(client_id)
+        .bind(secret_hash)
+        .bind(created_by)
+        .bind(Utc::now().naive_utc())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error creating service credential for user ID {}: {:?}", user_id, e);
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Find active client credentials of a service account, newest first
+    pub async fn find_service_credentials(
+        pool: &PgPool,
+        user_id: i64,
+    ) -> Result<Vec<ServiceCredentialEntity>, ServiceError> {
+        sqlx::query_as::<_, ServiceCredentialEntity>(
+            "SELECT id, client_id, created_by, last_used_at, created_at
+             FROM service_account_credentials
+             WHERE user_id = $1 AND revoked_at IS NULL
+             ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Database error finding service credentials for user ID {}: {:?}",
+                user_id,
+                e
+            );
+            ServiceError::DatabaseQueryFailed
+        })
+    }
+
+    /// Revoke a client credential of a service account. Returns false if it was not active.
+    pub async fn revoke_service_credential(
+        pool: &PgPool,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, ServiceError> {
+        let result = sqlx::query(
+            "UPDATE service_account_credentials SET revoked_at = $3
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error revoking service credential ID {}: {:?}", id, e);
+            ServiceError::DatabaseQueryFailed
+        })?;
 
         Ok(result.rows_affected() > 0)
     }
@@ -1,10 +1,13 @@
 use super::{
     dto::{
-        CreateUserDto, UpdateUserDto, UpdateUserPasswordDto, UpdateUserStatusDto, UserOptionsDto,
-        UserQueryDto,
+        CreateServiceAccountDto, CreateUserDto, ReviewUserDto, UpdateServiceAccountDto,
+        UpdateUserDto, UpdateUserPasswordDto, UpdateUserStatusDto, UserOptionsDto, UserQueryDto,
     },
     service::UserService,
-    vo::{UserItemVo, UserOptionVo},
+    vo::{
+        ServiceAccountItemVo, ServiceCredentialCreatedVo, ServiceCredentialVo, UserItemVo,
+        UserOptionVo,
+    },
 };
 use crate::{
     common::{
@@ -12,15 +15,24 @@ use crate::{
         error::ServiceError,
         router_ext::RouterExt,
     },
-    core::permission::PermissionsCheck,
+    core::{extractor::CurrentUser, permission::PermissionsCheck},
+    features::{
+        auth::{
+            dto::CreateApiKeyRequest,
+            vo::{ApiKeyCreatedVo, ApiKeyVo, ImpersonationVo, SessionVo},
+        },
+        system::{dept::scope::DataScope, log::service::LogService},
+    },
 };
 
 use axum::{
     Json, Router,
-    extract::{Path, Query, State},
+    extract::{ConnectInfo, Path, Query, State},
+    http::HeaderMap,
     routing::{delete, get, post, put},
 };
 use sqlx::PgPool;
+use std::{net::SocketAddr, time::Instant};
 use tracing::instrument;
 
 /// User management routes
@@ -29,54 +41,135 @@ pub fn user_routes() -> Router<sqlx::PgPool> {
         .route_with_permission(
             "/",
             get(get_user_list),
-            PermissionsCheck::Any(vec!["system:*", "system:user:*", "system:user:list"]),
+            PermissionsCheck::Single("system:user:list"),
         )
         .route_with_permission(
             "/",
             post(create_user),
-            PermissionsCheck::Any(vec!["system:*", "system:user:*", "system:user:create"]),
+            PermissionsCheck::Single("system:user:create"),
         )
         .route_with_permission(
             "/{id}",
             put(update_user),
-            PermissionsCheck::Any(vec!["system:*", "system:user:*", "system:user:update"]),
+            PermissionsCheck::Single("system:user:update"),
         )
         .route_with_permission(
             "/{id}",
             delete(delete_user),
-            PermissionsCheck::Any(vec!["system:*", "system:user:*", "system:user:delete"]),
+            PermissionsCheck::Single("system:user:delete"),
         )
         .route_with_permission(
             "/options",
             get(get_user_options),
-            PermissionsCheck::Any(vec!["system:*", "system:user:*", "system:user:list"]),
+            PermissionsCheck::Single("system:user:list"),
         )
         .route_with_permission(
             "/status-options",
             get(get_user_status_options),
-            PermissionsCheck::Any(vec!["system:*", "system:user:*", "system:user:list"]),
+            PermissionsCheck::Single("system:user:list"),
         )
         .route_with_permission(
             "/{id}/password",
             put(update_user_password),
-            PermissionsCheck::Any(vec!["system:*", "system:user:*", "system:user:password"]),
+            PermissionsCheck::Single("system:user:password"),
         )
         .route_with_permission(
             "/{id}/status",
             put(update_user_status),
-            PermissionsCheck::Any(vec!["system:*", "system:user:*", "system:user:status"]),
+            PermissionsCheck::Single("system:user:status"),
+        )
+        .route_with_permission(
+            "/{id}/sessions",
+            get(get_user_sessions),
+            PermissionsCheck::Single("system:user:session"),
+        )
+        .route_with_permission(
+            "/{id}/sessions",
+            delete(force_logout_user),
+            PermissionsCheck::Single("system:user:session"),
+        )
+        .route_with_permission(
+            "/{id}/impersonate",
+            post(impersonate_user),
+            PermissionsCheck::Single("system:user:impersonate"),
+        )
+        .route_with_permission(
+            "/{id}/approve",
+            put(approve_user),
+            PermissionsCheck::Single("system:user:approve"),
+        )
+        .route_with_permission(
+            "/{id}/reject",
+            put(reject_user),
+            PermissionsCheck::Single("system:user:approve"),
+        )
+        .route_with_permission(
+            "/{id}/unlock",
+            put(unlock_user),
+            PermissionsCheck::Single("system:user:unlock"),
+        )
+        .route_with_permission(
+            "/{id}/mfa",
+            delete(reset_user_mfa),
+            PermissionsCheck::Single("system:user:mfa"),
+        )
+        .route_with_permission(
+            "/{id}/api-keys",
+            get(get_user_api_keys),
+            PermissionsCheck::Single("system:user:apikey"),
+        )
+        .route_with_permission(
+            "/{id}/api-keys",
+            post(create_user_api_key),
+            PermissionsCheck::Single("system:user:apikey"),
+        )
+        .route_with_permission(
+            "/{id}/api-keys/{key_id}",
+            delete(revoke_user_api_key),
+            PermissionsCheck::Single("system:user:apikey"),
+        )
+        .route_with_permission(
+            "/service-accounts",
+            get(get_service_account_list),
+            PermissionsCheck::Single("system:user:service"),
+        )
+        .route_with_permission(
+            "/service-accounts",
+            post(create_service_account),
+            PermissionsCheck::Single("system:user:service"),
+        )
+        .route_with_permission(
+            "/service-accounts/{id}",
+            put(update_service_account),
+            PermissionsCheck::Single("system:user:service"),
+        )
+        .route_with_permission(
+            "/service-accounts/{id}/credentials",
+            get(get_service_credentials),
+            PermissionsCheck::Single("system:user:service"),
+        )
+        .route_with_permission(
+            "/service-accounts/{id}/credentials",
+            post(create_service_credential),
+            PermissionsCheck::Single("system:user:service"),
+        )
+        .route_with_permission(
+            "/service-accounts/{id}/credentials/{credential_id}",
+            delete(revoke_service_credential),
+            PermissionsCheck::Single("system:user:service"),
         )
 }
 
 /// Get user list
-#[instrument(skip(pool, query))]
+#[instrument(skip(pool, query, scope))]
 pub async fn get_user_list(
     State(pool): State<PgPool>,
+    scope: DataScope,
     Query(query): Query<UserQueryDto>,
 ) -> AppResult<Vec<UserItemVo>> {
     tracing::info!("Getting user list");
 
-    let (users, total) = UserService::get_user_list(&pool, query).await?;
+    let (users, total) = UserService::get_user_list(&pool, query, &scope).await?;
 
     tracing::info!("Successfully retrieved {} users", users.len());
     Ok(ApiResponse::page(users, total))
@@ -178,3 +271,336 @@ pub async fn update_user_status(
     tracing::info!("Successfully updated user status");
     Ok(ApiResponse::success(result))
 }
+
+/// Get active sessions of a user
+#[instrument(skip(pool, id))]
+pub async fn get_user_sessions(
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> AppResult<Vec<SessionVo>> {
+    tracing::info!("Getting sessions for user: {}", id);
+
+    let sessions = UserService::get_user_sessions(&pool, id).await?;
+
+    tracing::info!("Successfully retrieved {} sessions", sessions.len());
+    Ok(ApiResponse::success(sessions))
+}
+
+/// Force logout a user from every session
+#[instrument(skip(pool, id))]
+pub async fn force_logout_user(State(pool): State<PgPool>, Path(id): Path<i64>) -> AppResult<u64> {
+    tracing::info!("Force logout for user: {}", id);
+
+    let revoked = UserService::force_logout(&pool, id).await?;
+
+    tracing::info!("Successfully revoked {} sessions", revoked);
+    Ok(ApiResponse::success(revoked))
+}
+
+/// Impersonate a user: get a short-lived token acting as the user
+#[instrument(skip(current_user, pool, addr, headers, id))]
+pub async fn impersonate_user(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> AppResult<ImpersonationVo> {
+    let start_time = Instant::now();
+    tracing::info!("Impersonating user: {}", id);
+
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    let result = UserService::impersonate(&pool, &current_user, id, &ip_address, user_agent).await;
+    let (description, target, status) = match &result {
+        Ok(vo) => (
+            format!("Started impersonating {}", vo.user_info.username),
+            Some(vo.user_info.username.as_str()),
+            "SUCCESS",
+        ),
+        Err(err) => (err.to_string(), None, "FAIL"),
+    };
+    if let Err(e) = LogService::log_business_operation(
+        &pool,
+        current_user.user_id,
+        &current_user.username,
+        "USER_IMPERSONATE",
+        &description,
+        serde_json::json!({ "userId": id, "username": target }),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &ip_address,
+        user_agent,
+        current_user.actor.as_ref(),
+    )
+    .await
+    {
+        tracing::error!("Failed to log USER_IMPERSONATE: {:?}", e);
+    }
+
+    let impersonation = result?;
+    tracing::info!("Successfully started impersonation");
+    Ok(ApiResponse::success(impersonation))
+}
+
+/// Unlock a locked user
+#[instrument(skip(pool, id))]
+pub async fn unlock_user(State(pool): State<PgPool>, Path(id): Path<i64>) -> AppResult<()> {
+    tracing::info!("Unlocking user: {}", id);
+
+    UserService::unlock_user(&pool, id).await?;
+
+    tracing::info!("Successfully unlocked user");
+    Ok(ApiResponse::success(()))
+}
+
+/// Reset the MFA enrollment of a user
+#[instrument(skip(pool, id))]
+pub async fn reset_user_mfa(State(pool): State<PgPool>, Path(id): Path<i64>) -> AppResult<()> {
+    tracing::info!("Resetting MFA for user: {}", id);
+
+    UserService::reset_mfa(&pool, id).await?;
+
+    tracing::info!("Successfully reset user MFA");
+    Ok(ApiResponse::success(()))
+}
+
+/// Get API keys of a user
+#[instrument(skip(pool, id))]
+pub async fn get_user_api_keys(
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> AppResult<Vec<ApiKeyVo>> {
+    tracing::info!("Getting API keys for user: {}", id);
+
+    let api_keys = UserService::get_user_api_keys(&pool, id).await?;
+
+    tracing::info!("Successfully retrieved {} API keys", api_keys.len());
+    Ok(ApiResponse::success(api_keys))
+}
+
+/// Create an API key on behalf of a service account
+#[instrument(skip(current_user, pool, id, request))]
+pub async fn create_user_api_key(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> AppResult<ApiKeyCreatedVo> {
+    tracing::info!("Creating API key for user: {}", id);
+
+    let api_key =
+        UserService::create_user_api_key(&pool, id, current_user.user_id, request).await?;
+
+    tracing::info!("Successfully created API key {}", api_key.api_key.id);
+    Ok(ApiResponse::success(api_key))
+}
+
+/// Revoke an API key of a user
+#[instrument(skip(pool, id, key_id))]
+pub async fn revoke_user_api_key(
+    State(pool): State<PgPool>,
+    Path((id, key_id)): Path<(i64, i64)>,
+) -> AppResult<()> {
+    tracing::info!("Revoking API key {} of user: {}", key_id, id);
+
+    UserService::revoke_user_api_key(&pool, id, key_id).await?;
+
+    tracing::info!("Successfully revoked API key");
+    Ok(ApiResponse::success(()))
+}
+
+/// Get service account list
+#[instrument(skip(pool, query, scope))]
+pub async fn get_service_account_list(
+    State(pool): State<PgPool>,
+    scope: DataScope,
+    Query(query): Query<UserQueryDto>,
+) -> AppResult<Vec<ServiceAccountItemVo>> {
+    tracing::info!("Getting service account list");
+
+    let (accounts, total) = UserService::get_service_account_list(&pool, query, &scope).await?;
+
+    tracing::info!("Successfully retrieved {} service accounts", accounts.len());
+    Ok(ApiResponse::page(accounts, total))
+}
+
+/// Create service account
+#[instrument(skip(pool, dto))]
+pub async fn create_service_account(
+    State(pool): State<PgPool>,
+    Json(dto): Json<CreateServiceAccountDto>,
+) -> AppResult<i64> {
+    tracing::info!("Creating service account: {}", dto.username);
+
+    let user_id = UserService::create_service_account(&pool, dto).await?;
+
+    tracing::info!("Successfully created service account");
+    Ok(ApiResponse::success(user_id))
+}
+
+/// Update service account
+#[instrument(skip(pool, id, dto))]
+pub async fn update_service_account(
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    Json(dto): Json<UpdateServiceAccountDto>,
+) -> AppResult<()> {
+    tracing::info!("Updating service account ID: {}", id);
+
+    UserService::update_service_account(&pool, id, dto).await?;
+
+    tracing::info!("Successfully updated service account");
+    Ok(ApiResponse::success(()))
+}
+
+/// Get client credentials of a service account
+#[instrument(skip(pool, id))]
+pub async fn get_service_credentials(
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> AppResult<Vec<ServiceCredentialVo>> {
+    tracing::info!("Getting credentials for service account: {}", id);
+
+    let credentials = UserService::get_service_credentials(&pool, id).await?;
+
+    tracing::info!("Successfully retrieved {} credentials", credentials.len());
+    Ok(ApiResponse::success(credentials))
+}
+
+/// Create a client credential for a service account
+#[instrument(skip(current_user, pool, id))]
+pub async fn create_service_credential(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> AppResult<ServiceCredentialCreatedVo> {
+    tracing::info!("Creating credential for service account: {}", id);
+
+    let credential =
+        UserService::create_service_credential(&pool, id, current_user.user_id).await?;
+
+    tracing::info!("Successfully created credential {}", credential.credential.id);
+    Ok(ApiResponse::success(credential))
+}
+
+/// Revoke a client credential of a service account
+#[instrument(skip(pool, id, credential_id))]
+pub async fn revoke_service_credential(
+    State(pool): State<PgPool>,
+    Path((id, credential_id)): Path<(i64, i64)>,
+) -> AppResult<()> {
+    tracing::info!("Revoking credential {} of service account: {}", credential_id, id);
+
+    UserService::revoke_service_credential(&pool, id, credential_id).await?;
+
+    tracing::info!("Successfully revoked credential");
+    Ok(ApiResponse::success(()))
+}
+
+/// Approve a self-registered (pending) user
+#[instrument(skip(current_user, pool, addr, headers, id, dto))]
+pub async fn approve_user(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+    dto: Option<Json<ReviewUserDto>>,
+) -> AppResult<()> {
+    let start_time = Instant::now();
+    tracing::info!("Approving user: {}", id);
+
+    let result = UserService::approve_user(&pool, id).await;
+    log_review(
+        &pool,
+        &current_user,
+        "USER_APPROVE",
+        "Approved registration of",
+        id,
+        &result,
+        dto,
+        start_time,
+        addr,
+        &headers,
+    )
+    .await;
+
+    result?;
+    tracing::info!("Successfully approved user");
+    Ok(ApiResponse::success(()))
+}
+
+/// Reject a self-registered (pending) user
+#[instrument(skip(current_user, pool, addr, headers, id, dto))]
+pub async fn reject_user(
+    current_user: CurrentUser,
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+    dto: Option<Json<ReviewUserDto>>,
+) -> AppResult<()> {
+    let start_time = Instant::now();
+    tracing::info!("Rejecting user: {}", id);
+
+    let result = UserService::reject_user(&pool, id).await;
+    log_review(
+        &pool,
+        &current_user,
+        "USER_REJECT",
+        "Rejected registration of",
+        id,
+        &result,
+        dto,
+        start_time,
+        addr,
+        &headers,
+    )
+    .await;
+
+    result?;
+    tracing::info!("Successfully rejected user");
+    Ok(ApiResponse::success(()))
+}
+
+/// Record an approve/reject decision in the audit log
+#[allow(clippy::too_many_arguments)]
+async fn log_review(
+    pool: &PgPool,
+    current_user: &CurrentUser,
+    action: &str,
+    verb: &str,
+    id: i64,
+    result: &Result<String, ServiceError>,
+    dto: Option<Json<ReviewUserDto>>,
+    start_time: Instant,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+) {
+    let reason = dto.and_then(|Json(dto)| dto.reason);
+    let (description, target, status) = match result {
+        Ok(username) => (format!("{} {}", verb, username), Some(username.as_str()), "SUCCESS"),
+        Err(err) => (err.to_string(), None, "FAIL"),
+    };
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown");
+
+    if let Err(e) = LogService::log_business_operation(
+        pool,
+        current_user.user_id,
+        &current_user.username,
+        action,
+        &description,
+        serde_json::json!({ "userId": id, "username": target, "reason": reason }),
+        status,
+        start_time.elapsed().as_millis() as i32,
+        &addr.ip().to_string(),
+        user_agent,
+        current_user.actor.as_ref(),
+    )
+    .await
+    {
+        tracing::error!("Failed to log {}: {:?}", action, e);
+    }
+}
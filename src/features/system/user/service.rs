@@ -1,17 +1,42 @@
 use super::{
     dto::{
-        CreateUserDto, UpdateUserDto, UpdateUserPasswordDto, UpdateUserStatusDto, UserOptionsDto,
-        UserQueryDto,
+        CreateServiceAccountDto, CreateUserDto, RoleGrantDto, UpdateServiceAccountDto,
+        UpdateUserDto, UpdateUserPasswordDto, UpdateUserStatusDto, UserOptionsDto, UserQueryDto,
     },
+    entity::{RoleGrantState, UserType, UserWithRolesEntity},
     repo::UserRepository,
-    vo::{UserItemVo, UserOptionVo},
+    vo::{
+        ServiceAccountItemVo, ServiceCredentialCreatedVo, ServiceCredentialVo, UserItemVo,
+        UserOptionVo,
+    },
 };
 use crate::{
     common::{error::ServiceError, pagination::Pagination},
-    core::password::PasswordUtils,
+    core::{
+        config::CONFIG,
+        extractor::CurrentUser,
+        password::{NO_PASSWORD_HASH, PASSWORD_POLICY, PasswordUtils},
+        token::TokenUtils,
+    },
+    features::{
+        auth::{
+            dto::CreateApiKeyRequest,
+            service::AuthService,
+            vo::{ApiKeyCreatedVo, ApiKeyVo, ImpersonationVo, SessionVo},
+        },
+        system::dept::{scope::DataScope, service::DeptService},
+    },
 };
 
+use chrono::{NaiveDateTime, Utc};
 use sqlx::PgPool;
+use std::time::Duration;
+
+/// Domain of the placeholder emails of service accounts (reserved, never deliverable)
+const SERVICE_ACCOUNT_EMAIL_DOMAIN: &str = "service-account.invalid";
+
+/// Prefix of service account client ids
+const CLIENT_ID_PREFIX: &str = "rzsa_";
 
 /// User service for business operations
 pub struct UserService;
@@ -21,13 +46,21 @@ impl UserService {
     pub async fn get_user_list(
         pool: &PgPool,
         query: UserQueryDto,
+        scope: &DataScope,
     ) -> Result<(Vec<UserItemVo>, i64), ServiceError> {
         tracing::info!("Fetching user list with query: {:?}", query);
 
         let (limit, offset, _) = Pagination::normalize(query.current, query.page_size);
 
-        let (users, total) =
-            UserRepository::find_with_pagination(pool, offset, limit, query).await?;
+        let (users, total) = UserRepository::find_with_pagination(
+            pool,
+            UserType::Human,
+            offset,
+            limit,
+            query,
+            scope,
+        )
+        .await?;
 
         tracing::info!("Users: {:?}", users);
         let list = users.into_iter().map(UserItemVo::from).collect();
@@ -49,7 +82,13 @@ impl UserService {
             return Err(ServiceError::EmailConflict);
         }
 
-        // Hash password
+        if let Some(dept_id) = dto.dept_id {
+            DeptService::validate_dept_exists(pool, dept_id).await?;
+        }
+        Self::validate_role_grants(&dto.role_ids, &dto.role_grants, Utc::now().naive_utc())?;
+
+        // Check password policy and hash password
+        PASSWORD_POLICY.validate(&dto.password, &dto.username)?;
         let password_hash = PasswordUtils::hash_password(&dto.password)?;
 
         // Create user DTO with hashed password
@@ -60,6 +99,9 @@ impl UserService {
             real_name: dto.real_name,
             status: dto.status,
             role_ids: dto.role_ids,
+            must_change_password: dto.must_change_password,
+            dept_id: dto.dept_id,
+            role_grants: dto.role_grants,
         };
 
         let user_id = UserRepository::create_user(pool, &create_dto).await?;
@@ -75,6 +117,13 @@ impl UserService {
     ) -> Result<i64, ServiceError> {
         tracing::debug!("Updating user ID: {}", id);
 
+        if let Some(dept_id) = request.dept_id {
+            DeptService::validate_dept_exists(pool, dept_id).await?;
+        }
+        if let Some(role_grants) = &request.role_grants {
+            Self::validate_role_grants(&request.role_ids, role_grants, Utc::now().naive_utc())?;
+        }
+
         // Update user
         let user_id = UserRepository::update_user(
             pool,
@@ -82,12 +131,89 @@ impl UserService {
             &request.email,
             &request.real_name,
             &request.role_ids,
+            request.role_grants.as_deref(),
+            request.dept_id,
         )
         .await?;
+        AuthService::sync_permission_caches(pool, &[user_id]).await;
 
         Ok(user_id)
     }
 
+    /// A time-bound grant needs a bound, must not have expired yet and must not
+    /// duplicate a permanent role or another grant of the same role
+    fn validate_role_grants(
+        role_ids: &[i64],
+        grants: &[RoleGrantDto],
+        now: NaiveDateTime,
+    ) -> Result<(), ServiceError> {
+        for (i, grant) in grants.iter().enumerate() {
+            let reason = match (grant.valid_from, grant.valid_until) {
+                (None, None) => Some("needs a start or an end, use roleIds for permanent roles"),
+                (Some(from), Some(until)) if from >= until => Some("must start before it ends"),
+                (_, Some(until)) if until <= now => Some("has already expired"),
+                _ if role_ids.contains(&grant.role_id) => Some("is already granted permanently"),
+                _ if grants[..i].iter().any(|g| g.role_id == grant.role_id) => {
+                    Some("is granted more than once")
+                }
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                return Err(ServiceError::InvalidOperation(format!(
+                    "Role grant of role {} {}",
+                    grant.role_id, reason
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Start and expire time-bound role grants every `role_grant_sweep_interval`
+    /// seconds. Runs for the lifetime of the server.
+    pub fn spawn_role_grant_sweeper(pool: PgPool) {
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(CONFIG.role_grant_sweep_interval));
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::sweep_role_grants(&pool).await {
+                    tracing::error!("Role grant sweep failed: {:?}", e);
+                }
+            }
+        });
+    }
+
+    /// Advance the state of time-bound grants whose window started or ended.
+    /// The database bumps the permission versions of their users, which evicts
+    /// cached permissions on every instance.
+    pub async fn sweep_role_grants(pool: &PgPool) -> Result<usize, ServiceError> {
+        let swept = UserRepository::sweep_role_grants(pool, Utc::now().naive_utc()).await?;
+        if swept.is_empty() {
+            return Ok(0);
+        }
+
+        for grant in &swept {
+            let change = match grant.state {
+                s if s == RoleGrantState::Expired as i16 => "expired",
+                s if s == RoleGrantState::Active as i16 => "started",
+                s if s == RoleGrantState::Upcoming as i16 => "postponed",
+                _ => "changed",
+            };
+            tracing::info!(
+                "Role grant {}: user_id={}, role_id={}",
+                change,
+                grant.user_id,
+                grant.role_id
+            );
+        }
+        let mut user_ids: Vec<i64> = swept.iter().map(|grant| grant.user_id).collect();
+        user_ids.sort_unstable();
+        user_ids.dedup();
+        AuthService::sync_permission_caches(pool, &user_ids).await;
+
+        Ok(swept.len())
+    }
+
     /// Delete user
     pub async fn delete_user(pool: &PgPool, id: i64) -> Result<(), ServiceError> {
         tracing::debug!("Deleting user ID: {}", id);
@@ -99,6 +225,7 @@ impl UserService {
 
         // Soft delete user
         UserRepository::soft_delete(pool, id).await?;
+        AuthService::sync_permission_caches(pool, &[id]).await;
 
         Ok(())
     }
@@ -135,11 +262,18 @@ impl UserService {
     ) -> Result<bool, ServiceError> {
         tracing::debug!("Updating user password for user ID: {}", id);
 
-        let password_hash = PasswordUtils::hash_password(&dto.password)?;
+        let user = UserRepository::find_by_id(pool, id)
+            .await?
+            .ok_or(ServiceError::NotFound("User".to_string()))?;
+        if user.user_type == UserType::ServiceAccount as i16 {
+            return Err(ServiceError::InvalidOperation(
+                "Service accounts have no password".to_string(),
+            ));
+        }
 
-        let result = UserRepository::update_user_password(pool, id, &password_hash).await?;
+        AuthService::set_password(pool, id, &dto.password, dto.must_change_password).await?;
 
-        Ok(result)
+        Ok(true)
     }
 
     pub async fn update_user_status(
@@ -150,7 +284,384 @@ impl UserService {
         tracing::debug!("Updating user status for user ID: {}", id);
 
         let result = UserRepository::update_user_status(pool, id, dto.status).await?;
+        AuthService::sync_permission_caches(pool, &[id]).await;
 
         Ok(result)
     }
+
+    /// Get active sessions of a user
+    pub async fn get_user_sessions(pool: &PgPool, id: i64) -> Result<Vec<SessionVo>, ServiceError> {
+        tracing::debug!("Getting sessions for user ID: {}", id);
+
+        if UserRepository::find_by_id(pool, id).await?.is_none() {
+            return Err(ServiceError::NotFound("User".to_string()));
+        }
+
+        AuthService::list_sessions(pool, id, None).await
+    }
+
+    /// Force logout: revoke every session of a user
+    pub async fn force_logout(pool: &PgPool, id: i64) -> Result<u64, ServiceError> {
+        tracing::debug!("Force logout for user ID: {}", id);
+
+        if UserRepository::find_by_id(pool, id).await?.is_none() {
+            return Err(ServiceError::NotFound("User".to_string()));
+        }
+
+        AuthService::revoke_all_sessions(pool, id).await
+    }
+
+    /// Impersonate a user ("log in as user"), acting as `actor`
+    pub async fn impersonate(
+        pool: &PgPool,
+        actor: &CurrentUser,
+        id: i64,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<ImpersonationVo, ServiceError> {
+        tracing::debug!("Impersonating user ID: {}", id);
+
+        let user = UserRepository::find_by_id(pool, id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("User".to_string()))?;
+        if user.user_type != UserType::Human as i16 {
+            return Err(ServiceError::InvalidOperation(
+                "Service accounts cannot be impersonated".into(),
+            ));
+        }
+
+        AuthService::impersonate(pool, actor, id, ip_address, user_agent).await
+    }
+
+    /// Unlock a user locked by failed logins (or manually)
+    pub async fn unlock_user(pool: &PgPool, id: i64) -> Result<(), ServiceError> {
+        tracing::debug!("Unlocking user ID: {}", id);
+
+        if UserRepository::find_by_id(pool, id).await?.is_none() {
+            return Err(ServiceError::NotFound("User".to_string()));
+        }
+
+        if !UserRepository::unlock_user(pool, id).await? {
+            return Err(ServiceError::InvalidOperation("User is not locked".to_string()));
+        }
+
+        tracing::info!("User {} unlocked", id);
+        Ok(())
+    }
+
+    /// Reset the MFA enrollment of a user who lost their authenticator
+    pub async fn reset_mfa(pool: &PgPool, id: i64) -> Result<(), ServiceError> {
+        tracing::debug!("Resetting MFA for user ID: {}", id);
+
+        if UserRepository::find_by_id(pool, id).await?.is_none() {
+            return Err(ServiceError::NotFound("User".to_string()));
+        }
+
+        AuthService::reset_mfa(pool, id).await
+    }
+
+    /// List API keys of a user
+    pub async fn get_user_api_keys(pool: &PgPool, id: i64) -> Result<Vec<ApiKeyVo>, ServiceError> {
+        tracing::debug!("Getting API keys for user ID: {}", id);
+
+        if UserRepository::find_by_id(pool, id).await?.is_none() {
+            return Err(ServiceError::NotFound("User".to_string()));
+        }
+
+        AuthService::list_api_keys(pool, id).await
+    }
+
+    /// Create an API key on behalf of a service account
+    pub async fn create_user_api_key(
+        pool: &PgPool,
+        id: i64,
+        created_by: i64,
+        request: CreateApiKeyRequest,
+    ) -> Result<ApiKeyCreatedVo, ServiceError> {
+        tracing::debug!("Creating API key for user ID: {}", id);
+
+        let user = UserRepository::find_by_id(pool, id)
+            .await?
+            .ok_or(ServiceError::NotFound("User".to_string()))?;
+        // Humans create their own keys
+        if user.user_type != UserType::ServiceAccount as i16 {
+            tracing::warn!(
+                "User_id={} tried to create an API key for human user_id={}",
+                created_by,
+                id
+            );
+            return Err(ServiceError::PermissionDenied);
+        }
+
+        AuthService::create_api_key(pool, id, created_by, request).await
+    }
+
+    /// Revoke an API key of a user
+    pub async fn revoke_user_api_key(
+        pool: &PgPool,
+        id: i64,
+        key_id: i64,
+    ) -> Result<(), ServiceError> {
+        tracing::debug!("Revoking API key {} of user ID: {}", key_id, id);
+
+        AuthService::revoke_api_key(pool, id, key_id).await
+    }
+
+    /// Approve a pending registration. Returns the username for the audit log.
+    pub async fn approve_user(pool: &PgPool, id: i64) -> Result<String, ServiceError> {
+        tracing::debug!("Approving user ID: {}", id);
+
+        let user = UserRepository::find_by_id(pool, id)
+            .await?
+            .ok_or(ServiceError::NotFound("User".to_string()))?;
+
+        if !UserRepository::approve_user(pool, id).await? {
+            return Err(ServiceError::InvalidOperation("User is not pending approval".to_string()));
+        }
+
+        tracing::info!("User {} approved", id);
+        Ok(user.username)
+    }
+
+    /// Reject a pending registration. Returns the username for the audit log.
+    pub async fn reject_user(pool: &PgPool, id: i64) -> Result<String, ServiceError> {
+        tracing::debug!("Rejecting user ID: {}", id);
+
+        let user = UserRepository::find_by_id(pool, id)
+            .await?
+            .ok_or(ServiceError::NotFound("User".to_string()))?;
+
+        if !UserRepository::reject_user(pool, id).await? {
+            return Err(ServiceError::InvalidOperation("User is not pending approval".to_string()));
+        }
+
+        tracing::info!("User {} rejected", id);
+        Ok(user.username)
+    }
+
+    /// Get service account list with pagination
+    pub async fn get_service_account_list(
+        pool: &PgPool,
+        query: UserQueryDto,
+        scope: &DataScope,
+    ) -> Result<(Vec<ServiceAccountItemVo>, i64), ServiceError> {
+        tracing::info!("Fetching service account list with query: {:?}", query);
+
+        let (limit, offset, _) = Pagination::normalize(query.current, query.page_size);
+
+        let (users, total) = UserRepository::find_with_pagination(
+            pool,
+            UserType::ServiceAccount,
+            offset,
+            limit,
+            query,
+            scope,
+        )
+        .await?;
+
+        let list = users.into_iter().map(ServiceAccountItemVo::from).collect();
+
+        Ok((list, total))
+    }
+
+    /// Create a service account: no password, no interactive login
+    pub async fn create_service_account(
+        pool: &PgPool,
+        dto: CreateServiceAccountDto,
+    ) -> Result<i64, ServiceError> {
+        tracing::debug!("Creating service account: {}", dto.username);
+
+        let username = dto.username.trim();
+        if username.is_empty() {
+            return Err(ServiceError::InvalidOperation("Username is required".to_string()));
+        }
+        if UserRepository::username_exists(pool, username).await? {
+            return Err(ServiceError::UsernameConflict);
+        }
+        let email = format!("{}@{}", username, SERVICE_ACCOUNT_EMAIL_DOMAIN);
+        if UserRepository::email_exists(pool, &email).await? {
+            return Err(ServiceError::EmailConflict);
+        }
+        Self::check_owner(pool, dto.owner_id).await?;
+
+        UserRepository::create_service_account(
+            pool,
+            username,
+            &email,
+            NO_PASSWORD_HASH,
+            dto.real_name.as_deref(),
+            dto.owner_id,
+            &dto.role_ids,
+        )
+        .await
+    }
+
+    /// Update the display name, owner and roles of a service account
+    pub async fn update_service_account(
+        pool: &PgPool,
+        id: i64,
+        dto: UpdateServiceAccountDto,
+    ) -> Result<(), ServiceError> {
+        tracing::debug!("Updating service account ID: {}", id);
+
+        Self::check_owner(pool, dto.owner_id).await?;
+        if !UserRepository::update_service_account(
+            pool,
+            id,
+            dto.real_name.as_deref(),
+            dto.owner_id,
+            &dto.role_ids,
+        )
+        .await?
+        {
+            return Err(ServiceError::NotFound("Service account".to_string()));
+        }
+        AuthService::sync_permission_caches(pool, &[id]).await;
+
+        Ok(())
+    }
+
+    /// List active client credentials of a service account
+    pub async fn get_service_credentials(
+        pool: &PgPool,
+        id: i64,
+    ) -> Result<Vec<ServiceCredentialVo>, ServiceError> {
+        tracing::debug!("Getting credentials for service account ID: {}", id);
+
+        Self::find_service_account(pool, id).await?;
+
+        let credentials = UserRepository::find_service_credentials(pool, id).await?;
+        Ok(credentials.into_iter().map(ServiceCredentialVo::from).collect())
+    }
+
+    /// Create a client credential for a service account
+    pub async fn create_service_credential(
+        pool: &PgPool,
+        id: i64,
+        created_by: i64,
+    ) -> Result<ServiceCredentialCreatedVo, ServiceError> {
+        tracing::debug!("Creating credential for service account ID: {}", id);
+
+        Self::find_service_account(pool, id).await?;
+
+        let client_id = format!("{}{}", CLIENT_ID_PREFIX, &TokenUtils::generate_token()[..24]);
+        let client_secret = TokenUtils::generate_token();
+        let credential = UserRepository::create_service_credential(
+            pool,
+            id,
+            &client_id,
+            &TokenUtils::hash_token(&client_secret),
+            created_by,
+        )
+        .await?;
+
+        Ok(ServiceCredentialCreatedVo { client_secret, credential: credential.into() })
+    }
+
+    /// Revoke a client credential of a service account.
+    /// Access tokens already issued to the account are revoked as well.
+    pub async fn revoke_service_credential(
+        pool: &PgPool,
+        id: i64,
+        credential_id: i64,
+    ) -> Result<(), ServiceError> {
+        tracing::debug!("Revoking credential {} of service account ID: {}", credential_id, id);
+
+        if !UserRepository::revoke_service_credential(pool, id, credential_id).await? {
+            return Err(ServiceError::NotFound("Credential".to_string()));
+        }
+        AuthService::revoke_all_sessions(pool, id).await?;
+
+        Ok(())
+    }
+
+    /// Find a service account by ID
+    async fn find_service_account(
+        pool: &PgPool,
+        id: i64,
+    ) -> Result<UserWithRolesEntity, ServiceError> {
+        UserRepository::find_by_id(pool, id)
+            .await?
+            .filter(|user| user.user_type == UserType::ServiceAccount as i16)
+            .ok_or(ServiceError::NotFound("Service account".to_string()))
+    }
+
+    /// Service accounts must be owned by an existing human user
+    async fn check_owner(pool: &PgPool, owner_id: i64) -> Result<(), ServiceError> {
+        match UserRepository::find_by_id(pool, owner_id).await? {
+            Some(owner) if owner.user_type == UserType::Human as i16 => Ok(()),
+            _ => Err(ServiceError::InvalidOperation(
+                "Owner must be an existing human user".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration as ChronoDuration, NaiveDate};
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn grant(role_id: i64, from_hours: Option<i64>, until_hours: Option<i64>) -> RoleGrantDto {
+        RoleGrantDto {
+            role_id,
+            valid_from: from_hours.map(|h| now() + ChronoDuration::hours(h)),
+            valid_until: until_hours.map(|h| now() + ChronoDuration::hours(h)),
+        }
+    }
+
+    fn rejects(role_ids: &[i64], grants: &[RoleGrantDto], reason: &str) {
+        match UserService::validate_role_grants(role_ids, grants, now()) {
+            Err(ServiceError::InvalidOperation(message)) => {
+                assert!(message.ends_with(reason), "{message}")
+            }
+            other => panic!("expected rejection '{reason}', got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_validate_role_grants_accepts_bounded_grants() {
+        let grants =
+            [grant(2, None, Some(1)), grant(3, Some(1), None), grant(4, Some(-1), Some(1))];
+        assert!(UserService::validate_role_grants(&[1], &grants, now()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_role_grants_rejects_no_bound() {
+        rejects(
+            &[],
+            &[grant(2, None, None)],
+            "needs a start or an end, use roleIds for permanent roles",
+        );
+    }
+
+    #[test]
+    fn test_validate_role_grants_rejects_start_not_before_end() {
+        rejects(&[], &[grant(2, Some(2), Some(1))], "must start before it ends");
+        rejects(&[], &[grant(2, Some(1), Some(1))], "must start before it ends");
+    }
+
+    #[test]
+    fn test_validate_role_grants_rejects_expired() {
+        rejects(&[], &[grant(2, None, Some(-1))], "has already expired");
+        rejects(&[], &[grant(2, None, Some(0))], "has already expired");
+    }
+
+    #[test]
+    fn test_validate_role_grants_rejects_permanent_duplicate() {
+        rejects(&[2], &[grant(2, None, Some(1))], "is already granted permanently");
+    }
+
+    #[test]
+    fn test_validate_role_grants_rejects_duplicate_grant() {
+        rejects(
+            &[],
+            &[grant(2, None, Some(1)), grant(2, Some(2), Some(3))],
+            "is granted more than once",
+        );
+    }
 }
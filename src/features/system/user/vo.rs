@@ -1,8 +1,8 @@
-use super::entity::UserWithRolesEntity;
+use super::entity::{ServiceCredentialEntity, UserWithRolesEntity};
 use crate::common::api::OptionItem;
 
 use chrono::NaiveDateTime;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// User item for list display
 #[derive(Debug, Serialize)]
@@ -16,10 +16,26 @@ pub struct UserItemVo {
     pub status: i16,
     pub last_login_at: Option<NaiveDateTime>,
     pub roles: Vec<UserOptionVo>,
+    pub dept_id: Option<i64>,
+    pub dept_name: Option<String>,
+    /// Time-bound role grants: upcoming, active and expired
+    pub role_grants: Vec<RoleGrantVo>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
 
+/// Time-bound role grant
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleGrantVo {
+    pub role_id: i64,
+    pub role_name: String,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+    /// 1: upcoming, 2: active, 3: expired
+    pub state: i16,
+}
+
 /// User option
 pub type UserOptionVo = OptionItem<i64>;
 
@@ -36,6 +52,73 @@ impl From<UserWithRolesEntity> for UserItemVo {
             created_at: user.created_at,
             updated_at: user.updated_at,
             roles: serde_json::from_value::<Vec<UserOptionVo>>(user.roles).unwrap_or_default(),
+            dept_id: user.dept_id,
+            dept_name: user.dept_name,
+            role_grants: serde_json::from_value::<Vec<RoleGrantVo>>(user.role_grants)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Service account item for list display
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceAccountItemVo {
+    pub id: i64,
+    pub username: String,
+    pub real_name: Option<String>,
+    pub status: i16,
+    pub owner_id: Option<i64>,
+    /// Last token exchange
+    pub last_login_at: Option<NaiveDateTime>,
+    pub roles: Vec<UserOptionVo>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<UserWithRolesEntity> for ServiceAccountItemVo {
+    fn from(user: UserWithRolesEntity) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            real_name: user.real_name,
+            status: user.status,
+            owner_id: user.owner_id,
+            last_login_at: user.last_login_at,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            roles: serde_json::from_value::<Vec<UserOptionVo>>(user.roles).unwrap_or_default(),
+        }
+    }
+}
+
+/// Service account client credential, without its secret
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceCredentialVo {
+    pub id: i64,
+    pub client_id: String,
+    pub created_by: Option<i64>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<ServiceCredentialEntity> for ServiceCredentialVo {
+    fn from(credential: ServiceCredentialEntity) -> Self {
+        Self {
+            id: credential.id,
+            client_id: credential.client_id,
+            created_by: credential.created_by,
+            last_used_at: credential.last_used_at,
+            created_at: credential.created_at,
         }
     }
 }
+
+/// Newly created client credential. The secret is only shown once.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceCredentialCreatedVo {
+    pub client_secret: String,
+    pub credential: ServiceCredentialVo,
+}
@@ -2,22 +2,57 @@ use crate::{
     common::error::{AppError, ServiceError},
     core::extractor::CurrentUser,
     core::jwt,
+    features::auth::service::{API_KEY_PREFIX, AuthService},
 };
 
 use axum::{
-    extract::{Request, State},
+    extract::{ConnectInfo, Request, State},
     http::header,
     middleware::Next,
     response::Response,
 };
 use sqlx::PgPool;
+use std::net::SocketAddr;
+
+/// Routes still reachable while the user must change the password
+const PASSWORD_CHANGE_ALLOWED_PATHS: &[&str] = &["/auth/me", "/auth/logout", "/auth/password"];
+
+/// Account routes reachable with an API key; the rest of `/auth` needs an interactive login
+const API_KEY_ALLOWED_AUTH_PATHS: &[&str] = &["/auth/me"];
+
+/// Account routes reachable while impersonating; the impersonated user's
+/// credentials, MFA, sessions and keys stay out of reach
+const IMPERSONATION_ALLOWED_AUTH_PATHS: &[&str] = &["/auth/me", "/auth/logout"];
+
+/// Whether an API key may reach a path. Approving OAuth authorization
+/// requests also needs an interactive login.
+fn api_key_allows(path: &str) -> bool {
+    if path.starts_with("/auth/") {
+        return API_KEY_ALLOWED_AUTH_PATHS.contains(&path);
+    }
+    !path.starts_with("/oauth/")
+}
+
+/// Whether an impersonation token may reach a path. Impersonators see what the
+/// user sees, but cannot act on the user's account, approve OAuth requests as
+/// the user or impersonate someone else from the impersonation.
+fn impersonation_allows(path: &str) -> bool {
+    if path.starts_with("/auth/") {
+        return IMPERSONATION_ALLOWED_AUTH_PATHS.contains(&path);
+    }
+    let nested = path.starts_with("/system/users/") && path.ends_with("/impersonate");
+    !path.starts_with("/oauth/") && !nested
+}
 
 /// JWT authentication middleware
 ///
 /// Steps:
-/// 1. Extract JWT from Authorization header
-/// 2. Validate token and extract claims
-/// 3. Inject CurrentUser and PgPool into request extensions
+/// 1. Extract JWT (or API key) from Authorization header
+/// 2. Validate token and extract claims, or look up the API key
+/// 3. Check that the token's session (jti) has not been revoked
+/// 4. Block everything but the password change routes when a password change is required
+/// 5. Keep impersonation tokens away from the impersonated user's account routes
+/// 6. Inject CurrentUser and PgPool into request extensions
 ///
 /// Note: Only handles authentication, not authorization
 pub async fn auth_middleware(
@@ -40,6 +75,32 @@ pub async fn auth_middleware(
             AppError::from(ServiceError::InvalidToken)
         })?;
 
+    // API keys act as their owner, limited to the key's scopes
+    if token.starts_with(API_KEY_PREFIX) {
+        let path = parts.uri.path();
+        if !api_key_allows(path) {
+            tracing::debug!("API key blocked from {}", path);
+            return Err(ServiceError::PermissionDenied.into());
+        }
+
+        let ip_address = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+        let current_user =
+            AuthService::authenticate_api_key(&pool, token, ip_address.as_deref()).await?;
+        tracing::debug!(
+            "API key authenticated for user {} ({}) accessing {}",
+            current_user.user_id,
+            current_user.username,
+            path
+        );
+
+        parts.extensions.insert(current_user);
+        parts.extensions.insert(pool);
+        return Ok(next.run(Request::from_parts(parts, body)).await);
+    }
+
     // Verify JWT and extract claims
     let claims = jwt::verify_token(token).map_err(|e| {
         tracing::warn!("JWT verification failed for {}: {:?}", parts.uri.path(), e);
@@ -53,8 +114,54 @@ pub async fn auth_middleware(
         parts.uri.path()
     );
 
+    // Reject tokens whose session was revoked (logout, log out everywhere, admin force logout)
+    let actor_id = claims.act.as_ref().map(|actor| actor.user_id);
+    let must_change_password = AuthService::verify_session(
+        &pool,
+        claims.user_id,
+        claims.jti,
+        actor_id,
+    )
+    .await
+    .map_err(|e| {
+        tracing::warn!("Session {} rejected for user {}: {:?}", claims.jti, claims.user_id, e);
+        e
+    })?;
+
+    if let Some(actor) = &claims.act {
+        let path = parts.uri.path();
+        if !impersonation_allows(path) {
+            tracing::debug!(
+                "Impersonation of user {} by {} blocked from {}",
+                claims.user_id,
+                actor.user_id,
+                path
+            );
+            return Err(ServiceError::PermissionDenied.into());
+        }
+    }
+
+    // Users who must change their password may only reach the password change routes.
+    // Impersonators cannot change it, so they are not held up by it.
+    if must_change_password
+        && claims.act.is_none()
+        && !PASSWORD_CHANGE_ALLOWED_PATHS.contains(&parts.uri.path())
+    {
+        tracing::debug!(
+            "Password change required for user {}, blocked {}",
+            claims.user_id,
+            parts.uri.path()
+        );
+        return Err(ServiceError::PasswordChangeRequired.into());
+    }
+
     // Inject user and database pool into request extensions
-    let current_user = CurrentUser::new(claims.user_id, claims.username.clone());
+    let current_user = match claims.act.clone() {
+        Some(actor) => {
+            CurrentUser::impersonated(claims.user_id, claims.username.clone(), claims.jti, actor)
+        }
+        None => CurrentUser::new(claims.user_id, claims.username.clone(), claims.jti),
+    };
     parts.extensions.insert(current_user);
     parts.extensions.insert(pool);
 
@@ -64,3 +171,47 @@ pub async fn auth_middleware(
 
     Ok(next.run(request).await)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impersonation_allows_account_basics() {
+        assert!(impersonation_allows("/auth/me"));
+        assert!(impersonation_allows("/auth/logout"));
+        for path in ["/auth/password", "/auth/mfa/setup", "/auth/sessions", "/auth/api-keys"] {
+            assert!(!impersonation_allows(path), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_impersonation_blocks_oauth() {
+        assert!(!impersonation_allows("/oauth/authorize"));
+        // The admin pages of OAuth clients are ordinary permission-checked routes
+        assert!(impersonation_allows("/system/oauth-clients"));
+    }
+
+    #[test]
+    fn test_impersonation_blocks_nested_impersonation() {
+        assert!(!impersonation_allows("/system/users/5/impersonate"));
+        assert!(impersonation_allows("/system/users/5"));
+        assert!(impersonation_allows("/system/users/5/sessions"));
+    }
+
+    #[test]
+    fn test_impersonation_allows_the_users_pages() {
+        assert!(impersonation_allows("/dashboard/stats"));
+        assert!(impersonation_allows("/system/users"));
+        assert!(impersonation_allows("/system/logs"));
+    }
+
+    #[test]
+    fn test_api_key_allows() {
+        assert!(api_key_allows("/auth/me"));
+        assert!(!api_key_allows("/auth/logout"));
+        assert!(!api_key_allows("/auth/api-keys"));
+        assert!(!api_key_allows("/oauth/authorize"));
+        assert!(api_key_allows("/system/users"));
+    }
+}
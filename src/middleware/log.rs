@@ -50,6 +50,7 @@ pub async fn log_middleware(
         &user_agent,
         status_code,
         duration.as_millis() as i32,
+        current_user.as_ref().and_then(|u| u.actor.as_ref()),
     )
     .await
     {
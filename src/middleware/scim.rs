@@ -0,0 +1,31 @@
+use crate::{
+    core::{config::CONFIG, token::TokenUtils},
+    features::scim::error::ScimError,
+};
+
+use axum::{extract::Request, http::header, middleware::Next, response::Response};
+
+/// SCIM bearer token middleware
+///
+/// Provisioning clients authenticate with the static token configured in
+/// `RUSTZEN_SCIM_TOKEN`. The endpoints are hidden while no token is set.
+pub async fn scim_auth_middleware(request: Request, next: Next) -> Result<Response, ScimError> {
+    if CONFIG.scim_token.is_empty() {
+        return Err(ScimError::not_found("Resource"));
+    }
+
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .ok_or_else(ScimError::unauthorized)?;
+
+    // Compare digests so the comparison time does not depend on the token prefix
+    if TokenUtils::hash_token(token.trim()) != TokenUtils::hash_token(&CONFIG.scim_token) {
+        tracing::warn!("SCIM request with an invalid bearer token to {}", request.uri().path());
+        return Err(ScimError::unauthorized());
+    }
+
+    Ok(next.run(request).await)
+}